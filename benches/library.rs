@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The work done over the whole library after a scan and on every redraw, timed on a
+//! synthetic library of 100,000 tracks.
+
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use jams::library::search::SearchIndex;
+use jams::library::{collation, scan, track_number, MusicFile};
+
+const TRACKS: usize = 100_000;
+const TRACKS_PER_ALBUM: usize = 10;
+const ALBUMS_PER_ARTIST: usize = 4;
+
+/// A library of albums by a few thousand artists, some starting with an article and some
+/// with accents, and every fiftieth track a second copy of the one before.
+fn library() -> Vec<MusicFile> {
+    (0..TRACKS)
+        .map(|id| {
+            let track = if id % 50 == 49 { id - 1 } else { id };
+            let album = track / TRACKS_PER_ALBUM;
+            let artist = match album / ALBUMS_PER_ARTIST {
+                n if n % 3 == 0 => format!("The Artist {}", n),
+                n if n % 3 == 1 => format!("Ártist {}", n),
+                n => format!("Artist {}", n),
+            };
+
+            let mut file = MusicFile {
+                album_artist: artist.clone(),
+                album_artist_sort: String::new(),
+                album_artist_key: String::new(),
+                album: format!("Album {}", album),
+                disc_number: 1,
+                track_number: (track % TRACKS_PER_ALBUM) as u16 + 1,
+                side: None,
+                gapless: false,
+                artist,
+                artist_sort: String::new(),
+                artist_key: String::new(),
+                track_title: format!("Song {}", track),
+                title_key: String::new(),
+                duration: Duration::from_secs(180 + (track % 120) as u64),
+                channels: 2,
+                release_date: None,
+                original_date: None,
+                release_group_id: String::new(),
+                genre: "Rock".to_string(),
+                label: String::new(),
+                language: String::new(),
+                bpm: None,
+                key: None,
+                saved_path: PathBuf::from(format!("/music/{}/{}.flac", album, id)),
+                inode: None,
+                size: 0,
+                archive: None,
+                uri: format!("file:///music/{}/{}.flac", album, id),
+                id,
+            };
+            collation::update_keys(&mut file);
+            file
+        })
+        .collect()
+}
+
+fn sorting(c: &mut Criterion) {
+    let files = library();
+
+    c.bench_function("update_keys", |b| {
+        b.iter_batched(
+            || files.clone(),
+            |mut files| {
+                files.iter_mut().for_each(collation::update_keys);
+                files
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("sort_library", |b| {
+        b.iter_batched(
+            || files.clone(),
+            |mut files| {
+                track_number::sort_library(&mut files);
+                files
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("distinct_songs", |b| {
+        b.iter(|| track_number::distinct_songs(black_box(&files), |_| true).len())
+    });
+
+    c.bench_function("build_albums", |b| {
+        b.iter(|| scan::build_albums(black_box(&files)))
+    });
+}
+
+fn searching(c: &mut Criterion) {
+    let files = library();
+    let (albums, _) = scan::build_albums(&files);
+
+    c.bench_function("search_index_build", |b| {
+        b.iter(|| SearchIndex::build(black_box(&files), black_box(&albums)))
+    });
+
+    let index = SearchIndex::build(&files, &albums);
+    c.bench_function("search", |b| b.iter(|| index.search(black_box("song 4"))));
+}
+
+criterion_group!(benches, sorting, searching);
+criterion_main!(benches);
@@ -5,6 +5,18 @@ welcome = Welcome to Jams!
 spelled-out = (...Yet Another Music Player)
 page-2-description = This is page 2.
 scan = gimme the files
+scanning-found = { $count ->
+    [one] Scanning… 1 file found
+   *[other] Scanning… { $count } files found
+}
+scanning-checked = { $count ->
+    [one] Scanning… { $checked } of { $total } files, 1 track found
+   *[other] Scanning… { $checked } of { $total } files, { $count } tracks found
+}
+scanning-files = { $count ->
+    [one] Scanning… 1 file
+   *[other] Scanning… { $count } files
+}
 play = play that music
 get-files = open file picker
 debug = Debug
@@ -12,4 +24,388 @@ debug-play = Play Sample File
 debug-file-listing = List Files From Dir
 debug-file-play = Open File And Play
 debug-gstreamer-file = Gstreamer Open File And Play
-add-folder = Add Folder
\ No newline at end of file
+inspect-pipeline = Pipeline Inspector
+pipeline-state = State: { $state }
+pipeline-pending = , changing to { $state }
+pipeline-elements = Elements
+pipeline-no-elements = The playback backend has no pipeline to show.
+pipeline-messages = Recent messages
+pipeline-write-graph = Write DOT Graph
+pipeline-graph-written = Graph written to { $path }
+pipeline-refresh = Refresh
+diagnostics = Diagnostics
+diagnostics-menu = Audio Diagnostics…
+diagnostics-signal = Test signal
+diagnostics-description = Plays through the same output as music. Playback is paused while the test runs.
+diagnostics-tone = Tone
+diagnostics-sweep = Sweep
+diagnostics-stop = Stop
+diagnostics-frequency = Frequency
+diagnostics-output = Output
+diagnostics-sink = Sink
+diagnostics-sample-rate = Sample rate
+diagnostics-channels = Channels
+diagnostics-format = Sample format
+diagnostics-buffer = Buffer size
+diagnostics-period = Period size
+diagnostics-latency = Pipeline latency
+diagnostics-underruns = Underruns
+diagnostics-unknown = Unknown
+add-folder = Add Folder
+not-a-local-folder = {$url} is not a local folder.
+not-a-local-file = {$url} is not a local file.
+library-folders-not-saved = Couldn't remember the library folders: {$error}
+file-chooser-failed = Couldn't open the file chooser: {$error}
+add-archive = Add Zip Archive…
+not-an-archive = Only zip archives can be added.
+open-playlist = Open Playlist…
+not-a-playlist = Only M3U and PLS playlists can be opened.
+playlist-failed = Couldn't open the playlist: {$error}
+playlist-empty = None of the tracks in the playlist could be found.
+playlist-missing = { $count ->
+    [one] Skipped 1 track that couldn't be found
+   *[other] Skipped { $count } tracks that couldn't be found
+}
+export = Export
+conversion = Conversion
+conversion-settings = Conversion Settings…
+conversion-preset = Format
+conversion-preset-opus = Opus { $bitrate } kbps
+conversion-preset-mp3 = MP3 { $bitrate } kbps
+conversion-preset-mp3-v0 = MP3 V0
+conversion-preset-flac = FLAC
+conversion-replaygain = Keep ReplayGain tags
+conversion-normalize = Normalize loudness
+library = Library
+organize = Organize Files
+organize-files = Organize Files…
+library-settings = Library Settings…
+library-settings-title = Library Settings
+library-folders = Library folders
+library-folders-description = Music in every folder that's switched on is part of the library.
+library-folders-link = Folders in the library
+open-settings = Open
+settings = Settings
+settings-menu = Settings…
+rescan-library = Rescan Library
+rescan-now = Scan now
+rescan-on-start = Scan on start
+rescan-on-start-description = Otherwise the library stays empty until it's scanned, which saves reading every folder when Jams opens.
+startup = Startup
+default-page = Start on
+default-page-description = The page shown when Jams opens.
+cache = Cache
+cache-folder = Cache folder
+choose-cache-folder = Choose…
+reset-cache-folder = Use default
+sorting = Sorting
+sort-articles = Ignore at the start of artist names
+sort-articles-description = Comma separated, so "The National" sorts under N. Sort names from ARTISTSORT and ALBUMARTISTSORT tags are used as they are.
+display = Display
+key-notation = Key notation
+key-notation-description = Camelot notation numbers keys around the wheel DJs use, so tracks a step apart mix well.
+key-notation-standard = Standard
+key-notation-camelot = Camelot
+tile-size = Album cover size
+tile-size-description = Also changed with Ctrl and the scroll wheel over the album grid.
+scanning = Scanning
+follow-symlinks = Follow symbolic links
+follow-symlinks-description = Scan the files and folders links point to. Folders reached more than once are scanned once. Applies to the next scan.
+merge-hardlinks = Merge linked copies
+merge-hardlinks-description = Add a file reached through several hard or symbolic links as one track. Applies to the next scan.
+title-fallback = Titles of untagged files
+title-fallback-description = How tracks without a title tag are named after their file. Cleaning up drops the extension and a leading track number, as in “03 - Title.flac”. Applies to the next scan.
+title-fallback-file-name = File name
+title-fallback-cleaned = Cleaned up
+title-fallback-title-case = Cleaned up, title case
+tag-writing = Writing tags
+keep-modified-times = Keep modification times
+keep-modified-times-description = Files keep their modification date when Jams writes a cover into them, so other apps don't take them for new music.
+organize-pattern = Folder and file name pattern
+organize-pattern-help = Placeholders: {"{"}album_artist{"}"}, {"{"}artist{"}"}, {"{"}album{"}"}, {"{"}title{"}"}, {"{"}track{"}"}, {"{"}year{"}"}, {"{"}original_year{"}"}
+organize-preview = Preview
+organize-apply = Move Files
+organize-no-changes = Every file is already in place.
+organize-no-folder = Add a library folder to organize first.
+organize-planned = { $count } files will be moved
+organize-more = …and { $count } more
+organize-moved = Moved { $count } files
+organize-failed = { $count } files could not be moved
+choose-cover = Choose Cover…
+paste-cover = Paste Cover
+clipboard-no-image = The clipboard doesn't contain an image file.
+embed-cover = Also embed into files
+embed-missing-artwork = Embed Missing Artwork
+embedding-artwork = Embedding artwork…
+embedding-artwork-progress = Checked { $done } of { $total } files
+artwork-embedded = Artwork embedded
+artwork-embedded-count = Added covers to { $count } files.
+artwork-embed-failed = Skipped { $count } files because of errors:
+track-info = Track Info
+track-title = Title
+track-artist = Artist
+track-album = Album
+track-key = Key
+embedded-pictures = Embedded pictures
+no-embedded-pictures = This file has no embedded pictures.
+use-as-cover = Use as Album Cover
+edit-history = Edit history
+no-edits = Jams hasn't changed the tags in this file.
+edit-added = Added
+edit-removed = Removed
+edit-replaced = Replaced
+revert = Revert
+revert-failed = Couldn't revert the edit: {$error}
+more-like-this = More Like This
+no-similar-tracks = Nothing in the library is much like this track.
+match-same-artist = Same artist
+match-same-genre = Same genre
+match-same-label = Same label
+match-same-decade = Same decade
+match-played-together = Often played together
+picture-front = Front cover
+picture-back = Back cover
+picture-booklet = Booklet
+picture-media = Disc
+picture-artist = Artist
+picture-other = Other
+verify-library = Verify Files
+verify-files = Verify Files
+verifying = Verifying files…
+verifying-progress = Checked { $done } of { $total } files
+verify-finished = Verification finished
+verify-checked = Checked { $count } files, { $damaged } damaged.
+cover-cache = Album covers
+cover-cache-failed = { $count } covers couldn't be read or cached.
+measure-loudness = Measure Loudness
+measuring-loudness = Measuring loudness…
+measuring-loudness-progress = Measured { $done } of { $total } tracks
+detect-tempo = Detect Missing Tempos
+detecting-tempo = Detecting tempos…
+detecting-tempo-progress = Analyzed { $done } of { $total } tracks
+bpm = BPM
+bpm-from = From
+bpm-to = To
+shuffle-bpm-range = Shuffle Range
+group-by-album = Group by album
+loudness-dr = DR{ $dr }
+loudness-lufs = { $lufs } LUFS
+loudness-album-gain = Album gain { $gain } dB
+loudness-silent = Silent
+close = Close
+save = Save
+cancel = Cancel
+discard = Discard
+all-music = All Music
+songs = Songs
+albums = Albums
+artists = Artists
+unknown-artist = Unknown Artist
+labels = Labels
+no-labels = No tracks in the library are tagged with a record label.
+album-versions = { $count } versions
+album-version-original = Original
+discography = Discography
+discography-loading = Looking up on MusicBrainz…
+discography-unknown = MusicBrainz doesn't know this artist.
+discography-retry = Look Up on MusicBrainz
+discography-owned = { $owned } of { $total } in the library
+song-count = { $count ->
+    [one] 1 song
+   *[other] { $count } songs
+}
+track-count = { $count ->
+    [one] 1 track
+   *[other] { $count } tracks
+}
+day-count = { $count ->
+    [one] 1 day
+   *[other] { $count } days
+}
+missing-plugins = Some of your music can't be played.
+missing-plugin = To play { $formats } files, install { $package }.
+all-decades = All
+decade = { $decade }s
+all-languages = All languages
+playback = Playback
+playback-settings = Playback Settings…
+playback-settings-link = Crossfade and end of queue
+playback-settings-link-description = Set in the playback settings, along with the output and filters.
+crossfeed = Headphone crossfeed
+crossfeed-description = Blends a little of each channel into the other so stereo on headphones sounds more like speakers. Applies from the next track.
+crossfeed-off = Off
+crossfeed-light = Light
+crossfeed-medium = Medium
+crossfeed-strong = Strong
+equalizer = Headphone correction
+equalizer-description = Import a ParametricEQ.txt profile from AutoEq to correct your headphones. Applies from the next track.
+equalizer-profile = Using { $name }. Applies from the next track.
+import-equalizer = Import…
+remove-equalizer = Remove
+output-level = Level
+output-level-description = { $gain } dB. Remembered for each output device.
+output-device = Output device
+output-device-description = Headphone correction and level switch with the device.
+output-device-unknown = Unknown
+limiter = Soft limiter
+limiter-description = Keeps loud passages from clipping. Applies from the next track.
+output-buffer = Output buffer
+alarm = Alarm
+alarm-enabled = Wake up to music
+alarm-enabled-description = Starts playing every day at the set time, quietly at first and louder over two minutes. Jams needs to be running.
+alarm-time = Time
+alarm-time-invalid = Enter a time like 07:30.
+alarm-source = Play
+alarm-notification = Good morning
+stop-alarm = Stop
+resume = Long tracks
+resume-length = Resume tracks longer than
+resume-length-description = Mixes and audiobooks pick up where they were left instead of starting over.
+resume-length-off = Off
+resume-length-minutes = { $count } min
+resume-length-hours = { $count } h
+resume-only = Only for
+resume-only-description = Genres or folders, separated by commas. Leave empty for every long track.
+resume-only-placeholder = Audiobook, ~/Music/Mixes
+bluetooth-output = Bluetooth output
+bluetooth-unknown-codec = Unknown codec
+bluetooth-battery = {" · "}Battery { $percent }%
+output-buffer-description = Raise this if playback stutters, for example over Bluetooth. Applies from the next track.
+output-buffer-default = Default
+output-buffer-milliseconds = { $count } ms
+downmix = Surround sound
+downmix-description = Play multichannel files as they are, or mix them down to stereo for headphones and stereo speakers. Applies from the next track.
+downmix-passthrough = Passthrough
+downmix-stereo = Stereo
+downmix-stereo-lfe = Stereo with LFE
+channels-mono = Mono
+channels-stereo = Stereo
+channels-count = { $count } channels
+format-downmixed = { $format } · { $layout } → Stereo
+limiting = Limiting
+network = Network
+network-settings = Network…
+network-mode = Network access
+network-mode-automatic = Automatic
+network-mode-online = Always online
+network-mode-offline = Offline
+network-status = Status
+network-online = Online
+network-offline = Offline
+network-pending = Waiting for a connection
+network-local-only = Only show tracks on this device
+network-local-only-description = Hides tracks that need the network to play, marked with a server icon.
+network-discography = Show discographies from MusicBrainz
+network-discography-description = Artist pages list the artist's albums and EPs, marking the ones missing from the library.
+scrobbling = Scrobbling
+scrobble-url = Submit listens to
+scrobble-url-description = The full submit-listens URL of a ListenBrainz-compatible server, such as Maloja's /apis/listenbrainz/1/submit-listens.
+scrobble-token = Token
+scrobble-waiting = {$count ->
+    [one] 1 listen waiting to be sent
+   *[other] {$count} listens waiting to be sent
+}
+scrobble-send-now = Send now
+scrobble-failed = Couldn't submit listens: {$error}
+listening-history = Listening history
+listening-history-description = Every listen is kept on this device, even without a server. Export it to import into ListenBrainz.
+export-history-button = Export…
+history-exported = {$count ->
+    [one] Wrote 1 listen to {$file}.
+   *[other] Wrote {$count} listens to {$file}.
+}
+history-export-failed = Couldn't export the listening history: {$error}
+hooks = Hooks
+hook-command = Run command
+hook-command-description = Runs with sh when a track starts, pauses, resumes or stops, or is made a favorite. JAMS_EVENT, JAMS_TITLE, JAMS_ARTIST, JAMS_ALBUM and JAMS_PATH describe what happened, and JAMS_PAYLOAD has it all as JSON.
+hook-url = Post to URL
+hook-url-description = Receives the same JSON for every event.
+now-playing-file = Now playing file
+now-playing-file-enabled = Write the playing track to files
+now-playing-file-description = Keeps now-playing.txt and now-playing-cover.jpg in { $folder } up to date, for text and image sources in OBS.
+now-playing-template = Text
+now-playing-template-description = {"{"}title{"}"}, {"{"}artist{"}"}, {"{"}album{"}"} and {"{"}album_artist{"}"} are replaced with the track's tags.
+now-playing-folder = Folder
+open-now-playing-folder = Open
+discord-presence = Show on Discord
+discord-presence-description = Shows the playing track on your Discord profile. Album art appears for tracks tagged with a MusicBrainz release group.
+show-cues = Show DJ cue markers
+show-cues-description = Hot cues saved by Serato DJ appear above the progress bar.
+scroll-titles = Scroll long titles
+scroll-titles-description = In a narrow window, the playing track moves across the header bar when it doesn't fit. Turn off to cut it short instead.
+preview-on-hover = Preview search results
+preview-on-hover-description = Quietly plays ten seconds from a search result while the pointer rests on it.
+reduce-motion = Reduce motion and save power
+reduce-motion-description = Holds scrolling text still and updates the progress bar less often. Automatic follows the desktop's animation setting and power saver mode.
+touch-mode = Touch layout
+touch-mode-description = Taller rows and bigger buttons. Swipe across the playback controls to skip, and hold a track to see its details. Automatic switches over once the screen is touched.
+idle-inhibit-reason = Playing music
+export-queue = Export Queue to Folder…
+export-history = Export Listening History to Folder…
+sync-library-rockbox = Copy Library to Rockbox Player…
+sync-queue-rockbox = Copy Queue to Rockbox Player…
+choose-rockbox = Choose the Player's Root Folder
+sync-library-phone = Copy Library to Android Phone
+sync-queue-phone = Copy Queue to Android Phone
+choose-phone = Choose Where to Copy To
+no-phone = No phone found. Unlock it and choose File transfer when it asks what the USB connection is for.
+device-queue-playlist = Jams Queue
+conversion-export-transcode = Convert files when exporting the queue
+exporting = Exporting…
+exporting-progress = Exported { $done } of { $total } files
+export-finished = Export finished
+exported-count = Exported { $count } files.
+export-failed = { $count } files could not be exported:
+crossfade = Crossfade
+crossfade-description = Albums and tracks tagged as gapless still play back to back.
+crossfade-off = Off
+crossfade-seconds = { $count } s
+end-of-queue = When the queue ends
+end-of-queue-description = Auto-DJ picks tracks close to what just played, by artist and decade.
+end-of-queue-stop = Stop
+end-of-queue-repeat = Repeat queue
+end-of-queue-shuffle = Shuffle library
+end-of-queue-auto-dj = Auto-DJ
+announce-tracks = Announce track changes
+announce-tracks-description = Tells screen readers what is playing, even while Jams is in the background.
+lyrics = Lyrics
+karaoke-mode = Karaoke Mode
+karaoke-exit = Exit Karaoke
+karaoke-no-synced-lyrics = No synced lyrics for this track.
+vocal-reduction = Reduce vocals
+vocal-reduction-description = Cancels sound panned to the center, where vocals usually sit. Applies from the next track.
+lyrics-menu = Lyrics…
+copy-share-text = Copy Share Text
+copy-link = Copy Link
+reread-tags = Re-read Tags from Files
+reread-changed = {$count ->
+    [0] No tags changed.
+    [one] Updated 1 track.
+   *[other] Updated {$count} tracks.
+}
+reread-failed = {$count ->
+    [one] 1 file couldn't be read.
+   *[other] {$count} files couldn't be read.
+}
+jobs = Background Jobs
+jobs-menu = Background Jobs…
+jobs-running = Running
+jobs-none = Nothing is running.
+queue = Up Next
+queue-menu = Up Next…
+queue-empty = Nothing is queued after this track.
+job-scan = Scanning library…
+job-paused = Paused. { $status }
+job-cancelling = Stopping…
+job-cancelled = Stopped before finishing.
+lyrics-nothing-playing = Lyrics of the playing track show up here.
+lyrics-searching = Searching online…
+lyrics-not-found = No lyrics found.
+lyrics-fetch-failed = Lyrics could not be fetched.
+lyrics-edit = Edit
+lyrics-edit-hint = Lines may start with a [mm:ss.xx] time. Saving writes an .lrc file next to the track.
+lyrics-save-failed = Lyrics could not be saved: { $error }
+lyrics-draft = Unsaved Lyrics
+lyrics-draft-body = Lyrics edited for { $track } were never saved.
+now-playing = Now playing: { $title }
+now-playing-by = Now playing: { $title } by { $artist }
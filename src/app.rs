@@ -1,45 +1,165 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use std::cell::RefCell;
+use crate::bluetooth::{self, AudioDevice};
+use crate::config::{Config, LibraryFolder};
+use crate::core::alarm::{self, Alarm, AlarmSource};
+use crate::core::motion::{self, DesktopMotion, Motion, MotionMode};
+use crate::core::discord::{self, NowPlaying};
+use crate::core::gamepad::{self, PadAction};
+use crate::core::touch::{Gesture, Gestures, Touch, TouchMode};
+use crate::core::{announce, flatpak, inhibit, profiling};
+use crate::export::mtp::{self, MtpStorage};
+use crate::export::{
+    self, ConversionJob, DevicePlaylist, DeviceTrack, ExportEntry, ExportProgress, ExportReport,
+    TranscodePreset,
+};
 use crate::fl;
+use crate::hooks::{self, HookEvent, Hooks};
+use crate::marquee;
+use crate::scrobble::{self, Endpoint, Listen};
+use crate::share::{self, ShareLink};
+use crate::track_columns::{self, ColumnWidths, TrackColumn};
 use cosmic::app::{context_drawer, Core, Task};
+use cosmic::cosmic_config;
 use cosmic::iced::alignment::{Horizontal, Vertical};
-use cosmic::iced::{alignment, keyboard, time, Alignment, ContentFit, Length, Subscription};
+use cosmic::iced::futures::SinkExt;
+use cosmic::iced::{
+    alignment, event, keyboard, mouse, time, touch, window, Alignment, Color, ContentFit, Length,
+    Subscription,
+};
 use cosmic::widget::{self, button, icon, image, menu, nav_bar, slider, text, Column, Container, FlexRow, Grid, Row};
 use cosmic::{cosmic_theme, theme, Application, ApplicationExt, Apply, Element};
-use lofty::prelude::{Accessor, TaggedFileExt};
-use lofty::tag::ItemKey;
-use std::collections::HashMap;
-use std::fs;
-use std::path::{Path, PathBuf};
-
-use std::fs::File;
-use std::io::{Read, Write};
-use std::ops::Deref;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
 
-use infer::Infer;
+use std::ops::Deref;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::icon_cache::IconCache;
+use crate::jobs::{JobControl, JobKind};
+use crate::library::archive;
+use crate::library::artwork::{
+    self, CoverReport, CoverSource, EmbedCandidate, EmbedProgress, EmbedReport, EmbeddedPicture,
+};
+use crate::library::collation;
+use crate::library::cues::{self, CueMarker};
+use crate::library::edition;
+use crate::library::journal::{self, Edit, Field};
+use crate::library::loudness::{self, AlbumLoudness, LoudnessProgress};
+use crate::library::lyrics::{self, Lyrics, LyricsDraft, LyricsQuery};
+use crate::library::musical_key::KeyNotation;
+use crate::library::musicbrainz::{self, Discography};
+use crate::library::organize::{self, PlannedMove};
+use crate::library::playlist::{self, LoadedPlaylist};
+use crate::library::scan::{self, build_albums, ScanOptions, ScanProgress, TitleFallback};
+use crate::library::search::{SearchIndex, SearchResults};
+use crate::library::similar::{self, MatchReason};
+use crate::library::tempo::{self, TempoProgress};
+use crate::library::track_number;
+use crate::library::verify::{self, VerifyCandidate, VerifyProgress, VerifyReport};
+use crate::library::watch::LibraryWatcher;
+use crate::library::{Album, MusicFile, TrackSource};
+use crate::network::{self, Connectivity, NetworkMode};
+use crate::now_playing::{self, NowPlayingOutput};
+use crate::virtual_list::{ListScroll, VirtualList};
+use crate::player::diagnostics::{OutputReport, TestSignal, ToneTest};
+use crate::player::plugins::{self, MissingPlugin};
+use crate::player::transport::{self, Transition};
+use crate::player::{
+    self, autodj, resume, Crossfade, Crossfeed, DeviceProfile, Downmix, EndOfQueue, FilterSettings,
+    OutputSettings, ParametricEq, PipelineSnapshot, PlayState, PlaybackState, PlayerBackend,
+    PlayerEvent, Preview, Queue, ResumeRules,
+};
 use cosmic::dialog::file_chooser::{self};
+use cosmic::iced::widget::scrollable::{self, AbsoluteOffset, Viewport};
+use cosmic::iced::widget::text::Wrapping;
+use cosmic::iced::widget::text_editor;
 use cosmic::iced_widget::Scrollable;
+use lofty::picture::PictureType;
+use serde::{Deserialize, Serialize};
 use url::Url;
-use walkdir::WalkDir;
 
-use gstreamer as gst;
 use gstreamer::prelude::*;
-use gstreamer::{glib, ClockTime};
-use gstreamer_play as gst_play;
-use lofty::picture::Picture;
+use gstreamer::glib;
 
 const REPOSITORY: &str = "https://github.com/benfuddled/Jams";
+/// How long typing has to pause before the library is filtered.
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(150);
+/// How long the pointer rests on a search result before a snippet of it plays.
+const PREVIEW_DELAY: Duration = Duration::from_millis(400);
+/// How many planned moves the organizer lists before summarizing the rest.
+const ORGANIZE_PREVIEW_LIMIT: usize = 200;
+/// Height of every row in the virtualized track lists.
+const TRACK_ROW_HEIGHT: f32 = 48.0;
+/// The same in the touch layout, tall enough for a fingertip.
+const TOUCH_ROW_HEIGHT: f32 = 64.0;
+/// Height of the column headings above a track list.
+const TRACK_HEADER_HEIGHT: f32 = 32.0;
+/// Gap between the cells of a track row, which the handles between headings fill.
+const TRACK_CELL_SPACING: f32 = 8.0;
+/// Height of a track row's text cells, one line of body text.
+const TRACK_CELL_HEIGHT: f32 = 20.0;
+/// Roughly how wide a character of body text is, to tell which cells are cut off without
+/// laying the text out.
+const AVERAGE_CHAR_WIDTH: f32 = 7.0;
+/// Padding on either side of libcosmic's icon buttons.
+const ICON_BUTTON_PADDING: f32 = 8.0;
+/// How far apart a gesture's first touch and the press it caused may be, as the two arrive
+/// through different events.
+const PRESS_MATCH: Duration = Duration::from_millis(150);
+/// Height of the album headers in a track list grouped by album.
+const ALBUM_HEADER_HEIGHT: f32 = 72.0;
+/// Size of the cover in an album header.
+const ALBUM_HEADER_COVER: f32 = 56.0;
+/// Range of cover sizes in the album grid, in pixels.
+const TILE_SIZES: std::ops::RangeInclusive<f32> = 120.0..=400.0;
+/// How much one step of Ctrl+scroll grows or shrinks the album grid covers.
+const TILE_ZOOM_STEP: f32 = 20.0;
+/// How many tracks "More like this" queues after the track it starts from.
+const SIMILAR_TRACKS: usize = 25;
+/// How long the cover size has to stay put before the covers are scaled to it.
+const COVER_SCALE_DELAY: Duration = Duration::from_millis(300);
+/// How long the limiter indicator stays lit after the limiter last acted.
+const LIMITER_INDICATOR_HOLD: Duration = Duration::from_secs(1);
+/// How often NetworkManager is asked about connectivity in automatic mode.
+const CONNECTIVITY_POLL: Duration = Duration::from_secs(30);
+/// How often BlueZ is asked about codecs and batteries while the playback page is open.
+const BLUETOOTH_POLL: Duration = Duration::from_secs(15);
+/// How often the default output device is looked up, to notice headphones being plugged in.
+const OUTPUT_DEVICE_POLL: Duration = Duration::from_secs(5);
+/// How often gamepad buttons are picked up while a gamepad is connected.
+const GAMEPAD_POLL: Duration = Duration::from_millis(50);
+/// How often the clock is checked while the alarm is on. Shorter than a minute, so the set
+/// minute isn't missed.
+const ALARM_POLL: Duration = Duration::from_secs(15);
+/// Range of the level slider in dB.
+const GAIN_RANGE: std::ops::RangeInclusive<f64> = -30.0..=6.0;
+/// How often the playback position is updated while playing.
+const POSITION_POLL: Duration = Duration::from_millis(100);
+/// The same with reduced motion, trading a smooth progress bar for fewer wakeups.
+const REDUCED_POSITION_POLL: Duration = Duration::from_millis(500);
+/// How often the desktop is asked about animations and power saving in automatic mode.
+const MOTION_POLL: Duration = Duration::from_secs(60);
+/// How long to wait before sending listens again after a submission failed.
+const SCROBBLE_RETRY: Duration = Duration::from_secs(5 * 60);
+/// Characters of the playing track shown in the header bar of a narrow window.
+const TICKER_WIDTH: usize = 32;
+/// How often the progress of background jobs is redrawn.
+const PROGRESS_REFRESH: Duration = Duration::from_millis(250);
+/// Frames of the spinner in the header bar while a job runs, one per progress refresh.
+const SPINNER: [&str; 4] = ["◐", "◓", "◑", "◒"];
 lazy_static::lazy_static! {
     static ref ICON_CACHE: Mutex<IconCache> = Mutex::new(IconCache::new());
+    /// The scrollable holding the active page's content, moved to where it was left.
+    static ref PAGE_SCROLL: scrollable::Id = scrollable::Id::new("page");
 }
 
 pub fn icon_cache_get(name: &'static str, size: u16) -> widget::icon::Icon {
-    let mut icon_cache = ICON_CACHE.lock().unwrap();
+    // A panic while the cache was held leaves it as usable as before.
+    let mut icon_cache = ICON_CACHE.lock().unwrap_or_else(PoisonError::into_inner);
     icon_cache.get(name, size)
 }
 
@@ -56,66 +176,278 @@ pub struct Jams {
     nav: nav_bar::Model,
     /// A vector that contains the list of scanned files
     scanned_files: Vec<MusicFile>,
+    /// What the track number column shows for each file, keyed by ID.
+    track_labels: HashMap<usize, String>,
+    /// Albums aggregated from `scanned_files`, built on a background thread the first time
+    /// the Albums page is opened, or right away when something needs them sooner.
     albums: Vec<Album>,
-    audio_player: GStreamerPlayer,
-    global_play_state: PlayState,
+    albums_loaded: bool,
+    /// Set while the albums are being built on a background thread.
+    albums_building: bool,
+    /// Goes up whenever tracks are added to or changed in `scanned_files`, so albums built
+    /// from an older library aren't used.
+    library_generation: u64,
+    /// Index into `albums` of the album whose detail view is open.
+    selected_album: Option<usize>,
+    /// Only albums from this decade are shown on the Albums page.
+    album_decade: Option<u16>,
+    /// Only albums with a track in this language are shown on the Albums page.
+    album_language: Option<String>,
+    /// The artist opened from the Artists page.
+    selected_artist: Option<String>,
+    /// The record label opened from the Labels page.
+    selected_label: Option<String>,
+    /// Whether artwork picked by the user is also written into the audio files.
+    embed_custom_covers: bool,
+    /// Whether files keep their modification time when Jams writes their tags.
+    keep_modified_times: bool,
+    /// Albums whose embedded cover hasn't been handed to the art cache yet.
+    pending_covers: Vec<CoverSource>,
+    /// Covers that couldn't be written to the art cache, by album key.
+    memory_covers: HashMap<String, image::Handle>,
+    /// Why covers couldn't be cached, shown with the background jobs.
+    cover_errors: Vec<String>,
+    /// Set while a folder is being scanned. Found files are moved into `scanned_files`
+    /// in batches as the scan goes on.
+    scan_job: Option<Arc<ScanProgress>>,
+    /// Files the last scan skipped because they were still being written, to be scanned
+    /// once they've settled.
+    held_back: Vec<PathBuf>,
+    /// Set once the library has been scanned, from when the library folders are watched
+    /// for changes.
+    watching: bool,
+    /// Set while library files are being organized, when the watcher's reports of them
+    /// disappearing and turning up elsewhere are ignored.
+    organizing: bool,
+    config: Config,
+    /// Writes changes to `config`, unless the settings couldn't be opened.
+    config_handler: Option<cosmic_config::Config>,
+    /// A `jams://` link Jams was started with, opened once the library has loaded.
+    pending_link: Option<ShareLink>,
+    /// A playlist Jams was started with, queued once the library has loaded.
+    pending_playlist: Option<PathBuf>,
+    /// Tracks queued from a playlist that aren't in the library.
+    outside_files: Vec<MusicFile>,
+    scan_options: ScanOptions,
+    /// Errors shown over the window.
+    toasts: widget::toaster::Toasts<Message>,
+    /// Formats in the library GStreamer has no plugin for, shown in a banner until closed.
+    missing_plugins: Vec<MissingPlugin>,
+    /// Progress of the running "embed missing artwork" job.
+    embed_job: Option<Arc<EmbedProgress>>,
+    /// Outcome of the last "embed missing artwork" job, shown until dismissed.
+    embed_report: Option<EmbedReport>,
+    /// Convert files exported to a folder with `conversion_job` instead of copying them.
+    export_transcode: bool,
+    /// Set while the queue is being exported to a folder.
+    export_job: Option<Arc<ExportProgress>>,
+    export_report: Option<ExportReport>,
+    /// Phone storages to pick from, when more than one is mounted.
+    phone_choice: Option<(DeviceSync, Vec<MtpStorage>)>,
+    /// Set while files are being checked by decoding them.
+    verify_job: Option<Arc<VerifyProgress>>,
+    verify_report: Option<VerifyReport>,
+    /// Tracks whose files failed the last check, with what's wrong with them.
+    damaged: HashMap<usize, String>,
+    /// Set while an album's loudness is being measured.
+    loudness_job: Option<Arc<LoudnessProgress>>,
+    /// Measured albums by album key, filled from the cache as albums are opened.
+    loudness: HashMap<String, AlbumLoudness>,
+    /// Set while tempos are being detected for files without a `BPM` tag.
+    tempo_job: Option<Arc<TempoProgress>>,
+    /// Tempos measured for files without a `BPM` tag, by path.
+    tempos: HashMap<PathBuf, u16>,
+    /// What's typed into the BPM range on the All Music page, and the bounds read from it.
+    tempo_from: String,
+    tempo_to: String,
+    tempo_range: (Option<u16>, Option<u16>),
+    /// How musical keys are written in the track list and under the player.
+    key_notation: KeyNotation,
+    /// Size of the covers in the album grid.
+    tile_size: f32,
+    /// Physical pixels per logical pixel on the window's screen.
+    scale_factor: f32,
+    /// Album covers scaled to `scaled_size` physical pixels, by album key.
+    scaled_covers: HashMap<String, image::Handle>,
+    scaled_size: u32,
+    /// Modifier keys held down, so Ctrl+scroll can zoom the album grid.
+    modifiers: keyboard::Modifiers,
+    audio_player: Box<dyn PlayerBackend>,
+    /// What plays next when the current track ends or is skipped.
+    queue: Queue,
+    /// Where in the queue the track being dragged in the queue sidebar is.
+    queue_drag: Option<usize>,
+    /// Why each track of a "More like this" queue was picked, by track ID. Emptied when
+    /// the queue is replaced.
+    similar_reasons: HashMap<usize, Vec<MatchReason>>,
+    /// The loaded track and whether it plays. Track rows show their state from this.
+    playback: PlaybackState,
     current_track_duration: Duration,
     seek_position: Duration,
-    last_tick: Instant,
+    /// Set from a scrub until the backend reports the seek is done.
+    seeking: bool,
+    filter_settings: FilterSettings,
+    output_settings: OutputSettings,
+    /// Paths of the tracks marked as favorites.
+    favorites: HashSet<PathBuf>,
+    /// Bluetooth devices audio is streamed to, refreshed while the playback page is open.
+    bluetooth_devices: Vec<AudioDevice>,
+    /// The device audio goes to, once the sound server has said.
+    output_device: Option<String>,
+    /// EQ and level last used with each output device, by device name.
+    device_profiles: BTreeMap<String, DeviceProfile>,
+    /// How long the end of a track overlaps the next one, zero for no overlap.
+    crossfade_length: Duration,
+    crossfade: Option<Crossfade>,
+    /// The track handed to the player to follow the current one without a gap.
+    gapless_next: Option<usize>,
+    /// Which long tracks pick up where they were left.
+    resume_rules: ResumeRules,
+    /// Where those tracks were left, by file.
+    resume_positions: HashMap<PathBuf, Duration>,
+    /// When the position of the playing track was last saved.
+    resume_saved_at: Instant,
+    /// Play a snippet of search results while the pointer rests on them.
+    preview_on_hover: bool,
+    /// The search result under the pointer.
+    hovered_track: Option<usize>,
+    preview: Option<Preview>,
+    /// What plays once the queue runs out.
+    end_of_queue: EndOfQueue,
+    /// Announce track changes for screen readers. Switched on when one is detected.
+    announce_tracks: bool,
+    /// The track last announced, so each change is announced once.
+    announced_track: Option<usize>,
+    /// Notification ID of the last announcement, replaced by the next one.
+    announcement_id: u32,
+    network: Connectivity,
+    /// Whether to hold text still and poll less, to save battery.
+    motion: Motion,
+    /// Whether rows and buttons are sized for fingers.
+    touch: Touch,
+    gestures: Gestures,
+    /// When the playback controls were last pressed, to tell a swipe across them.
+    controls_pressed: Option<Instant>,
+    /// The track row last pressed and when, to tell a long press on it.
+    track_pressed: Option<(usize, Instant)>,
+    /// Hide tracks that would have to be streamed.
+    local_only: bool,
+    /// Show what MusicBrainz lists for an artist on their page.
+    discography_lookup: bool,
+    /// Discographies looked up this session, by artist as tagged. `None` when MusicBrainz
+    /// doesn't know the artist.
+    discographies: HashMap<String, Option<Discography>>,
+    /// The artist whose discography is being looked up.
+    discography_loading: Option<String>,
+    discography_error: Option<String>,
+    /// Network work started while offline, run once the connection is back.
+    pending_network_tasks: Vec<Task<Message>>,
+    /// Where listens are submitted.
+    scrobble_endpoint: Endpoint,
+    /// Told when tracks start, pause or stop and when favorites change.
+    hooks: Hooks,
+    /// Show the playing track on the listener's Discord profile.
+    discord_presence: bool,
+    /// Files streaming software reads the playing track from.
+    now_playing_output: NowPlayingOutput,
+    alarm: Alarm,
+    /// The alarm time as typed, kept apart so half-typed times aren't thrown away.
+    alarm_time: String,
+    /// The day the alarm last went off, as year and day of the year.
+    alarm_rang: Option<(i32, i32)>,
+    /// When the alarm started playing, while its volume is still rising.
+    alarm_ramp: Option<Instant>,
+    /// Listens not submitted yet, oldest first. Kept on disk until they're sent.
+    scrobble_queue: Vec<Listen>,
+    /// A submission is on its way, so the queue isn't sent twice.
+    scrobble_sending: bool,
+    /// Why the last submission failed. Cleared once one gets through.
+    scrobble_error: Option<String>,
+    /// How the last export of the listening history went.
+    history_status: Option<String>,
+    /// The track whose listening time is being counted.
+    listen_track: Option<usize>,
+    /// When that track started playing.
+    listen_started: SystemTime,
+    /// How long that track has actually played, leaving out seeks and pauses.
+    listened: Duration,
+    /// That track was already queued as a listen.
+    listen_recorded: bool,
+    /// When the limiter last had to act, for the indicator next to the scrubber.
+    limiting_at: Option<Instant>,
+    /// Shown in the pipeline inspector until it's closed.
+    pipeline_snapshot: Option<PipelineSnapshot>,
+    /// Where the last pipeline graph went, or why it couldn't be written.
+    pipeline_graph: Option<Result<PathBuf, String>>,
+    /// Test signal playing from the diagnostics page.
+    tone_test: Option<ToneTest>,
+    /// What the output reported during the current or last test.
+    output_report: Option<OutputReport>,
+    /// Full-screen lyrics for singing along, in place of the library.
+    karaoke: bool,
+    /// Lyrics of the current track, once found.
+    lyrics: Option<Lyrics>,
+    /// The track `lyrics` belong to, or are being looked up for.
+    lyrics_track: Option<usize>,
+    /// Where the lyrics lookup stands when nothing can be shown yet, or why saving failed.
+    lyrics_status: Option<String>,
+    /// Set while the lyrics are being corrected by hand.
+    lyrics_editor: Option<text_editor::Content>,
+    /// Unsaved lyrics left over from the last session, or from a track change mid-edit,
+    /// waiting to be saved or thrown away.
+    lyrics_draft: Option<LyricsDraft>,
+    /// Why saving [`Self::lyrics_draft`] failed.
+    lyrics_draft_error: Option<String>,
+    /// Hot cues of the current track, drawn above the scrubber.
+    current_cues: Vec<CueMarker>,
+    show_cues: bool,
+    /// Scroll the track shown in the header bar of a narrow window when it doesn't fit,
+    /// rather than cutting it short.
+    scroll_titles: bool,
     scrub_value: u8,
     search_expanded: bool,
     search_term: String,
-}
-
-pub struct GStreamerPlayer {
-    /// The sink responsible for managing the audio playback.
-    player: gst_play::Play,
-    /// Store content for rewind/replay
-    content: Vec<u8>,
-}
-
-#[derive(Debug, Clone, Eq, Ord, PartialEq, PartialOrd)]
-pub struct MusicFile {
-    album_artist: String,
-    album: String,
-    track_number: u16,
-    artist: String,
-    track_title: String,
-    duration: Duration,
-    date: String,
-    saved_path: PathBuf,
-    uri: String,
-    playing: bool,
-    paused: bool,
-    id: usize,
-}
-
-#[derive(Debug, Clone)]
-pub struct Album {
-    album_artist: String,
-    album: String,
-    cached_cover_path: String,
-    tracks: Vec<usize>, // TODO: refactor to use arc
-}
-
-// TODO: MAKE THESE SOME()
-impl Default for MusicFile {
-    fn default() -> Self {
-        MusicFile {
-            saved_path: PathBuf::new(),
-            uri: "/uri-does-not-exist".to_string(),
-            playing: false,
-            paused: false,
-            track_title: "Invalid Title".to_string(),
-            track_number: 0,
-            duration: Duration::new(0, 0),
-            artist: "Invalid Artist".to_string(),
-            album: "Invalid Album".to_string(),
-            album_artist: "Invalid Album Artist".to_string(),
-            date: "Invalid Date".to_string(),
-            id: 0,
-        }
-    }
+    /// Lowercased search data, rebuilt whenever the library changes.
+    search_index: Arc<SearchIndex>,
+    /// Results of the last finished search, or `None` when nothing is being filtered.
+    search_results: Option<Arc<SearchResults>>,
+    /// Bumped on every search input so stale debounce timers and results can be dropped.
+    search_generation: u64,
+    /// What [`Self::song_files`] lists, as indices into `scanned_files`. Made on the first
+    /// redraw that needs it and dropped whenever the library or what's visible changes.
+    song_list: RefCell<Option<Vec<usize>>>,
+    /// Scroll position of the track list on the active page.
+    list_scroll: ListScroll,
+    /// The track the arrow keys or the d-pad moved to, by ID.
+    cursor: Option<usize>,
+    /// Where each page, album and artist was scrolled to when it was last shown.
+    scroll_offsets: HashMap<ScrollSpot, f32>,
+    /// Set while the boundary between two column headings is being dragged.
+    column_drag: Option<ColumnDrag>,
+    /// Cluster the tracks on All Music and Songs under a header for each album.
+    group_by_album: bool,
+    /// Albums whose tracks are hidden under their header, by [`artwork::album_key`].
+    collapsed_albums: HashSet<String>,
+    /// Format and loudness options used when converting tracks for export.
+    conversion_job: ConversionJob,
+    /// Layout the organizer moves files into.
+    organize_pattern: String,
+    /// Articles ignored at the start of artist names when sorting, comma separated.
+    sort_articles: String,
+    /// Moves from the last organizer preview, applied once the user confirms.
+    organize_plan: Vec<PlannedMove>,
+    /// Outcome of the last organizer preview or run.
+    organize_status: Option<String>,
+    /// Outcome of the last time tags were re-read from files.
+    reread_status: Option<String>,
+    /// The track shown in the track info panel.
+    track_info: Option<usize>,
+    /// Pictures embedded in that track's file, ready to draw.
+    track_pictures: Vec<(EmbeddedPicture, image::Handle)>,
+    /// Why the track's pictures couldn't be read.
+    track_pictures_error: Option<String>,
+    /// Changes Jams made to that track's tags, newest first.
+    track_edits: Vec<Edit>,
 }
 
 /// This is the enum that contains all the possible variants that your application will need to transmit messages.
@@ -128,40 +460,324 @@ pub enum Message {
     Cancelled,
     CloseError,
     Error(String),
+    CloseToast(widget::ToastId),
+    PluginsChecked(Vec<MissingPlugin>),
     FileRead(Url, String),
     OpenError(Arc<file_chooser::Error>),
     AddFolder,
     AddSongsToLibrary(Url),
+    /// Switches the library folder at this index on or off.
+    ToggleLibraryFolder(usize, bool),
+    RemoveLibraryFolder(usize),
+    /// Asks for a zip archive to add the albums in it to the library.
+    AddArchive,
+    ScanArchive(Url),
+    /// Asks for an M3U or PLS playlist to play.
+    OpenPlaylist,
+    /// Replaces the queue with the playlist at this path.
+    LoadPlaylist(PathBuf),
+    PlaylistLoaded(Arc<Result<LoadedPlaylist, String>>),
+    /// A file was dropped onto the window.
+    FileDropped(PathBuf),
     StartPlayingNewTrack(String),
+    /// Plays an album from the given track to its end.
+    PlayAlbumFrom(usize, usize),
     PauseCurrentTrack,
     ResumeCurrentTrack,
+    TogglePlayPause,
     WatchTick(Instant),
+    /// The pipeline reached the end of the current track.
+    TrackEnded,
     Scrub(u8),
     SkipNext,
     SkipPrev,
     SearchExpand,
     SearchInput(String),
-    DebugStub,
+    SearchDebounced(u64),
+    SearchResults(u64, Arc<SearchResults>),
+    ConversionPreset(usize),
+    ConversionReplayGain(bool),
+    ConversionNormalize(bool),
+    SetLimiter(bool),
+    SetVocalReduction(bool),
+    SetCrossfeed(usize),
+    /// Asks for an AutoEq `ParametricEQ.txt` to correct headphones with.
+    ImportEqualizer,
+    EqualizerImported(Result<ParametricEq, String>),
+    RemoveEqualizer,
+    /// Sets the level in dB.
+    SetGain(f64),
+    CheckOutputDevice,
+    OutputDeviceDetected(Option<String>),
+    Karaoke(bool),
+    AnnounceTracks(bool),
+    ScreenReaderDetected(bool),
+    SetMotionMode(usize),
+    CheckMotion,
+    MotionDetected(DesktopMotion),
+    SetTouchMode(usize),
+    Touch(touch::Event),
+    ControlsPressed,
+    TrackPressed(usize),
+    Announced(Option<u32>),
+    SetCrossfade(usize),
+    SetEndOfQueue(usize),
+    /// Index into [`resume::MIN_LENGTHS`].
+    SetResumeLength(usize),
+    ResumeOnly(String),
+    SetOutputBuffer(usize),
+    SetDownmix(usize),
+    ListScrolled(Viewport),
+    /// The boundary after this column was grabbed.
+    StartColumnDrag(TrackColumn),
+    /// The pointer moved over the column headings while dragging, to this x position in
+    /// headings this wide.
+    DragColumn(f32, f32),
+    EndColumnDrag,
+    GroupByAlbum(bool),
+    SetTileSize(f32),
+    /// The window moved to a screen with another scale factor, or the scale changed.
+    Rescaled(f32),
+    /// Scales the album covers to this many pixels, unless the grid was resized since.
+    ScaleCovers(u32),
+    CoversScaled(u32, Arc<Vec<(String, PathBuf)>>),
+    ModifiersChanged(keyboard::Modifiers),
+    /// Moves the cursor this many tracks down the list, or up for negative steps.
+    MoveCursor(isize),
+    PlayCursor,
+    /// Closes whatever was opened last: the context drawer, an album or an artist.
+    GoBack,
+    /// Moves this many pages down the navigation bar, or up for negative steps.
+    SwitchPage(isize),
+    PollGamepad,
+    /// Scrolling over the album grid that the grid itself didn't take, as with Ctrl held.
+    ZoomAlbumGrid(mouse::ScrollDelta),
+    /// Collapses or expands an album in the grouped list, by [`artwork::album_key`].
+    ToggleAlbumGroup(String),
+    SeekToCue(Duration),
+    ShowCues(bool),
+    ScrollTitles(bool),
+    PreviewOnHover(bool),
+    /// The pointer moved onto a search result.
+    HoverTrack(usize),
+    /// The pointer left a search result.
+    UnhoverTrack(usize),
+    StartPreview(usize),
+    PreviewTick,
+    SetNetworkMode(usize),
+    LocalOnly(bool),
+    DiscographyLookup(bool),
+    FetchDiscography(String),
+    DiscographyFetched(String, Result<Option<Discography>, String>),
+    CheckConnectivity,
+    ScrobbleUrl(String),
+    ScrobbleToken(String),
+    HookCommand(String),
+    HookUrl(String),
+    DiscordPresence(bool),
+    NowPlayingFile(bool),
+    AlarmEnabled(bool),
+    AlarmTime(String),
+    SetAlarmSource(usize),
+    CheckAlarm,
+    StopAlarm,
+    NowPlayingTemplate(String),
+    OpenNowPlayingFolder,
+    SubmitScrobbles,
+    /// How many listens from the front of the queue were sent, and how it went.
+    ScrobblesSubmitted(usize, Result<(), String>),
+    ToggleFavorite,
+    /// Picks up the track at this index of the queue, to drop it somewhere else.
+    DragQueueTrack(usize),
+    /// Drops the dragged track at this index of the queue.
+    DropQueueTrack(usize),
+    CancelQueueDrag,
+    RemoveFromQueue(usize),
+    /// Queues tracks like this one after it and plays it.
+    MoreLikeThis(usize),
+    SimilarHistoryRead(usize, Arc<Vec<Listen>>),
+    LyricsLoaded(usize, Option<String>),
+    LyricsFetched(usize, Result<Option<String>, String>),
+    EditLyrics,
+    LyricsEdit(text_editor::Action),
+    SaveLyrics,
+    CancelLyricsEdit,
+    SaveLyricsDraft,
+    PauseJob(JobKind, bool),
+    CancelJob(JobKind),
+    DiscardLyricsDraft,
+    CheckBluetooth,
+    BluetoothDevices(Option<Vec<AudioDevice>>),
+    ConnectivityDetected(Option<bool>),
+    OrganizePattern(String),
+    SortArticles(String),
+    SetKeyNotation(usize),
+    FollowSymlinks(bool),
+    MergeHardlinks(bool),
+    SetTitleFallback(usize),
+    OrganizePreview,
+    OrganizeApply,
+    OrganizeFinished(Arc<(Vec<PlannedMove>, Vec<String>)>),
+    OpenAlbum(usize),
+    CloseAlbum,
+    /// Reads the tags of these tracks again, for edits made outside of Jams.
+    RereadTags(Vec<usize>),
+    /// Each track with what its file reads as now, or `None` if it couldn't be read.
+    TagsReread(Arc<Vec<(usize, Option<MusicFile>)>>),
+    /// Opens the track info panel and reads the pictures embedded in the track.
+    ShowTrackInfo(usize),
+    TrackPictures(usize, Arc<Result<Vec<EmbeddedPicture>, String>>),
+    TrackEdits(usize, Arc<Vec<Edit>>),
+    /// Undoes the edit at this index of `track_edits`.
+    RevertEdit(usize),
+    EditReverted(usize, Result<(), String>),
+    /// Makes the picture at this index of `track_pictures` the cover of its album.
+    UsePicture(usize),
+    /// The album key, and the chosen picture to show until the cache is read again.
+    PictureChosen(String, image::Handle, Result<PathBuf, String>),
+    FilterDecade(Option<u16>),
+    FilterLanguage(Option<String>),
+    OpenArtist(String),
+    CloseArtist,
+    OpenLabel(String),
+    CloseLabel,
+    ChooseCover(ArtworkTarget),
+    PasteCover(ArtworkTarget),
+    /// Puts share text or a `jams://` link on the clipboard.
+    CopyText(String),
+    CoverPasted(ArtworkTarget, Option<String>),
+    SetCover(ArtworkTarget, PathBuf),
+    CoverSet(ArtworkTarget, Result<PathBuf, String>),
+    CoversCached(Arc<CoverReport>),
+    /// Albums built on a background thread from the library as it was at a generation.
+    AlbumsBuilt(u64, Arc<(Vec<Album>, Vec<CoverSource>)>),
+    EmbedCovers(bool),
+    KeepModifiedTimes(bool),
+    EmbedMissingArtwork,
+    ExportQueue,
+    ExportQueueTo(PathBuf),
+    /// Asks for a folder to write the listening history to, for ListenBrainz to import.
+    ExportHistory,
+    ExportHistoryTo(PathBuf),
+    HistoryExported(Result<usize, String>),
+    /// Asks for the mount point of a Rockbox player to copy tracks onto.
+    SyncToRockbox(DeviceSync),
+    SyncToRockboxAt(DeviceSync, PathBuf),
+    /// Looks for phones mounted over MTP to sync to.
+    SyncToPhone(DeviceSync),
+    PhonesFound(DeviceSync, Vec<MtpStorage>),
+    SyncToPhoneAt(DeviceSync, PathBuf),
+    ClosePhoneChoice,
+    ExportFinished(ExportReport),
+    CloseExportReport,
+    ExportTranscode(bool),
+    EmbedMissingArtworkFinished(EmbedReport),
+    CloseEmbedReport,
+    VerifyLibrary,
+    /// Decodes the files of these tracks in full to find damaged ones.
+    VerifyFiles(Vec<usize>),
+    VerifyFinished(VerifyReport),
+    /// Measures the loudness and dynamic range of an album, by index into `albums`.
+    MeasureLoudness(usize),
+    /// The album key and what was measured.
+    LoudnessMeasured(String, Result<AlbumLoudness, String>),
+    /// Measures the tempo of every local track without a `BPM` tag.
+    DetectTempo,
+    TempoDetected(Vec<(PathBuf, u16)>),
+    TempoFrom(String),
+    TempoTo(String),
+    /// Plays the tracks in the BPM range in random order.
+    ShuffleTempo,
+    RefreshProgress,
+    ScanFinished,
+    /// Scans the files held back because they were still changing.
+    RescanHeldBack,
+    /// Files or folders under the library folder were added, changed or removed.
+    LibraryFilesChanged(Vec<PathBuf>),
+    /// Library tracks whose files changed, with what they read as now, or `None` if they
+    /// no longer can be.
+    WatchedFilesReread(Arc<Vec<(usize, Option<MusicFile>)>>),
+    WritePipelineGraph,
+    ClosePipelineInspector,
+    StartToneTest(TestSignal),
+    StopToneTest,
+    ToneTestTick,
+    InspectPipeline,
     SearchMinimize,
-    SaveLibraryLocation,
-    ResetLibraryLocation,
-    ReOpenLibraryLocation,
+    /// Scans every enabled library folder again.
+    RescanLibrary,
+    RescanOnStart(bool),
+    SetDefaultPage(usize),
+    ChooseCacheFolder,
+    /// A folder to keep the caches in, or `None` for the default one.
+    SetCacheFolder(Option<PathBuf>),
+}
+
+/// What a piece of custom artwork is being assigned to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArtworkTarget {
+    /// An index into `Jams::albums`.
+    Album(usize),
+    /// A track ID.
+    Track(usize),
 }
 
 /// Identifies a page in the application.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Page {
-    Page1,
-    Page2,
-    Page3,
-    Page4,
+    #[default]
+    AllMusic,
+    Songs,
+    Albums,
+    Artists,
+    Labels,
 }
 
-#[derive(Default)]
-pub enum PlayState {
-    #[default]
-    Idle,
-    Paused,
-    Playing,
+impl Page {
+    pub const ALL: [Page; 5] = [
+        Page::AllMusic,
+        Page::Songs,
+        Page::Albums,
+        Page::Artists,
+        Page::Labels,
+    ];
+
+    /// Labels for a dropdown, in the same order as [`Page::ALL`].
+    pub fn names() -> &'static [String] {
+        static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+        NAMES.get_or_init(|| {
+            vec![
+                fl!("all-music"),
+                fl!("songs"),
+                fl!("albums"),
+                fl!("artists"),
+                fl!("labels"),
+            ]
+        })
+    }
+}
+
+/// A boundary between column headings being dragged.
+#[derive(Debug, Clone, Copy)]
+struct ColumnDrag {
+    /// The column left of the boundary.
+    column: TrackColumn,
+    /// Where the pointer was first seen, once it has moved.
+    start_x: Option<f32>,
+    /// The widths when the drag started.
+    start: ColumnWidths,
+    /// The widths shown while dragging, saved once the boundary is let go.
+    widths: ColumnWidths,
+}
+
+/// A view whose scroll position is remembered: a page, or an album or artist opened on it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ScrollSpot {
+    Page(Page),
+    /// By [`artwork::album_key`].
+    Album(String),
+    Artist(String),
+    Label(String),
 }
 
 /// Identifies a context page to display in the context drawer.
@@ -169,23 +785,71 @@ pub enum PlayState {
 pub enum ContextPage {
     #[default]
     About,
+    Settings,
+    Conversion,
+    Organize,
+    Library,
+    Playback,
+    Network,
+    Diagnostics,
+    Lyrics,
+    Jobs,
+    TrackInfo,
+    Queue,
 }
 
 impl ContextPage {
     fn title(&self) -> String {
         match self {
             Self::About => fl!("about"),
+            Self::Settings => fl!("settings"),
+            Self::Conversion => fl!("conversion"),
+            Self::Organize => fl!("organize"),
+            Self::Library => fl!("library-settings-title"),
+            Self::Playback => fl!("playback"),
+            Self::Network => fl!("network"),
+            Self::Diagnostics => fl!("diagnostics"),
+            Self::Lyrics => fl!("lyrics"),
+            Self::Jobs => fl!("jobs"),
+            Self::TrackInfo => fl!("track-info"),
+            Self::Queue => fl!("queue"),
         }
     }
 }
 
+/// What goes onto a portable player. The queue is written as a playlist either way.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeviceSync {
+    Library,
+    Queue,
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MenuAction {
     About,
-    DebugStub,
-    SaveLibraryLocation,
-    ResetLibraryLocation,
-    ReOpenLibraryLocation,
+    Settings,
+    ConversionSettings,
+    ExportQueue,
+    ExportHistory,
+    SyncToRockbox(DeviceSync),
+    SyncToPhone(DeviceSync),
+    PlaybackSettings,
+    Karaoke,
+    EndOfQueue(EndOfQueue),
+    NetworkSettings,
+    Lyrics,
+    Jobs,
+    Queue,
+    AddArchive,
+    OpenPlaylist,
+    OrganizeFiles,
+    LibrarySettings,
+    EmbedMissingArtwork,
+    VerifyLibrary,
+    DetectTempo,
+    Diagnostics,
+    InspectPipeline,
+    RescanLibrary,
 }
 
 impl menu::action::MenuAction for MenuAction {
@@ -194,12 +858,35 @@ impl menu::action::MenuAction for MenuAction {
     fn message(&self) -> Self::Message {
         match self {
             MenuAction::About => Message::ToggleContextPage(ContextPage::About),
-            MenuAction::DebugStub => Message::DebugStub,
-            MenuAction::SaveLibraryLocation => Message::SaveLibraryLocation,
-            MenuAction::ResetLibraryLocation => Message::ResetLibraryLocation,
-            MenuAction::ReOpenLibraryLocation => Message::ReOpenLibraryLocation,
-            }
-
+            MenuAction::Settings => Message::ToggleContextPage(ContextPage::Settings),
+            MenuAction::ConversionSettings => Message::ToggleContextPage(ContextPage::Conversion),
+            MenuAction::AddArchive => Message::AddArchive,
+            MenuAction::OpenPlaylist => Message::OpenPlaylist,
+            MenuAction::OrganizeFiles => Message::ToggleContextPage(ContextPage::Organize),
+            MenuAction::LibrarySettings => Message::ToggleContextPage(ContextPage::Library),
+            MenuAction::PlaybackSettings => Message::ToggleContextPage(ContextPage::Playback),
+            MenuAction::Karaoke => Message::Karaoke(true),
+            MenuAction::EndOfQueue(action) => Message::SetEndOfQueue(
+                EndOfQueue::ALL
+                    .iter()
+                    .position(|candidate| *candidate == *action)
+                    .unwrap_or_default(),
+            ),
+            MenuAction::NetworkSettings => Message::ToggleContextPage(ContextPage::Network),
+            MenuAction::Lyrics => Message::ToggleContextPage(ContextPage::Lyrics),
+            MenuAction::Jobs => Message::ToggleContextPage(ContextPage::Jobs),
+            MenuAction::Queue => Message::ToggleContextPage(ContextPage::Queue),
+            MenuAction::EmbedMissingArtwork => Message::EmbedMissingArtwork,
+            MenuAction::VerifyLibrary => Message::VerifyLibrary,
+            MenuAction::DetectTempo => Message::DetectTempo,
+            MenuAction::ExportQueue => Message::ExportQueue,
+            MenuAction::ExportHistory => Message::ExportHistory,
+            MenuAction::SyncToRockbox(sync) => Message::SyncToRockbox(sync),
+            MenuAction::SyncToPhone(sync) => Message::SyncToPhone(sync),
+            MenuAction::Diagnostics => Message::ToggleContextPage(ContextPage::Diagnostics),
+            MenuAction::InspectPipeline => Message::InspectPipeline,
+            MenuAction::RescanLibrary => Message::RescanLibrary,
+        }
     }
 }
 
@@ -244,68 +931,269 @@ impl Application for Jams {
         let mut nav = nav_bar::Model::default();
 
         nav.insert()
-            .text("All Music")
-            .data::<Page>(Page::Page1)
+            .text(fl!("all-music"))
+            .data::<Page>(Page::AllMusic)
             .icon(icon_cache_get("music-note-symbolic", 16))
             .activate();
 
         nav.insert()
-            .text("Songs")
-            .data::<Page>(Page::Page2)
+            .text(fl!("songs"))
+            .data::<Page>(Page::Songs)
             .icon(icon_cache_get("music-note-single-symbolic", 16));
 
         nav.insert()
-            .text("Albums")
-            .data::<Page>(Page::Page3)
+            .text(fl!("albums"))
+            .data::<Page>(Page::Albums)
             .icon(icon_cache_get("library-music-symbolic", 16));
 
         nav.insert()
-            .text("Artists")
-            .data::<Page>(Page::Page4)
+            .text(fl!("artists"))
+            .data::<Page>(Page::Artists)
             .icon(icon_cache_get("music-artist-symbolic", 16));
 
-        let mut scanned_files = vec![];
-        let mut albums = vec![];
-
-        match get_loc_from_config() {
-            Ok(url) => {
-                get_all_files(url, &mut albums, &mut scanned_files);
-            }
-            Err(err_msg) => {
-                println!("{}", err_msg);
-            }
+        nav.insert()
+            .text(fl!("labels"))
+            .data::<Page>(Page::Labels)
+            .icon(icon_cache_get("media-optical-symbolic", 16));
+
+        let (config_handler, config) = Config::load(Self::APP_ID);
+        artwork::set_cache_folder(config.cache_folder.clone());
+
+        let start_page = nav
+            .iter()
+            .find(|id| nav.data::<Page>(*id) == Some(&config.default_page));
+        if let Some(id) = start_page {
+            nav.activate(id);
         }
 
-        gst::init().expect("Could not initialize GStreamer.");
+        // Before anything is scanned, so the sort keys are made with them.
+        collation::set_articles(&config.sort_articles);
 
-        let play = gst_play::Play::new(None::<gst_play::PlayVideoRenderer>);
-        let gst_content = Vec::new();
+        let tempos = tempo::read_cache();
 
-        let audio_player = GStreamerPlayer {
-            player: play,
-            content: gst_content,
+        let output_settings = OutputSettings {
+            buffer: config.output_buffer,
+            downmix: config.downmix,
         };
+        let mut audio_player = player::backend_from_env();
+        audio_player.set_filters(config.filter_settings.clone());
+        audio_player.set_output(output_settings);
 
-        let global_play_state: PlayState = PlayState::default();
+        let alarm = config.alarm;
 
         let mut app = Jams {
             core,
             context_page: ContextPage::default(),
             key_binds: HashMap::new(),
             nav,
-            scanned_files,
-            albums,
+            track_labels: HashMap::new(),
+            scanned_files: Vec::new(),
+            albums: Vec::new(),
+            albums_loaded: false,
+            albums_building: false,
+            library_generation: 0,
+            selected_album: None,
+            album_decade: None,
+            album_language: None,
+            selected_artist: None,
+            selected_label: None,
+            embed_custom_covers: false,
+            keep_modified_times: false,
+            pending_covers: Vec::new(),
+            memory_covers: HashMap::new(),
+            cover_errors: Vec::new(),
+            scan_job: None,
+            embed_job: None,
+            embed_report: None,
+            export_transcode: false,
+            export_job: None,
+            export_report: None,
+            phone_choice: None,
+            verify_job: None,
+            verify_report: None,
+            damaged: HashMap::new(),
+            loudness_job: None,
+            loudness: HashMap::new(),
+            tempo_job: None,
+            tempos,
+            tempo_from: String::new(),
+            tempo_to: String::new(),
+            tempo_range: (None, None),
+            key_notation: config.key_notation,
+            tile_size: config.tile_size,
+            scale_factor: 1.0,
+            scaled_covers: HashMap::new(),
+            scaled_size: 0,
+            modifiers: keyboard::Modifiers::default(),
             audio_player,
-            global_play_state,
+            queue: Queue::default(),
+            queue_drag: None,
+            similar_reasons: HashMap::new(),
+            playback: PlaybackState::default(),
             scrub_value: 50,
             current_track_duration: Duration::default(),
             seek_position: Duration::default(),
-            last_tick: Instant::now(),
+            seeking: false,
+            filter_settings: config.filter_settings.clone(),
+            output_settings,
+            bluetooth_devices: Vec::new(),
+            output_device: None,
+            device_profiles: config.device_profiles.clone(),
+            favorites: config.favorites.iter().cloned().collect(),
+            crossfade_length: config.crossfade_length,
+            crossfade: None,
+            gapless_next: None,
+            resume_rules: config.resume_rules.clone(),
+            resume_positions: resume::read_positions(),
+            resume_saved_at: Instant::now(),
+            preview_on_hover: config.preview_on_hover,
+            hovered_track: None,
+            preview: None,
+            end_of_queue: config.end_of_queue,
+            announce_tracks: config.announce_tracks,
+            announced_track: None,
+            announcement_id: 0,
+            network: Connectivity {
+                mode: config.network_mode,
+                ..Connectivity::default()
+            },
+            motion: Motion {
+                mode: config.motion_mode,
+                ..Motion::default()
+            },
+            touch: Touch {
+                mode: config.touch_mode,
+                ..Touch::default()
+            },
+            gestures: Gestures::default(),
+            controls_pressed: None,
+            track_pressed: None,
+            local_only: config.local_only,
+            discography_lookup: false,
+            discographies: HashMap::new(),
+            discography_loading: None,
+            discography_error: None,
+            pending_network_tasks: Vec::new(),
+            scrobble_endpoint: scrobble::read_endpoint(),
+            hooks: config.hooks.clone(),
+            discord_presence: false,
+            now_playing_output: config.now_playing_output.clone(),
+            alarm,
+            alarm_time: alarm.time(),
+            alarm_rang: None,
+            alarm_ramp: None,
+            scrobble_queue: scrobble::read_queue(),
+            scrobble_sending: false,
+            scrobble_error: None,
+            history_status: None,
+            listen_track: None,
+            listen_started: SystemTime::now(),
+            listened: Duration::ZERO,
+            listen_recorded: false,
+            limiting_at: None,
+            current_cues: Vec::new(),
+            show_cues: true,
+            scroll_titles: true,
+            pipeline_snapshot: None,
+            pipeline_graph: None,
+            tone_test: None,
+            karaoke: false,
+            lyrics: None,
+            lyrics_track: None,
+            lyrics_status: None,
+            lyrics_editor: None,
+            lyrics_draft: config.lyrics_draft.clone(),
+            lyrics_draft_error: None,
+            output_report: None,
             search_expanded: false,
             search_term: "".to_string(),
+            search_index: Arc::new(SearchIndex::default()),
+            search_results: None,
+            search_generation: 0,
+            song_list: RefCell::new(None),
+            list_scroll: ListScroll::default(),
+            cursor: None,
+            column_drag: None,
+            scroll_offsets: HashMap::new(),
+            group_by_album: config.group_by_album,
+            collapsed_albums: HashSet::new(),
+            conversion_job: ConversionJob::default(),
+            organize_pattern: organize::DEFAULT_PATTERN.to_string(),
+            sort_articles: config.sort_articles.clone(),
+            organize_plan: Vec::new(),
+            organize_status: None,
+            reread_status: None,
+            track_info: None,
+            track_pictures: Vec::new(),
+            track_pictures_error: None,
+            track_edits: Vec::new(),
+            held_back: Vec::new(),
+            watching: false,
+            organizing: false,
+            config,
+            config_handler,
+            pending_link: None,
+            pending_playlist: None,
+            outside_files: Vec::new(),
+            toasts: widget::toaster::Toasts::new(Message::CloseToast),
+            missing_plugins: Vec::new(),
+            scan_options: config.scan_options,
+        };
+
+        // The window opens straight away and the library fills in as it's read, a batch
+        // at a time, rather than being read in full before the first frame.
+        let load = if app.config.rescan_on_start {
+            app.scan_library()
+        } else {
+            Task::none()
+        };
+
+        let command = Task::batch(vec![
+            load,
+            app.update_titles(),
+            cosmic::task::future(async {
+                Message::ConnectivityDetected(network::detect().await)
+            }),
+            cosmic::task::future(async {
+                Message::ScreenReaderDetected(announce::screen_reader_enabled().await)
+            }),
+            cosmic::task::future(async { Message::MotionDetected(motion::detect().await) }),
+        ]);
+
+        gamepad::start();
+
+        // Jams was started to open a `jams://` link.
+        let links: Vec<ShareLink> = std::env::args()
+            .skip(1)
+            .filter_map(|arg| Url::parse(&arg).ok())
+            .filter_map(|url| ShareLink::parse(&url))
+            .collect();
+        let link = links.into_iter().last();
+        let command = match link {
+            Some(link) if app.scan_job.is_none() => {
+                Task::batch(vec![command, app.open_share_link(link)])
+            }
+            link => {
+                app.pending_link = link;
+                command
+            }
         };
 
-        let command = app.update_titles();
+        // Or to play a playlist, as the file manager does when one is opened with Jams.
+        let playlist = std::env::args()
+            .skip(1)
+            .map(PathBuf::from)
+            .filter(|path| playlist::is_playlist(path))
+            .last();
+        let command = match playlist {
+            Some(path) if app.scan_job.is_none() => {
+                Task::batch(vec![command, app.update(Message::LoadPlaylist(path))])
+            }
+            playlist => {
+                app.pending_playlist = playlist;
+                command
+            }
+        };
 
         (app, command)
     }
@@ -317,7 +1205,118 @@ impl Application for Jams {
                 menu::root(fl!("view")),
                 menu::items(
                     &self.key_binds,
-                    vec![menu::Item::Button(fl!("about"), None, MenuAction::About)],
+                    vec![
+                        menu::Item::Button(
+                            fl!("network-settings"),
+                            None,
+                            MenuAction::NetworkSettings,
+                        ),
+                        menu::Item::Button(fl!("lyrics-menu"), None, MenuAction::Lyrics),
+                        menu::Item::Button(fl!("jobs-menu"), None, MenuAction::Jobs),
+                        menu::Item::Button(fl!("queue-menu"), None, MenuAction::Queue),
+                        menu::Item::Button(fl!("settings-menu"), None, MenuAction::Settings),
+                        menu::Item::Button(fl!("about"), None, MenuAction::About),
+                    ],
+                ),
+            ),
+            menu::Tree::with_children(
+                menu::root(fl!("playback")),
+                menu::items(
+                    &self.key_binds,
+                    vec![
+                        menu::Item::Button(
+                            fl!("playback-settings"),
+                            None,
+                            MenuAction::PlaybackSettings,
+                        ),
+                        menu::Item::Button(fl!("karaoke-mode"), None, MenuAction::Karaoke),
+                        menu::Item::Folder(
+                            fl!("end-of-queue"),
+                            EndOfQueue::ALL
+                                .iter()
+                                .zip(EndOfQueue::names())
+                                .map(|(action, name)| {
+                                    menu::Item::CheckBox(
+                                        name.clone(),
+                                        None,
+                                        self.end_of_queue == *action,
+                                        MenuAction::EndOfQueue(*action),
+                                    )
+                                })
+                                .collect(),
+                        ),
+                    ],
+                ),
+            ),
+            menu::Tree::with_children(
+                menu::root(fl!("library")),
+                menu::items(
+                    &self.key_binds,
+                    vec![
+                        menu::Item::Button(fl!("add-archive"), None, MenuAction::AddArchive),
+                        menu::Item::Button(fl!("open-playlist"), None, MenuAction::OpenPlaylist),
+                        menu::Item::Button(fl!("rescan-library"), None, MenuAction::RescanLibrary),
+                        menu::Item::Button(
+                            fl!("organize-files"),
+                            None,
+                            MenuAction::OrganizeFiles,
+                        ),
+                        menu::Item::Button(
+                            fl!("library-settings"),
+                            None,
+                            MenuAction::LibrarySettings,
+                        ),
+                        menu::Item::Button(
+                            fl!("embed-missing-artwork"),
+                            None,
+                            MenuAction::EmbedMissingArtwork,
+                        ),
+                        menu::Item::Button(
+                            fl!("verify-library"),
+                            None,
+                            MenuAction::VerifyLibrary,
+                        ),
+                        menu::Item::Button(fl!("detect-tempo"), None, MenuAction::DetectTempo),
+                    ],
+                ),
+            ),
+            menu::Tree::with_children(
+                menu::root(fl!("export")),
+                menu::items(
+                    &self.key_binds,
+                    vec![
+                        menu::Item::Button(
+                            fl!("export-queue"),
+                            None,
+                            MenuAction::ExportQueue,
+                        ),
+                        menu::Item::Button(fl!("export-history"), None, MenuAction::ExportHistory),
+                        menu::Item::Button(
+                            fl!("sync-library-rockbox"),
+                            None,
+                            MenuAction::SyncToRockbox(DeviceSync::Library),
+                        ),
+                        menu::Item::Button(
+                            fl!("sync-queue-rockbox"),
+                            None,
+                            MenuAction::SyncToRockbox(DeviceSync::Queue),
+                        ),
+                        menu::Item::Button(
+                            fl!("sync-library-phone"),
+                            None,
+                            MenuAction::SyncToPhone(DeviceSync::Library),
+                        ),
+                        menu::Item::Button(
+                            fl!("sync-queue-phone"),
+                            None,
+                            MenuAction::SyncToPhone(DeviceSync::Queue),
+                        ),
+                        menu::Item::Button(
+                            fl!("conversion-settings"),
+                            None,
+                            MenuAction::ConversionSettings,
+                        ),
+                    ],
                 ),
             ),
             menu::Tree::with_children(
@@ -325,24 +1324,14 @@ impl Application for Jams {
                 menu::items(
                     &self.key_binds,
                     vec![menu::Item::Button(
-                        fl!("debug"),
-                        None,
-                        MenuAction::DebugStub,
-                    ),
-                         menu::Item::Button(
-                             "Save Library Location".to_string(),
-                             None,
-                             MenuAction::SaveLibraryLocation,
-                         ),
-                    menu::Item::Button(
-                        "Reset Library Location".to_string(),
+                        fl!("inspect-pipeline"),
                         None,
-                        MenuAction::ResetLibraryLocation,
+                        MenuAction::InspectPipeline,
                     ),
                     menu::Item::Button(
-                        "Re-Open Library Location".to_string(),
+                        fl!("diagnostics-menu"),
                         None,
-                        MenuAction::ReOpenLibraryLocation,
+                        MenuAction::Diagnostics,
                     )],
                 ),
             ),
@@ -351,8 +1340,55 @@ impl Application for Jams {
         vec![menu_bar.into()]
     }
 
+    /// The playing track, when the window is too narrow for the controls bar to show it well.
+    fn header_center(&self) -> Vec<Element<Self::Message>> {
+        if !self.core.is_condensed() {
+            return Vec::new();
+        }
+        let Some(track) = self.queue.current().and_then(|id| self.find_track(id)) else {
+            return Vec::new();
+        };
+
+        let label = format!("{} – {}", track.artist, track.track_title);
+        // Moves with playback, so it holds still while paused.
+        let shown = if self.scroll_titles && !self.motion.is_reduced() {
+            marquee::scroll(&label, TICKER_WIDTH, self.seek_position)
+        } else {
+            marquee::truncate(&label, TICKER_WIDTH)
+        };
+
+        vec![text::body(shown).font(cosmic::font::mono()).into()]
+    }
+
     fn header_end(&self) -> Vec<Element<Self::Message>> {
-        let mut elements = Vec::with_capacity(1);
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut elements = Vec::with_capacity(3);
+
+        // Says why the disk and CPU are busy, and opens the jobs to see more or stop them.
+        if let Some(status) = self.busy_status() {
+            let spinner = if self.motion.is_reduced() {
+                SPINNER[0]
+            } else {
+                let elapsed = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default();
+                let frame = elapsed.as_millis() / PROGRESS_REFRESH.as_millis();
+                SPINNER[frame as usize % SPINNER.len()]
+            };
+            let indicator = Row::new()
+                .spacing(space_xxs)
+                .align_y(Alignment::Center)
+                .push(text::body(spinner).font(cosmic::font::mono()))
+                .push(text::caption(status));
+            elements.push(
+                button::custom(indicator)
+                    .class(cosmic::style::Button::Text)
+                    .padding([4, 8])
+                    .on_press(Message::ToggleContextPage(ContextPage::Jobs))
+                    .into(),
+            );
+        }
 
         if self.search_expanded {
             elements.push(
@@ -374,6 +1410,15 @@ impl Application for Jams {
             );
         }
 
+        let queue_open = self.core.window.show_context && self.context_page == ContextPage::Queue;
+        elements.push(
+            widget::button::icon(icon::from_name("view-list-symbolic"))
+                .on_press(Message::ToggleContextPage(ContextPage::Queue))
+                .padding(8)
+                .selected(queue_open)
+                .into(),
+        );
+
         elements
     }
 
@@ -384,241 +1429,32 @@ impl Application for Jams {
     ///
     /// To get a better sense of which widgets are available, check out the `widget` module.
     fn view(&self) -> Element<Self::Message> {
-        // self.nav.text() - pass it a nav item from the model to get its text
-        println!("{:?}", self.nav.active()); // - get currently active nav
-                                             // println!("{:?}", self
-                                             //     .nav
-                                             //     .active_data::<String>()
-                                             //     .map_or("No page selected", String::as_str));
-        println!("{:?}", self.nav.text(self.nav.active()));
-        let mut window_col = Column::new().spacing(10);
-
-        // https://hermanradtke.com/2015/06/22/effectively-using-iterators-in-rust.html/
-        if &self.scanned_files.len() > &0 {
-            let mut controls_row = Row::new()
-                .spacing(10)
-                .align_y(Alignment::Center)
-                .height(Length::Fill);
-
-            //let controls_button_prev_txt = text("Previous");
-            let controls_prev_button =
-                button::icon(icon::from_name("media-skip-backward-symbolic"))
-                    .icon_size(16)
-                    .on_press(Message::SkipPrev);
-
-            controls_row = controls_row.push(controls_prev_button);
-
-            match &self.global_play_state {
-                PlayState::Playing => {
-                    //let controls_button_txt = text("Pause");
-                    let controls_pause_button =
-                        button::icon(icon::from_name("media-playback-pause-symbolic"))
-                            .icon_size(24)
-                            .padding([15, 15, 15, 15])
-                            .class(cosmic::style::Button::Suggested)
-                            .on_press(Message::PauseCurrentTrack);
-
-                    controls_row = controls_row.push(controls_pause_button);
-                }
-                PlayState::Paused => {
-                    //let controls_button_txt = text("Play");
-                    let controls_pause_button =
-                        button::icon(icon::from_name("media-playback-start-symbolic"))
-                            .icon_size(24)
-                            .padding([15, 15, 15, 15])
-                            .class(cosmic::style::Button::Suggested)
-                            .on_press(Message::ResumeCurrentTrack);
-
-                    controls_row = controls_row.push(controls_pause_button);
-                }
-                PlayState::Idle => {
-                    //let controls_button_txt = text("This Button Is Disabled");
-                    let controls_pause_button =
-                        button::icon(icon::from_name("media-playback-start-symbolic"))
-                            .icon_size(24)
-                            .padding([15, 15, 15, 15])
-                            .class(cosmic::style::Button::Icon);
-
-                    controls_row = controls_row.push(controls_pause_button);
-                }
-            }
-
-            //let controls_button_next_txt = text("Next");
-            let controls_next_button = button::icon(icon::from_name("media-skip-forward-symbolic"))
-                .icon_size(16)
-                .on_press(Message::SkipNext);
-
-            let controls_row = controls_row.push(controls_next_button);
-
-            let mut controls_col = Column::new()
-                .push(controls_row)
-                .height(Length::Fixed(110.0))
-                .width(Length::Fill)
-                .align_x(Alignment::Center);
+        profiling::first_frame();
 
-            let min_seek_position = self.seek_position.as_secs() / 60;
-            let sec_seek_position = self.seek_position.as_secs() % 60;
-
-            let min_duration = self.current_track_duration.as_secs() / 60;
-            let sec_duration = self.current_track_duration.as_secs() % 60;
-
-            //println!("{} : {}", min_seek_position, sec_seek_position);
-
-            //let pos = self.seek_position.as_secs().to_string();
-            //https://stackoverflow.com/questions/66666348/println-to-print-a-2-digit-integer
-            let pos = format!("{}:{:02}", min_seek_position, sec_seek_position);
-            //let total = self.current_track_duration.as_secs().to_string();
-            let total = format!("{}:{:02}", min_duration, sec_duration);
-
-            //println!("{}", self.seek_position.as_secs());
-
-            let pos_txt = text(pos).size(18);
-            let progress_scrubber = slider(0..=100, self.scrub_value, Message::Scrub).width(250);
-            let total_txt = text(total).size(18);
-
-            let timing_row = Row::new()
-                .spacing(5)
-                .align_y(Alignment::Center)
-                .push(pos_txt)
-                .push(progress_scrubber)
-                .push(total_txt);
+        if self.karaoke {
+            return self.karaoke_view();
+        }
 
-            controls_col = controls_col.push(timing_row);
+        let mut content_col = Column::new().spacing(10).height(Length::Fill);
 
-            let controls_container =
-                Container::new(controls_col).class(cosmic::style::Container::ContextDrawer);
+        if !self.missing_plugins.is_empty() {
+            let banner = widget::warning(self.missing_plugins_text()).on_close(Message::CloseError);
+            content_col = content_col.push(banner);
+        }
 
+        // https://hermanradtke.com/2015/06/22/effectively-using-iterators-in-rust.html/
+        if &self.scanned_files.len() > &0 {
             // TODO: Improve performance when rendering pages (specifically switching between them)
-            if self.nav.text(self.nav.active()) == Option::from("All Music") {
-                let mut file_col = Column::new().spacing(2);
-
-                for file in &self.scanned_files {
-                    if self.search_term.is_empty()
-                        || file
-                            .album
-                            .to_lowercase()
-                            .contains(&self.search_term.to_lowercase())
-                        || file
-                            .artist
-                            .to_lowercase()
-                            .contains(&self.search_term.to_lowercase())
-                        || file
-                            .track_title
-                            .to_lowercase()
-                            .contains(&self.search_term.to_lowercase())
-                        || file
-                            .album_artist
-                            .to_lowercase()
-                            .contains(&self.search_term.to_lowercase())
-                    {
-                        let mut file_txt_row = Row::new()
-                            .align_y(Alignment::Center)
-                            .spacing(8)
-                            .padding([6, 4, 6, 4]);
-
-                        let track_number = text(file.track_number.to_string())
-                            .align_x(Horizontal::Center)
-                            .width(Length::FillPortion(1));
-                        file_txt_row = file_txt_row.push(track_number);
-
-                        if file.paused == true {
-                            //let resume_txt = text("Resume");
-                            let button =
-                                button::icon(icon::from_name("media-playback-start-symbolic"))
-                                    .on_press(Message::ResumeCurrentTrack);
-                            file_txt_row = file_txt_row.push(button);
-                        } else if file.playing == true {
-                            //let playing_txt = text("Pause");
-                            let button =
-                                button::icon(icon::from_name("media-playback-pause-symbolic"))
-                                    .on_press(Message::PauseCurrentTrack);
-                            file_txt_row = file_txt_row.push(button);
-                        } else {
-                            //let paused_txt = text("Play");
-                            let button =
-                                button::icon(icon::from_name("media-playback-start-symbolic"))
-                                    .on_press(Message::StartPlayingNewTrack(file.uri.clone()));
-                            file_txt_row = file_txt_row.push(button);
-                        }
-
-                        let title = text(file.track_title.clone()).width(Length::FillPortion(40));
-                        let artist = text(file.artist.clone()).width(Length::FillPortion(20));
-                        let album = text(file.album.clone()).width(Length::FillPortion(20));
-                        file_txt_row = file_txt_row.push(title);
-                        file_txt_row = file_txt_row.push(artist);
-                        file_txt_row = file_txt_row.push(album);
-
-                        file_col = file_col.push(file_txt_row);
-
-                        file_col = file_col.push(widget::divider::horizontal::default());
-
-                        // let file_txt = text(file.saved_path.display().to_string());
-                        // let file_txt_container = Container::new(file_txt).width(Length::Fill);
-                        //
-                        // col = col.push(file_txt_container);
-                    }
-                }
-
-                let scroll_list = Scrollable::new(file_col)
-                    .height(Length::Fill)
-                    .width(Length::Fill);
-                let scroll_container = Container::new(scroll_list)
-                    .height(Length::Fill)
-                    .width(Length::Fill);
-
-                // let paused_txt = text("Play");
-                // let button = button(paused_txt);
-
-                window_col = window_col.push(scroll_container);
-            } else if self.nav.text(self.nav.active()) == Option::from("Albums") {
-
-                let mut list_of_albums = Row::new().width(Length::Fill).align_y(Alignment::Center);
-
-                for album in &self.albums {
-                    if self.search_term.is_empty()
-                        || album
-                            .album
-                            .to_lowercase()
-                            .contains(&self.search_term.to_lowercase())
-                        || album
-                            .album_artist
-                            .to_lowercase()
-                            .contains(&self.search_term.to_lowercase())
-                    {
-                        let mut album_content = Column::new();
-
-                        let album_front_cover = image(album.cached_cover_path.clone()).width(Length::Fixed(270.0)).height(Length::Fixed(270.0)).content_fit(ContentFit::Contain);
-                        let album_name = text(album.album.clone()).width(Length::Fill).align_x(Alignment::Center);
-
-                        album_content = album_content.push(album_front_cover);
-                        album_content = album_content.push(album_name);
-
-                        let mut album_content_alignment = Row::new().align_y(Alignment::Start);
-                        album_content_alignment = album_content_alignment.push(album_content);
-
-                        let mut album_block = Column::new()
-                            .width(Length::Fill)
-                            .max_width(300)
-                            .spacing(8)
-                            .padding([6, 4, 6, 4]);
-                        album_block = album_block.push(album_content_alignment);
-
-                        list_of_albums = list_of_albums.push(album_block);
-                    }
-                }
-
-                let list_of_albums_wrapped = list_of_albums.wrap();
-
-                let scroll_list = Scrollable::new(list_of_albums_wrapped)
-                    .height(Length::Fill)
-                    .width(Length::Fill);
-                let scroll_container = Container::new(scroll_list)
-                    .height(Length::Fill)
-                    .width(Length::Fill);
-
-                window_col = window_col.push(scroll_container);
-            }
-            window_col = window_col.push(controls_container);
+            let page = match self.nav.active_data::<Page>() {
+                Some(Page::AllMusic) => self.all_music(),
+                Some(Page::Songs) => self.songs(),
+                Some(Page::Albums) => self.album_grid(),
+                Some(Page::Artists) => self.artists(),
+                Some(Page::Labels) => self.labels(),
+                None => widget::Space::new(Length::Fill, Length::Fill).into(),
+            };
+
+            content_col = content_col.push(page);
         } else {
             let mut splash_screen = Column::new().align_x(Alignment::Center).spacing(15);
 
@@ -652,6 +1488,20 @@ impl Application for Jams {
 
             splash_screen = splash_screen.push(btn_open);
 
+            // The library isn't scanned on start, so there's music to be found.
+            if self.scan_job.is_none() && !self.config.enabled_folders().is_empty() {
+                splash_screen = splash_screen.push(
+                    button::link(fl!("rescan-library"))
+                        .font_size(20)
+                        .padding([16, 32])
+                        .on_press(Message::RescanLibrary),
+                );
+            }
+
+            if let Some(status) = self.scan_status() {
+                splash_screen = splash_screen.push(status);
+            }
+
             let mut splash_screen_container = Row::new()
                 .align_y(Alignment::Center)
                 .width(Length::Fill)
@@ -659,32 +1509,163 @@ impl Application for Jams {
 
             splash_screen_container = splash_screen_container.push(splash_screen);
 
-            window_col = window_col.push(splash_screen_container);
+            content_col = content_col.push(splash_screen_container);
         }
 
-        window_col.into()
+        // The controls keep their place in the widget tree whatever the page shows, so
+        // switching pages never rebuilds them or drops a slider mid-drag.
+        let controls = if !self.scanned_files.is_empty() || !self.playback.is_idle() {
+            self.controls_bar()
+        } else {
+            widget::Space::new(Length::Fill, Length::Shrink).into()
+        };
+
+        let window_col = Column::new().spacing(10).push(content_col).push(controls);
+
+        widget::toaster(&self.toasts, window_col)
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        let tick = match self.global_play_state {
+        let tick = match self.playback.status {
             PlayState::Idle => Subscription::none(),
             PlayState::Paused => Subscription::none(),
-            PlayState::Playing { .. } => {
-                time::every(Duration::from_millis(100)).map(Message::WatchTick)
+            // A fade needs the fast rate to ramp the volume smoothly.
+            PlayState::Playing { .. } if self.motion.is_reduced() && self.crossfade.is_none() => {
+                time::every(REDUCED_POSITION_POLL).map(Message::WatchTick)
             }
+            PlayState::Playing { .. } => time::every(POSITION_POLL).map(Message::WatchTick),
         };
 
         fn handle_hotkey(key: keyboard::Key, _modifiers: keyboard::Modifiers) -> Option<Message> {
             use keyboard::key;
 
             match key.as_ref() {
-                keyboard::Key::Named(key::Named::Space) => Some(Message::ResumeCurrentTrack),
+                keyboard::Key::Named(key::Named::Space) => Some(Message::TogglePlayPause),
                 keyboard::Key::Character("r") => Some(Message::PauseCurrentTrack),
+                keyboard::Key::Named(key::Named::Escape) => Some(Message::Karaoke(false)),
+                keyboard::Key::Named(key::Named::ArrowUp) => Some(Message::MoveCursor(-1)),
+                keyboard::Key::Named(key::Named::ArrowDown) => Some(Message::MoveCursor(1)),
+                keyboard::Key::Named(key::Named::Enter) => Some(Message::PlayCursor),
+                keyboard::Key::Named(key::Named::Backspace) => Some(Message::GoBack),
+                _ => None,
+            }
+        }
+
+        fn watch_input(
+            event: cosmic::iced::Event,
+            _status: event::Status,
+            _window: window::Id,
+        ) -> Option<Message> {
+            match event {
+                cosmic::iced::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                    Some(Message::ModifiersChanged(modifiers))
+                }
+                // Only lifts end a gesture, but the rest are needed to follow it.
+                cosmic::iced::Event::Touch(event) => Some(Message::Touch(event)),
+                cosmic::iced::Event::Window(window::Event::Rescaled(scale_factor)) => {
+                    Some(Message::Rescaled(scale_factor))
+                }
+                cosmic::iced::Event::Window(window::Event::FileDropped(path)) => {
+                    Some(Message::FileDropped(path))
+                }
                 _ => None,
             }
         }
 
-        Subscription::batch(vec![tick, keyboard::on_key_press(handle_hotkey)])
+        let mut subscriptions = vec![
+            tick,
+            keyboard::on_key_press(handle_hotkey),
+            event::listen_with(watch_input),
+        ];
+
+        if self.network.mode == NetworkMode::Automatic {
+            subscriptions.push(time::every(CONNECTIVITY_POLL).map(|_| Message::CheckConnectivity));
+        }
+
+        if self.scrobble_error.is_some() && !self.scrobble_sending {
+            subscriptions.push(time::every(SCROBBLE_RETRY).map(|_| Message::SubmitScrobbles));
+        }
+
+        if self.motion.mode == MotionMode::Automatic {
+            subscriptions.push(time::every(MOTION_POLL).map(|_| Message::CheckMotion));
+        }
+
+        if self.alarm.enabled {
+            subscriptions.push(time::every(ALARM_POLL).map(|_| Message::CheckAlarm));
+        }
+
+        // A gamepad connected while idle is noticed with the next output device poll.
+        if gamepad::connected() {
+            subscriptions.push(time::every(GAMEPAD_POLL).map(|_| Message::PollGamepad));
+        }
+
+        if self.core.window.show_context && self.context_page == ContextPage::Playback {
+            subscriptions.push(time::every(BLUETOOTH_POLL).map(|_| Message::CheckBluetooth));
+        }
+
+        subscriptions.push(time::every(OUTPUT_DEVICE_POLL).map(|_| Message::CheckOutputDevice));
+
+        // Dropping the subscription once playback stops lets the session go idle again.
+        if self.playback.is_playing() {
+            let reason = fl!("idle-inhibit-reason");
+            subscriptions.push(Subscription::run_with_id(
+                "idle-inhibit",
+                cosmic::iced::stream::channel(1, |_output| async move {
+                    let _inhibitor = inhibit::inhibit_idle(&reason).await;
+                    std::future::pending::<()>().await;
+                }),
+            ));
+        }
+
+        let watched = if self.watching {
+            self.config.enabled_folders()
+        } else {
+            Vec::new()
+        };
+        for root in watched {
+            subscriptions.push(Subscription::run_with_id(
+                ("library-watch", root.clone()),
+                cosmic::iced::stream::channel(16, |mut output| async move {
+                    match LibraryWatcher::new(&root) {
+                        Ok(mut watcher) => {
+                            while let Some(paths) = watcher.next_changes().await {
+                                let changed = Message::LibraryFilesChanged(paths);
+                                if output.send(changed).await.is_err() {
+                                    break;
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            eprintln!("Failed to watch {} for changes: {}", root.display(), err);
+                        }
+                    }
+                    std::future::pending::<()>().await;
+                }),
+            ));
+        }
+
+        if self.preview.is_some() {
+            subscriptions.push(time::every(POSITION_POLL).map(|_| Message::PreviewTick));
+        }
+
+        // Frequent enough for the sweep to sound continuous.
+        if self.tone_test.is_some() {
+            subscriptions
+                .push(time::every(Duration::from_millis(50)).map(|_| Message::ToneTestTick));
+        }
+
+        // Long-running jobs report progress through shared counters, so redraw while they run.
+        if self.scan_job.is_some()
+            || self.embed_job.is_some()
+            || self.export_job.is_some()
+            || self.verify_job.is_some()
+            || self.loudness_job.is_some()
+            || self.tempo_job.is_some()
+        {
+            subscriptions.push(time::every(PROGRESS_REFRESH).map(|_| Message::RefreshProgress));
+        }
+
+        Subscription::batch(subscriptions)
     }
 
     /// Application messages are handled here. The application state can be modified based on
@@ -693,110 +1674,76 @@ impl Application for Jams {
     fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
         match message {
             Message::WatchTick(now) => {
-                if let PlayState::Playing = &mut self.global_play_state {
-                    self.seek_position += now - self.last_tick;
-                    self.last_tick = now;
+                let ended = self.handle_player_events();
 
+                if self.playback.is_playing() {
                     // update scrubber
-                    self.scrub_value = (self.seek_position.as_secs() as f64
-                        / self.current_track_duration.as_secs() as f64
-                        * 100.0) as u8;
-
-                    if self.seek_position.as_millis() >= self.current_track_duration.as_millis() {
-                        println!("{}", String::from("End of track reached."));
-                        self.global_play_state = PlayState::Idle;
+                    self.scrub_value = if self.current_track_duration.is_zero() {
+                        0
+                    } else {
+                        (self.seek_position.as_secs_f64()
+                            / self.current_track_duration.as_secs_f64()
+                            * 100.0) as u8
+                    };
+
+                    if let Some(fade) = &mut self.crossfade {
+                        if !fade.step(self.audio_player.as_mut()) {
+                            self.crossfade = None;
+                        }
+                    }
 
-                        let next_index = self
-                            .scanned_files
-                            .iter()
-                            .position(|x| x.playing == true)
-                            .unwrap()
-                            + 1;
-
-                        let next_file = self.scanned_files.get(next_index);
-
-                        match next_file {
-                            Some(track) => {
-                                println!("Moving to next track: {}", track.track_title);
-                                self.seek_position = Duration::new(0, 0);
-                                self.audio_player.player.stop();
-                                self.global_play_state = PlayState::Idle;
-                                self.current_track_duration = Duration::new(0, 0);
-                                self.switch_track(track.uri.clone());
-                            }
-                            None => {
-                                println!("End of list reached. Stopping playback.");
-                                self.seek_position = Duration::new(0, 0);
-                                self.audio_player.player.stop();
-                                self.global_play_state = PlayState::Idle;
-                                self.current_track_duration = Duration::new(0, 0);
-                            }
+                    if let Some(started) = self.alarm_ramp {
+                        let elapsed = now.saturating_duration_since(started);
+                        self.audio_player.set_volume(alarm::ramp_volume(elapsed));
+                        if elapsed >= alarm::RAMP {
+                            self.alarm_ramp = None;
                         }
-                        //Message::StartPlayingNewTrack();
                     }
-                }
-            }
-            Message::SkipNext => {
-                let next_index = self
-                    .scanned_files
-                    .iter()
-                    .position(|x| x.playing == true || x.paused == true)
-                    .unwrap()
-                    + 1;
-
-                let next_file = self.scanned_files.get(next_index);
-
-                match next_file {
-                    Some(track) => {
-                        println!("Moving to next track: {}", track.track_title);
-                        self.seek_position = Duration::new(0, 0);
-                        self.audio_player.player.stop();
-                        self.global_play_state = PlayState::Idle;
-                        self.current_track_duration = Duration::new(0, 0);
-                        self.switch_track(track.uri.clone());
+
+                    self.preload_next();
+
+                    if self.resume_saved_at.elapsed() >= resume::SAVE_EVERY {
+                        self.save_resume_position();
                     }
-                    None => {
-                        println!("End of list reached. Stopping playback.");
-                        self.seek_position = Duration::new(0, 0);
-                        self.audio_player.player.stop();
-                        self.global_play_state = PlayState::Idle;
-                        self.current_track_duration = Duration::new(0, 0);
+
+                    // The end itself is left to the pipeline, see `Message::TrackEnded`.
+                    let remaining = self.current_track_duration.saturating_sub(self.seek_position);
+                    if !self.crossfade_length.is_zero()
+                        && !ended
+                        && !self.seeking
+                        && self.alarm_ramp.is_none()
+                        && remaining <= self.crossfade_length
+                    {
+                        self.start_crossfade(remaining);
                     }
                 }
+
+                let ended = if ended {
+                    self.update(Message::TrackEnded)
+                } else {
+                    Task::none()
+                };
+                let lyrics = self.load_lyrics();
+                let listen = self.record_listen();
+                return Task::batch(vec![ended, self.announce_track_change(), lyrics, listen]);
+            }
+            Message::TrackEnded => {
+                self.skip_next();
+            }
+            Message::SkipNext => {
+                self.skip_next();
             }
             Message::SkipPrev => {
-                let curr_index = self
-                    .scanned_files
-                    .iter()
-                    .position(|x| x.playing == true || x.paused == true);
-
-                match curr_index {
-                    Some(index) => {
-                        if index == 0 {
-                            self.scrub(0);
-                        } else {
-                            let prev_file = self.scanned_files.get(index - 1);
-
-                            match prev_file {
-                                Some(track) => {
-                                    println!("Moving to prev track: {}", track.track_title);
-                                    self.seek_position = Duration::new(0, 0);
-                                    self.global_play_state = PlayState::Idle;
-                                    self.current_track_duration = Duration::new(0, 0);
-                                    self.switch_track(track.uri.clone());
-                                }
-                                None => {
-                                    println!("End of list reached. Stopping playback.");
-                                    self.seek_position = Duration::new(0, 0);
-                                    self.audio_player.player.stop();
-                                    self.global_play_state = PlayState::Idle;
-                                    self.current_track_duration = Duration::new(0, 0);
-                                }
-                            }
+                if self.queue.current().is_none() {
+                    eprintln!("Can't move to previous track. No track currently playing.");
+                } else {
+                    match self.queue.go_back().and_then(|id| self.find_track(id)) {
+                        Some(track) => {
+                            let uri = track.uri.clone();
+                            self.switch_track(uri);
                         }
-                    }
-                    None => {
-                        println!("Can't move to previous track. No track currently playing.");
+                        // Already at the first track, so start it over instead.
+                        None => self.scrub(0),
                     }
                 }
             }
@@ -809,7 +1756,23 @@ impl Application for Jams {
                     let dialog = file_chooser::open::Dialog::new().title(fl!("add-folder"));
 
                     match dialog.open_folder().await {
-                        Ok(response) => Message::AddSongsToLibrary(response.url().to_owned()),
+                        Ok(response) => {
+                            let url = response.url().to_owned();
+                            let Ok(path) = url.to_file_path() else {
+                                return Message::AddSongsToLibrary(url);
+                            };
+                            // Under Flatpak the folder is only reachable until logout unless
+                            // the portal is asked to keep it.
+                            match flatpak::keep_access(path).await {
+                                Ok(path) => Message::AddSongsToLibrary(
+                                    Url::from_directory_path(path).unwrap_or(url),
+                                ),
+                                Err(err) => {
+                                    eprintln!("{}", err);
+                                    Message::AddSongsToLibrary(url)
+                                }
+                            }
+                        }
 
                         Err(file_chooser::Error::Cancelled) => Message::Cancelled,
 
@@ -819,269 +1782,6331 @@ impl Application for Jams {
             }
 
             Message::AddSongsToLibrary(url) => {
-                write_loc_to_config(&url);
-                get_all_files(url, &mut self.albums, &mut self.scanned_files);
+                let Ok(path) = url.to_file_path() else {
+                    return self.update(Message::Error(fl!(
+                        "not-a-local-folder",
+                        url = url.to_string()
+                    )));
+                };
+
+                // Adding a folder that's there already switches it back on.
+                let mut folders = self.config.library_folders.clone();
+                match folders.iter_mut().find(|folder| folder.path == path) {
+                    Some(folder) => folder.enabled = true,
+                    None => folders.push(LibraryFolder {
+                        path: path.clone(),
+                        enabled: true,
+                    }),
+                }
+
+                let save = self.set_library_folders(folders);
+                return Task::batch(vec![save, self.scan_folder(path)]);
             }
+            Message::ToggleLibraryFolder(index, enabled) => {
+                let mut folders = self.config.library_folders.clone();
+                let Some(folder) = folders.get_mut(index) else {
+                    return Task::none();
+                };
+                folder.enabled = enabled;
+                let path = folder.path.clone();
+
+                let save = self.set_library_folders(folders);
+                let update = if enabled {
+                    self.scan_folder(path)
+                } else {
+                    self.forget_folder(&path)
+                };
+                return Task::batch(vec![save, update]);
+            }
+            Message::RemoveLibraryFolder(index) => {
+                let mut folders = self.config.library_folders.clone();
+                if index >= folders.len() {
+                    return Task::none();
+                }
+                let removed = folders.remove(index);
 
-            Message::StartPlayingNewTrack(uri) => {
-                self.switch_track(uri);
+                let save = self.set_library_folders(folders);
+                return Task::batch(vec![save, self.forget_folder(&removed.path)]);
             }
 
-            Message::PauseCurrentTrack => {
-                self.audio_player.player.pause();
-                //self.audio_player.player.pause();
-                self.global_play_state = PlayState::Paused;
+            Message::AddArchive => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new().title(fl!("add-archive"));
+
+                    match dialog.open_file().await {
+                        Ok(response) => {
+                            let url = response.url().to_owned();
+                            match url.to_file_path() {
+                                Ok(path) if archive::is_archive(&path) => Message::ScanArchive(url),
+                                _ => Message::Error(fl!("not-an-archive")),
+                            }
+                        }
 
-                for file in &mut self.scanned_files {
-                    if file.playing == true {
-                        file.playing = false;
-                        file.paused = true;
-                    }
-                }
-            }
+                        Err(file_chooser::Error::Cancelled) => Message::Cancelled,
 
-            Message::ResumeCurrentTrack => {
-                self.last_tick = Instant::now();
-                self.audio_player.player.play();
-                //self.audio_player.player.play();
-                self.global_play_state = PlayState::Playing;
-                for file in &mut self.scanned_files {
-                    if file.paused == true {
-                        file.playing = true;
-                        file.paused = false;
+                        Err(why) => Message::OpenError(Arc::new(why)),
                     }
-                }
+                });
             }
 
-            // Displays an error in the application's warning bar.
-            Message::Error(why) => {
-                //self.error_status = Some(why);
+            // Archives aren't saved as the library location, which stays the folder.
+            Message::ScanArchive(url) => {
+                return self.start_scan(url);
             }
 
-            // Displays an error in the application's warning bar.
-            Message::OpenError(why) => {
-                // if let Some(why) = Arc::into_inner(why) {
-                //     let mut source: &dyn std::error::Error = &why;
-                //     let mut string =
-                //         format!("open dialog subscription errored\n    cause: {source}");
-                //
-                //     while let Some(new_source) = source.source() {
-                //         string.push_str(&format!("\n    cause: {new_source}"));
-                //         source = new_source;
-                //     }
-                //
-                //     self.error_status = Some(string);
-                // }
-            }
+            Message::OpenPlaylist => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new().title(fl!("open-playlist"));
 
-            Message::SearchExpand => {
-                self.search_expanded = true;
+                    match dialog.open_file().await {
+                        Ok(response) => match response.url().to_file_path() {
+                            Ok(path) if playlist::is_playlist(&path) => Message::LoadPlaylist(path),
+                            _ => Message::Error(fl!("not-a-playlist")),
+                        },
+                        Err(file_chooser::Error::Cancelled) => Message::Cancelled,
+                        Err(why) => Message::OpenError(Arc::new(why)),
+                    }
+                });
             }
 
-            Message::SearchMinimize => {
-                self.search_term = "".to_string();
-                self.search_expanded = false;
-            }
+            Message::LoadPlaylist(path) => {
+                let library: HashMap<PathBuf, usize> = self
+                    .scanned_files
+                    .iter()
+                    .map(|file| (file.saved_path.clone(), file.id))
+                    .collect();
+                let first_id = self.next_track_id();
+                let fallback = self.scan_options.title_fallback;
 
-            Message::SearchInput(term) => {
+                return cosmic::task::future(async move {
+                    let result = tokio::task::spawn_blocking(move || {
+                        playlist::load(&path, &library, first_id, fallback)
+                    })
+                    .await
+                    .unwrap_or_else(|err| Err(err.to_string()));
+                    Message::PlaylistLoaded(Arc::new(result))
+                });
+            }
+
+            Message::PlaylistLoaded(result) => {
+                let loaded = match result.as_ref() {
+                    Ok(loaded) => loaded,
+                    Err(err) => {
+                        return self.update(Message::Error(fl!("playlist-failed", error = err)));
+                    }
+                };
+                for entry in &loaded.missing {
+                    eprintln!("Skipped playlist entry {}: not a local audio file", entry);
+                }
+                let Some(&first) = loaded.tracks.first() else {
+                    return self.update(Message::Error(fl!("playlist-empty")));
+                };
+
+                // The tracks of an earlier playlist leave with the queue they were in.
+                self.outside_files = loaded.outside.clone();
+                self.queue = Queue::new(loaded.tracks.clone(), first);
+                self.similar_reasons.clear();
+                if let Some(track) = self.find_track(first) {
+                    let uri = track.uri.clone();
+                    self.switch_track(uri);
+                }
+
+                if !loaded.missing.is_empty() {
+                    let count = loaded.missing.len();
+                    return self.update(Message::Error(fl!("playlist-missing", count = count)));
+                }
+            }
+
+            Message::FileDropped(path) => {
+                if playlist::is_playlist(&path) {
+                    return self.update(Message::LoadPlaylist(path));
+                }
+                eprintln!("Ignoring dropped file {}", path.display());
+            }
+
+            Message::StartPlayingNewTrack(uri) => {
+                if let Some(track) = self.scanned_files.iter().find(|file| file.uri == uri) {
+                    let ids = self.scanned_files.iter().map(|file| file.id).collect();
+                    self.queue = Queue::new(ids, track.id);
+                    self.similar_reasons.clear();
+                }
+                self.switch_track(uri);
+            }
+
+            Message::PlayAlbumFrom(index, id) => {
+                let ids: Vec<usize> = self
+                    .album_tracks(index)
+                    .iter()
+                    .map(|file| file.id)
+                    .collect();
+                if let Some(track) = self.find_track(id) {
+                    let uri = track.uri.clone();
+                    self.queue = Queue::new(ids, id);
+                    self.similar_reasons.clear();
+                    self.switch_track(uri);
+                }
+            }
+
+            Message::PauseCurrentTrack => {
+                self.cancel_crossfade();
+                self.audio_player.pause();
+                self.playback.pause();
+                self.save_resume_position();
+                self.fire_hook(HookEvent::Paused);
+                self.update_presence();
+            }
+
+            Message::ResumeCurrentTrack => {
+                self.audio_player.play();
+                self.playback.resume();
+                self.fire_hook(HookEvent::Resumed);
+                self.update_presence();
+            }
+
+            Message::TogglePlayPause => {
+                return match self.playback.status {
+                    PlayState::Playing => self.update(Message::PauseCurrentTrack),
+                    PlayState::Paused => self.update(Message::ResumeCurrentTrack),
+                    // Nothing loaded yet: pick up where the queue is, keeping it as it is, or
+                    // start from the top.
+                    PlayState::Idle => {
+                        let queued = self
+                            .queue
+                            .current()
+                            .and_then(|id| self.find_track(id))
+                            .map(|file| file.uri.clone());
+                        if let Some(uri) = queued {
+                            self.switch_track(uri);
+                            return Task::none();
+                        }
+
+                        match self.scanned_files.first().map(|file| file.uri.clone()) {
+                            Some(uri) => self.update(Message::StartPlayingNewTrack(uri)),
+                            None => Task::none(),
+                        }
+                    }
+                };
+            }
+
+            // Shows an error as a toast, which goes away by itself.
+            Message::Error(why) => {
+                eprintln!("{}", why);
+                return self
+                    .toasts
+                    .push(widget::toaster::Toast::new(why))
+                    .map(cosmic::Action::App);
+            }
+
+            Message::OpenError(why) => {
+                let mut source: &dyn std::error::Error = why.as_ref();
+                let mut string = fl!("file-chooser-failed", error = source.to_string());
+                while let Some(new_source) = source.source() {
+                    string.push_str(&format!("\n    {new_source}"));
+                    source = new_source;
+                }
+                return self.update(Message::Error(string));
+            }
+
+            Message::CloseToast(id) => {
+                self.toasts.remove(id);
+            }
+
+            Message::SearchExpand => {
+                self.search_expanded = true;
+            }
+
+            Message::SearchMinimize => {
+                self.search_term = "".to_string();
+                self.search_expanded = false;
+                self.search_results = None;
+                self.search_generation += 1;
+                self.forget_song_list();
+                self.hovered_track = None;
+                self.preview = None;
+            }
+
+            Message::SearchInput(term) => {
                 self.search_term = term;
+                self.search_generation += 1;
+
+                if self.search_term.is_empty() {
+                    self.search_results = None;
+                    self.forget_song_list();
+                } else {
+                    let generation = self.search_generation;
+                    return cosmic::task::future(async move {
+                        tokio::time::sleep(SEARCH_DEBOUNCE).await;
+                        Message::SearchDebounced(generation)
+                    });
+                }
+            }
+
+            Message::SearchDebounced(generation) => {
+                if generation == self.search_generation {
+                    return self.run_search();
+                }
+            }
+
+            Message::SearchResults(generation, results) => {
+                // Results for a term that has since been edited are thrown away.
+                if generation == self.search_generation {
+                    self.search_results = Some(results);
+                    self.forget_song_list();
+                }
+            }
+
+            Message::Cancelled => {}
+            Message::CloseError => {
+                self.missing_plugins.clear();
+            }
+            Message::PluginsChecked(missing) => {
+                self.missing_plugins = missing;
+            }
+            Message::FileRead(_, _) => {}
+
+            Message::ToggleContextPage(context_page) => {
+                if self.context_page == context_page {
+                    // Close the context drawer if the toggled context page is the same.
+                    self.core.window.show_context = !self.core.window.show_context;
+                } else {
+                    // Open the context drawer to display the requested context page.
+                    self.context_page = context_page;
+                    self.core.window.show_context = true;
+                }
+
+                if self.core.window.show_context && context_page == ContextPage::Playback {
+                    return self.update(Message::CheckBluetooth);
+                }
+            }
+            Message::Scrub(value) => {
+                self.scrub(value);
+            }
+            Message::ListScrolled(viewport) => {
+                self.list_scroll.update(viewport);
+                if let Some(spot) = self.scroll_spot() {
+                    self.scroll_offsets.insert(spot, self.list_scroll.offset());
+                }
+            }
+            Message::StartColumnDrag(column) => {
+                let widths = self.config.track_columns;
+                self.column_drag = Some(ColumnDrag {
+                    column,
+                    start_x: None,
+                    start: widths,
+                    widths,
+                });
+            }
+            Message::DragColumn(x, width) => {
+                let shared = (width - self.fixed_row_width()).max(1.0);
+                if let Some(drag) = &mut self.column_drag {
+                    let start_x = *drag.start_x.get_or_insert(x);
+                    let portions_per_pixel = f32::from(drag.start.total()) / shared;
+                    drag.widths = drag
+                        .start
+                        .dragged(drag.column, (x - start_x) * portions_per_pixel);
+                }
+            }
+            Message::EndColumnDrag => {
+                let Some(drag) = self.column_drag.take() else {
+                    return Task::none();
+                };
+                let Some(handler) = &self.config_handler else {
+                    self.config.track_columns = drag.widths;
+                    return Task::none();
+                };
+                if let Err(err) = self.config.set_track_columns(handler, drag.widths) {
+                    eprintln!("Failed to save the column widths: {}", err);
+                }
+            }
+            Message::SetTileSize(size) => {
+                self.tile_size = size.clamp(*TILE_SIZES.start(), *TILE_SIZES.end());
+                self.save_setting("tile size", self.tile_size, Config::set_tile_size);
+                return self.schedule_cover_scaling();
+            }
+            Message::Rescaled(scale_factor) => {
+                self.scale_factor = scale_factor;
+                return self.schedule_cover_scaling();
+            }
+            Message::ScaleCovers(size) => {
+                // The grid was resized again while waiting.
+                if size != self.physical_tile_size() {
+                    return Task::none();
+                }
+                if size != self.scaled_size {
+                    self.scaled_covers.clear();
+                    self.scaled_size = size;
+                }
+
+                let covers: Vec<(String, PathBuf)> = self
+                    .albums
+                    .iter()
+                    .filter(|album| album.cover_ready)
+                    .map(|album| {
+                        let key = artwork::album_key(&album.album_artist, &album.album);
+                        (key, PathBuf::from(&album.cached_cover_path))
+                    })
+                    .filter(|(key, _)| !self.scaled_covers.contains_key(key))
+                    .collect();
+                if covers.is_empty() {
+                    return Task::none();
+                }
+
+                return cosmic::task::future(async move {
+                    let scaled =
+                        tokio::task::spawn_blocking(move || artwork::scale_covers(covers, size))
+                            .await
+                            .unwrap_or_default();
+                    Message::CoversScaled(size, Arc::new(scaled))
+                });
+            }
+            Message::CoversScaled(size, scaled) => {
+                if size == self.scaled_size {
+                    for (key, path) in scaled.iter() {
+                        self.scaled_covers
+                            .insert(key.clone(), image::Handle::from_path(path));
+                    }
+                }
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+            }
+            Message::MoveCursor(step) => {
+                let tracks = self.listed_tracks();
+                let Some(last) = tracks.len().checked_sub(1) else {
+                    return Task::none();
+                };
+
+                let index = match tracks.iter().position(|(id, _)| Some(*id) == self.cursor) {
+                    Some(index) => index.saturating_add_signed(step).min(last),
+                    None => 0,
+                };
+                let (id, top) = tracks[index];
+                self.cursor = Some(id);
+
+                let row_height = self.row_height();
+                let offset = top.and_then(|top| self.list_scroll.reveal(top, top + row_height));
+                if let Some(offset) = offset {
+                    self.list_scroll.restore(offset);
+                    return scrollable::scroll_to(
+                        PAGE_SCROLL.clone(),
+                        AbsoluteOffset { x: 0.0, y: offset },
+                    );
+                }
+            }
+            Message::PlayCursor => {
+                let listed = self
+                    .listed_tracks()
+                    .iter()
+                    .any(|(id, _)| Some(*id) == self.cursor);
+                let cursor = self.cursor.filter(|_| listed);
+                if let Some(file) = cursor.and_then(|id| self.find_track(id)) {
+                    return self.update(Message::StartPlayingNewTrack(file.uri.clone()));
+                }
+            }
+            Message::GoBack => {
+                if self.core.window.show_context {
+                    self.core.window.show_context = false;
+                } else if self.selected_album.is_some() {
+                    return self.update(Message::CloseAlbum);
+                } else if self.selected_artist.is_some() {
+                    return self.update(Message::CloseArtist);
+                } else if self.selected_label.is_some() {
+                    return self.update(Message::CloseLabel);
+                }
+            }
+            Message::SwitchPage(step) => {
+                let pages: Vec<nav_bar::Id> = self.nav.iter().collect();
+                let active = self.nav.active();
+                if let Some(index) = pages.iter().position(|id| *id == active) {
+                    let index = index.saturating_add_signed(step).min(pages.len() - 1);
+                    return self.on_nav_select(pages[index]);
+                }
+            }
+            Message::PollGamepad => {
+                let tasks: Vec<Task<Message>> = gamepad::presses()
+                    .into_iter()
+                    .map(|action| {
+                        self.update(match action {
+                            PadAction::Up => Message::MoveCursor(-1),
+                            PadAction::Down => Message::MoveCursor(1),
+                            PadAction::Left => Message::SwitchPage(-1),
+                            PadAction::Right => Message::SwitchPage(1),
+                            PadAction::Activate => Message::PlayCursor,
+                            PadAction::Back => Message::GoBack,
+                            PadAction::Previous => Message::SkipPrev,
+                            PadAction::Next => Message::SkipNext,
+                            PadAction::PlayPause => Message::TogglePlayPause,
+                        })
+                    })
+                    .collect();
+                return Task::batch(tasks);
+            }
+            Message::ZoomAlbumGrid(delta) => {
+                let steps = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    // Touchpads scroll in pixels, many events to a gesture.
+                    mouse::ScrollDelta::Pixels { y, .. } => y / 50.0,
+                };
+                if self.modifiers.command() && steps != 0.0 {
+                    return self.update(Message::SetTileSize(
+                        self.tile_size + steps * TILE_ZOOM_STEP,
+                    ));
+                }
+            }
+            Message::GroupByAlbum(enabled) => {
+                self.group_by_album = enabled;
+                self.save_setting("grouping", enabled, Config::set_group_by_album);
+                // Rows move around, so the old position means nothing in the new layout.
+                if let Some(spot) = self.scroll_spot() {
+                    self.scroll_offsets.remove(&spot);
+                }
+                return self.restore_scroll();
+            }
+            Message::ToggleAlbumGroup(key) => {
+                if !self.collapsed_albums.remove(&key) {
+                    self.collapsed_albums.insert(key);
+                }
+            }
+            Message::SetCrossfade(index) => {
+                if let Some(length) = player::crossfade::LENGTHS.get(index).copied() {
+                    self.crossfade_length = length;
+                    self.save_setting("crossfade length", length, Config::set_crossfade_length);
+                }
+            }
+            Message::SetResumeLength(index) => {
+                if let Some(length) = resume::MIN_LENGTHS.get(index) {
+                    self.resume_rules.min_length = *length;
+                    self.save_setting(
+                        "resume settings",
+                        self.resume_rules.clone(),
+                        Config::set_resume_rules,
+                    );
+                }
+            }
+            Message::ResumeOnly(only) => {
+                self.resume_rules.only = only;
+                self.save_setting(
+                    "resume settings",
+                    self.resume_rules.clone(),
+                    Config::set_resume_rules,
+                );
+            }
+            Message::SetEndOfQueue(index) => {
+                if let Some(action) = EndOfQueue::ALL.get(index).copied() {
+                    self.end_of_queue = action;
+                    self.save_setting("end of queue action", action, Config::set_end_of_queue);
+                }
+            }
+            Message::SetOutputBuffer(index) => {
+                if let Some(buffer) = player::output::BUFFER_SIZES.get(index) {
+                    self.output_settings.buffer = *buffer;
+                    self.audio_player.set_output(self.output_settings);
+                    self.save_setting("output buffer", *buffer, Config::set_output_buffer);
+                }
+            }
+            Message::SetDownmix(index) => {
+                if let Some(downmix) = Downmix::ALL.get(index) {
+                    self.output_settings.downmix = *downmix;
+                    self.audio_player.set_output(self.output_settings);
+                    self.save_setting("downmix", *downmix, Config::set_downmix);
+                }
+            }
+            Message::AnnounceTracks(enabled) => {
+                self.announce_tracks = enabled;
+                self.save_setting("announcements", enabled, Config::set_announce_tracks);
+            }
+            Message::ScreenReaderDetected(enabled) => {
+                self.announce_tracks |= enabled;
+            }
+            Message::SetMotionMode(index) => {
+                let Some(mode) = MotionMode::ALL.get(index) else {
+                    return Task::none();
+                };
+                self.motion.mode = *mode;
+                self.save_setting("motion setting", *mode, Config::set_motion_mode);
+
+                if self.motion.mode == MotionMode::Automatic {
+                    return self.update(Message::CheckMotion);
+                }
+            }
+            Message::CheckMotion => {
+                return cosmic::task::future(async {
+                    Message::MotionDetected(motion::detect().await)
+                });
+            }
+            Message::MotionDetected(detected) => {
+                self.motion.detected = detected;
+            }
+            Message::SetTouchMode(index) => {
+                if let Some(mode) = TouchMode::ALL.get(index) {
+                    self.touch.mode = *mode;
+                    self.save_setting("touch setting", *mode, Config::set_touch_mode);
+                }
+            }
+            Message::Touch(event) => {
+                self.touch.detected = true;
+
+                let Some((gesture, started)) = self.gestures.handle(event) else {
+                    return Task::none();
+                };
+                let near_start = |pressed: Instant| {
+                    pressed.saturating_duration_since(started) <= PRESS_MATCH
+                        && started.saturating_duration_since(pressed) <= PRESS_MATCH
+                };
+                let on_controls = self.controls_pressed.take().is_some_and(near_start);
+                let on_track = self
+                    .track_pressed
+                    .take()
+                    .filter(|(_, pressed)| near_start(*pressed))
+                    .map(|(id, _)| id);
+
+                match gesture {
+                    Gesture::SwipeLeft if on_controls => return self.update(Message::SkipNext),
+                    Gesture::SwipeRight if on_controls => return self.update(Message::SkipPrev),
+                    Gesture::LongPress => {
+                        if let Some(id) = on_track {
+                            return self.update(Message::ShowTrackInfo(id));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Message::ControlsPressed => {
+                self.controls_pressed = Some(Instant::now());
+            }
+            Message::TrackPressed(id) => {
+                self.track_pressed = Some((id, Instant::now()));
+            }
+            Message::Announced(id) => {
+                if let Some(id) = id {
+                    self.announcement_id = id;
+                }
+            }
+            Message::SeekToCue(position) => {
+                self.seek_to(position);
+            }
+            Message::ShowCues(enabled) => {
+                self.show_cues = enabled;
+            }
+            Message::ScrollTitles(enabled) => {
+                self.scroll_titles = enabled;
+            }
+            Message::PreviewOnHover(enabled) => {
+                self.preview_on_hover = enabled;
+                self.save_setting("preview setting", enabled, Config::set_preview_on_hover);
+                if !enabled {
+                    self.preview = None;
+                }
+            }
+            Message::HoverTrack(id) => {
+                self.hovered_track = Some(id);
+                return cosmic::task::future(async move {
+                    tokio::time::sleep(PREVIEW_DELAY).await;
+                    Message::StartPreview(id)
+                });
+            }
+            Message::UnhoverTrack(id) => {
+                if self.hovered_track == Some(id) {
+                    self.hovered_track = None;
+                }
+                if self.preview.as_ref().map(Preview::track) == Some(id) {
+                    self.preview = None;
+                }
+            }
+            Message::StartPreview(id) => {
+                // Only once the pointer has stayed put, and never for what's already playing.
+                let previewing = self.preview.as_ref().map(Preview::track);
+                if self.hovered_track == Some(id) && previewing != Some(id) {
+                    let playing = self.playback.current == Some(id) && self.playback.is_playing();
+                    if let Some(file) = self.find_track(id).filter(|_| !playing) {
+                        self.preview = Some(Preview::start(file));
+                    }
+                }
+            }
+            Message::PreviewTick => {
+                if let Some(preview) = &mut self.preview {
+                    if !preview.step() {
+                        self.preview = None;
+                    }
+                }
+            }
+            Message::ConversionPreset(index) => {
+                if let Some(preset) = TranscodePreset::ALL.get(index) {
+                    self.conversion_job.preset = *preset;
+                }
+            }
+            Message::ConversionReplayGain(enabled) => {
+                self.conversion_job.propagate_replaygain = enabled;
+            }
+            Message::SetLimiter(enabled) => {
+                self.filter_settings.limiter = enabled;
+                self.apply_filters();
+            }
+            Message::SetVocalReduction(enabled) => {
+                self.filter_settings.vocal_reduction = enabled;
+                self.apply_filters();
+            }
+            Message::SetCrossfeed(index) => {
+                if let Some(crossfeed) = Crossfeed::ALL.get(index) {
+                    self.filter_settings.crossfeed = *crossfeed;
+                    self.apply_filters();
+                }
+            }
+            Message::ImportEqualizer => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new().title(fl!("import-equalizer"));
+
+                    match dialog.open_file().await {
+                        Ok(response) => match response.url().to_file_path() {
+                            Ok(path) => Message::EqualizerImported(ParametricEq::load(&path)),
+                            Err(_) => Message::Error(fl!(
+                                "not-a-local-file",
+                                url = response.url().to_string()
+                            )),
+                        },
+
+                        Err(file_chooser::Error::Cancelled) => Message::Cancelled,
+
+                        Err(why) => Message::OpenError(Arc::new(why)),
+                    }
+                });
+            }
+            Message::EqualizerImported(result) => match result {
+                Ok(equalizer) => {
+                    self.filter_settings.equalizer = Some(equalizer);
+                    self.apply_filters();
+                    self.remember_device_profile();
+                }
+                Err(err) => return self.update(Message::Error(err)),
+            },
+            Message::RemoveEqualizer => {
+                self.filter_settings.equalizer = None;
+                self.apply_filters();
+                self.remember_device_profile();
+            }
+            Message::SetGain(gain) => {
+                self.filter_settings.gain = gain;
+                self.apply_filters();
+                self.remember_device_profile();
+            }
+            Message::CheckOutputDevice => {
+                return cosmic::task::future(async {
+                    let device = tokio::task::spawn_blocking(player::devices::default_output)
+                        .await
+                        .unwrap_or_default();
+                    Message::OutputDeviceDetected(device)
+                });
+            }
+            Message::OutputDeviceDetected(device) => {
+                // Keep what's set when the sound server can't tell.
+                if device.is_none() || device == self.output_device {
+                    return Task::none();
+                }
+
+                let first = self.output_device.is_none();
+                self.output_device = device;
+
+                let profile = self
+                    .output_device
+                    .as_ref()
+                    .and_then(|device| self.device_profiles.get(device))
+                    .cloned();
+                let profile = match profile {
+                    Some(profile) => profile,
+                    // Whatever was set before the device was known belongs to it.
+                    None if first => {
+                        self.remember_device_profile();
+                        return Task::none();
+                    }
+                    // Devices seen for the first time start out flat.
+                    None => DeviceProfile::default(),
+                };
+                profile.apply(&mut self.filter_settings);
+                self.apply_filters();
+            }
+            Message::Karaoke(enabled) => {
+                if self.karaoke == enabled {
+                    return Task::none();
+                }
+                self.karaoke = enabled;
+
+                // The nav bar and header would only take room from the lyrics.
+                self.core.nav_bar_set_toggled(!enabled);
+                self.core.window.show_headerbar = !enabled;
+
+                let mode = if enabled {
+                    window::Mode::Fullscreen
+                } else {
+                    window::Mode::Windowed
+                };
+                return match self.core.main_window_id() {
+                    Some(id) => window::set_mode(id, mode),
+                    None => Task::none(),
+                };
+            }
+            Message::SetNetworkMode(index) => {
+                let Some(mode) = NetworkMode::ALL.get(index) else {
+                    return Task::none();
+                };
+                let was_online = self.network.is_online();
+                self.network.mode = *mode;
+                self.save_setting("network setting", *mode, Config::set_network_mode);
+
+                if self.network.mode == NetworkMode::Automatic {
+                    return self.update(Message::CheckConnectivity);
+                }
+                return self.connectivity_changed(was_online);
+            }
+            Message::LocalOnly(enabled) => {
+                self.local_only = enabled;
+                self.forget_song_list();
+                self.save_setting("streaming setting", enabled, Config::set_local_only);
+            }
+            Message::DiscographyLookup(enabled) => {
+                self.discography_lookup = enabled;
+                if let Some(artist) = self.selected_artist.clone().filter(|_| enabled) {
+                    return self.update(Message::FetchDiscography(artist));
+                }
+            }
+            Message::FetchDiscography(artist) => {
+                if artist.is_empty()
+                    || self.discographies.contains_key(&artist)
+                    || self.discography_loading.is_some()
+                {
+                    return Task::none();
+                }
+
+                self.discography_loading = Some(artist.clone());
+                self.discography_error = None;
+
+                let task = cosmic::task::future(async move {
+                    let name = artist.clone();
+                    let result =
+                        tokio::task::spawn_blocking(move || musicbrainz::fetch_discography(&name))
+                            .await
+                            .unwrap_or_else(|err| Err(err.to_string()));
+                    Message::DiscographyFetched(artist, result)
+                });
+                return self.when_online(task);
+            }
+            Message::DiscographyFetched(artist, result) => {
+                self.discography_loading = None;
+                match result {
+                    Ok(discography) => {
+                        self.discographies.insert(artist, discography);
+                    }
+                    Err(err) => {
+                        eprintln!("Failed to look up {} on MusicBrainz: {}", artist, err);
+                        self.discography_error = Some(err);
+                    }
+                }
+            }
+            Message::ScrobbleUrl(url) => {
+                self.scrobble_endpoint.url = url;
+                if let Err(err) = scrobble::write_endpoint(&self.scrobble_endpoint) {
+                    eprintln!("Failed to save the scrobble endpoint: {}", err);
+                }
+            }
+            Message::ScrobbleToken(token) => {
+                self.scrobble_endpoint.token = token;
+                if let Err(err) = scrobble::write_endpoint(&self.scrobble_endpoint) {
+                    eprintln!("Failed to save the scrobble endpoint: {}", err);
+                }
+            }
+            Message::HookCommand(command) => {
+                self.hooks.command = command;
+                self.save_setting("hooks", self.hooks.clone(), Config::set_hooks);
+            }
+            Message::HookUrl(url) => {
+                self.hooks.url = url;
+                self.save_setting("hooks", self.hooks.clone(), Config::set_hooks);
+            }
+            Message::DiscordPresence(enabled) => {
+                self.discord_presence = enabled;
+                if enabled {
+                    self.update_presence();
+                } else {
+                    discord::show(None);
+                }
+            }
+            Message::AlarmEnabled(enabled) => {
+                self.alarm.enabled = enabled;
+                self.save_setting("alarm", self.alarm, Config::set_alarm);
+            }
+            Message::AlarmTime(time) => {
+                if self.alarm.set_time(&time) {
+                    self.save_setting("alarm", self.alarm, Config::set_alarm);
+                }
+                self.alarm_time = time;
+            }
+            Message::SetAlarmSource(index) => {
+                if let Some(source) = AlarmSource::ALL.get(index) {
+                    self.alarm.source = *source;
+                    self.save_setting("alarm", self.alarm, Config::set_alarm);
+                }
+            }
+            Message::CheckAlarm => {
+                if let Some(today) = self.alarm.due(self.alarm_rang) {
+                    self.alarm_rang = Some(today);
+                    return self.ring_alarm();
+                }
+            }
+            Message::StopAlarm => {
+                if self.alarm_ramp.take().is_some() || self.playback.is_playing() {
+                    self.stop_playback();
+                }
+            }
+            Message::NowPlayingFile(enabled) => {
+                self.now_playing_output.enabled = enabled;
+                self.save_setting(
+                    "now playing settings",
+                    self.now_playing_output.clone(),
+                    Config::set_now_playing_output,
+                );
+                self.write_now_playing();
+            }
+            Message::NowPlayingTemplate(template) => {
+                self.now_playing_output.template = template;
+                self.save_setting(
+                    "now playing settings",
+                    self.now_playing_output.clone(),
+                    Config::set_now_playing_output,
+                );
+                self.write_now_playing();
+            }
+            Message::OpenNowPlayingFolder => {
+                let dir = now_playing::output_dir();
+                if let Err(err) = std::fs::create_dir_all(&dir) {
+                    eprintln!("Failed to create {}: {}", dir.display(), err);
+                }
+                if let Err(err) = open::that_detached(&dir) {
+                    eprintln!("Failed to open {}: {}", dir.display(), err);
+                }
+            }
+            Message::SubmitScrobbles => {
+                return self.submit_scrobbles();
+            }
+            Message::ScrobblesSubmitted(count, result) => {
+                self.scrobble_sending = false;
+
+                match result {
+                    Ok(()) => {
+                        self.scrobble_queue
+                            .drain(..count.min(self.scrobble_queue.len()));
+                        self.scrobble_error = None;
+                        if let Err(err) = scrobble::write_queue(&self.scrobble_queue) {
+                            eprintln!("Failed to save the scrobble queue: {}", err);
+                        }
+                        // Catch up on whatever else is waiting.
+                        return self.submit_scrobbles();
+                    }
+                    Err(err) => {
+                        eprintln!("Failed to submit listens: {}", err);
+                        self.scrobble_error = Some(err);
+                    }
+                }
+            }
+            Message::CheckConnectivity => {
+                return cosmic::task::future(async {
+                    Message::ConnectivityDetected(network::detect().await)
+                });
+            }
+            Message::DragQueueTrack(index) => {
+                self.queue_drag = Some(index);
+            }
+            Message::DropQueueTrack(index) => {
+                if let Some(from) = self.queue_drag.take() {
+                    self.queue.move_track(from, index);
+                }
+            }
+            Message::CancelQueueDrag => {
+                self.queue_drag = None;
+            }
+            Message::RemoveFromQueue(index) => {
+                self.queue_drag = None;
+                self.queue.remove(index);
+            }
+            Message::MoreLikeThis(id) => {
+                // Listens shared with other tracks are part of the score.
+                return cosmic::task::future(async move {
+                    let history = tokio::task::spawn_blocking(scrobble::read_history)
+                        .await
+                        .unwrap_or_default();
+                    Message::SimilarHistoryRead(id, Arc::new(history))
+                });
+            }
+            Message::SimilarHistoryRead(id, history) => {
+                let Some(seed) = self.find_track(id) else {
+                    return Task::none();
+                };
+                let matches = similar::similar(seed, &self.scanned_files, &history, SIMILAR_TRACKS);
+                if matches.is_empty() {
+                    return self.update(Message::Error(fl!("no-similar-tracks")));
+                }
+
+                let uri = seed.uri.clone();
+                let ids = std::iter::once(id)
+                    .chain(matches.iter().map(|track| track.id))
+                    .collect();
+                self.queue = Queue::new(ids, id);
+                self.similar_reasons.clear();
+                self.similar_reasons
+                    .extend(matches.into_iter().map(|track| (track.id, track.reasons)));
+                if self.playback.current != Some(id) {
+                    self.switch_track(uri);
+                }
+
+                // Show what was picked and why.
+                self.context_page = ContextPage::Queue;
+                self.core.window.show_context = true;
             }
+            Message::ToggleFavorite => {
+                let Some(file) = self.queue.current().and_then(|id| self.find_track(id)) else {
+                    return Task::none();
+                };
+                let path = file.saved_path.clone();
+
+                if !self.favorites.remove(&path) {
+                    self.favorites.insert(path);
+                }
+                self.save_setting(
+                    "favorites",
+                    self.favorites.iter().cloned().collect(),
+                    Config::set_favorites,
+                );
+                self.fire_hook(HookEvent::FavoriteChanged);
+            }
+            Message::LyricsLoaded(id, text) => {
+                if self.lyrics_track != Some(id) {
+                    return Task::none();
+                }
+
+                if let Some(text) = text {
+                    self.lyrics = Some(Lyrics::parse(&text));
+                    return Task::none();
+                }
+
+                let Some(query) = self.find_track(id).map(LyricsQuery::from) else {
+                    return Task::none();
+                };
+                self.lyrics_status = Some(fl!("lyrics-searching"));
+
+                let fetch = cosmic::task::future(async move {
+                    let result = tokio::task::spawn_blocking(move || lyrics::fetch(&query))
+                        .await
+                        .unwrap_or_else(|err| Err(err.to_string()));
+                    Message::LyricsFetched(id, result)
+                });
+                return self.when_online(fetch);
+            }
+            Message::LyricsFetched(id, result) => {
+                if self.lyrics_track != Some(id) {
+                    return Task::none();
+                }
+
+                match result {
+                    Ok(Some(text)) => {
+                        if let Some(file) = self.find_track(id) {
+                            if let Err(err) = lyrics::cache(&file.saved_path, &text) {
+                                eprintln!("Failed to cache lyrics: {}", err);
+                            }
+                        }
+                        self.lyrics = Some(Lyrics::parse(&text));
+                        self.lyrics_status = None;
+                    }
+                    Ok(None) => self.lyrics_status = Some(fl!("lyrics-not-found")),
+                    Err(err) => {
+                        eprintln!("Failed to fetch lyrics: {}", err);
+                        self.lyrics_status = Some(fl!("lyrics-fetch-failed"));
+                    }
+                }
+            }
+            Message::EditLyrics => {
+                let text = self
+                    .lyrics
+                    .as_ref()
+                    .map(|lyrics| {
+                        lyrics
+                            .lines
+                            .iter()
+                            .map(|line| match line.time {
+                                Some(time) => format!(
+                                    "[{:02}:{:02}.{:02}]{}",
+                                    time.as_secs() / 60,
+                                    time.as_secs() % 60,
+                                    time.subsec_millis() / 10,
+                                    line.text
+                                ),
+                                None => line.text.clone(),
+                            })
+                            .collect::<Vec<String>>()
+                            .join("\n")
+                    })
+                    .unwrap_or_default();
+                self.lyrics_editor = Some(text_editor::Content::with_text(&text));
+            }
+            Message::LyricsEdit(action) => {
+                let Some(editor) = &mut self.lyrics_editor else {
+                    return Task::none();
+                };
+                let changed = action.is_edit();
+                editor.perform(action);
+                let text = changed.then(|| editor.text());
+
+                // Kept on disk as it's typed, so quitting or crashing doesn't lose it.
+                if let Some(text) = text {
+                    if let Some(file) = self.lyrics_track.and_then(|id| self.find_track(id)) {
+                        let draft = LyricsDraft {
+                            track: file.saved_path.clone(),
+                            text,
+                        };
+                        self.save_setting("lyrics draft", Some(draft), Config::set_lyrics_draft);
+                    }
+                }
+            }
+            Message::SaveLyrics => {
+                let (Some(editor), Some(file)) = (
+                    &self.lyrics_editor,
+                    self.lyrics_track.and_then(|id| self.find_track(id)),
+                ) else {
+                    return Task::none();
+                };
+                let text = editor.text();
+
+                match lyrics::save_sidecar(&file.saved_path, &text) {
+                    Ok(_) => {
+                        self.lyrics = Some(Lyrics::parse(&text));
+                        self.lyrics_editor = None;
+                        self.lyrics_status = None;
+                        self.save_setting("lyrics draft", None, Config::set_lyrics_draft);
+                    }
+                    Err(err) => {
+                        self.lyrics_status = Some(fl!("lyrics-save-failed", error = err));
+                    }
+                }
+            }
+            Message::CancelLyricsEdit => {
+                self.lyrics_editor = None;
+                self.save_setting("lyrics draft", None, Config::set_lyrics_draft);
+            }
+            Message::SaveLyricsDraft => {
+                let Some(draft) = &self.lyrics_draft else {
+                    return Task::none();
+                };
+
+                match lyrics::save_sidecar(&draft.track, &draft.text) {
+                    Ok(_) => {
+                        let current = self.lyrics_track.and_then(|id| self.find_track(id));
+                        if current.is_some_and(|file| file.saved_path == draft.track) {
+                            self.lyrics = Some(Lyrics::parse(&draft.text));
+                        }
+                        self.lyrics_draft = None;
+                        self.lyrics_draft_error = None;
+                        self.save_setting("lyrics draft", None, Config::set_lyrics_draft);
+                    }
+                    Err(err) => {
+                        self.lyrics_draft_error = Some(fl!("lyrics-save-failed", error = err));
+                    }
+                }
+            }
+            Message::DiscardLyricsDraft => {
+                self.lyrics_draft = None;
+                self.lyrics_draft_error = None;
+                self.save_setting("lyrics draft", None, Config::set_lyrics_draft);
+            }
+            Message::CheckBluetooth => {
+                return cosmic::task::future(async {
+                    Message::BluetoothDevices(bluetooth::audio_devices().await)
+                });
+            }
+            Message::BluetoothDevices(devices) => {
+                self.bluetooth_devices = devices.unwrap_or_default();
+            }
+            Message::ConnectivityDetected(online) => {
+                let was_online = self.network.is_online();
+                self.network.detected = online;
+                return self.connectivity_changed(was_online);
+            }
+            Message::ConversionNormalize(enabled) => {
+                self.conversion_job.normalize_loudness = enabled;
+            }
+            Message::OrganizePattern(pattern) => {
+                self.organize_pattern = pattern;
+                // A preview of the old pattern must not be applied by accident.
+                self.organize_plan.clear();
+                self.organize_status = None;
+            }
+            Message::FollowSymlinks(enabled) => {
+                self.scan_options.follow_symlinks = enabled;
+                self.save_setting("scan options", self.scan_options, Config::set_scan_options);
+            }
+            Message::MergeHardlinks(enabled) => {
+                self.scan_options.merge_hardlinks = enabled;
+                self.save_setting("scan options", self.scan_options, Config::set_scan_options);
+            }
+            Message::SetTitleFallback(index) => {
+                if let Some(fallback) = TitleFallback::ALL.get(index) {
+                    self.scan_options.title_fallback = *fallback;
+                    self.save_setting("scan options", self.scan_options, Config::set_scan_options);
+                }
+            }
+            Message::SortArticles(articles) => {
+                collation::set_articles(&articles);
+                self.save_setting("sort articles", articles.clone(), Config::set_sort_articles);
+                self.sort_articles = articles;
+
+                for file in &mut self.scanned_files {
+                    collation::update_keys(file);
+                }
+                track_number::sort_library(&mut self.scanned_files);
+                self.library_changed();
+                return self.rebuild_search_index();
+            }
+            Message::SetKeyNotation(index) => {
+                if let Some(notation) = KeyNotation::ALL.get(index) {
+                    self.key_notation = *notation;
+                    self.save_setting("key notation", *notation, Config::set_key_notation);
+                }
+            }
+            Message::OrganizePreview => {
+                let folders = self.config.enabled_folders();
+                if folders.is_empty() {
+                    self.organize_status = Some(fl!("organize-no-folder"));
+                    return Task::none();
+                }
+
+                // Files stay within the folder they're in.
+                self.organize_plan = folders
+                    .iter()
+                    .flat_map(|root| {
+                        let files: Vec<MusicFile> = self
+                            .scanned_files
+                            .iter()
+                            .filter(|file| {
+                                self.config.folder_of(&file.saved_path) == Some(root.as_path())
+                            })
+                            .cloned()
+                            .collect();
+                        organize::plan(root, &self.organize_pattern, &files)
+                    })
+                    .collect();
+                self.organize_status = Some(if self.organize_plan.is_empty() {
+                    fl!("organize-no-changes")
+                } else {
+                    fl!("organize-planned", count = self.organize_plan.len())
+                });
+            }
+            Message::OrganizeApply => {
+                let mut by_folder: Vec<(PathBuf, Vec<PlannedMove>)> = Vec::new();
+                for planned in std::mem::take(&mut self.organize_plan) {
+                    let Some(root) = self.config.folder_of(&planned.from) else {
+                        continue;
+                    };
+                    match by_folder.iter_mut().find(|(folder, _)| folder.as_path() == root) {
+                        Some((_, moves)) => moves.push(planned),
+                        None => by_folder.push((root.to_path_buf(), vec![planned])),
+                    }
+                }
+
+                if !by_folder.is_empty() {
+                    self.organizing = true;
+                    return cosmic::task::future(async move {
+                        let result = tokio::task::spawn_blocking(move || {
+                            let mut result: (Vec<PlannedMove>, Vec<String>) = Default::default();
+                            for (root, moves) in by_folder {
+                                let (done, errors) = organize::apply(&root, moves);
+                                result.0.extend(done);
+                                result.1.extend(errors);
+                            }
+
+                            let moved: HashMap<PathBuf, PathBuf> = result
+                                .0
+                                .iter()
+                                .map(|planned| (planned.from.clone(), planned.to.clone()))
+                                .collect();
+                            if let Err(err) = journal::follow_moves(&moved) {
+                                eprintln!("Failed to update the edit journal: {}", err);
+                            }
+                            result
+                        })
+                        .await
+                        .unwrap_or_default();
+                        Message::OrganizeFinished(Arc::new(result))
+                    });
+                }
+            }
+            Message::OpenAlbum(index) => {
+                self.selected_album = Some(index);
+                self.reread_status = None;
+                self.load_loudness(index);
+                return self.restore_scroll();
+            }
+            Message::CloseAlbum => {
+                self.selected_album = None;
+                return self.restore_scroll();
+            }
+            Message::RereadTags(ids) => {
+                let files: Vec<(usize, PathBuf)> = ids
+                    .iter()
+                    .filter_map(|id| self.find_track(*id))
+                    .filter(|file| file.source() == TrackSource::Local && file.archive.is_none())
+                    .map(|file| (file.id, file.saved_path.clone()))
+                    .collect();
+                let fallback = self.scan_options.title_fallback;
+
+                return cosmic::task::future(async move {
+                    let result = tokio::task::spawn_blocking(move || {
+                        files
+                            .into_iter()
+                            .map(|(id, path)| (id, scan::reread(&path, id, fallback)))
+                            .collect()
+                    })
+                    .await
+                    .unwrap_or_default();
+                    Message::TagsReread(Arc::new(result))
+                });
+            }
+            Message::ShowTrackInfo(id) => {
+                let Some(file) = self.find_track(id) else {
+                    return Task::none();
+                };
+                let path = file.saved_path.clone();
+
+                self.track_info = Some(id);
+                self.track_pictures.clear();
+                self.track_pictures_error = None;
+                self.track_edits.clear();
+                self.context_page = ContextPage::TrackInfo;
+                self.core.window.show_context = true;
+
+                let journal_path = path.clone();
+                let pictures = cosmic::task::future(async move {
+                    let result =
+                        tokio::task::spawn_blocking(move || artwork::embedded_pictures(&path))
+                            .await
+                            .unwrap_or_else(|err| Err(err.to_string()));
+                    Message::TrackPictures(id, Arc::new(result))
+                });
+                let edits = cosmic::task::future(async move {
+                    let edits =
+                        tokio::task::spawn_blocking(move || journal::history(&journal_path))
+                            .await
+                            .unwrap_or_default();
+                    Message::TrackEdits(id, Arc::new(edits))
+                });
+                return Task::batch(vec![pictures, edits]);
+            }
+            Message::TrackEdits(id, edits) => {
+                if self.track_info == Some(id) {
+                    self.track_edits = edits.as_ref().clone();
+                }
+            }
+            Message::RevertEdit(index) => {
+                let (Some(id), Some(edit)) = (self.track_info, self.track_edits.get(index)) else {
+                    return Task::none();
+                };
+                let edit = edit.clone();
+                let keep_modified = self.keep_modified_times;
+
+                return cosmic::task::future(async move {
+                    let result =
+                        tokio::task::spawn_blocking(move || journal::revert(&edit, keep_modified))
+                            .await
+                            .unwrap_or_else(|err| Err(err.to_string()));
+                    Message::EditReverted(id, result)
+                });
+            }
+            Message::EditReverted(id, result) => match result {
+                // Read the track again to show what it holds now.
+                Ok(()) if self.track_info == Some(id) => {
+                    return self.update(Message::ShowTrackInfo(id));
+                }
+                Ok(()) => {}
+                Err(err) => {
+                    return self.update(Message::Error(fl!("revert-failed", error = err)));
+                }
+            },
+            Message::TrackPictures(id, result) => {
+                if self.track_info != Some(id) {
+                    return Task::none();
+                }
+
+                match result.as_ref() {
+                    Ok(pictures) => {
+                        self.track_pictures = pictures
+                            .iter()
+                            .map(|picture| {
+                                let handle = image::Handle::from_bytes(picture.data.clone());
+                                (picture.clone(), handle)
+                            })
+                            .collect();
+                    }
+                    Err(err) => {
+                        eprintln!("Failed to read pictures: {}", err);
+                        self.track_pictures_error = Some(err.clone());
+                    }
+                }
+            }
+            Message::UsePicture(index) => {
+                let Some(file) = self.track_info.and_then(|id| self.find_track(id)) else {
+                    return Task::none();
+                };
+                let Some((picture, handle)) = self.track_pictures.get(index) else {
+                    return Task::none();
+                };
+
+                let key = artwork::album_key(&file.album_artist, &file.album);
+                let picture = picture.clone();
+                let handle = handle.clone();
+
+                return cosmic::task::future(async move {
+                    let chosen = key.clone();
+                    let result = tokio::task::spawn_blocking(move || {
+                        artwork::choose_picture(&chosen, &picture)
+                    })
+                    .await
+                    .unwrap_or_else(|err| Err(err.to_string()));
+                    Message::PictureChosen(key, handle, result)
+                });
+            }
+            Message::PictureChosen(key, handle, result) => match result {
+                Ok(path) => {
+                    let is_album = |album: &&mut Album| {
+                        artwork::album_key(&album.album_artist, &album.album) == key
+                    };
+                    if let Some(album) = self.albums.iter_mut().find(is_album) {
+                        album.cached_cover_path = path.display().to_string();
+                        album.cover_ready = true;
+                    }
+
+                    // The cache file keeps its name, so an image cached by path would stay
+                    // stale. Show the picture from memory instead.
+                    self.scaled_covers.remove(&key);
+                    self.memory_covers.insert(key, handle);
+                }
+                Err(err) => {
+                    eprintln!("Failed to set cover: {}", err);
+                }
+            },
+            Message::TagsReread(result) => {
+                let mut changed = 0;
+                let mut failed = 0;
+
+                for (id, reread) in result.iter() {
+                    let Some(existing) = self.scanned_files.iter_mut().find(|file| file.id == *id)
+                    else {
+                        continue;
+                    };
+                    let Some(reread) = reread else {
+                        eprintln!(
+                            "Failed to re-read tags of {}",
+                            existing.saved_path.display()
+                        );
+                        failed += 1;
+                        continue;
+                    };
+
+                    let mut reread = reread.clone();
+                    // A measured tempo stands in for a missing tag.
+                    reread.bpm = reread.bpm.or(existing.bpm);
+                    if *existing != reread {
+                        *existing = reread;
+                        changed += 1;
+                    }
+                }
+
+                let mut status = fl!("reread-changed", count = changed);
+                if failed > 0 {
+                    status.push('\n');
+                    status.push_str(&fl!("reread-failed", count = failed));
+                }
+                self.reread_status = Some(status);
+
+                if changed == 0 {
+                    return Task::none();
+                }
+
+                // Albums are rebuilt from scratch, so find the one that was open again, even
+                // if its name changed.
+                let reopen = self.selected_album.is_some();
+                track_number::sort_library(&mut self.scanned_files);
+                self.library_changed();
+                if reopen {
+                    self.ensure_albums();
+                    self.selected_album = result.first().and_then(|(id, _)| {
+                        self.albums
+                            .iter()
+                            .position(|album| album.tracks.contains(id))
+                    });
+                }
+
+                return self.rebuild_search_index();
+            }
+            Message::FilterDecade(decade) => {
+                self.album_decade = decade;
+            }
+            Message::FilterLanguage(language) => {
+                self.album_language = language;
+            }
+            Message::OpenArtist(artist) => {
+                self.selected_artist = Some(artist.clone());
+                self.discography_error = None;
+                let restore = self.restore_scroll();
+                if self.discography_lookup {
+                    return Task::batch(vec![
+                        restore,
+                        self.update(Message::FetchDiscography(artist)),
+                    ]);
+                }
+                return restore;
+            }
+            Message::CloseArtist => {
+                self.selected_artist = None;
+                return self.restore_scroll();
+            }
+            Message::OpenLabel(label) => {
+                self.selected_label = Some(label);
+                return self.restore_scroll();
+            }
+            Message::CloseLabel => {
+                self.selected_label = None;
+                return self.restore_scroll();
+            }
+            Message::ChooseCover(target) => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new().title(fl!("choose-cover"));
+
+                    match dialog.open_file().await {
+                        Ok(response) => match response.url().to_file_path() {
+                            Ok(path) => Message::SetCover(target, path),
+                            Err(_) => Message::Error(fl!(
+                                "not-a-local-file",
+                                url = response.url().to_string()
+                            )),
+                        },
+
+                        Err(file_chooser::Error::Cancelled) => Message::Cancelled,
+
+                        Err(why) => Message::OpenError(Arc::new(why)),
+                    }
+                });
+            }
+            Message::CopyText(text) => {
+                return cosmic::iced::clipboard::write(text);
+            }
+            Message::PasteCover(target) => {
+                return cosmic::iced::clipboard::read()
+                    .map(move |contents| Message::CoverPasted(target, contents).into());
+            }
+            Message::CoverPasted(target, contents) => {
+                // Copying an image in a file manager puts its path or file URI on the clipboard.
+                let path = contents
+                    .as_deref()
+                    .and_then(|contents| contents.lines().next())
+                    .map(str::trim)
+                    .and_then(|line| match Url::parse(line) {
+                        Ok(url) => url.to_file_path().ok(),
+                        Err(_) => Some(PathBuf::from(line)),
+                    })
+                    .filter(|path| path.is_file());
+
+                match path {
+                    Some(path) => return self.update(Message::SetCover(target, path)),
+                    None => return self.update(Message::Error(fl!("clipboard-no-image"))),
+                }
+            }
+            Message::SetCover(target, path) => {
+                let (key, files): (String, Vec<PathBuf>) = match target {
+                    ArtworkTarget::Album(index) => match self.albums.get(index) {
+                        Some(album) => (
+                            artwork::album_key(&album.album_artist, &album.album),
+                            album
+                                .tracks
+                                .iter()
+                                .filter_map(|id| self.find_track(*id))
+                                .map(|file| file.saved_path.clone())
+                                .collect(),
+                        ),
+                        None => return Task::none(),
+                    },
+                    ArtworkTarget::Track(id) => match self.find_track(id) {
+                        Some(file) => (
+                            artwork::track_key(&file.saved_path),
+                            vec![file.saved_path.clone()],
+                        ),
+                        None => return Task::none(),
+                    },
+                };
+                let embed = self.embed_custom_covers;
+                let keep_modified = self.keep_modified_times;
+
+                return cosmic::task::future(async move {
+                    let result = tokio::task::spawn_blocking(move || {
+                        let image = std::fs::read(&path).map_err(|err| err.to_string())?;
+                        let cover = artwork::set_custom_cover(&key, &image)?;
+
+                        if embed {
+                            for file in &files {
+                                let edited = journal::edit(
+                                    file,
+                                    Field::FrontCover,
+                                    Some(&image),
+                                    keep_modified,
+                                );
+                                if let Err(err) = edited {
+                                    eprintln!("Failed to embed cover in {}: {}", file.display(), err);
+                                }
+                            }
+                        }
+
+                        Ok(cover)
+                    })
+                    .await
+                    .unwrap_or_else(|err| Err(err.to_string()));
+
+                    Message::CoverSet(target, result)
+                });
+            }
+            Message::CoverSet(target, result) => match result {
+                Ok(path) => {
+                    if let ArtworkTarget::Album(index) = target {
+                        if let Some(album) = self.albums.get_mut(index) {
+                            album.cached_cover_path = path.display().to_string();
+                            album.cover_ready = true;
+                            let key = artwork::album_key(&album.album_artist, &album.album);
+                            self.memory_covers.remove(&key);
+                            self.scaled_covers.remove(&key);
+                            return self.schedule_cover_scaling();
+                        }
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Failed to set cover: {}", err);
+                }
+            },
+            Message::AlbumsBuilt(generation, built) => {
+                self.albums_building = false;
+                if self.albums_loaded {
+                    return Task::none();
+                }
+
+                // The library changed while they were being built, so build them again.
+                if generation != self.library_generation {
+                    return match self.nav.active_data::<Page>() {
+                        Some(Page::Albums) => self.build_albums_later(),
+                        _ => Task::none(),
+                    };
+                }
+
+                let (albums, covers) = Arc::unwrap_or_clone(built);
+                self.albums = albums;
+                self.pending_covers.extend(covers);
+                self.albums_loaded = true;
+                return self.rebuild_search_index();
+            }
+            Message::CoversCached(report) => {
+                for album in self.albums.iter_mut() {
+                    let key = artwork::album_key(&album.album_artist, &album.album);
+                    if report.cached.contains(&key) {
+                        album.cover_ready = true;
+                    }
+                }
+                for (key, data) in &report.in_memory {
+                    self.memory_covers
+                        .insert(key.clone(), image::Handle::from_bytes(data.clone()));
+                }
+                for failure in &report.failed {
+                    eprintln!("{}", failure);
+                }
+                self.cover_errors.extend(report.failed.iter().cloned());
+                return self.schedule_cover_scaling();
+            }
+            Message::EmbedCovers(embed) => {
+                self.embed_custom_covers = embed;
+            }
+            Message::KeepModifiedTimes(keep) => {
+                self.keep_modified_times = keep;
+            }
+            Message::EmbedMissingArtwork => {
+                if self.embed_job.is_some() {
+                    return Task::none();
+                }
+
+                let rebuild_search = self.ensure_albums();
+
+                let candidates = self
+                    .albums
+                    .iter()
+                    .map(|album| EmbedCandidate {
+                        key: artwork::album_key(&album.album_artist, &album.album),
+                        cached_cover: PathBuf::from(&album.cached_cover_path),
+                        files: album
+                            .tracks
+                            .iter()
+                            .filter_map(|id| self.find_track(*id))
+                            .map(|file| file.saved_path.clone())
+                            .collect(),
+                    })
+                    .collect::<Vec<EmbedCandidate>>();
+
+                let progress = Arc::new(EmbedProgress::default());
+                let keep_modified = self.keep_modified_times;
+                self.embed_job = Some(progress.clone());
+                self.embed_report = None;
+                self.show_jobs();
+
+                let job = cosmic::task::future(async move {
+                    let report = tokio::task::spawn_blocking(move || {
+                        artwork::embed_missing_covers(candidates, &progress, keep_modified)
+                    })
+                    .await
+                    .unwrap_or_else(|err| EmbedReport {
+                        failed: vec![err.to_string()],
+                        ..Default::default()
+                    });
+
+                    Message::EmbedMissingArtworkFinished(report)
+                });
+
+                if rebuild_search {
+                    return Task::batch(vec![job, self.rebuild_search_index()]);
+                }
+                return job;
+            }
+            Message::EmbedMissingArtworkFinished(report) => {
+                self.embed_job = None;
+                self.embed_report = Some(report);
+            }
+            Message::CloseEmbedReport => {
+                self.embed_report = None;
+            }
+            Message::VerifyLibrary => {
+                let ids = self.scanned_files.iter().map(|file| file.id).collect();
+                return self.update(Message::VerifyFiles(ids));
+            }
+            Message::VerifyFiles(ids) => {
+                if self.verify_job.is_some() {
+                    return Task::none();
+                }
+
+                let candidates: Vec<VerifyCandidate> = ids
+                    .iter()
+                    .filter_map(|id| self.find_track(*id))
+                    .filter(|file| file.source() == TrackSource::Local)
+                    .map(|file| VerifyCandidate {
+                        id: file.id,
+                        path: file.saved_path.clone(),
+                        duration: file.duration,
+                    })
+                    .collect();
+
+                let progress = Arc::new(VerifyProgress::default());
+                self.verify_job = Some(progress.clone());
+                self.verify_report = None;
+                self.show_jobs();
+
+                return cosmic::task::future(async move {
+                    let report = tokio::task::spawn_blocking(move || {
+                        verify::verify_files(candidates, &progress)
+                    })
+                    .await
+                    .unwrap_or_else(|err| VerifyReport {
+                        failed: vec![err.to_string()],
+                        ..Default::default()
+                    });
+
+                    Message::VerifyFinished(report)
+                });
+            }
+            Message::VerifyFinished(report) => {
+                self.verify_job = None;
+
+                for id in &report.passed {
+                    self.damaged.remove(id);
+                }
+                self.damaged.extend(report.damaged.iter().cloned());
+
+                self.verify_report = Some(report);
+            }
+            Message::MeasureLoudness(index) => {
+                if self.loudness_job.is_some() {
+                    return Task::none();
+                }
+                let Some(album) = self.albums.get(index) else {
+                    return Task::none();
+                };
+
+                let key = artwork::album_key(&album.album_artist, &album.album);
+                let files: Vec<PathBuf> = self
+                    .album_tracks(index)
+                    .into_iter()
+                    .filter(|file| file.source() == TrackSource::Local)
+                    .map(|file| file.saved_path.clone())
+                    .collect();
+
+                let progress = Arc::new(LoudnessProgress::default());
+                self.loudness_job = Some(progress.clone());
+
+                return cosmic::task::future(async move {
+                    let result = tokio::task::spawn_blocking(move || {
+                        loudness::analyze_album(files, &progress)
+                    })
+                    .await
+                    .unwrap_or_else(|err| Err(err.to_string()));
+
+                    Message::LoudnessMeasured(key, result)
+                });
+            }
+            Message::LoudnessMeasured(key, result) => {
+                self.loudness_job = None;
+
+                match result {
+                    Ok(measured) => {
+                        if let Err(err) = loudness::cache(&key, &measured) {
+                            eprintln!("Failed to cache loudness: {}", err);
+                        }
+                        self.loudness.insert(key, measured);
+                    }
+                    Err(err) => {
+                        eprintln!("Failed to measure loudness: {}", err);
+                    }
+                }
+            }
+            Message::DetectTempo => {
+                if self.tempo_job.is_some() {
+                    return Task::none();
+                }
+
+                let files: Vec<PathBuf> = self
+                    .scanned_files
+                    .iter()
+                    .filter(|file| file.bpm.is_none() && file.archive.is_none())
+                    .filter(|file| file.source() == TrackSource::Local)
+                    .map(|file| file.saved_path.clone())
+                    .collect();
+                if files.is_empty() {
+                    return Task::none();
+                }
+
+                let progress = Arc::new(TempoProgress::default());
+                self.tempo_job = Some(progress.clone());
+
+                return cosmic::task::future(async move {
+                    let detected =
+                        tokio::task::spawn_blocking(move || tempo::detect_all(files, &progress))
+                            .await
+                            .unwrap_or_default();
+
+                    Message::TempoDetected(detected)
+                });
+            }
+            Message::TempoDetected(detected) => {
+                self.tempo_job = None;
+                if detected.is_empty() {
+                    return Task::none();
+                }
+
+                self.tempos.extend(detected);
+                if let Err(err) = tempo::write_cache(&self.tempos) {
+                    eprintln!("Failed to cache tempos: {}", err);
+                }
+                tempo::fill_in(&mut self.scanned_files, &self.tempos);
+                self.forget_song_list();
+            }
+            Message::TempoFrom(from) => {
+                self.tempo_range.0 = from.trim().parse().ok();
+                self.tempo_from = from;
+                self.forget_song_list();
+            }
+            Message::TempoTo(to) => {
+                self.tempo_range.1 = to.trim().parse().ok();
+                self.tempo_to = to;
+                self.forget_song_list();
+            }
+            Message::ShuffleTempo => {
+                let files: Vec<&MusicFile> = self
+                    .scanned_files
+                    .iter()
+                    .filter(|file| self.track_visible(file))
+                    .collect();
+                let ids = autodj::shuffled(&files);
+
+                let Some(first) = ids.first().and_then(|id| self.find_track(*id)) else {
+                    return Task::none();
+                };
+                let (start, uri) = (first.id, first.uri.clone());
+                self.queue = Queue::new(ids, start);
+                self.similar_reasons.clear();
+                self.switch_track(uri);
+            }
+            Message::ExportQueue => {
+                if self.export_job.is_some() || self.queue.tracks().is_empty() {
+                    return Task::none();
+                }
+
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new().title(fl!("export-queue"));
+
+                    match dialog.open_folder().await {
+                        Ok(response) => match response.url().to_file_path() {
+                            Ok(path) => Message::ExportQueueTo(path),
+                            Err(()) => Message::Cancelled,
+                        },
+                        Err(file_chooser::Error::Cancelled) => Message::Cancelled,
+                        Err(why) => Message::OpenError(Arc::new(why)),
+                    }
+                });
+            }
+            Message::ExportHistory => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new().title(fl!("export-history"));
+
+                    match dialog.open_folder().await {
+                        Ok(response) => match response.url().to_file_path() {
+                            Ok(path) => Message::ExportHistoryTo(path),
+                            Err(()) => Message::Cancelled,
+                        },
+                        Err(file_chooser::Error::Cancelled) => Message::Cancelled,
+                        Err(why) => Message::OpenError(Arc::new(why)),
+                    }
+                });
+            }
+            Message::ExportHistoryTo(folder) => {
+                return cosmic::task::future(async move {
+                    let result =
+                        tokio::task::spawn_blocking(move || scrobble::export_history(&folder))
+                            .await
+                            .unwrap_or_else(|err| Err(err.to_string()));
+                    Message::HistoryExported(result)
+                });
+            }
+            Message::HistoryExported(result) => {
+                self.history_status = Some(match result {
+                    Ok(count) => fl!(
+                        "history-exported",
+                        count = count,
+                        file = scrobble::EXPORT_FILE_NAME
+                    ),
+                    Err(err) => fl!("history-export-failed", error = err),
+                });
+            }
+            Message::ExportQueueTo(target) => {
+                let entries = self
+                    .queue
+                    .tracks()
+                    .iter()
+                    .filter_map(|id| self.find_track(*id))
+                    .map(|file| ExportEntry {
+                        source: file.saved_path.clone(),
+                        artist: file.artist.clone(),
+                        title: file.track_title.clone(),
+                        duration: file.duration,
+                    })
+                    .collect::<Vec<ExportEntry>>();
+                let job = self.export_transcode.then(|| self.conversion_job.clone());
+
+                let progress = Arc::new(ExportProgress::default());
+                self.export_job = Some(progress.clone());
+                self.export_report = None;
+                self.show_jobs();
+
+                return cosmic::task::future(async move {
+                    let report = tokio::task::spawn_blocking(move || {
+                        export::folder::export_to_folder(entries, &target, job.as_ref(), &progress)
+                    })
+                    .await
+                    .unwrap_or_else(|err| ExportReport {
+                        failed: vec![err.to_string()],
+                        ..Default::default()
+                    });
+
+                    Message::ExportFinished(report)
+                });
+            }
+            Message::SyncToRockbox(sync) => {
+                if self.export_job.is_some() {
+                    return Task::none();
+                }
+
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new().title(fl!("choose-rockbox"));
+
+                    match dialog.open_folder().await {
+                        Ok(response) => match response.url().to_file_path() {
+                            Ok(path) => Message::SyncToRockboxAt(sync, path),
+                            Err(()) => Message::Cancelled,
+                        },
+                        Err(file_chooser::Error::Cancelled) => Message::Cancelled,
+                        Err(why) => Message::OpenError(Arc::new(why)),
+                    }
+                });
+            }
+            Message::SyncToRockboxAt(sync, root) => {
+                let (tracks, playlists) = self.device_sync(sync);
+                let job = self.conversion_job.clone();
+
+                return self.start_device_export(move |progress| {
+                    export::rockbox::export_to_rockbox(tracks, playlists, &root, &job, progress)
+                });
+            }
+            Message::SyncToPhone(sync) => {
+                if self.export_job.is_some() {
+                    return Task::none();
+                }
+
+                return cosmic::task::future(async move {
+                    let storages = tokio::task::spawn_blocking(mtp::storages)
+                        .await
+                        .unwrap_or_default();
+                    Message::PhonesFound(sync, storages)
+                });
+            }
+            Message::PhonesFound(sync, storages) => match storages.as_slice() {
+                [] => {
+                    self.export_report = Some(ExportReport {
+                        failed: vec![fl!("no-phone")],
+                        ..Default::default()
+                    });
+                }
+                [storage] => {
+                    let root = storage.root.clone();
+                    return self.update(Message::SyncToPhoneAt(sync, root));
+                }
+                _ => {
+                    self.phone_choice = Some((sync, storages));
+                }
+            },
+            Message::SyncToPhoneAt(sync, root) => {
+                self.phone_choice = None;
+
+                let (tracks, playlists) = self.device_sync(sync);
+                let job = self.conversion_job.clone();
+
+                return self.start_device_export(move |progress| {
+                    export::mtp::sync_to_phone(tracks, playlists, &root, &job, progress)
+                });
+            }
+            Message::ClosePhoneChoice => {
+                self.phone_choice = None;
+            }
+            Message::ExportFinished(report) => {
+                self.export_job = None;
+                self.export_report = Some(report);
+            }
+            Message::CloseExportReport => {
+                self.export_report = None;
+            }
+            Message::ExportTranscode(enabled) => {
+                self.export_transcode = enabled;
+            }
+            Message::PauseJob(kind, paused) => {
+                if let Some(control) = self.job_control(kind) {
+                    control.set_paused(paused);
+                }
+            }
+            Message::CancelJob(kind) => {
+                if let Some(control) = self.job_control(kind) {
+                    control.cancel();
+                }
+            }
+            Message::RefreshProgress => {
+                if self.take_scanned() {
+                    return self.rebuild_search_index();
+                }
+            }
+            Message::ScanFinished => {
+                self.take_scanned();
+                if let Some(progress) = self.scan_job.take() {
+                    self.held_back.extend(progress.take_held_back());
+                }
+
+                // Albums were appended as they turned up, so put them in order now, keeping
+                // the open album open.
+                let selected = self
+                    .selected_album
+                    .and_then(|index| self.albums.get(index))
+                    .map(|album| (album.album_artist.clone(), album.album.clone()));
+                scan::sort_albums(&mut self.albums);
+                self.selected_album = selected.and_then(|(album_artist, album)| {
+                    self.albums.iter().position(|candidate| {
+                        candidate.album_artist == album_artist && candidate.album == album
+                    })
+                });
+
+                // Watching starts after the first scan, which would otherwise report every
+                // file it reads.
+                self.watching = true;
+
+                let mut tasks = vec![
+                    self.rebuild_search_index(),
+                    self.rescan_later(),
+                    self.check_plugins(),
+                ];
+                if let Some(link) = self.pending_link.take() {
+                    tasks.push(self.open_share_link(link));
+                }
+                if let Some(path) = self.pending_playlist.take() {
+                    tasks.push(self.update(Message::LoadPlaylist(path)));
+                }
+                return Task::batch(tasks);
+            }
+            Message::RescanHeldBack => {
+                // A scan that's running reschedules this when it's done.
+                if self.scan_job.is_none() && !self.held_back.is_empty() {
+                    let paths = std::mem::take(&mut self.held_back);
+                    let options = self.scan_options;
+                    return self.run_scan(move |first_id, progress| {
+                        scan::scan_paths(paths, first_id, options, progress)
+                    });
+                }
+            }
+            // The organizer updates the paths itself once it's done.
+            Message::LibraryFilesChanged(_) if self.organizing => {}
+            Message::LibraryFilesChanged(paths) => {
+                let mut removed = false;
+                let mut changed = Vec::new();
+
+                for path in paths {
+                    let known = self
+                        .scanned_files
+                        .iter()
+                        .find(|file| file.saved_path == path)
+                        .map(|file| file.id);
+
+                    if !path.exists() {
+                        // A folder that's gone takes its tracks along.
+                        let before = self.scanned_files.len();
+                        self.scanned_files
+                            .retain(|file| !file.saved_path.starts_with(&path));
+                        removed |= self.scanned_files.len() != before;
+                    } else if let Some(id) = known {
+                        changed.push((id, path));
+                    } else {
+                        // New files are scanned like held back ones, so ones still being
+                        // copied in wait until they've settled.
+                        self.held_back.push(path);
+                    }
+                }
+
+                let mut tasks = vec![self.update(Message::RescanHeldBack)];
+                if removed {
+                    tasks.push(self.refresh_library());
+                }
+                if !changed.is_empty() {
+                    let fallback = self.scan_options.title_fallback;
+                    tasks.push(cosmic::task::future(async move {
+                        let result = tokio::task::spawn_blocking(move || {
+                            changed
+                                .into_iter()
+                                .map(|(id, path)| (id, scan::reread(&path, id, fallback)))
+                                .collect()
+                        })
+                        .await
+                        .unwrap_or_default();
+                        Message::WatchedFilesReread(Arc::new(result))
+                    }));
+                }
+                return Task::batch(tasks);
+            }
+            Message::WatchedFilesReread(result) => {
+                let mut changed = false;
+
+                for (id, reread) in result.iter() {
+                    let Some(reread) = reread else {
+                        // No longer an audio file, or replaced by a folder.
+                        let before = self.scanned_files.len();
+                        self.scanned_files.retain(|file| file.id != *id);
+                        changed |= self.scanned_files.len() != before;
+                        continue;
+                    };
+                    let Some(existing) = self.scanned_files.iter_mut().find(|file| file.id == *id)
+                    else {
+                        continue;
+                    };
+
+                    let mut reread = reread.clone();
+                    reread.bpm = reread.bpm.or(existing.bpm);
+                    if *existing != reread {
+                        *existing = reread;
+                        changed = true;
+                    }
+                }
+
+                if changed {
+                    return self.refresh_library();
+                }
+            }
+            Message::OrganizeFinished(result) => {
+                self.organizing = false;
+                let (done, errors) = result.as_ref();
+
+                let mut favorites_moved = false;
+                let mut positions_moved = false;
+                let mut tempos_moved = false;
+                for planned in done {
+                    if self.favorites.remove(&planned.from) {
+                        self.favorites.insert(planned.to.clone());
+                        favorites_moved = true;
+                    }
+                    if let Some(position) = self.resume_positions.remove(&planned.from) {
+                        self.resume_positions.insert(planned.to.clone(), position);
+                        positions_moved = true;
+                    }
+                    if let Some(bpm) = self.tempos.remove(&planned.from) {
+                        self.tempos.insert(planned.to.clone(), bpm);
+                        tempos_moved = true;
+                    }
+
+                    if let Some(file) = self.scanned_files.iter_mut().find(|file| file.id == planned.id) {
+                        file.saved_path = planned.to.clone();
+                        if let Ok(url) = Url::from_file_path(&planned.to) {
+                            file.uri = url.to_string();
+                        }
+                    }
+                }
+
+                if favorites_moved {
+                    self.save_setting(
+                        "favorites",
+                        self.favorites.iter().cloned().collect(),
+                        Config::set_favorites,
+                    );
+                }
+                if positions_moved {
+                    if let Err(err) = resume::write_positions(&self.resume_positions) {
+                        eprintln!("Failed to save the resume positions: {}", err);
+                    }
+                }
+                if tempos_moved {
+                    if let Err(err) = tempo::write_cache(&self.tempos) {
+                        eprintln!("Failed to cache tempos: {}", err);
+                    }
+                }
+
+                for error in errors {
+                    eprintln!("Failed to move file {}", error);
+                }
+
+                let mut status = fl!("organize-moved", count = done.len());
+                if !errors.is_empty() {
+                    status.push('\n');
+                    status.push_str(&fl!("organize-failed", count = errors.len()));
+                }
+                self.organize_status = Some(status);
+            }
+            Message::RescanLibrary => {
+                return self.scan_library();
+            }
+            Message::RescanOnStart(enabled) => match &self.config_handler {
+                Some(handler) => {
+                    if let Err(err) = self.config.set_rescan_on_start(handler, enabled) {
+                        eprintln!("Failed to save the settings: {}", err);
+                    }
+                }
+                None => self.config.rescan_on_start = enabled,
+            },
+            Message::SetDefaultPage(index) => {
+                let Some(page) = Page::ALL.get(index).copied() else {
+                    return Task::none();
+                };
+                match &self.config_handler {
+                    Some(handler) => {
+                        if let Err(err) = self.config.set_default_page(handler, page) {
+                            eprintln!("Failed to save the settings: {}", err);
+                        }
+                    }
+                    None => self.config.default_page = page,
+                }
+            }
+            Message::ChooseCacheFolder => {
+                return cosmic::task::future(async move {
+                    let dialog =
+                        file_chooser::open::Dialog::new().title(fl!("choose-cache-folder"));
+
+                    match dialog.open_folder().await {
+                        Ok(response) => {
+                            let Ok(path) = response.url().to_file_path() else {
+                                return Message::Error(fl!(
+                                    "not-a-local-folder",
+                                    url = response.url().to_string()
+                                ));
+                            };
+                            match flatpak::keep_access(path.clone()).await {
+                                Ok(path) => Message::SetCacheFolder(Some(path)),
+                                Err(err) => {
+                                    eprintln!("{}", err);
+                                    Message::SetCacheFolder(Some(path))
+                                }
+                            }
+                        }
+
+                        Err(file_chooser::Error::Cancelled) => Message::Cancelled,
+
+                        Err(why) => Message::OpenError(Arc::new(why)),
+                    }
+                });
+            }
+            Message::SetCacheFolder(folder) => {
+                if folder == self.config.cache_folder {
+                    return Task::none();
+                }
+
+                artwork::set_cache_folder(folder.clone());
+                match &self.config_handler {
+                    Some(handler) => {
+                        if let Err(err) = self.config.set_cache_folder(handler, folder) {
+                            eprintln!("Failed to save the settings: {}", err);
+                        }
+                    }
+                    None => self.config.cache_folder = folder,
+                }
+
+                // Covers are pulled out of the files again into the new folder. Artwork
+                // picked by hand stays behind in the old one.
+                return self.refresh_library();
+            }
+            Message::StartToneTest(signal) => {
+                // Dropping the previous test stops it before the new one opens the output.
+                self.tone_test = None;
+
+                let pause = if self.playback.is_playing() {
+                    self.update(Message::PauseCurrentTrack)
+                } else {
+                    Task::none()
+                };
+
+                match ToneTest::start(signal) {
+                    Ok(test) => {
+                        self.tone_test = Some(test);
+                        self.output_report = Some(OutputReport::default());
+                    }
+                    Err(err) => {
+                        self.output_report = Some(OutputReport {
+                            errors: vec![err],
+                            ..Default::default()
+                        });
+                    }
+                }
+
+                return pause;
+            }
+            Message::StopToneTest => {
+                if let Some(test) = self.tone_test.take() {
+                    self.output_report = Some(test.finish());
+                }
+            }
+            Message::ToneTestTick => {
+                if let Some(test) = &mut self.tone_test {
+                    self.output_report = Some(test.poll().clone());
+                }
+            }
+            Message::InspectPipeline => {
+                // Backends without a pipeline still get the dialog, it just says so.
+                self.pipeline_snapshot = Some(self.audio_player.inspect().unwrap_or_default());
+            }
+            Message::WritePipelineGraph => {
+                self.pipeline_graph = Some(self.audio_player.write_graph());
+            }
+            Message::ClosePipelineInspector => {
+                self.pipeline_snapshot = None;
+                self.pipeline_graph = None;
+            }
+        }
+        Task::none()
+    }
+
+    /// Shows the pipeline inspector and the progress and results of long-running jobs.
+    fn dialog(&self) -> Option<Element<Self::Message>> {
+        if let Some(snapshot) = &self.pipeline_snapshot {
+            return Some(self.pipeline_inspector(snapshot));
+        }
+
+        if let Some(draft) = &self.lyrics_draft {
+            return Some(self.lyrics_draft_prompt(draft));
+        }
+
+        if let Some((sync, storages)) = &self.phone_choice {
+            return Some(self.phone_prompt(*sync, storages));
+        }
+
+        if let Some(report) = &self.export_report {
+            let mut body = Column::new()
+                .spacing(8)
+                .push(text(fl!("exported-count", count = report.exported)));
+
+            if report.cancelled {
+                body = body.push(text(fl!("job-cancelled")));
+            }
+
+            if let Some(playlist) = &report.playlist {
+                body = body.push(text::caption(playlist.display().to_string()));
+            }
+
+            if !report.failed.is_empty() {
+                let mut failures = Column::new().spacing(4);
+                for failure in &report.failed {
+                    failures = failures.push(text::caption(failure.clone()));
+                }
+
+                body = body
+                    .push(text(fl!("export-failed", count = report.failed.len())))
+                    .push(Scrollable::new(failures).height(Length::Fixed(200.0)));
+            }
+
+            return Some(
+                widget::dialog()
+                    .title(fl!("export-finished"))
+                    .control(body)
+                    .primary_action(
+                        button::suggested(fl!("close")).on_press(Message::CloseExportReport),
+                    )
+                    .into(),
+            );
+        }
+
+        let report = self.embed_report.as_ref()?;
+
+        let mut body = Column::new()
+            .spacing(8)
+            .push(text(fl!("artwork-embedded-count", count = report.embedded)));
+
+        if report.cancelled {
+            body = body.push(text(fl!("job-cancelled")));
+        }
+
+        if !report.failed.is_empty() {
+            let mut failures = Column::new().spacing(4);
+            for failure in &report.failed {
+                failures = failures.push(text::caption(failure.clone()));
+            }
+
+            body = body
+                .push(text(fl!("artwork-embed-failed", count = report.failed.len())))
+                .push(Scrollable::new(failures).height(Length::Fixed(200.0)));
+        }
+
+        Some(
+            widget::dialog()
+                .title(fl!("artwork-embedded"))
+                .control(body)
+                .primary_action(
+                    button::suggested(fl!("close")).on_press(Message::CloseEmbedReport),
+                )
+                .into(),
+        )
+    }
+
+    /// Display a context drawer if the context page is requested.
+    fn context_drawer(&self) -> Option<context_drawer::ContextDrawer<Self::Message>> {
+        if !self.core.window.show_context {
+            return None;
+        }
+
+        Some(match self.context_page {
+            ContextPage::About => context_drawer::context_drawer(
+                self.about(),
+                Message::ToggleContextPage(ContextPage::About),
+            )
+            .title(fl!("about")),
+            ContextPage::Settings => context_drawer::context_drawer(
+                self.settings(),
+                Message::ToggleContextPage(ContextPage::Settings),
+            )
+            .title(fl!("settings")),
+            ContextPage::Conversion => context_drawer::context_drawer(
+                self.conversion_settings(),
+                Message::ToggleContextPage(ContextPage::Conversion),
+            )
+            .title(fl!("conversion")),
+            ContextPage::Organize => context_drawer::context_drawer(
+                self.organize_view(),
+                Message::ToggleContextPage(ContextPage::Organize),
+            )
+            .title(fl!("organize")),
+            ContextPage::Library => context_drawer::context_drawer(
+                self.library_settings(),
+                Message::ToggleContextPage(ContextPage::Library),
+            )
+            .title(fl!("library-settings-title")),
+            ContextPage::Playback => context_drawer::context_drawer(
+                self.playback_settings(),
+                Message::ToggleContextPage(ContextPage::Playback),
+            )
+            .title(fl!("playback")),
+            ContextPage::Network => context_drawer::context_drawer(
+                self.network_settings(),
+                Message::ToggleContextPage(ContextPage::Network),
+            )
+            .title(fl!("network")),
+            ContextPage::Diagnostics => context_drawer::context_drawer(
+                self.diagnostics(),
+                Message::ToggleContextPage(ContextPage::Diagnostics),
+            )
+            .title(fl!("diagnostics")),
+            ContextPage::Lyrics => context_drawer::context_drawer(
+                self.lyrics_view(),
+                Message::ToggleContextPage(ContextPage::Lyrics),
+            )
+            .title(fl!("lyrics")),
+            ContextPage::Jobs => context_drawer::context_drawer(
+                self.jobs_view(),
+                Message::ToggleContextPage(ContextPage::Jobs),
+            )
+            .title(fl!("jobs")),
+            ContextPage::TrackInfo => context_drawer::context_drawer(
+                self.track_info_view(),
+                Message::ToggleContextPage(ContextPage::TrackInfo),
+            )
+            .title(fl!("track-info")),
+            ContextPage::Queue => context_drawer::context_drawer(
+                self.queue_view(),
+                Message::ToggleContextPage(ContextPage::Queue),
+            )
+            .title(fl!("queue")),
+        })
+    }
+
+    /// Called when a nav item is selected.
+    /// Links opened while Jams is already running are handed over by the new instance.
+    fn dbus_activation(&mut self, msg: cosmic::dbus_activation::Message) -> Task<Self::Message> {
+        let cosmic::dbus_activation::Details::Open { url } = msg.msg else {
+            return Task::none();
+        };
+
+        let tasks: Vec<Task<Message>> = url
+            .iter()
+            .filter_map(ShareLink::parse)
+            .map(|link| self.open_share_link(link))
+            .collect();
+        Task::batch(tasks)
+    }
+
+    fn on_nav_select(&mut self, id: nav_bar::Id) -> Task<Self::Message> {
+        // Activate the page in the model.
+        self.nav.activate(id);
+
+        let mut tasks = vec![self.update_titles(), self.restore_scroll()];
+
+        if let Some(Page::Albums) = self.nav.active_data::<Page>() {
+            tasks.push(self.build_albums_later());
+        }
+
+        Task::batch(tasks)
+    }
+}
+
+impl Jams {
+    /// The about page for this app.
+    pub fn about(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let icon = widget::svg(widget::svg::Handle::from_memory(
+            &include_bytes!("../res/icons/hicolor/128x128/apps/com.example.CosmicAppTemplate.svg")
+                [..],
+        ));
+
+        let title = widget::text::title3(fl!("app-title"));
+
+        let link = widget::button::link(REPOSITORY)
+            .on_press(Message::LaunchUrl(REPOSITORY.to_string()))
+            .padding(0);
+
+        widget::column()
+            .push(icon)
+            .push(title)
+            .push(link)
+            //.align_items(Alignment::Center)
+            .spacing(space_xxs)
+            .into()
+    }
+
+    /// Where the library comes from, what Jams starts with and where it keeps its caches.
+    pub fn settings(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut folders = widget::settings::section().title(fl!("library-folders"));
+        for (index, folder) in self.config.library_folders.iter().enumerate() {
+            let controls = Row::new()
+                .spacing(space_xxs)
+                .align_y(Alignment::Center)
+                .push(
+                    widget::toggler(folder.enabled)
+                        .on_toggle(move |enabled| Message::ToggleLibraryFolder(index, enabled)),
+                )
+                .push(
+                    button::icon(icon::from_name("list-remove-symbolic"))
+                        .on_press(Message::RemoveLibraryFolder(index)),
+                );
+            folders = folders.add(
+                widget::settings::item::builder(folder.path.display().to_string())
+                    .control(controls),
+            );
+        }
+        let folders = folders.add(
+            widget::settings::item::builder(fl!("library-folders-description"))
+                .control(button::standard(fl!("add-folder")).on_press(Message::AddFolder)),
+        );
+
+        let scan_now = button::standard(fl!("rescan-now"))
+            .on_press_maybe(self.scan_job.is_none().then_some(Message::RescanLibrary));
+        let folders = folders
+            .add(
+                widget::settings::item::builder(fl!("rescan-on-start"))
+                    .description(fl!("rescan-on-start-description"))
+                    .toggler(self.config.rescan_on_start, Message::RescanOnStart),
+            )
+            .add(widget::settings::item::builder(fl!("rescan-library")).control(scan_now));
+
+        let selected_page = Page::ALL
+            .iter()
+            .position(|page| *page == self.config.default_page);
+        let startup = widget::settings::section().title(fl!("startup")).add(
+            widget::settings::item::builder(fl!("default-page"))
+                .description(fl!("default-page-description"))
+                .control(widget::dropdown(
+                    Page::names(),
+                    selected_page,
+                    Message::SetDefaultPage,
+                )),
+        );
+
+        let cache_controls = Row::new()
+            .spacing(space_xxs)
+            .push_maybe(self.config.cache_folder.as_ref().map(|_| {
+                button::standard(fl!("reset-cache-folder")).on_press(Message::SetCacheFolder(None))
+            }))
+            .push(
+                button::standard(fl!("choose-cache-folder")).on_press(Message::ChooseCacheFolder),
+            );
+        let cache = widget::settings::section().title(fl!("cache")).add(
+            widget::settings::item::builder(fl!("cache-folder"))
+                .description(artwork::cache_folder().display().to_string())
+                .control(cache_controls),
+        );
+
+        // Crossfade and the end of the queue live with the rest of the playback settings.
+        let playback = widget::settings::section().title(fl!("playback")).add(
+            widget::settings::item::builder(fl!("playback-settings-link"))
+                .description(fl!("playback-settings-link-description"))
+                .control(
+                    button::standard(fl!("open-settings"))
+                        .on_press(Message::ToggleContextPage(ContextPage::Playback)),
+                ),
+        );
+
+        widget::settings::view_column(vec![
+            folders.into(),
+            startup.into(),
+            cache.into(),
+            playback.into(),
+        ])
+        .into()
+    }
+
+    /// Options for converting tracks when they're exported.
+    pub fn conversion_settings(&self) -> Element<Message> {
+        let selected_preset = TranscodePreset::ALL
+            .iter()
+            .position(|preset| *preset == self.conversion_job.preset);
+
+        widget::settings::view_column(vec![widget::settings::section()
+            .add(widget::settings::item(
+                fl!("conversion-export-transcode"),
+                widget::toggler(self.export_transcode).on_toggle(Message::ExportTranscode),
+            ))
+            .add(widget::settings::item(
+                fl!("conversion-preset"),
+                widget::dropdown(
+                    TranscodePreset::names(),
+                    selected_preset,
+                    Message::ConversionPreset,
+                ),
+            ))
+            .add(widget::settings::item(
+                fl!("conversion-replaygain"),
+                widget::toggler(self.conversion_job.propagate_replaygain)
+                    .on_toggle(Message::ConversionReplayGain),
+            ))
+            .add(widget::settings::item(
+                fl!("conversion-normalize"),
+                widget::toggler(self.conversion_job.normalize_loudness)
+                    .on_toggle(Message::ConversionNormalize),
+            ))
+            .into()])
+        .into()
+    }
+
+    pub fn playback_settings(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let selected_crossfade = player::crossfade::LENGTHS
+            .iter()
+            .position(|length| *length == self.crossfade_length);
+        let selected_motion = MotionMode::ALL
+            .iter()
+            .position(|mode| *mode == self.motion.mode);
+        let selected_touch = TouchMode::ALL
+            .iter()
+            .position(|mode| *mode == self.touch.mode);
+        let selected_end = EndOfQueue::ALL
+            .iter()
+            .position(|action| *action == self.end_of_queue);
+        let selected_buffer = player::output::BUFFER_SIZES
+            .iter()
+            .position(|buffer| *buffer == self.output_settings.buffer);
+        let selected_downmix = Downmix::ALL
+            .iter()
+            .position(|downmix| *downmix == self.output_settings.downmix);
+        let selected_crossfeed = Crossfeed::ALL
+            .iter()
+            .position(|crossfeed| *crossfeed == self.filter_settings.crossfeed);
+
+        let mut sections = vec![widget::settings::section()
+            .add(
+                widget::settings::item::builder(fl!("crossfade"))
+                    .description(fl!("crossfade-description"))
+                    .control(widget::dropdown(
+                        player::crossfade::length_names(),
+                        selected_crossfade,
+                        Message::SetCrossfade,
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("end-of-queue"))
+                    .description(fl!("end-of-queue-description"))
+                    .control(widget::dropdown(
+                        EndOfQueue::names(),
+                        selected_end,
+                        Message::SetEndOfQueue,
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("vocal-reduction"))
+                    .description(fl!("vocal-reduction-description"))
+                    .toggler(self.filter_settings.vocal_reduction, Message::SetVocalReduction),
+            )
+            .add(
+                widget::settings::item::builder(fl!("crossfeed"))
+                    .description(fl!("crossfeed-description"))
+                    .control(widget::dropdown(
+                        Crossfeed::names(),
+                        selected_crossfeed,
+                        Message::SetCrossfeed,
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("equalizer"))
+                    .description(match &self.filter_settings.equalizer {
+                        Some(equalizer) => fl!("equalizer-profile", name = equalizer.name.as_str()),
+                        None => fl!("equalizer-description"),
+                    })
+                    .control(
+                        Row::new()
+                            .spacing(space_xxs)
+                            .push_maybe(self.filter_settings.equalizer.as_ref().map(|_| {
+                                button::standard(fl!("remove-equalizer"))
+                                    .on_press(Message::RemoveEqualizer)
+                            }))
+                            .push(
+                                button::standard(fl!("import-equalizer"))
+                                    .on_press(Message::ImportEqualizer),
+                            ),
+                    ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("output-level"))
+                    .description(fl!(
+                        "output-level-description",
+                        gain = format!("{:+.0}", self.filter_settings.gain)
+                    ))
+                    .control(
+                        slider(GAIN_RANGE, self.filter_settings.gain, Message::SetGain).step(1.0),
+                    ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("output-device"))
+                    .description(fl!("output-device-description"))
+                    .control(text(
+                        self.output_device
+                            .clone()
+                            .unwrap_or_else(|| fl!("output-device-unknown")),
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("limiter"))
+                    .description(fl!("limiter-description"))
+                    .toggler(self.filter_settings.limiter, Message::SetLimiter),
+            )
+            .add(
+                widget::settings::item::builder(fl!("output-buffer"))
+                    .description(fl!("output-buffer-description"))
+                    .control(widget::dropdown(
+                        player::output::buffer_names(),
+                        selected_buffer,
+                        Message::SetOutputBuffer,
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("downmix"))
+                    .description(fl!("downmix-description"))
+                    .control(widget::dropdown(
+                        Downmix::names(),
+                        selected_downmix,
+                        Message::SetDownmix,
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("announce-tracks"))
+                    .description(fl!("announce-tracks-description"))
+                    .toggler(self.announce_tracks, Message::AnnounceTracks),
+            )
+            .add(
+                widget::settings::item::builder(fl!("show-cues"))
+                    .description(fl!("show-cues-description"))
+                    .toggler(self.show_cues, Message::ShowCues),
+            )
+            .add(
+                widget::settings::item::builder(fl!("scroll-titles"))
+                    .description(fl!("scroll-titles-description"))
+                    .toggler(self.scroll_titles, Message::ScrollTitles),
+            )
+            .add(
+                widget::settings::item::builder(fl!("preview-on-hover"))
+                    .description(fl!("preview-on-hover-description"))
+                    .toggler(self.preview_on_hover, Message::PreviewOnHover),
+            )
+            .add(
+                widget::settings::item::builder(fl!("reduce-motion"))
+                    .description(fl!("reduce-motion-description"))
+                    .control(widget::dropdown(
+                        &MotionMode::NAMES,
+                        selected_motion,
+                        Message::SetMotionMode,
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("touch-mode"))
+                    .description(fl!("touch-mode-description"))
+                    .control(widget::dropdown(
+                        &TouchMode::NAMES,
+                        selected_touch,
+                        Message::SetTouchMode,
+                    )),
+            )
+            .into()];
+
+        let selected_source = AlarmSource::ALL
+            .iter()
+            .position(|source| *source == self.alarm.source);
+        let time_valid = Alarm::default().set_time(&self.alarm_time);
+        let mut alarm_section = widget::settings::section()
+            .title(fl!("alarm"))
+            .add(
+                widget::settings::item::builder(fl!("alarm-enabled"))
+                    .description(fl!("alarm-enabled-description"))
+                    .toggler(self.alarm.enabled, Message::AlarmEnabled),
+            )
+            .add(widget::settings::item(
+                fl!("alarm-time"),
+                widget::text_input("07:00", &self.alarm_time).on_input(Message::AlarmTime),
+            ))
+            .add(widget::settings::item(
+                fl!("alarm-source"),
+                widget::dropdown(
+                    &AlarmSource::NAMES,
+                    selected_source,
+                    Message::SetAlarmSource,
+                ),
+            ));
+        if !time_valid {
+            alarm_section = alarm_section.add(text::caption(fl!("alarm-time-invalid")));
+        }
+        sections.push(alarm_section.into());
+
+        let selected_resume = resume::MIN_LENGTHS
+            .iter()
+            .position(|length| *length == self.resume_rules.min_length);
+        let resume_section = widget::settings::section()
+            .title(fl!("resume"))
+            .add(
+                widget::settings::item::builder(fl!("resume-length"))
+                    .description(fl!("resume-length-description"))
+                    .control(widget::dropdown(
+                        resume::min_length_names(),
+                        selected_resume,
+                        Message::SetResumeLength,
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("resume-only"))
+                    .description(fl!("resume-only-description"))
+                    .control(
+                        widget::text_input(fl!("resume-only-placeholder"), &self.resume_rules.only)
+                            .on_input(Message::ResumeOnly),
+                    ),
+            );
+        sections.push(resume_section.into());
+
+        // Lets listeners see why quality changed, e.g. a fallback from LDAC to SBC.
+        if !self.bluetooth_devices.is_empty() {
+            let mut bluetooth = widget::settings::section().title(fl!("bluetooth-output"));
+
+            for device in &self.bluetooth_devices {
+                let mut status = device
+                    .codec
+                    .map_or_else(|| fl!("bluetooth-unknown-codec"), String::from);
+                if let Some(battery) = device.battery {
+                    status.push_str(&fl!("bluetooth-battery", percent = battery));
+                }
+
+                bluetooth = bluetooth.add(widget::settings::item(device.name.clone(), text(status)));
+            }
+
+            sections.push(bluetooth.into());
+        }
+
+        widget::settings::view_column(sections).into()
+    }
+
+    /// The tracks after the current one, which can be dragged by their handle to play in
+    /// another order or removed.
+    fn queue_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let tracks = self.queue.tracks();
+        let start = self
+            .queue
+            .position()
+            .map_or(0, |position| position + 1)
+            .min(tracks.len());
+        if tracks[start..].is_empty() {
+            return text(fl!("queue-empty")).into();
+        }
+
+        let mut list = Column::new().spacing(space_xxs);
+        for (index, id) in tracks.iter().enumerate().skip(start) {
+            let Some(file) = self.find_track(*id) else {
+                continue;
+            };
+
+            let handle = widget::mouse_area(
+                icon::from_name("list-drag-handle-symbolic")
+                    .size(self.small_icon_size())
+                    .icon(),
+            )
+            .on_press(Message::DragQueueTrack(index));
+            let remove_button = button::icon(icon::from_name("list-remove-symbolic"))
+                .icon_size(self.small_icon_size())
+                .on_press(Message::RemoveFromQueue(index));
+
+            let row = Row::new()
+                .spacing(space_xxs)
+                .align_y(Alignment::Center)
+                .push(handle)
+                .push(
+                    Column::new()
+                        .width(Length::Fill)
+                        .push(text(file.track_title.as_str()))
+                        .push(text::caption(file.artist.as_str()))
+                        .push_maybe(self.similar_reasons.get(id).map(|reasons| {
+                            let reasons: Vec<String> = reasons
+                                .iter()
+                                .map(|reason| match_reason_name(*reason))
+                                .collect();
+                            text::caption(reasons.join(", "))
+                        })),
+                )
+                .push(remove_button);
+
+            let row: Element<Message> = if self.queue_drag == Some(index) {
+                Container::new(row)
+                    .class(cosmic::style::Container::Card)
+                    .into()
+            } else {
+                row.into()
+            };
+            // Letting go over a row drops the dragged track there.
+            list = list.push(widget::mouse_area(row).on_release(Message::DropQueueTrack(index)));
+        }
+
+        // Letting go anywhere else puts it back.
+        widget::mouse_area(list)
+            .on_release(Message::CancelQueueDrag)
+            .into()
+    }
+
+    /// Every running background job with its progress and controls, followed by what the
+    /// last finished ones reported.
+    pub fn jobs_view(&self) -> Element<Message> {
+        let mut running = widget::settings::section().title(fl!("jobs-running"));
+        let mut any_running = false;
+
+        if let Some(progress) = &self.scan_job {
+            let (status, fraction) = scan_progress_text(progress);
+            running = running.add(self.job_row(
+                JobKind::Scan,
+                fl!("job-scan"),
+                status,
+                fraction,
+                &progress.control,
+            ));
+            any_running = true;
+        }
+
+        if let Some(progress) = &self.embed_job {
+            let total = progress.total.load(Ordering::Relaxed);
+            let done = progress.done.load(Ordering::Relaxed);
+            running = running.add(self.job_row(
+                JobKind::EmbedArtwork,
+                fl!("embedding-artwork"),
+                fl!("embedding-artwork-progress", done = done, total = total),
+                Some((done, total)),
+                &progress.control,
+            ));
+            any_running = true;
+        }
+
+        if let Some(progress) = &self.export_job {
+            let total = progress.total.load(Ordering::Relaxed);
+            let done = progress.done.load(Ordering::Relaxed);
+            running = running.add(self.job_row(
+                JobKind::Export,
+                fl!("exporting"),
+                fl!("exporting-progress", done = done, total = total),
+                Some((done, total)),
+                &progress.control,
+            ));
+            any_running = true;
+        }
+
+        if let Some(progress) = &self.verify_job {
+            let total = progress.total.load(Ordering::Relaxed);
+            let done = progress.done.load(Ordering::Relaxed);
+            running = running.add(self.job_row(
+                JobKind::Verify,
+                fl!("verifying"),
+                fl!("verifying-progress", done = done, total = total),
+                Some((done, total)),
+                &progress.control,
+            ));
+            any_running = true;
+        }
+
+        if let Some(progress) = &self.loudness_job {
+            let total = progress.total.load(Ordering::Relaxed);
+            let done = progress.done.load(Ordering::Relaxed);
+            running = running.add(self.job_row(
+                JobKind::Loudness,
+                fl!("measuring-loudness"),
+                fl!("measuring-loudness-progress", done = done, total = total),
+                Some((done, total)),
+                &progress.control,
+            ));
+            any_running = true;
+        }
+
+        if let Some(progress) = &self.tempo_job {
+            let total = progress.total.load(Ordering::Relaxed);
+            let done = progress.done.load(Ordering::Relaxed);
+            running = running.add(self.job_row(
+                JobKind::Tempo,
+                fl!("detecting-tempo"),
+                fl!("detecting-tempo-progress", done = done, total = total),
+                Some((done, total)),
+                &progress.control,
+            ));
+            any_running = true;
+        }
+
+        if !any_running {
+            running = running.add(text(fl!("jobs-none")));
+        }
+
+        let mut sections = vec![running.into()];
+
+        let finished = [
+            self.embed_report.as_ref().map(|report| {
+                (
+                    fl!("artwork-embedded"),
+                    fl!("artwork-embedded-count", count = report.embedded),
+                    report.cancelled,
+                    &report.failed,
+                )
+            }),
+            self.export_report.as_ref().map(|report| {
+                (
+                    fl!("export-finished"),
+                    fl!("exported-count", count = report.exported),
+                    report.cancelled,
+                    &report.failed,
+                )
+            }),
+            self.verify_report.as_ref().map(|report| {
+                (
+                    fl!("verify-finished"),
+                    fl!(
+                        "verify-checked",
+                        count = report.checked(),
+                        damaged = report.damaged.len()
+                    ),
+                    report.cancelled,
+                    &report.failed,
+                )
+            }),
+            (!self.cover_errors.is_empty()).then(|| {
+                (
+                    fl!("cover-cache"),
+                    fl!("cover-cache-failed", count = self.cover_errors.len()),
+                    false,
+                    &self.cover_errors,
+                )
+            }),
+        ];
+
+        for (title, summary, cancelled, failed) in finished.into_iter().flatten() {
+            let mut details = Column::new().spacing(4).push(text(summary));
+            if cancelled {
+                details = details.push(text(fl!("job-cancelled")));
+            }
+            for failure in failed {
+                details = details.push(text::caption(failure.clone()));
+            }
+
+            sections.push(widget::settings::section().title(title).add(details).into());
+        }
+
+        widget::settings::view_column(sections).into()
+    }
+
+    /// A running job: name, progress and buttons to pause, resume or cancel it.
+    fn job_row(
+        &self,
+        kind: JobKind,
+        title: String,
+        status: String,
+        fraction: Option<(usize, usize)>,
+        control: &JobControl,
+    ) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let paused = control.is_paused();
+        let cancelling = control.is_cancelled();
+
+        let pause_button = if paused {
+            button::icon(icon::from_name("media-playback-start-symbolic"))
+        } else {
+            button::icon(icon::from_name("media-playback-pause-symbolic"))
+        }
+        .on_press_maybe((!cancelling).then_some(Message::PauseJob(kind, !paused)));
+        let cancel_button = button::icon(icon::from_name("process-stop-symbolic"))
+            .on_press_maybe((!cancelling).then_some(Message::CancelJob(kind)));
+
+        let header = Row::new()
+            .spacing(space_xxs)
+            .align_y(Alignment::Center)
+            .push(text::heading(title).width(Length::Fill))
+            .push(pause_button)
+            .push(cancel_button);
+
+        let status = if cancelling {
+            fl!("job-cancelling")
+        } else if paused {
+            fl!("job-paused", status = status)
+        } else {
+            status
+        };
+
+        let mut column = Column::new().spacing(space_xxs).push(header);
+        if let Some((done, total)) = fraction {
+            column = column.push(widget::progress_bar(0.0..=total.max(1) as f32, done as f32));
+        }
+
+        column.push(text::caption(status)).into()
+    }
+
+    /// Asks whether to keep lyrics edited by hand that were never saved.
+    fn lyrics_draft_prompt<'a>(&'a self, draft: &'a LyricsDraft) -> Element<'a, Message> {
+        let name = draft
+            .track
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut body = Column::new()
+            .spacing(8)
+            .push(text(fl!("lyrics-draft-body", track = name)))
+            .push(Scrollable::new(text::caption(draft.text.as_str())).height(Length::Fixed(200.0)));
+
+        if let Some(error) = &self.lyrics_draft_error {
+            body = body.push(text::caption(error.as_str()));
+        }
+
+        widget::dialog()
+            .title(fl!("lyrics-draft"))
+            .control(body)
+            .primary_action(button::suggested(fl!("save")).on_press(Message::SaveLyricsDraft))
+            .secondary_action(
+                button::standard(fl!("discard")).on_press(Message::DiscardLyricsDraft),
+            )
+            .into()
+    }
+
+    /// Asks which phone storage to sync to.
+    fn phone_prompt<'a>(
+        &'a self,
+        sync: DeviceSync,
+        storages: &'a [MtpStorage],
+    ) -> Element<'a, Message> {
+        let mut body = Column::new().spacing(8);
+        for storage in storages {
+            body = body.push(
+                button::standard(storage.name.as_str())
+                    .on_press(Message::SyncToPhoneAt(sync, storage.root.clone())),
+            );
+        }
+
+        widget::dialog()
+            .title(fl!("choose-phone"))
+            .control(body)
+            .secondary_action(button::standard(fl!("cancel")).on_press(Message::ClosePhoneChoice))
+            .into()
+    }
+
+    /// Element states, negotiated caps and recent bus messages of the playback pipeline.
+    fn pipeline_inspector<'a>(&'a self, snapshot: &'a PipelineSnapshot) -> Element<'a, Message> {
+        let mut state = fl!("pipeline-state", state = snapshot.state.as_str());
+        if let Some(pending) = &snapshot.pending {
+            state.push_str(&fl!("pipeline-pending", state = pending.as_str()));
+        }
+
+        let mut details = Column::new().spacing(4);
+
+        if snapshot.elements.is_empty() {
+            details = details.push(text(fl!("pipeline-no-elements")));
+        } else {
+            details = details.push(text::heading(fl!("pipeline-elements")));
+        }
+
+        for element in &snapshot.elements {
+            details = details.push(text(format!(
+                "{} ({}) {}",
+                element.name, element.factory, element.state
+            )));
+            for caps in &element.caps {
+                details = details.push(text::caption(caps.as_str()));
+            }
+        }
+
+        if !snapshot.messages.is_empty() {
+            details = details.push(text::heading(fl!("pipeline-messages")));
+            for message in &snapshot.messages {
+                details = details.push(text::caption(message.as_str()));
+            }
+        }
+
+        let mut body = Column::new()
+            .spacing(8)
+            .push(text(state))
+            .push(Scrollable::new(details).height(Length::Fixed(320.0)));
+
+        match &self.pipeline_graph {
+            Some(Ok(path)) => {
+                body = body.push(text::caption(fl!(
+                    "pipeline-graph-written",
+                    path = path.display().to_string()
+                )));
+            }
+            Some(Err(err)) => {
+                body = body.push(text::caption(err.as_str()));
+            }
+            None => {}
+        }
+
+        widget::dialog()
+            .title(fl!("inspect-pipeline"))
+            .control(body)
+            .primary_action(
+                button::suggested(fl!("close")).on_press(Message::ClosePipelineInspector),
+            )
+            .secondary_action(
+                button::standard(fl!("pipeline-write-graph")).on_press(Message::WritePipelineGraph),
+            )
+            .tertiary_action(
+                button::text(fl!("pipeline-refresh")).on_press(Message::InspectPipeline),
+            )
+            .into()
+    }
+
+    /// The line being sung in large type with the next one below it.
+    fn karaoke_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_m, .. } = theme::active().cosmic().spacing;
+
+        let mut lines = Column::new()
+            .spacing(space_m)
+            .align_x(Alignment::Center)
+            .width(Length::Fill);
+
+        match &self.lyrics {
+            Some(lyrics) if lyrics.is_synced() => {
+                let current = lyrics.line_at(self.seek_position);
+                let next = current.map_or(0, |index| index + 1);
+
+                let current_line = current.map_or("", |index| lyrics.lines[index].text.as_str());
+                lines = lines.push(text::title1(current_line).size(64));
+
+                if let Some(line) = lyrics.lines.get(next) {
+                    lines = lines.push(text::title3(line.text.as_str()).size(36));
+                }
+            }
+            _ => {
+                let title = self
+                    .queue
+                    .current()
+                    .and_then(|id| self.find_track(id))
+                    .map(|file| file.track_title.clone())
+                    .unwrap_or_default();
+                lines = lines
+                    .push(text::title1(title).size(64))
+                    .push(text::body(fl!("karaoke-no-synced-lyrics")));
+            }
+        }
+
+        let controls = Row::new()
+            .spacing(space_m)
+            .align_y(Alignment::Center)
+            .push(
+                widget::settings::item::builder(fl!("vocal-reduction"))
+                    .toggler(self.filter_settings.vocal_reduction, Message::SetVocalReduction),
+            )
+            .push(button::standard(fl!("karaoke-exit")).on_press(Message::Karaoke(false)));
+
+        Column::new()
+            .padding(space_m)
+            .push(
+                widget::container(lines)
+                    .center_y(Length::Fill)
+                    .width(Length::Fill),
+            )
+            .push(controls)
+            .into()
+    }
+
+    /// Lyrics of the current track, following along when they're synced.
+    pub fn lyrics_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut column = Column::new().spacing(space_xxs);
+
+        if let Some(editor) = &self.lyrics_editor {
+            column = column
+                .push(text::caption(fl!("lyrics-edit-hint")))
+                .push(
+                    text_editor(editor)
+                        .height(Length::Fixed(400.0))
+                        .on_action(Message::LyricsEdit),
+                )
+                .push(
+                    Row::new()
+                        .spacing(space_xxs)
+                        .push(button::suggested(fl!("save")).on_press(Message::SaveLyrics))
+                        .push(button::standard(fl!("cancel")).on_press(Message::CancelLyricsEdit)),
+                );
+        } else if let Some(lyrics) = &self.lyrics {
+            let current = lyrics.line_at(self.seek_position);
+
+            for (index, line) in lyrics.lines.iter().enumerate() {
+                if Some(index) == current {
+                    column = column.push(text::heading(line.text.as_str()));
+                } else {
+                    column = column.push(text::body(line.text.as_str()));
+                }
+            }
+        } else if self.lyrics_track.is_none() {
+            column = column.push(text::body(fl!("lyrics-nothing-playing")));
+        }
+
+        if let Some(status) = &self.lyrics_status {
+            column = column.push(text::caption(status.as_str()));
+        }
+
+        if self.lyrics_editor.is_none() && self.lyrics_track.is_some() {
+            column = column.push(button::standard(fl!("lyrics-edit")).on_press(Message::EditLyrics));
+        }
+
+        column.into()
+    }
+
+    /// Test signal controls and what the audio output reported while it played.
+    pub fn diagnostics(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let playing = self.tone_test.as_ref().map(|test| test.signal());
+        let buttons = Row::new()
+            .spacing(space_xxs)
+            .push(
+                button::standard(fl!("diagnostics-tone")).on_press_maybe(
+                    (playing != Some(TestSignal::Tone))
+                        .then_some(Message::StartToneTest(TestSignal::Tone)),
+                ),
+            )
+            .push(
+                button::standard(fl!("diagnostics-sweep")).on_press_maybe(
+                    (playing != Some(TestSignal::Sweep))
+                        .then_some(Message::StartToneTest(TestSignal::Sweep)),
+                ),
+            )
+            .push(
+                button::standard(fl!("diagnostics-stop"))
+                    .on_press_maybe(playing.map(|_| Message::StopToneTest)),
+            );
+
+        let mut section = widget::settings::section().add(
+            widget::settings::item::builder(fl!("diagnostics-signal"))
+                .description(fl!("diagnostics-description"))
+                .control(buttons),
+        );
+
+        if let Some(test) = &self.tone_test {
+            section = section.add(widget::settings::item(
+                fl!("diagnostics-frequency"),
+                text(format!("{:.0} Hz", test.frequency())),
+            ));
+        }
+
+        let mut sections = vec![section.into()];
+
+        if let Some(report) = &self.output_report {
+            let unknown = || fl!("diagnostics-unknown");
+            let millis = |value: Option<Duration>| {
+                value.map_or_else(unknown, |value| format!("{} ms", value.as_millis()))
+            };
+
+            let mut output = widget::settings::section()
+                .title(fl!("diagnostics-output"))
+                .add(widget::settings::item(
+                    fl!("diagnostics-sink"),
+                    text(report.sink.clone().unwrap_or_else(unknown)),
+                ))
+                .add(widget::settings::item(
+                    fl!("diagnostics-sample-rate"),
+                    text(
+                        report
+                            .sample_rate
+                            .map_or_else(unknown, |rate| format!("{} Hz", rate)),
+                    ),
+                ))
+                .add(widget::settings::item(
+                    fl!("diagnostics-channels"),
+                    text(
+                        report
+                            .channels
+                            .map_or_else(unknown, |channels| channels.to_string()),
+                    ),
+                ))
+                .add(widget::settings::item(
+                    fl!("diagnostics-format"),
+                    text(report.format.clone().unwrap_or_else(unknown)),
+                ))
+                .add(widget::settings::item(
+                    fl!("diagnostics-buffer"),
+                    text(millis(report.buffer_time)),
+                ))
+                .add(widget::settings::item(
+                    fl!("diagnostics-period"),
+                    text(millis(report.latency_time)),
+                ))
+                .add(widget::settings::item(
+                    fl!("diagnostics-latency"),
+                    text(millis(report.latency)),
+                ))
+                .add(widget::settings::item(
+                    fl!("diagnostics-underruns"),
+                    text(report.underruns.to_string()),
+                ));
+
+            for error in &report.errors {
+                output = output.add(text::caption(error.clone()));
+            }
+
+            sections.push(output.into());
+        }
+
+        widget::settings::view_column(sections).into()
+    }
+    pub fn network_settings(&self) -> Element<Message> {
+        let selected_mode = NetworkMode::ALL
+            .iter()
+            .position(|mode| *mode == self.network.mode);
+
+        let status = if self.network.is_online() {
+            fl!("network-online")
+        } else {
+            fl!("network-offline")
+        };
+
+        let mut section = widget::settings::section()
+            .add(widget::settings::item(
+                fl!("network-mode"),
+                widget::dropdown(NetworkMode::names(), selected_mode, Message::SetNetworkMode),
+            ))
+            .add(widget::settings::item(fl!("network-status"), text(status)))
+            .add(
+                widget::settings::item::builder(fl!("network-local-only"))
+                    .description(fl!("network-local-only-description"))
+                    .toggler(self.local_only, Message::LocalOnly),
+            )
+            .add(
+                widget::settings::item::builder(fl!("network-discography"))
+                    .description(fl!("network-discography-description"))
+                    .toggler(self.discography_lookup, Message::DiscographyLookup),
+            );
+
+        if !self.pending_network_tasks.is_empty() {
+            section = section.add(widget::settings::item(
+                fl!("network-pending"),
+                text(self.pending_network_tasks.len().to_string()),
+            ));
+        }
+
+        let mut scrobbling = widget::settings::section()
+            .title(fl!("scrobbling"))
+            .add(
+                widget::settings::item::builder(fl!("scrobble-url"))
+                    .description(fl!("scrobble-url-description"))
+                    .control(
+                        widget::text_input("", &self.scrobble_endpoint.url)
+                            .on_input(Message::ScrobbleUrl),
+                    ),
+            )
+            .add(widget::settings::item(
+                fl!("scrobble-token"),
+                widget::secure_input("", &self.scrobble_endpoint.token, None, true)
+                    .on_input(Message::ScrobbleToken),
+            ));
+
+        if !self.scrobble_queue.is_empty() {
+            scrobbling = scrobbling.add(widget::settings::item(
+                fl!("scrobble-waiting", count = self.scrobble_queue.len()),
+                button::standard(fl!("scrobble-send-now"))
+                    .on_press_maybe((!self.scrobble_sending).then_some(Message::SubmitScrobbles)),
+            ));
+        }
+        if let Some(error) = &self.scrobble_error {
+            scrobbling = scrobbling.add(text::caption(fl!(
+                "scrobble-failed",
+                error = error.as_str()
+            )));
+        }
+
+        scrobbling = scrobbling.add(
+            widget::settings::item::builder(fl!("listening-history"))
+                .description(fl!("listening-history-description"))
+                .control(
+                    button::standard(fl!("export-history-button")).on_press(Message::ExportHistory),
+                ),
+        );
+        if let Some(status) = &self.history_status {
+            scrobbling = scrobbling.add(text::caption(status.clone()));
+        }
+
+        let hooks = widget::settings::section()
+            .title(fl!("hooks"))
+            .add(
+                widget::settings::item::builder(fl!("hook-command"))
+                    .description(fl!("hook-command-description"))
+                    .control(
+                        widget::text_input("", &self.hooks.command).on_input(Message::HookCommand),
+                    ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("hook-url"))
+                    .description(fl!("hook-url-description"))
+                    .control(widget::text_input("", &self.hooks.url).on_input(Message::HookUrl)),
+            );
+
+        let now_playing = widget::settings::section()
+            .title(fl!("now-playing-file"))
+            .add(
+                widget::settings::item::builder(fl!("now-playing-file-enabled"))
+                    .description(fl!(
+                        "now-playing-file-description",
+                        folder = now_playing::output_dir().display().to_string()
+                    ))
+                    .toggler(self.now_playing_output.enabled, Message::NowPlayingFile),
+            )
+            .add(
+                widget::settings::item::builder(fl!("now-playing-template"))
+                    .description(fl!("now-playing-template-description"))
+                    .control(
+                        widget::text_input(
+                            now_playing::DEFAULT_TEMPLATE,
+                            &self.now_playing_output.template,
+                        )
+                        .on_input(Message::NowPlayingTemplate),
+                    ),
+            )
+            .add(widget::settings::item(
+                fl!("now-playing-folder"),
+                button::standard(fl!("open-now-playing-folder"))
+                    .on_press(Message::OpenNowPlayingFolder),
+            ));
+
+        let mut sections = vec![
+            section.into(),
+            scrobbling.into(),
+            hooks.into(),
+            now_playing.into(),
+        ];
+        if discord::available() {
+            sections.push(
+                widget::settings::section()
+                    .add(
+                        widget::settings::item::builder(fl!("discord-presence"))
+                            .description(fl!("discord-presence-description"))
+                            .toggler(self.discord_presence, Message::DiscordPresence),
+                    )
+                    .into(),
+            );
+        }
+
+        widget::settings::view_column(sections).into()
+    }
+
+    /// The current track's hot cues as colored ticks, laid out to line up with the scrubber.
+    fn cue_markers(&self) -> Element<Message> {
+        const SCALE: u128 = 1000;
+
+        let total = self.current_track_duration.as_millis().max(1);
+        let mut row = Row::new().width(Length::Fill).height(Length::Fixed(8.0));
+        let mut last = 0;
+
+        let mut cues: Vec<&CueMarker> = self.current_cues.iter().collect();
+        cues.sort_by_key(|cue| cue.position);
+
+        for cue in cues {
+            let at = cue.position.as_millis().min(total) * SCALE / total;
+            if at > last {
+                row = row.push(widget::Space::new(
+                    Length::FillPortion((at - last) as u16),
+                    Length::Shrink,
+                ));
+            }
+            last = at;
+
+            let [r, g, b] = cue.color;
+            let marker = widget::container(widget::Space::new(
+                Length::Fixed(3.0),
+                Length::Fixed(8.0),
+            ))
+            .class(cosmic::theme::Container::custom(move |_| {
+                widget::container::Style {
+                    background: Some(Color::from_rgb8(r, g, b).into()),
+                    ..Default::default()
+                }
+            }));
+
+            row = row.push(widget::mouse_area(marker).on_press(Message::SeekToCue(cue.position)));
+        }
+
+        if last < SCALE {
+            row = row.push(widget::Space::new(
+                Length::FillPortion((SCALE - last) as u16),
+                Length::Shrink,
+            ));
+        }
+
+        row.into()
+    }
+
+    /// Whether `file` survives the active search and the local-only filter.
+    fn track_visible(&self, file: &MusicFile) -> bool {
+        if self.local_only && file.source() == TrackSource::Network {
+            return false;
+        }
+
+        // Tracks of unknown tempo are left out as soon as a bound is set.
+        let in_range = match (self.tempo_range, file.bpm) {
+            ((None, None), _) => true,
+            ((from, to), Some(bpm)) => {
+                from.is_none_or(|from| bpm >= from) && to.is_none_or(|to| bpm <= to)
+            }
+            (_, None) => false,
+        };
+        if !in_range {
+            return false;
+        }
+
+        self.search_results
+            .as_ref()
+            .is_none_or(|results| results.tracks.contains(&file.id))
+    }
+
+    /// Height of a track row, taller in the touch layout.
+    fn row_height(&self) -> f32 {
+        if self.touch.is_active() {
+            TOUCH_ROW_HEIGHT
+        } else {
+            TRACK_ROW_HEIGHT
+        }
+    }
+
+    /// Size of the smaller icon buttons, which the touch layout enlarges.
+    fn small_icon_size(&self) -> u16 {
+        if self.touch.is_active() {
+            24
+        } else {
+            16
+        }
+    }
+
+    /// A row of a track list: number, play/pause button, title, artist and album.
+    fn track_row(&self, file: &MusicFile) -> Element<Message> {
+        let padding = if self.touch.is_active() {
+            [12, 4, 12, 4]
+        } else {
+            [6, 4, 6, 4]
+        };
+        let mut file_txt_row = Row::new()
+            .align_y(Alignment::Center)
+            .spacing(TRACK_CELL_SPACING)
+            .padding(padding);
+
+        let widths = self.track_columns();
+        let track_number = text(self.track_label(file.id))
+            .align_x(Horizontal::Center)
+            .width(Length::FillPortion(track_columns::NUMBER_PORTION));
+        file_txt_row = file_txt_row.push(track_number);
+
+        let button = match self.playback.track_state(file.id) {
+            PlayState::Paused => button::icon(icon::from_name("media-playback-start-symbolic"))
+                .on_press(Message::ResumeCurrentTrack),
+            PlayState::Playing => button::icon(icon::from_name("media-playback-pause-symbolic"))
+                .on_press(Message::PauseCurrentTrack),
+            PlayState::Idle => button::icon(icon::from_name("media-playback-start-symbolic"))
+                .on_press(Message::StartPlayingNewTrack(file.uri.clone())),
+        };
+        file_txt_row = file_txt_row.push(button.icon_size(self.small_icon_size()));
+
+        let title = track_cell(file.track_title.clone(), widths.portion(TrackColumn::Title));
+        let artist = track_cell(file.artist.clone(), widths.portion(TrackColumn::Artist));
+        let album = track_cell(file.album.clone(), widths.portion(TrackColumn::Album));
+        let bpm = text(file.bpm.map(|bpm| bpm.to_string()).unwrap_or_default())
+            .align_x(Horizontal::Right)
+            .width(Length::FillPortion(widths.portion(TrackColumn::Bpm)));
+        let key = text(
+            file.key
+                .map(|key| key.display(self.key_notation))
+                .unwrap_or_default(),
+        )
+        .align_x(Horizontal::Right)
+        .width(Length::FillPortion(widths.portion(TrackColumn::Key)));
+        file_txt_row = file_txt_row.push(title);
+        file_txt_row = file_txt_row.push(artist);
+        file_txt_row = file_txt_row.push(album);
+        file_txt_row = file_txt_row.push(bpm);
+        file_txt_row = file_txt_row.push(key);
+
+        // Badge tracks that only play with a connection.
+        if file.source() == TrackSource::Network {
+            file_txt_row = file_txt_row.push(icon::from_name("network-server-symbolic").size(16));
+        }
+
+        let file_txt_row: Element<Message> = if self.cursor == Some(file.id) {
+            Container::new(file_txt_row)
+                .class(cosmic::style::Container::Card)
+                .into()
+        } else {
+            file_txt_row.into()
+        };
+
+        // Hover previews make no sense without a pointer, so touch takes over the row.
+        if self.touch.is_active() {
+            return widget::mouse_area(file_txt_row)
+                .on_press(Message::TrackPressed(file.id))
+                .into();
+        }
+
+        if self.preview_on_hover && self.search_results.is_some() {
+            return widget::mouse_area(file_txt_row)
+                .on_enter(Message::HoverTrack(file.id))
+                .on_exit(Message::UnhoverTrack(file.id))
+                .into();
+        }
+
+        file_txt_row
+    }
+
+    /// The column widths, as they're being dragged if they are.
+    fn track_columns(&self) -> ColumnWidths {
+        self.column_drag
+            .map(|drag| drag.widths)
+            .unwrap_or(self.config.track_columns)
+    }
+
+    /// Width of a track row that isn't shared out between its columns: the padding, the
+    /// play button and the gaps between the seven cells.
+    fn fixed_row_width(&self) -> f32 {
+        8.0 + self.play_button_width() + 6.0 * TRACK_CELL_SPACING
+    }
+
+    fn play_button_width(&self) -> f32 {
+        f32::from(self.small_icon_size()) + 2.0 * ICON_BUTTON_PADDING
+    }
+
+    /// Headings for the columns of a track list, laid out like [`Self::track_row`], with
+    /// handles between them to drag to resize the columns.
+    fn track_header(&self) -> Element<Message> {
+        let widths = self.track_columns();
+        let play_button = self.play_button_width();
+
+        let header = widget::responsive(move |size| {
+            let mut row = Row::new()
+                .align_y(Alignment::Center)
+                .height(Length::Fill)
+                .padding([0, 4])
+                .push(
+                    text::heading("#")
+                        .align_x(Horizontal::Center)
+                        .width(Length::FillPortion(track_columns::NUMBER_PORTION)),
+                )
+                .push(widget::Space::with_width(Length::Fixed(
+                    play_button + 2.0 * TRACK_CELL_SPACING,
+                )));
+
+            for (index, column) in TrackColumn::ALL.into_iter().enumerate() {
+                if let Some(left) = index.checked_sub(1).map(|left| TrackColumn::ALL[left]) {
+                    row = row.push(column_handle(left));
+                }
+
+                let (name, align) = match column {
+                    TrackColumn::Title => (fl!("track-title"), Horizontal::Left),
+                    TrackColumn::Artist => (fl!("track-artist"), Horizontal::Left),
+                    TrackColumn::Album => (fl!("track-album"), Horizontal::Left),
+                    TrackColumn::Bpm => (fl!("bpm"), Horizontal::Right),
+                    TrackColumn::Key => (fl!("track-key"), Horizontal::Right),
+                };
+                row = row.push(
+                    text::heading(name)
+                        .align_x(align)
+                        .width(Length::FillPortion(widths.portion(column))),
+                );
+            }
+
+            widget::mouse_area(row)
+                .on_move(move |point| Message::DragColumn(point.x, size.width))
+                .on_release(Message::EndColumnDrag)
+                .into()
+        });
+
+        Container::new(header)
+            .height(Length::Fixed(TRACK_HEADER_HEIGHT))
+            .into()
+    }
+
+    /// A column of track rows separated by dividers.
+    fn track_list<'a>(
+        &'a self,
+        files: impl IntoIterator<Item = &'a MusicFile>,
+    ) -> Column<'a, Message> {
+        let mut file_col = Column::new().spacing(2);
+
+        for file in files {
+            file_col = file_col
+                .push(self.track_row(file))
+                .push(widget::divider::horizontal::default());
+        }
+
+        file_col
+    }
+
+    /// A track row with the divider below it, sized for a [`VirtualList`].
+    fn virtual_track_row(&self, file: &MusicFile) -> Element<Message> {
+        Column::new()
+            .push(self.track_row(file))
+            .push(widget::divider::horizontal::default())
+            .into()
+    }
+
+    /// Every file in library order, below the BPM range.
+    fn all_music(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let files: Vec<&MusicFile> = self
+            .scanned_files
+            .iter()
+            .filter(|file| self.track_visible(file))
+            .collect();
+
+        let tempo_row = Row::new()
+            .spacing(space_xxs)
+            .align_y(Alignment::Center)
+            .push(text(fl!("bpm")))
+            .push(
+                widget::text_input(fl!("bpm-from"), &self.tempo_from)
+                    .on_input(Message::TempoFrom)
+                    .width(80),
+            )
+            .push(text("–"))
+            .push(
+                widget::text_input(fl!("bpm-to"), &self.tempo_to)
+                    .on_input(Message::TempoTo)
+                    .width(80),
+            )
+            .push(
+                button::standard(fl!("shuffle-bpm-range")).on_press_maybe(
+                    (self.tempo_range != (None, None) && !files.is_empty())
+                        .then_some(Message::ShuffleTempo),
+                ),
+            )
+            .push(widget::Space::with_width(Length::Fill))
+            .push(self.group_toggle());
+
+        let size: u64 = files.iter().map(|file| file.size).sum();
+        let summary = [
+            fl!("song-count", count = files.len()),
+            format_length(total_length(&files)),
+            format_size(size),
+        ];
+
+        let (header, list) = if self.group_by_album {
+            (None, self.album_grouped_list(files))
+        } else {
+            let list = VirtualList::new(
+                files.len(),
+                self.row_height(),
+                |index| self.virtual_track_row(files[index]),
+                Message::ListScrolled,
+            )
+            .id(PAGE_SCROLL.clone())
+            .view(&self.list_scroll);
+            (Some(self.track_header()), list)
+        };
+
+        Column::new()
+            .spacing(8)
+            .push(tempo_row)
+            .push_maybe(header)
+            .push(list)
+            .push(text::caption(summary.join(" · ")))
+            .into()
+    }
+
+    /// The view on the active page whose scroll position is remembered.
+    fn scroll_spot(&self) -> Option<ScrollSpot> {
+        let page = *self.nav.active_data::<Page>()?;
+        let spot = match page {
+            Page::Albums => match self.selected_album.and_then(|index| self.albums.get(index)) {
+                Some(album) => {
+                    ScrollSpot::Album(artwork::album_key(&album.album_artist, &album.album))
+                }
+                None => ScrollSpot::Page(page),
+            },
+            Page::Artists => match &self.selected_artist {
+                Some(artist) => ScrollSpot::Artist(artist.clone()),
+                None => ScrollSpot::Page(page),
+            },
+            Page::Labels => match &self.selected_label {
+                Some(label) => ScrollSpot::Label(label.clone()),
+                None => ScrollSpot::Page(page),
+            },
+            _ => ScrollSpot::Page(page),
+        };
+        Some(spot)
+    }
+
+    /// Scrolls the view just switched to back to where it was left, or to the top if it
+    /// wasn't shown before.
+    fn restore_scroll(&mut self) -> Task<Message> {
+        let offset = self
+            .scroll_spot()
+            .and_then(|spot| self.scroll_offsets.get(&spot).copied())
+            .unwrap_or(0.0);
+
+        self.list_scroll.restore(offset);
+        scrollable::scroll_to(PAGE_SCROLL.clone(), AbsoluteOffset { x: 0.0, y: offset })
+    }
+
+    /// Switches the track lists between plain and grouped by album.
+    fn group_toggle(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        Row::new()
+            .spacing(space_xxs)
+            .align_y(Alignment::Center)
+            .push(widget::toggler(self.group_by_album).on_toggle(Message::GroupByAlbum))
+            .push(text(fl!("group-by-album")))
+            .into()
+    }
+
+    /// `files` clustered under a header for each album, with its cover, title, year and a
+    /// play button. Collapsed albums only show their header. Files are put in library order
+    /// first so each album's tracks sit together.
+    fn album_grouped_list<'a>(&'a self, mut files: Vec<&'a MusicFile>) -> Element<'a, Message> {
+        #[derive(Clone, Copy)]
+        enum GroupedRow<'a> {
+            /// The album's first track, which names it, and how many tracks are listed.
+            Album(&'a MusicFile, usize),
+            Track(&'a MusicFile),
+        }
+
+        files.sort_by_cached_key(|file| track_number::library_key(file));
+
+        let album_indices: HashMap<(&str, &str), usize> = self
+            .albums
+            .iter()
+            .enumerate()
+            .map(|(index, album)| ((album.album_artist.as_str(), album.album.as_str()), index))
+            .collect();
+
+        let mut rows: Vec<GroupedRow> = Vec::new();
+        let mut header = 0;
+        for file in files {
+            let same_album = match rows.get(header) {
+                Some(GroupedRow::Album(first, _)) => {
+                    first.album == file.album && first.album_artist == file.album_artist
+                }
+                _ => false,
+            };
+            if !same_album {
+                header = rows.len();
+                rows.push(GroupedRow::Album(file, 0));
+            }
+
+            if let Some(GroupedRow::Album(first, count)) = rows.get_mut(header) {
+                *count += 1;
+                let key = artwork::album_key(&first.album_artist, &first.album);
+                if !self.collapsed_albums.contains(&key) {
+                    rows.push(GroupedRow::Track(file));
+                }
+            }
+        }
+
+        let heights: Vec<f32> = rows
+            .iter()
+            .map(|row| match row {
+                GroupedRow::Album(..) => ALBUM_HEADER_HEIGHT,
+                GroupedRow::Track(_) => self.row_height(),
+            })
+            .collect();
+
+        VirtualList::with_heights(
+            heights,
+            move |index| match rows[index] {
+                GroupedRow::Album(first, count) => {
+                    let album_index = album_indices
+                        .get(&(first.album_artist.as_str(), first.album.as_str()))
+                        .copied();
+                    self.album_header(first, album_index, count)
+                }
+                GroupedRow::Track(file) => self.virtual_track_row(file),
+            },
+            Message::ListScrolled,
+        )
+        .id(PAGE_SCROLL.clone())
+        .view(&self.list_scroll)
+    }
+
+    /// The header over an album's tracks in the grouped list. `first` is the album's first
+    /// track, `album_index` where it is in [`Jams::albums`], and `count` how many tracks
+    /// are listed under it.
+    fn album_header(
+        &self,
+        first: &MusicFile,
+        album_index: Option<usize>,
+        count: usize,
+    ) -> Element<Message> {
+        let cosmic_theme::Spacing {
+            space_xxs, space_s, ..
+        } = theme::active().cosmic().spacing;
+
+        let key = artwork::album_key(&first.album_artist, &first.album);
+        let collapsed = self.collapsed_albums.contains(&key);
+        let album = album_index.and_then(|index| self.albums.get(index));
+
+        let cover = match album {
+            Some(album) => self.album_cover(album, ALBUM_HEADER_COVER),
+            None => widget::Space::new(
+                Length::Fixed(ALBUM_HEADER_COVER),
+                Length::Fixed(ALBUM_HEADER_COVER),
+            )
+            .into(),
+        };
+
+        let mut details = vec![if first.album_artist.is_empty() {
+            fl!("unknown-artist")
+        } else {
+            first.album_artist.clone()
+        }];
+        if let Some(date) = album.and_then(|album| album.date) {
+            details.push(date.year.to_string());
+        }
+        details.push(fl!("song-count", count = count));
+
+        let play = album_index.and_then(|index| {
+            let first_id = self.album_tracks(index).first().map(|file| file.id)?;
+            Some(Message::PlayAlbumFrom(index, first_id))
+        });
+
+        Row::new()
+            .spacing(space_s)
+            .padding([space_xxs, 4])
+            .align_y(Alignment::Center)
+            .push(
+                button::icon(icon::from_name(if collapsed {
+                    "go-next-symbolic"
+                } else {
+                    "go-down-symbolic"
+                }))
+                .on_press(Message::ToggleAlbumGroup(key)),
+            )
+            .push(cover)
+            .push(
+                Column::new()
+                    .spacing(2)
+                    .width(Length::Fill)
+                    .push(text::heading(first.album.clone()))
+                    .push(text::caption(details.join(" · "))),
+            )
+            .push(
+                button::icon(icon::from_name("media-playback-start-symbolic"))
+                    .on_press_maybe(play),
+            )
+            .into()
+    }
+
+    /// Each visible song once, sorted by artist and title, keeping the first copy in library
+    /// order.
+    fn song_files(&self) -> Vec<&MusicFile> {
+        let mut song_list = self.song_list.borrow_mut();
+        let songs = song_list.get_or_insert_with(|| {
+            track_number::distinct_songs(&self.scanned_files, |file| self.track_visible(file))
+        });
+
+        songs
+            .iter()
+            .filter_map(|&index| self.scanned_files.get(index))
+            .collect()
+    }
+
+    /// Drops the cached [`Self::song_files`], to be made again on the next redraw.
+    fn forget_song_list(&mut self) {
+        self.song_list.get_mut().take();
+    }
+
+    /// The tracks the cursor moves through on the active page, in order, with where each
+    /// row starts in the page's list. Rows of an open album have no known position, and
+    /// lists grouped by album aren't walked.
+    fn listed_tracks(&self) -> Vec<(usize, Option<f32>)> {
+        let row_height = self.row_height();
+
+        match self.nav.active_data::<Page>() {
+            Some(Page::AllMusic) if !self.group_by_album => self
+                .scanned_files
+                .iter()
+                .filter(|file| self.track_visible(file))
+                .enumerate()
+                .map(|(index, file)| (file.id, Some(index as f32 * row_height)))
+                .collect(),
+            Some(Page::Songs) if !self.group_by_album => {
+                // Each artist's songs follow a heading row, as in `songs`.
+                let mut rows = 0;
+                let mut previous: Option<&MusicFile> = None;
+                let mut tracks = Vec::new();
+                for file in self.song_files() {
+                    let new_artist =
+                        previous.map_or(true, |previous| previous.artist_key != file.artist_key);
+                    if new_artist {
+                        rows += 1;
+                    }
+                    tracks.push((file.id, Some(rows as f32 * row_height)));
+                    rows += 1;
+                    previous = Some(file);
+                }
+                tracks
+            }
+            Some(Page::Albums) => match self.selected_album {
+                Some(index) => self
+                    .album_tracks(index)
+                    .iter()
+                    .map(|file| (file.id, None))
+                    .collect(),
+                None => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Each song once, even if several copies are in the library, grouped under its artist
+    /// or, if asked for, its album.
+    fn songs(&self) -> Element<Message> {
+        #[derive(Clone, Copy)]
+        enum SongRow<'a> {
+            Artist(&'a str),
+            Song(&'a MusicFile),
+        }
+
+        let files = self.song_files();
+        if self.group_by_album {
+            let list = self.album_grouped_list(files);
+            return Column::new()
+                .spacing(8)
+                .push(self.group_toggle())
+                .push(list)
+                .into();
+        }
+
+        let mut rows = Vec::new();
+        for file in files {
+            let new_artist = match rows.last() {
+                Some(SongRow::Song(previous)) => previous.artist_key != file.artist_key,
+                _ => true,
+            };
+            if new_artist {
+                rows.push(SongRow::Artist(&file.artist));
+            }
+            rows.push(SongRow::Song(file));
+        }
+
+        let list = VirtualList::new(
+            rows.len(),
+            self.row_height(),
+            |index| match rows[index] {
+                SongRow::Artist(artist) => {
+                    let name = if artist.is_empty() {
+                        fl!("unknown-artist")
+                    } else {
+                        artist.to_string()
+                    };
+
+                    Container::new(text::heading(name))
+                        .padding([16, 4, 4, 4])
+                        .into()
+                }
+                SongRow::Song(file) => self.virtual_track_row(file),
+            },
+            Message::ListScrolled,
+        )
+        .id(PAGE_SCROLL.clone())
+        .view(&self.list_scroll);
+
+        Column::new()
+            .spacing(8)
+            .push(self.group_toggle())
+            .push(self.track_header())
+            .push(list)
+            .into()
+    }
+
+    /// The cover grid, or the open album.
+    fn album_grid(&self) -> Element<Message> {
+        if let Some(index) = self.selected_album {
+            return self.album_detail(index);
+        }
+
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let decades: BTreeSet<u16> = self
+            .albums
+            .iter()
+            .filter_map(|album| album.date)
+            .map(|date| date.decade())
+            .collect();
+
+        let decade_button = |label: String, decade: Option<u16>| {
+            let button = if self.album_decade == decade {
+                button::suggested(label)
+            } else {
+                button::standard(label)
+            };
+            button.on_press(Message::FilterDecade(decade))
+        };
+
+        let mut decade_row = Row::new()
+            .spacing(space_xxs)
+            .push(decade_button(fl!("all-decades"), None));
+        for decade in decades {
+            decade_row = decade_row.push(decade_button(
+                fl!("decade", decade = decade.to_string()),
+                Some(decade),
+            ));
+        }
+
+        let languages: BTreeSet<&str> = self
+            .albums
+            .iter()
+            .flat_map(|album| album.languages.iter().map(String::as_str))
+            .collect();
+
+        let language_button = |label: String, language: Option<&str>| {
+            let button = if self.album_language.as_deref() == language {
+                button::suggested(label)
+            } else {
+                button::standard(label)
+            };
+            button.on_press(Message::FilterLanguage(language.map(str::to_string)))
+        };
+
+        // Only worth showing for libraries in more than one language.
+        let language_row = (languages.len() > 1).then(|| {
+            let mut language_row = Row::new()
+                .spacing(space_xxs)
+                .push(language_button(fl!("all-languages"), None));
+            for language in languages {
+                language_row =
+                    language_row.push(language_button(language.to_uppercase(), Some(language)));
+            }
+            language_row.wrap()
+        });
+
+        let mut list_of_albums = Row::new().width(Length::Fill).align_y(Alignment::Center);
+
+        let visible: Vec<(usize, &Album)> = self
+            .albums
+            .iter()
+            .enumerate()
+            .filter(|(index, album)| {
+                let in_decade = self.album_decade.is_none_or(|decade| {
+                    album.date.is_some_and(|date| date.decade() == decade)
+                });
+
+                let in_language = self
+                    .album_language
+                    .as_ref()
+                    .is_none_or(|language| album.languages.contains(language));
+
+                in_decade
+                    && in_language
+                    && self
+                        .search_results
+                        .as_ref()
+                        .is_none_or(|results| results.albums.contains(index))
+            })
+            .collect();
+
+        // Editions of an album share one tile, the first in sort order, which is usually
+        // the original release.
+        let mut version_counts: HashMap<&str, usize> = HashMap::new();
+        for (_, album) in &visible {
+            *version_counts
+                .entry(album.version_group.as_str())
+                .or_default() += 1;
+        }
+        let mut shown: HashSet<&str> = HashSet::new();
+
+        for (index, album) in visible {
+            if shown.insert(album.version_group.as_str()) {
+                let versions = version_counts[album.version_group.as_str()];
+                let mut album_content = Column::new();
+
+                let album_front_cover = self.album_cover(album, self.tile_size);
+                let title = if versions > 1 {
+                    edition::split_edition(&album.album).0
+                } else {
+                    album.album.as_str()
+                };
+                let album_name = clipped_text(title.to_string(), Horizontal::Center);
+
+                album_content = album_content.push(album_front_cover);
+                album_content = album_content.push(album_name);
+                if let Some(date) = album.date {
+                    album_content = album_content.push(
+                        text::caption(date.year.to_string())
+                            .width(Length::Fill)
+                            .align_x(Alignment::Center),
+                    );
+                }
+                if versions > 1 {
+                    album_content = album_content.push(
+                        text::caption(fl!("album-versions", count = versions))
+                            .width(Length::Fill)
+                            .align_x(Alignment::Center),
+                    );
+                }
+
+                let mut album_content_alignment = Row::new().align_y(Alignment::Start);
+                album_content_alignment = album_content_alignment.push(album_content);
+
+                let mut album_block = Column::new()
+                    .width(Length::Fill)
+                    .max_width(self.tile_size + 30.0)
+                    .spacing(8)
+                    .padding([6, 4, 6, 4]);
+                album_block = album_block.push(album_content_alignment);
+
+                let tile = button::custom(album_block)
+                    .class(cosmic::style::Button::Text)
+                    .padding(0)
+                    .on_press(Message::OpenAlbum(index));
+                // The title can be cut off, so screen readers are given it whole, and who
+                // it's by, which the tile only shows as a cover.
+                #[cfg(feature = "a11y")]
+                let tile = tile
+                    .name(album.album.clone())
+                    .description(album.album_artist.clone());
+
+                list_of_albums = list_of_albums.push(tile);
+            }
+        }
+
+        Column::new()
+            .spacing(8)
+            .push(decade_row.wrap())
+            .push_maybe(language_row)
+            .push(
+                // The grid ignores scrolling with Ctrl held, leaving it for zooming.
+                cosmic::iced::widget::mouse_area(scroll_page(list_of_albums.wrap()))
+                    .on_scroll(Message::ZoomAlbumGrid),
+            )
+            .into()
+    }
+
+    /// Every artist with a matching track, or the tracks of the open artist.
+    fn artists(&self) -> Element<Message> {
+        if let Some(artist) = &self.selected_artist {
+            return self.artist_detail(artist);
+        }
+
+        // Keyed by sort key first, so "The National" is listed under N.
+        let mut track_counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+        for file in self
+            .scanned_files
+            .iter()
+            .filter(|file| self.track_visible(file))
+        {
+            *track_counts
+                .entry((file.artist_key.as_str(), file.artist.as_str()))
+                .or_default() += 1;
+        }
+
+        let mut artist_col = Column::new().spacing(2);
+        for ((_, artist), count) in track_counts {
+            let name = if artist.is_empty() {
+                fl!("unknown-artist")
+            } else {
+                artist.to_string()
+            };
+
+            let artist_row = Row::new()
+                .align_y(Alignment::Center)
+                .spacing(8)
+                .padding([6, 4, 6, 4])
+                .push(text(name).width(Length::Fill))
+                .push(text::caption(fl!("song-count", count = count)));
+
+            artist_col = artist_col
+                .push(
+                    widget::mouse_area(artist_row)
+                        .on_press(Message::OpenArtist(artist.to_string())),
+                )
+                .push(widget::divider::horizontal::default());
+        }
+
+        scroll_page(artist_col)
+    }
+
+    /// The tracks credited to `artist`.
+    fn artist_detail(&self, artist: &str) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let name = if artist.is_empty() {
+            fl!("unknown-artist")
+        } else {
+            artist.to_string()
+        };
+
+        let header = Row::new()
+            .align_y(Alignment::Center)
+            .spacing(space_xxs)
+            .push(
+                button::icon(icon::from_name("go-previous-symbolic"))
+                    .on_press(Message::CloseArtist),
+            )
+            .push(text::title3(name));
+
+        let files = self
+            .scanned_files
+            .iter()
+            .filter(|file| file.artist == artist && self.track_visible(file));
+
+        let body: Element<Message> = if self.discography_lookup && !artist.is_empty() {
+            Row::new()
+                .spacing(space_xxs)
+                .push(
+                    Container::new(scroll_page(self.track_list(files)))
+                        .width(Length::FillPortion(3)),
+                )
+                .push(
+                    Container::new(scroll_page(self.discography(artist)))
+                        .width(Length::FillPortion(2)),
+                )
+                .into()
+        } else {
+            scroll_page(self.track_list(files))
+        };
+
+        Column::new().spacing(8).push(header).push(body).into()
+    }
+
+    /// Every record label in the library with how many tracks it has. Tracks without one
+    /// aren't listed.
+    fn labels(&self) -> Element<Message> {
+        if let Some(label) = &self.selected_label {
+            return self.label_detail(label);
+        }
+
+        // Labels are written inconsistently, so "warp" and "Warp" are listed together.
+        let mut track_counts: BTreeMap<String, (&str, usize)> = BTreeMap::new();
+        for file in self
+            .scanned_files
+            .iter()
+            .filter(|file| !file.label.is_empty() && self.track_visible(file))
+        {
+            track_counts
+                .entry(collation::fold(&file.label))
+                .or_insert((file.label.as_str(), 0))
+                .1 += 1;
+        }
+
+        if track_counts.is_empty() {
+            return scroll_page(text(fl!("no-labels")));
+        }
+
+        let mut label_col = Column::new().spacing(2);
+        for (label, count) in track_counts.into_values() {
+            let label_row = Row::new()
+                .align_y(Alignment::Center)
+                .spacing(8)
+                .padding([6, 4, 6, 4])
+                .push(text(label).width(Length::Fill))
+                .push(text::caption(fl!("song-count", count = count)));
+
+            label_col = label_col
+                .push(widget::mouse_area(label_row).on_press(Message::OpenLabel(label.to_string())))
+                .push(widget::divider::horizontal::default());
+        }
+
+        scroll_page(label_col)
+    }
+
+    /// The tracks released on `label`.
+    fn label_detail(&self, label: &str) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let header = Row::new()
+            .align_y(Alignment::Center)
+            .spacing(space_xxs)
+            .push(
+                button::icon(icon::from_name("go-previous-symbolic")).on_press(Message::CloseLabel),
+            )
+            .push(text::title3(label));
+
+        let key = collation::fold(label);
+        let files = self.scanned_files.iter().filter(|file| {
+            !file.label.is_empty()
+                && collation::fold(&file.label) == key
+                && self.track_visible(file)
+        });
+
+        Column::new()
+            .spacing(8)
+            .push(header)
+            .push(scroll_page(self.track_list(files)))
+            .into()
+    }
+
+    /// What MusicBrainz lists for `artist`, marking releases that aren't in the library.
+    fn discography(&self, artist: &str) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut column = Column::new()
+            .spacing(space_xxs)
+            .push(text::heading(fl!("discography")));
+
+        let discography = match self.discographies.get(artist) {
+            Some(Some(discography)) => discography,
+            Some(None) => return column.push(text(fl!("discography-unknown"))).into(),
+            None if self.discography_loading.as_deref() == Some(artist) => {
+                return column.push(text(fl!("discography-loading"))).into();
+            }
+            None => {
+                if let Some(error) = &self.discography_error {
+                    column = column.push(text::caption(error.clone()));
+                }
+                return column
+                    .push(
+                        button::standard(fl!("discography-retry"))
+                            .on_press(Message::FetchDiscography(artist.to_string())),
+                    )
+                    .into();
+            }
+        };
+
+        let owned: Vec<String> = self
+            .scanned_files
+            .iter()
+            .filter(|file| file.artist == artist || file.album_artist == artist)
+            .map(|file| file.album.clone())
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect();
+        let owned_count = discography
+            .releases
+            .iter()
+            .filter(|release| musicbrainz::is_owned(release, &owned))
+            .count();
+
+        column = column.push(text::caption(fl!(
+            "discography-owned",
+            owned = owned_count,
+            total = discography.releases.len()
+        )));
+
+        for release in &discography.releases {
+            let status = if musicbrainz::is_owned(release, &owned) {
+                icon::from_name("object-select-symbolic").size(16)
+            } else {
+                icon::from_name("list-add-symbolic").size(16)
+            };
+            let year = release.year.map(|year| year.to_string()).unwrap_or_default();
+
+            column = column.push(
+                Row::new()
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center)
+                    .push(status)
+                    .push(text::caption(year).width(Length::Fixed(40.0)))
+                    .push(
+                        Column::new()
+                            .push(text(release.title.clone()))
+                            .push(text::caption(release.kind.clone())),
+                    ),
+            );
+        }
+
+        column.into()
+    }
+
+    /// Cover, tracks and artwork actions for a single album.
+    pub fn album_detail(&self, index: usize) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, space_s, .. } = theme::active().cosmic().spacing;
+
+        let Some(album) = self.albums.get(index) else {
+            return widget::column().into();
+        };
+
+        let cover = self.album_cover(album, 200.0);
+
+        let artwork_buttons = Row::new()
+            .spacing(space_xxs)
+            .push(
+                button::standard(fl!("choose-cover"))
+                    .on_press(Message::ChooseCover(ArtworkTarget::Album(index))),
+            )
+            .push(
+                button::standard(fl!("paste-cover"))
+                    .on_press(Message::PasteCover(ArtworkTarget::Album(index))),
+            );
+
+        let embed_row = Row::new()
+            .spacing(space_xxs)
+            .align_y(Alignment::Center)
+            .push(widget::toggler(self.embed_custom_covers).on_toggle(Message::EmbedCovers))
+            .push(text(fl!("embed-cover")));
+
+        let share_buttons = Row::new()
+            .spacing(space_xxs)
+            .push(
+                button::standard(fl!("copy-share-text"))
+                    .on_press(Message::CopyText(share::album_text(album))),
+            )
+            .push(
+                button::standard(fl!("copy-link"))
+                    .on_press(Message::CopyText(ShareLink::album(album).to_url())),
+            );
+
+        let info = Column::new()
+            .spacing(space_xxs)
+            .push(
+                button::icon(icon::from_name("go-previous-symbolic"))
+                    .on_press(Message::CloseAlbum),
+            )
+            .push(text::title3(album.album.clone()))
+            .push(text(album.album_artist.clone()))
+            .push(artwork_buttons)
+            .push(embed_row)
+            .push(share_buttons)
+            .push(
+                Row::new()
+                    .spacing(space_xxs)
+                    .push(
+                        button::standard(fl!("reread-tags"))
+                            .on_press(Message::RereadTags(album.tracks.clone())),
+                    )
+                    .push(
+                        button::standard(fl!("verify-files")).on_press_maybe(
+                            self.verify_job
+                                .is_none()
+                                .then(|| Message::VerifyFiles(album.tracks.clone())),
+                        ),
+                    ),
+            );
+        let info = match &self.reread_status {
+            Some(status) => info.push(text::caption(status.clone())),
+            None => info,
+        };
+
+        let key = artwork::album_key(&album.album_artist, &album.album);
+        let info = match self.loudness.get(&key) {
+            Some(measured) => info.push(text::caption(loudness_summary(measured))),
+            None if self.loudness_job.is_some() => {
+                info.push(text::caption(fl!("measuring-loudness")))
+            }
+            None => info.push(
+                button::standard(fl!("measure-loudness")).on_press(Message::MeasureLoudness(index)),
+            ),
+        };
+
+        let header = Row::new().spacing(space_s).push(cover).push(info);
+
+        let versions: Vec<(usize, &Album)> = self
+            .albums
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| other.version_group == album.version_group)
+            .collect();
+        let version_switcher = (versions.len() > 1).then(|| {
+            let mut row = Row::new().spacing(space_xxs);
+            for (other_index, other) in versions {
+                let mut label = edition::split_edition(&other.album)
+                    .1
+                    .unwrap_or_else(|| fl!("album-version-original"));
+                if let Some(date) = other.date {
+                    label = format!("{} · {}", label, date.year);
+                }
+
+                let button = if other_index == index {
+                    button::suggested(label)
+                } else {
+                    button::standard(label)
+                };
+                row = row.push(button.on_press(Message::OpenAlbum(other_index)));
+            }
+            row.wrap()
+        });
+
+        let mut track_col = Column::new().spacing(2);
+        for file in self.album_tracks(index) {
+            let track_row = Row::new()
+                .align_y(Alignment::Center)
+                .spacing(8)
+                .padding([6, 4, 6, 4])
+                .push(
+                    text(self.track_label(file.id))
+                        .align_x(Horizontal::Center)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button::icon(icon::from_name("media-playback-start-symbolic"))
+                        .on_press(Message::PlayAlbumFrom(index, file.id)),
+                )
+                .push(text(file.track_title.clone()).width(Length::FillPortion(40)))
+                .push_maybe(self.damaged.get(&file.id).map(|problem| {
+                    widget::tooltip(
+                        icon::from_name("dialog-warning-symbolic").size(16),
+                        text(problem.clone()),
+                        widget::tooltip::Position::Left,
+                    )
+                }))
+                .push(
+                    button::icon(icon::from_name("image-x-generic-symbolic"))
+                        .on_press(Message::ChooseCover(ArtworkTarget::Track(file.id))),
+                )
+                .push(
+                    button::icon(icon::from_name("view-refresh-symbolic"))
+                        .on_press(Message::RereadTags(vec![file.id])),
+                )
+                .push(
+                    button::icon(icon::from_name("document-properties-symbolic"))
+                        .on_press(Message::ShowTrackInfo(file.id)),
+                )
+                .push(
+                    button::icon(icon::from_name("emblem-shared-symbolic")).on_press(
+                        Message::CopyText(format!(
+                            "{}\n{}",
+                            share::track_text(file),
+                            ShareLink::track(file).to_url()
+                        )),
+                    ),
+                );
+
+            track_col = track_col
+                .push(track_row)
+                .push(widget::divider::horizontal::default());
+        }
+
+        let tracks = self.album_tracks(index);
+        let summary = [
+            fl!("track-count", count = tracks.len()),
+            format_length(total_length(&tracks)),
+        ];
+
+        Column::new()
+            .spacing(space_s)
+            .padding([0, space_s])
+            .push(header)
+            .push_maybe(version_switcher)
+            .push(
+                Scrollable::new(track_col)
+                    .id(PAGE_SCROLL.clone())
+                    .on_scroll(Message::ListScrolled)
+                    .height(Length::Fill)
+                    .width(Length::Fill),
+            )
+            .push(text::caption(summary.join(" · ")))
+            .height(Length::Fill)
+            .into()
+    }
+
+    /// Where the track in the track info panel lives, and the pictures embedded in it.
+    pub fn track_info_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let Some(file) = self.track_info.and_then(|id| self.find_track(id)) else {
+            return widget::column().into();
+        };
+
+        let details = widget::settings::section()
+            .add(widget::settings::item(
+                fl!("track-title"),
+                text(file.track_title.clone()),
+            ))
+            .add(widget::settings::item(
+                fl!("track-artist"),
+                text(file.artist.clone()),
+            ))
+            .add(widget::settings::item(
+                fl!("track-album"),
+                text(file.album.clone()),
+            ))
+            .add(text::caption(file.saved_path.display().to_string()))
+            .add(button::standard(fl!("more-like-this")).on_press(Message::MoreLikeThis(file.id)));
+
+        let mut pictures = widget::settings::section().title(fl!("embedded-pictures"));
+        if let Some(err) = &self.track_pictures_error {
+            pictures = pictures.add(text(err.clone()));
+        } else if self.track_pictures.is_empty() {
+            pictures = pictures.add(text(fl!("no-embedded-pictures")));
+        }
+        for (index, (picture, handle)) in self.track_pictures.iter().enumerate() {
+            pictures = pictures.add(
+                Row::new()
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center)
+                    .push(
+                        image(handle.clone())
+                            .width(Length::Fixed(96.0))
+                            .height(Length::Fixed(96.0))
+                            .content_fit(ContentFit::Contain),
+                    )
+                    .push(text(picture_type_name(picture.pic_type)).width(Length::Fill))
+                    .push(
+                        button::standard(fl!("use-as-cover")).on_press(Message::UsePicture(index)),
+                    ),
+            );
+        }
+
+        let mut edits = widget::settings::section().title(fl!("edit-history"));
+        if self.track_edits.is_empty() {
+            edits = edits.add(text(fl!("no-edits")));
+        }
+        for (index, edit) in self.track_edits.iter().enumerate() {
+            let field = match edit.field {
+                Field::FrontCover => fl!("picture-front"),
+            };
+            let change = match (&edit.before, &edit.after) {
+                (None, _) => fl!("edit-added"),
+                (Some(_), None) => fl!("edit-removed"),
+                (Some(_), Some(_)) => fl!("edit-replaced"),
+            };
+
+            edits = edits.add(
+                widget::settings::item::builder(field)
+                    .description(change)
+                    .control(button::standard(fl!("revert")).on_press(Message::RevertEdit(index))),
+            );
+        }
+
+        widget::settings::view_column(vec![details.into(), pictures.into(), edits.into()]).into()
+    }
+
+    /// How the library is sorted.
+    pub fn library_settings(&self) -> Element<Message> {
+        // The folders themselves are chosen on the main settings page.
+        let folders = widget::settings::section()
+            .title(fl!("library-folders"))
+            .add(
+                widget::settings::item::builder(fl!("library-folders-link"))
+                    .description(fl!("library-folders-description"))
+                    .control(
+                        button::standard(fl!("open-settings"))
+                            .on_press(Message::ToggleContextPage(ContextPage::Settings)),
+                    ),
+            );
+
+        let sorting = widget::settings::section().title(fl!("sorting")).add(
+            widget::settings::item::builder(fl!("sort-articles"))
+                .description(fl!("sort-articles-description"))
+                .control(
+                    widget::text_input("", &self.sort_articles).on_input(Message::SortArticles),
+                ),
+        );
+
+        let selected_notation = KeyNotation::ALL
+            .iter()
+            .position(|notation| *notation == self.key_notation);
+        let display = widget::settings::section()
+            .title(fl!("display"))
+            .add(
+                widget::settings::item::builder(fl!("key-notation"))
+                    .description(fl!("key-notation-description"))
+                    .control(widget::dropdown(
+                        KeyNotation::names(),
+                        selected_notation,
+                        Message::SetKeyNotation,
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("tile-size"))
+                    .description(fl!("tile-size-description"))
+                    .control(slider(TILE_SIZES, self.tile_size, Message::SetTileSize).step(10.0)),
+            );
+
+        let selected_fallback = TitleFallback::ALL
+            .iter()
+            .position(|fallback| *fallback == self.scan_options.title_fallback);
+        let scanning = widget::settings::section()
+            .title(fl!("scanning"))
+            .add(
+                widget::settings::item::builder(fl!("follow-symlinks"))
+                    .description(fl!("follow-symlinks-description"))
+                    .toggler(self.scan_options.follow_symlinks, Message::FollowSymlinks),
+            )
+            .add(
+                widget::settings::item::builder(fl!("merge-hardlinks"))
+                    .description(fl!("merge-hardlinks-description"))
+                    .toggler(self.scan_options.merge_hardlinks, Message::MergeHardlinks),
+            )
+            .add(
+                widget::settings::item::builder(fl!("title-fallback"))
+                    .description(fl!("title-fallback-description"))
+                    .control(widget::dropdown(
+                        TitleFallback::names(),
+                        selected_fallback,
+                        Message::SetTitleFallback,
+                    )),
+            );
+
+        let tags = widget::settings::section().title(fl!("tag-writing")).add(
+            widget::settings::item::builder(fl!("keep-modified-times"))
+                .description(fl!("keep-modified-times-description"))
+                .toggler(self.keep_modified_times, Message::KeepModifiedTimes),
+        );
+
+        widget::settings::view_column(vec![
+            folders.into(),
+            sorting.into(),
+            display.into(),
+            scanning.into(),
+            tags.into(),
+        ])
+        .into()
+    }
+
+    /// Pattern editor and dry-run preview for organizing library files.
+    pub fn organize_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, space_s, .. } = theme::active().cosmic().spacing;
+
+        let mut column = widget::column()
+            .spacing(space_s)
+            .push(text(fl!("organize-pattern")))
+            .push(
+                widget::text_input("", &self.organize_pattern)
+                    .on_input(Message::OrganizePattern),
+            )
+            .push(text::caption(fl!("organize-pattern-help")));
+
+        let mut buttons = Row::new()
+            .spacing(space_xxs)
+            .push(button::standard(fl!("organize-preview")).on_press(Message::OrganizePreview));
+        if !self.organize_plan.is_empty() {
+            buttons = buttons.push(
+                button::suggested(fl!("organize-apply")).on_press(Message::OrganizeApply),
+            );
+        }
+        column = column.push(buttons);
+
+        if let Some(status) = &self.organize_status {
+            column = column.push(text(status.clone()));
+        }
+
+        let relative = |path: &std::path::Path| {
+            self.config
+                .folder_of(path)
+                .and_then(|root| path.strip_prefix(root).ok())
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+
+        for planned in self.organize_plan.iter().take(ORGANIZE_PREVIEW_LIMIT) {
+            column = column.push(
+                widget::column()
+                    .push(text::caption(relative(&planned.from)))
+                    .push(text::body(format!("→ {}", relative(&planned.to)))),
+            );
+        }
+
+        if self.organize_plan.len() > ORGANIZE_PREVIEW_LIMIT {
+            column = column.push(text::caption(fl!(
+                "organize-more",
+                count = self.organize_plan.len() - ORGANIZE_PREVIEW_LIMIT
+            )));
+        }
+
+        column.into()
+    }
+
+    /// Updates the header and window titles.
+    pub fn update_titles(&mut self) -> Task<Message> {
+        let mut window_title = fl!("app-title");
+        let mut header_title = String::new();
+
+        if let Some(page) = self.nav.text(self.nav.active()) {
+            window_title.push_str(" — ");
+            window_title.push_str(page);
+            header_title.push_str(page);
+        }
+
+        self.set_header_title(header_title);
+        self.set_window_title(window_title)
+    }
+
+    /// Picks up what was measured for the album at `index` in an earlier session.
+    fn load_loudness(&mut self, index: usize) {
+        let Some(album) = self.albums.get(index) else {
+            return;
+        };
+
+        let key = artwork::album_key(&album.album_artist, &album.album);
+        if !self.loudness.contains_key(&key) {
+            if let Some(measured) = loudness::read_cached(&key) {
+                self.loudness.insert(key, measured);
+            }
+        }
+    }
+
+    /// Builds the album aggregation if it hasn't been built yet. Returns whether anything changed.
+    fn ensure_albums(&mut self) -> bool {
+        if self.albums_loaded {
+            return false;
+        }
+
+        let (albums, covers) =
+            profiling::measure("album aggregation", || build_albums(&self.scanned_files));
+        self.albums = albums;
+        self.pending_covers.extend(covers);
+        self.albums_loaded = true;
+        true
+    }
+
+    /// Builds the album aggregation on a background thread if it hasn't been built yet, so
+    /// opening the Albums page of a large library doesn't hold up the window.
+    fn build_albums_later(&mut self) -> Task<Message> {
+        if self.albums_loaded || self.albums_building {
+            return Task::none();
+        }
+
+        self.albums_building = true;
+        let files = self.scanned_files.clone();
+        let generation = self.library_generation;
+        cosmic::task::future(async move {
+            let built = tokio::task::spawn_blocking(move || {
+                profiling::measure("album aggregation", || build_albums(&files))
+            })
+            .await
+            .unwrap_or_default();
+            Message::AlbumsBuilt(generation, Arc::new(built))
+        })
+    }
+
+    /// Scans every enabled library folder on a background thread, adding files to the
+    /// library as they're found.
+    fn scan_library(&mut self) -> Task<Message> {
+        let folders = self.config.enabled_folders();
+        if folders.is_empty() {
+            return Task::none();
+        }
+
+        let options = self.scan_options;
+        self.run_scan(move |first_id, progress| {
+            scan::scan_paths(folders, first_id, options, progress)
+        })
+    }
+
+    /// Scans a library folder that was just added or switched on. While another scan runs,
+    /// the folder waits for it like a held back file.
+    fn scan_folder(&mut self, folder: PathBuf) -> Task<Message> {
+        if self.scan_job.is_some() {
+            self.held_back.push(folder);
+            return Task::none();
+        }
+
+        let options = self.scan_options;
+        self.run_scan(move |first_id, progress| {
+            scan::scan_paths(vec![folder], first_id, options, progress)
+        })
+    }
+
+    /// Takes the tracks in `folder` out of the library, once it was removed or switched
+    /// off, unless another library folder holds them too.
+    fn forget_folder(&mut self, folder: &std::path::Path) -> Task<Message> {
+        let config = &self.config;
+        let before = self.scanned_files.len();
+        self.scanned_files.retain(|file| {
+            !file.saved_path.starts_with(folder) || config.folder_of(&file.saved_path).is_some()
+        });
+
+        if self.scanned_files.len() == before {
+            return Task::none();
+        }
+        self.refresh_library()
+    }
+
+    /// Saves the library folders, keeping them for this session even if that fails.
+    fn set_library_folders(&mut self, folders: Vec<LibraryFolder>) -> Task<Message> {
+        let Some(handler) = &self.config_handler else {
+            self.config.library_folders = folders;
+            return Task::none();
+        };
+
+        match self.config.set_library_folders(handler, folders) {
+            Ok(_) => Task::none(),
+            Err(err) => self.update(Message::Error(fl!(
+                "library-folders-not-saved",
+                error = err.to_string()
+            ))),
+        }
+    }
+
+    /// Saves `value` with `set`, one of the [`Config`] setters, naming `what` was saved if
+    /// that fails. The value is kept for this session either way.
+    fn save_setting<T>(
+        &mut self,
+        what: &str,
+        value: T,
+        set: fn(&mut Config, &cosmic_config::Config, T) -> Result<bool, cosmic_config::Error>,
+    ) {
+        let Some(handler) = &self.config_handler else {
+            return;
+        };
+        if let Err(err) = set(&mut self.config, handler, value) {
+            eprintln!("Failed to save the {}: {}", what, err);
+        }
+    }
+
+    /// Scans the folder behind `url` on a background thread, adding files to the library
+    /// as they're found.
+    fn start_scan(&mut self, url: Url) -> Task<Message> {
+        let options = self.scan_options;
+        self.run_scan(move |first_id, progress| {
+            scan::scan_incrementally(url, first_id, options, progress)
+        })
+    }
+
+    /// Runs `job` on a background thread as the scan job, unless one is running already.
+    fn run_scan(
+        &mut self,
+        job: impl FnOnce(usize, &ScanProgress) + Send + 'static,
+    ) -> Task<Message> {
+        if self.scan_job.is_some() {
+            return Task::none();
+        }
+
+        let first_id = self.next_track_id();
+        let progress = Arc::new(ScanProgress::default());
+        self.scan_job = Some(progress.clone());
+
+        cosmic::task::future(async move {
+            if let Err(err) = tokio::task::spawn_blocking(move || job(first_id, &progress)).await {
+                eprintln!("Library scan failed: {}", err);
+            }
+
+            Message::ScanFinished
+        })
+    }
+
+    /// The pause and cancel switches of the running job of `kind`.
+    fn job_control(&self, kind: JobKind) -> Option<&JobControl> {
+        match kind {
+            JobKind::Scan => self.scan_job.as_ref().map(|progress| &progress.control),
+            JobKind::EmbedArtwork => self.embed_job.as_ref().map(|progress| &progress.control),
+            JobKind::Export => self.export_job.as_ref().map(|progress| &progress.control),
+            JobKind::Verify => self.verify_job.as_ref().map(|progress| &progress.control),
+            JobKind::Loudness => self.loudness_job.as_ref().map(|progress| &progress.control),
+            JobKind::Tempo => self.tempo_job.as_ref().map(|progress| &progress.control),
+        }
+    }
+
+    /// Shows the album a `jams://` link points at, and plays the track if it names one.
+    fn open_share_link(&mut self, link: ShareLink) -> Task<Message> {
+        let rebuild_search = self.ensure_albums();
+
+        let (artist, album, title) = match &link {
+            ShareLink::Album { artist, album } => (artist, album, None),
+            ShareLink::Track {
+                artist,
+                album,
+                title,
+            } => (artist, album, Some(title)),
+        };
+
+        let Some(index) = self
+            .albums
+            .iter()
+            .position(|candidate| candidate.album_artist == *artist && candidate.album == *album)
+        else {
+            println!("Nothing in the library matches {}", link.to_url());
+            return Task::none();
+        };
+
+        let track = title.and_then(|title| {
+            self.albums[index].tracks.iter().copied().find(|id| {
+                self.find_track(*id)
+                    .is_some_and(|file| file.track_title == *title)
+            })
+        });
+
+        let mut tasks = Vec::new();
+        if rebuild_search {
+            tasks.push(self.rebuild_search_index());
+        }
+
+        let albums_page = self
+            .nav
+            .iter()
+            .find(|id| self.nav.data::<Page>(*id) == Some(&Page::Albums));
+        if let Some(id) = albums_page {
+            tasks.push(self.on_nav_select(id));
+        }
+        self.selected_album = Some(index);
+        self.load_loudness(index);
+
+        if let Some(id) = track {
+            tasks.push(self.update(Message::PlayAlbumFrom(index, id)));
+        }
+
+        Task::batch(tasks)
+    }
+
+    /// The tracks `sync` puts on a portable player, and the queue as a playlist of them.
+    fn device_sync(&self, sync: DeviceSync) -> (Vec<DeviceTrack>, Vec<DevicePlaylist>) {
+        let files: Vec<&MusicFile> = match sync {
+            DeviceSync::Library => self.scanned_files.iter().collect(),
+            DeviceSync::Queue => self
+                .queue
+                .tracks()
+                .iter()
+                .filter_map(|id| self.find_track(*id))
+                .collect(),
+        };
+        let files: Vec<&MusicFile> = files
+            .into_iter()
+            .filter(|file| file.source() == TrackSource::Local)
+            .collect();
+
+        let queue = self
+            .queue
+            .tracks()
+            .iter()
+            .filter_map(|id| files.iter().position(|file| file.id == *id))
+            .collect::<Vec<usize>>();
+        let playlists = if queue.is_empty() {
+            Vec::new()
+        } else {
+            vec![DevicePlaylist {
+                name: fl!("device-queue-playlist"),
+                tracks: queue,
+            }]
+        };
+
+        let tracks = files
+            .iter()
+            .map(|file| DeviceTrack {
+                source: file.saved_path.clone(),
+                album_artist: file.album_artist.clone(),
+                album: file.album.clone(),
+                disc_number: file.disc_number,
+                track_number: file.track_number,
+                artist: file.artist.clone(),
+                title: file.track_title.clone(),
+                duration: file.duration,
+            })
+            .collect();
+
+        (tracks, playlists)
+    }
+
+    /// Runs `export` as the export job, on a background thread.
+    fn start_device_export(
+        &mut self,
+        export: impl FnOnce(&ExportProgress) -> ExportReport + Send + 'static,
+    ) -> Task<Message> {
+        let progress = Arc::new(ExportProgress::default());
+        self.export_job = Some(progress.clone());
+        self.export_report = None;
+        self.show_jobs();
+
+        cosmic::task::future(async move {
+            let report = tokio::task::spawn_blocking(move || export(&progress))
+                .await
+                .unwrap_or_else(|err| ExportReport {
+                    failed: vec![err.to_string()],
+                    ..Default::default()
+                });
+
+            Message::ExportFinished(report)
+        })
+    }
+
+    /// Opens the jobs panel, so a job that was just started can be followed.
+    fn show_jobs(&mut self) {
+        self.context_page = ContextPage::Jobs;
+        self.core.window.show_context = true;
+    }
+
+    /// How far the running scan got, with a button to stop it.
+    fn scan_status(&self) -> Option<Element<Message>> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let progress = self.scan_job.as_ref()?;
+        let (status, fraction) = scan_progress_text(progress);
+        let cancelling = progress.control.is_cancelled();
+
+        let mut row = Row::new().spacing(space_xxs).align_y(Alignment::Center);
+        if let Some((checked, total)) = fraction {
+            row = row.push(
+                widget::progress_bar(0.0..=total.max(1) as f32, checked as f32)
+                    .width(Length::Fixed(160.0)),
+            );
+        }
+        let row = row.push(text::caption(status)).push(
+            button::icon(icon::from_name("process-stop-symbolic"))
+                .on_press_maybe((!cancelling).then_some(Message::CancelJob(JobKind::Scan))),
+        );
+
+        Some(row.into())
+    }
+
+    /// A few words on the background job that's running, for the header bar. A scan says
+    /// how many files it went through, the other jobs just what they're doing.
+    fn busy_status(&self) -> Option<String> {
+        if let Some(progress) = &self.scan_job {
+            let total = progress.total.load(Ordering::Relaxed);
+            let files = if total == 0 {
+                progress.found.load(Ordering::Relaxed)
+            } else {
+                progress.checked.load(Ordering::Relaxed).min(total)
+            };
+            return Some(fl!("scanning-files", count = files));
+        }
+
+        if self.embed_job.is_some() {
+            Some(fl!("embedding-artwork"))
+        } else if self.export_job.is_some() {
+            Some(fl!("exporting"))
+        } else if self.verify_job.is_some() {
+            Some(fl!("verifying"))
+        } else if self.loudness_job.is_some() {
+            Some(fl!("measuring-loudness"))
+        } else if self.tempo_job.is_some() {
+            Some(fl!("detecting-tempo"))
+        } else {
+            None
+        }
+    }
+
+    /// Moves files found by the running scan into the library. Returns whether there were any.
+    fn take_scanned(&mut self) -> bool {
+        let Some(progress) = &self.scan_job else {
+            return false;
+        };
+
+        let mut found = progress.take_found();
+        scan::drop_duplicates(&mut found, &self.scanned_files, self.scan_options);
+        if found.is_empty() {
+            return false;
+        }
+        tempo::fill_in(&mut found, &self.tempos);
+        self.library_generation += 1;
+
+        // New files only ever add albums at the end, so an open album stays put.
+        if self.albums_loaded {
+            let covers = scan::add_to_albums(&mut self.albums, &found);
+            self.pending_covers.extend(covers);
+        }
+
+        self.scanned_files.extend(found);
+        track_number::sort_library(&mut self.scanned_files);
+        self.track_labels = track_number::labels(&self.scanned_files);
+        true
+    }
+
+    /// Looks for formats in the library that GStreamer can't decode.
+    fn check_plugins(&self) -> Task<Message> {
+        let extensions: BTreeSet<String> = self
+            .scanned_files
+            .iter()
+            .filter_map(|file| file.saved_path.extension())
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .collect();
+
+        cosmic::task::future(async move {
+            let missing = tokio::task::spawn_blocking(move || plugins::missing(&extensions))
+                .await
+                .unwrap_or_default();
+            Message::PluginsChecked(missing)
+        })
+    }
+
+    /// The banner text for [`Self::missing_plugins`], naming the package for each format.
+    fn missing_plugins_text(&self) -> String {
+        let mut by_package: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for missing in &self.missing_plugins {
+            by_package
+                .entry(missing.package)
+                .or_default()
+                .push(missing.format);
+        }
+
+        let mut lines = vec![fl!("missing-plugins")];
+        for (package, formats) in by_package {
+            lines.push(fl!(
+                "missing-plugin",
+                formats = formats.join(", "),
+                package = package
+            ));
+        }
+        lines.join(" ")
+    }
+
+    /// Scans the files held back by the last scan once they've had time to settle.
+    fn rescan_later(&self) -> Task<Message> {
+        if self.held_back.is_empty() {
+            return Task::none();
+        }
+
+        cosmic::task::future(async {
+            tokio::time::sleep(scan::SETTLE_TIME).await;
+            Message::RescanHeldBack
+        })
+    }
+
+    /// Drops aggregations derived from `scanned_files`, rebuilding them straight away
+    /// only if the page showing them is open.
+    fn library_changed(&mut self) {
+        self.library_generation += 1;
+        self.track_labels = track_number::labels(&self.scanned_files);
+        self.albums.clear();
+        self.albums_loaded = false;
+        self.selected_album = None;
+        self.selected_artist = None;
+        self.selected_label = None;
+
+        if let Some(Page::Albums) = self.nav.active_data::<Page>() {
+            self.ensure_albums();
+        }
+    }
+
+    /// Rebuilds what's derived from `scanned_files` after tracks in it were changed or
+    /// removed, keeping the open album open if it's still there.
+    fn refresh_library(&mut self) -> Task<Message> {
+        let selected = self
+            .selected_album
+            .and_then(|index| self.albums.get(index))
+            .map(|album| (album.album_artist.clone(), album.album.clone()));
+
+        track_number::sort_library(&mut self.scanned_files);
+        self.library_changed();
+        if let Some((album_artist, album)) = selected {
+            self.ensure_albums();
+            self.selected_album = self.albums.iter().position(|candidate| {
+                candidate.album_artist == album_artist && candidate.album == album
+            });
+        }
+
+        self.rebuild_search_index()
+    }
+
+    /// Rebuilds the search index from the current library and re-runs any active search
+    /// so its results don't point at stale IDs. Also caches the covers of albums added
+    /// since the last rebuild.
+    pub fn rebuild_search_index(&mut self) -> Task<Message> {
+        self.forget_song_list();
+        self.search_index = Arc::new(SearchIndex::build(&self.scanned_files, &self.albums));
+
+        let search = if self.search_term.is_empty() {
+            Task::none()
+        } else {
+            self.search_generation += 1;
+            self.run_search()
+        };
+
+        Task::batch(vec![search, self.cache_pending_covers()])
+    }
+
+    /// Copies the embedded covers of newly added albums into the art cache on a background
+    /// thread. Albums show a placeholder until their cover is in.
+    fn cache_pending_covers(&mut self) -> Task<Message> {
+        if self.pending_covers.is_empty() {
+            return Task::none();
+        }
+
+        let sources = std::mem::take(&mut self.pending_covers);
+        cosmic::task::future(async move {
+            let report = tokio::task::spawn_blocking(move || {
+                profiling::measure("art cache warm-up", || artwork::cache_covers(sources))
+            })
+            .await
+            .unwrap_or_else(|err| CoverReport {
+                failed: vec![err.to_string()],
+                ..CoverReport::default()
+            });
+            Message::CoversCached(Arc::new(report))
+        })
+    }
+
+    /// The playback controls under every page, with the position and what's playing.
+    fn controls_bar(&self) -> Element<Message> {
+        let mut controls_row = Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .height(Length::Fill);
+
+        //let controls_button_prev_txt = text("Previous");
+        let controls_prev_button = button::icon(icon::from_name("media-skip-backward-symbolic"))
+            .icon_size(self.small_icon_size())
+            .on_press(Message::SkipPrev);
+
+        controls_row = controls_row.push(controls_prev_button);
+
+        match self.playback.status {
+            PlayState::Playing => {
+                //let controls_button_txt = text("Pause");
+                let controls_pause_button =
+                    button::icon(icon::from_name("media-playback-pause-symbolic"))
+                        .icon_size(24)
+                        .padding([15, 15, 15, 15])
+                        .class(cosmic::style::Button::Suggested)
+                        .on_press(Message::PauseCurrentTrack);
+
+                controls_row = controls_row.push(controls_pause_button);
+            }
+            PlayState::Paused => {
+                //let controls_button_txt = text("Play");
+                let controls_pause_button =
+                    button::icon(icon::from_name("media-playback-start-symbolic"))
+                        .icon_size(24)
+                        .padding([15, 15, 15, 15])
+                        .class(cosmic::style::Button::Suggested)
+                        .on_press(Message::ResumeCurrentTrack);
+
+                controls_row = controls_row.push(controls_pause_button);
+            }
+            PlayState::Idle => {
+                //let controls_button_txt = text("This Button Is Disabled");
+                let controls_pause_button =
+                    button::icon(icon::from_name("media-playback-start-symbolic"))
+                        .icon_size(24)
+                        .padding([15, 15, 15, 15])
+                        .class(cosmic::style::Button::Icon);
+
+                controls_row = controls_row.push(controls_pause_button);
+            }
+        }
+
+        //let controls_button_next_txt = text("Next");
+        let controls_next_button = button::icon(icon::from_name("media-skip-forward-symbolic"))
+            .icon_size(self.small_icon_size())
+            .on_press(Message::SkipNext);
+
+        let mut controls_row = controls_row.push(controls_next_button);
+
+        if let Some(file) = self.queue.current().and_then(|id| self.find_track(id)) {
+            let favorite_button = button::icon(icon::from_name("emblem-favorite-symbolic"))
+                .icon_size(self.small_icon_size())
+                .selected(self.favorites.contains(&file.saved_path))
+                .on_press(Message::ToggleFavorite);
+
+            controls_row = controls_row.push(favorite_button);
+        }
+
+        let mut controls_col = Column::new()
+            .push(controls_row)
+            .height(Length::Fixed(110.0))
+            .width(Length::Fill)
+            .align_x(Alignment::Center);
+
+        let min_seek_position = self.seek_position.as_secs() / 60;
+        let sec_seek_position = self.seek_position.as_secs() % 60;
+
+        let min_duration = self.current_track_duration.as_secs() / 60;
+        let sec_duration = self.current_track_duration.as_secs() % 60;
+
+        //println!("{} : {}", min_seek_position, sec_seek_position);
+
+        //let pos = self.seek_position.as_secs().to_string();
+        //https://stackoverflow.com/questions/66666348/println-to-print-a-2-digit-integer
+        let pos = format!("{}:{:02}", min_seek_position, sec_seek_position);
+        //let total = self.current_track_duration.as_secs().to_string();
+        let total = format!("{}:{:02}", min_duration, sec_duration);
+
+        //println!("{}", self.seek_position.as_secs());
+
+        let pos_txt = text(pos).size(18);
+        let mut progress_scrubber = Column::new().width(250);
+        if self.show_cues && !self.current_cues.is_empty() {
+            progress_scrubber = progress_scrubber.push(self.cue_markers());
+        }
+        progress_scrubber =
+            progress_scrubber.push(slider(0..=100, self.scrub_value, Message::Scrub));
+        let total_txt = text(total).size(18);
+
+        let mut timing_row = Row::new()
+            .spacing(5)
+            .align_y(Alignment::Center)
+            .push(pos_txt)
+            .push(progress_scrubber)
+            .push(total_txt);
+
+        if let Some(file) = self.queue.current().and_then(|id| self.find_track(id)) {
+            timing_row = timing_row.push(text::caption(format_badge(
+                file,
+                self.output_settings.downmix,
+            )));
+            if let Some(key) = file.key {
+                timing_row = timing_row.push(text::caption(key.display(self.key_notation)));
+            }
+        }
+
+        if self
+            .limiting_at
+            .is_some_and(|at| at.elapsed() < LIMITER_INDICATOR_HOLD)
+        {
+            timing_row = timing_row.push(text::caption(fl!("limiting")));
+        }
+
+        controls_col = controls_col.push(timing_row);
+
+        // The splash screen shows its own.
+        if !self.scanned_files.is_empty() {
+            if let Some(status) = self.scan_status() {
+                controls_col = controls_col.push(status);
+            }
+        }
+
+        let controls_container: Element<Message> = Container::new(controls_col)
+            .class(cosmic::style::Container::ContextDrawer)
+            .into();
+        // Swipes are told apart from other touches by where they started.
+        if self.touch.is_active() {
+            widget::mouse_area(controls_container)
+                .on_press(Message::ControlsPressed)
+                .into()
+        } else {
+            controls_container
+        }
+    }
+
+    /// Size of the covers in the album grid, in the screen's physical pixels.
+    fn physical_tile_size(&self) -> u32 {
+        (self.tile_size * self.scale_factor).round() as u32
+    }
+
+    /// Scales the album covers to the size they're shown at in the grid, once the size
+    /// stops changing, so they're drawn pixel for pixel.
+    fn schedule_cover_scaling(&self) -> Task<Message> {
+        let size = self.physical_tile_size();
+        cosmic::task::future(async move {
+            tokio::time::sleep(COVER_SCALE_DELAY).await;
+            Message::ScaleCovers(size)
+        })
+    }
+
+    /// The cover of `album` as a square of `size` pixels, or an empty square while it's
+    /// still being cached.
+    fn album_cover(&self, album: &Album, size: f32) -> Element<Message> {
+        let key = artwork::album_key(&album.album_artist, &album.album);
+
+        // Scaled covers only fit the grid; smaller covers elsewhere are left to the GPU.
+        let scaled = self
+            .scaled_covers
+            .get(&key)
+            .filter(|_| (size * self.scale_factor).round() as u32 == self.scaled_size);
+
+        if let Some(handle) = self.memory_covers.get(&key).or(scaled) {
+            image(handle.clone())
+                .width(Length::Fixed(size))
+                .height(Length::Fixed(size))
+                .content_fit(ContentFit::Contain)
+                .into()
+        } else if album.cover_ready {
+            image(album.cached_cover_path.clone())
+                .width(Length::Fixed(size))
+                .height(Length::Fixed(size))
+                .content_fit(ContentFit::Contain)
+                .into()
+        } else {
+            widget::Space::new(Length::Fixed(size), Length::Fixed(size)).into()
+        }
+    }
+
+    /// Filters the library against the current search term on a background thread.
+    fn run_search(&self) -> Task<Message> {
+        let generation = self.search_generation;
+        let index = self.search_index.clone();
+        let term = self.search_term.clone();
+
+        cosmic::task::future(async move {
+            let results = tokio::task::spawn_blocking(move || index.search(&term))
+                .await
+                .unwrap_or_default();
+            Message::SearchResults(generation, Arc::new(results))
+        })
+    }
 
-            Message::Cancelled => {}
-            Message::CloseError => {}
-            Message::FileRead(_, _) => {}
+    fn track_label(&self, id: usize) -> String {
+        self.track_labels.get(&id).cloned().unwrap_or_default()
+    }
 
-            Message::ToggleContextPage(context_page) => {
-                if self.context_page == context_page {
-                    // Close the context drawer if the toggled context page is the same.
-                    self.core.window.show_context = !self.core.window.show_context;
-                } else {
-                    // Open the context drawer to display the requested context page.
-                    self.context_page = context_page;
-                    self.core.window.show_context = true;
-                }
-            }
-            Message::Scrub(value) => {
-                self.scrub(value);
+    fn find_track(&self, id: usize) -> Option<&MusicFile> {
+        self.scanned_files
+            .iter()
+            .chain(&self.outside_files)
+            .find(|file| file.id == id)
+    }
+
+    /// An ID no track in the library or the queue has yet.
+    fn next_track_id(&self) -> usize {
+        scan::next_id(&self.scanned_files).max(scan::next_id(&self.outside_files))
+    }
+
+    /// The tracks of the album at `index`, in disc and track order.
+    fn album_tracks(&self, index: usize) -> Vec<&MusicFile> {
+        let mut tracks: Vec<&MusicFile> = match self.albums.get(index) {
+            Some(album) => album
+                .tracks
+                .iter()
+                .filter_map(|id| self.find_track(*id))
+                .collect(),
+            None => Vec::new(),
+        };
+        tracks.sort_by(|a, b| track_number::album_order(a, b));
+        tracks
+    }
+
+    /// Starts the next track on a fresh backend while the current one fades out over
+    /// `length`. Tracks meant to run into each other are left to play back to back.
+    fn start_crossfade(&mut self, length: Duration) {
+        self.extend_queue();
+        let Some(next) = self.upcoming().and_then(|id| self.find_track(id)) else {
+            return;
+        };
+        let Some(current) = self.queue.current().and_then(|id| self.find_track(id)) else {
+            return;
+        };
+        if player::crossfade::is_seamless(current, next) {
+            return;
+        }
+
+        let mut incoming = player::backend_from_env();
+        incoming.set_filters(self.filter_settings.clone());
+        incoming.set_output(self.output_settings);
+        incoming.set_volume(0.0);
+        let outgoing = std::mem::replace(&mut self.audio_player, incoming);
+
+        self.skip_next();
+
+        let mut fade = Crossfade::new(outgoing, length);
+        fade.step(self.audio_player.as_mut());
+        self.crossfade = Some(fade);
+    }
+
+    /// Hands the filters to the player and saves them.
+    fn apply_filters(&mut self) {
+        self.audio_player.set_filters(self.filter_settings.clone());
+        self.save_setting(
+            "filter settings",
+            self.filter_settings.clone(),
+            Config::set_filter_settings,
+        );
+    }
+
+    /// Keeps the EQ and level for the current output device, to bring back when it's
+    /// used again.
+    fn remember_device_profile(&mut self) {
+        if let Some(device) = &self.output_device {
+            self.device_profiles.insert(
+                device.clone(),
+                DeviceProfile::from_filters(&self.filter_settings),
+            );
+            self.save_setting(
+                "device profiles",
+                self.device_profiles.clone(),
+                Config::set_device_profiles,
+            );
+        }
+    }
+
+    /// Announces the current track if it changed since the last announcement.
+    fn announce_track_change(&mut self) -> Task<Message> {
+        let current = self.queue.current();
+        if current == self.announced_track {
+            return Task::none();
+        }
+        self.announced_track = current;
+        if current.is_some() {
+            self.fire_hook(HookEvent::TrackStarted);
+            self.update_presence();
+            self.write_now_playing();
+        }
+
+        if !self.announce_tracks {
+            return Task::none();
+        }
+        let Some(file) = current.and_then(|id| self.find_track(id)) else {
+            return Task::none();
+        };
+
+        let text = if file.artist.is_empty() {
+            fl!("now-playing", title = file.track_title.as_str())
+        } else {
+            fl!(
+                "now-playing-by",
+                title = file.track_title.as_str(),
+                artist = file.artist.as_str()
+            )
+        };
+        let replaces = self.announcement_id;
+        let app_id = Self::APP_ID;
+
+        cosmic::task::future(async move {
+            Message::Announced(announce::announce(app_id, &text, replaces).await)
+        })
+    }
+
+    /// Tells the hooks about `event` and the current track.
+    fn fire_hook(&self, event: HookEvent) {
+        if !self.hooks.is_set() {
+            return;
+        }
+
+        let file = self.queue.current().and_then(|id| self.find_track(id));
+        let favorite = file.is_some_and(|file| self.favorites.contains(&file.saved_path));
+        let payload = hooks::payload(event, file, self.seek_position, favorite);
+        hooks::spawn(self.hooks.clone(), event, payload);
+    }
+
+    /// Shows the current track on Discord while it plays, and clears it otherwise.
+    fn update_presence(&self) {
+        if !self.discord_presence {
+            return;
+        }
+
+        let file = self.queue.current().and_then(|id| self.find_track(id));
+        let now_playing = file
+            .filter(|_| self.playback.is_playing())
+            .map(|file| NowPlaying {
+                title: file.track_title.clone(),
+                artist: file.artist.clone(),
+                album: file.album.clone(),
+                // Discord fetches art itself, so only covers with a public URL can be shown.
+                cover_url: (!file.release_group_id.is_empty()).then(|| {
+                    format!(
+                        "https://coverartarchive.org/release-group/{}/front-250",
+                        file.release_group_id
+                    )
+                }),
+                started: SystemTime::now() - self.seek_position,
+                length: file.duration,
+            });
+        discord::show(now_playing);
+    }
+
+    /// Starts the alarm's music shuffled and silent, letting the volume rise from there, and
+    /// shows a notification that can stop it. Does nothing while music is already playing.
+    fn ring_alarm(&mut self) -> Task<Message> {
+        if self.playback.is_playing() {
+            return Task::none();
+        }
+
+        let files: Vec<&MusicFile> = self
+            .scanned_files
+            .iter()
+            .filter(|file| match self.alarm.source {
+                AlarmSource::Favorites => self.favorites.contains(&file.saved_path),
+                AlarmSource::Library => true,
+            })
+            .filter(|file| self.track_visible(file))
+            .collect();
+        let ids = autodj::shuffled(&files);
+
+        let Some(first) = ids.first().and_then(|id| self.find_track(*id)) else {
+            eprintln!("The alarm went off, but there's nothing to play");
+            return Task::none();
+        };
+        let (start, uri) = (first.id, first.uri.clone());
+        self.queue = Queue::new(ids, start);
+        self.similar_reasons.clear();
+        self.switch_track(uri);
+        self.audio_player.set_volume(0.0);
+        self.alarm_ramp = Some(Instant::now());
+
+        let app_id = Self::APP_ID;
+        let summary = fl!("alarm-notification");
+        let body = self.alarm.time();
+        let stop = fl!("stop-alarm");
+        cosmic::task::future(async move {
+            if alarm::notify_and_wait(app_id, &summary, &body, &stop).await {
+                Message::StopAlarm
+            } else {
+                Message::Cancelled
             }
-            Message::SaveLibraryLocation => {
-                println!("This doesn't do anything right now.");
-            }
-            Message::ReOpenLibraryLocation => {
-                let home_dir = std::env::var("HOME").unwrap();
-                let config_file_loc = format!("{}/.config/jams/locations", home_dir);
-                match fs::read_to_string(config_file_loc) {
-                    Ok(contents) => {
-                        println!("Locations contents: {}", contents);
-
-                        let path = Path::new(contents.trim_end());
-                        if path.exists() {
-                            match Url::from_file_path(path) {
-                                Ok(url) => {
-                                    println!("{}",url);
-                                    get_all_files(url, &mut self.albums, &mut self.scanned_files);
-                                },
-                                Err(_) => {
-                                    println!("Failed to convert library path to URL");
-                                }
-                            }
-                        } else {
-                            println!("dog the path don't exist");
-                            // Message::DebugStub
-                        }
-                    }
-                    Err(_) => {
-                        println!("Failed to open library config.");
-                    }
+        })
+    }
+
+    /// Writes the current track and its cover for streaming software, or blanks the files
+    /// when nothing plays.
+    fn write_now_playing(&self) {
+        if !self.now_playing_output.enabled {
+            return;
+        }
+
+        let current = self.queue.current().filter(|_| !self.playback.is_idle());
+        let file = current.and_then(|id| self.find_track(id));
+        let template = if self.now_playing_output.template.trim().is_empty() {
+            now_playing::DEFAULT_TEMPLATE
+        } else {
+            &self.now_playing_output.template
+        };
+        let text = file
+            .map(|file| now_playing::render(template, file))
+            .unwrap_or_default();
+        // Albums are only built once their page is opened, so the cover is looked up by the
+        // track's album, and cached from the track itself if it hasn't been yet.
+        let cover = file.and_then(|file| {
+            let key = artwork::album_key(&file.album_artist, &file.album);
+            artwork::album_cover(&key).or_else(|| {
+                // A playing track is already out of its archive.
+                let report = artwork::cache_covers(vec![CoverSource {
+                    key: key.clone(),
+                    file: file.saved_path.clone(),
+                    archive: None,
+                }]);
+                for err in report.failed {
+                    eprintln!("{}", err);
                 }
+                artwork::album_cover(&key)
+            })
+        });
+
+        if let Err(err) = now_playing::write(&text, cover.as_deref()) {
+            eprintln!("Failed to write the now playing files: {}", err);
+        }
+    }
+
+    /// Cuts a running crossfade short, silencing the outgoing track.
+    fn cancel_crossfade(&mut self) {
+        if let Some(mut fade) = self.crossfade.take() {
+            fade.finish(self.audio_player.as_mut());
+        }
+    }
+
+    /// Moves to the next track in the queue. At the end of it, carries on as set in
+    /// [`EndOfQueue`], or stops playback.
+    fn skip_next(&mut self) {
+        self.extend_queue();
+        let transition = transport::move_on(&mut self.queue, self.end_of_queue, None);
+        self.follow_queue(transition);
+    }
+
+    /// Hands the upcoming track to the player, so it follows the current one without the
+    /// gap of stopping one pipeline and starting another. Left out when a crossfade will
+    /// overlap the two instead, and for tracks in archives, whose extracted copy may have
+    /// to be made again first.
+    fn preload_next(&mut self) {
+        self.extend_queue();
+
+        let current = self.queue.current().and_then(|id| self.find_track(id));
+        let next = self
+            .upcoming()
+            .and_then(|id| self.find_track(id))
+            .filter(|next| next.archive.is_none())
+            .filter(|next| {
+                self.crossfade_length.is_zero()
+                    || current.is_some_and(|current| player::crossfade::is_seamless(current, next))
+            });
+
+        let id = next.map(|next| next.id);
+        if id == self.gapless_next {
+            return;
+        }
+        let uri = next.map(|next| next.uri.clone());
+
+        self.gapless_next = id;
+        self.audio_player.set_next_uri(uri.as_deref());
+    }
+
+    /// Catches up with the player, which moved on to the preloaded track by itself.
+    fn continue_gapless(&mut self) {
+        let Some(expected) = self.gapless_next.take() else {
+            return;
+        };
+        self.save_resume_position();
 
+        self.extend_queue();
+        let transition = transport::move_on(&mut self.queue, self.end_of_queue, Some(expected));
+        self.follow_queue(transition);
+    }
 
-                // let home_dir = std::env::var("HOME").unwrap();
-                // let config_file_loc = format!("{}/.config/jams/locations", home_dir);
-                // match fs::read_to_string(config_file_loc) {
-                //     Ok(contents) => {
-                //         println!("Locations contents: {}", contents);
-                //         let path = Path::new(contents.as_str());
-                //         if path.exists() {
-                //             match Url::from_file_path(path) {
-                //                 Ok(url) => {
-                //                     println!("{}",url);
-                //                 },
-                //                 Err(_) => {
-                //                     println!("Failed to convert library path to URL");
-                //                 }
-                //             }
-                //         } else {
-                //             // Message::DebugStub
-                //         }
-                //     }
-                //     Err(_) => {
-                //         println!("Failed to open library config.");
-                //     }
-                // }
-
-                //     let home_dir = std::env::var("HOME").unwrap();
-                //     let config_file_loc = format!("{}/.config/jams/locations", home_dir);
-                //     match fs::read_to_string(config_file_loc.clone()) {
-                //         Ok(contents) => {
-                //             println!("Locations contents: {}", contents);
-                //             let path = Path::new(contents.as_str());
-                //             if path.exists() {
-                //                 match Url::from_file_path(path.clone()) {
-                //                     Ok(url) => {
-                //                         println!("{}",url);
-                //                     },
-                //                     Err(_) => {
-                //                         println!("Failed to convert library path to URL");
-                //                     }
-                //                 }
-                //             } else {
-                //                 println!("{:?}", path);
-                //                 println!("library location does not exist sorry");
-                //             }
-                //         }
-                //         Err(_) => {
-                //             println!("Failed to open library config.");
-                //         }
-                //     }
-                //     Message::DebugStub
-
-            }
-            Message::ResetLibraryLocation => {
-                println!("ugh");
-            }
-            Message::DebugStub => {
-                println!("This doesn't do anything right now.");
+    /// Plays what the queue moved on to, or stops at its end.
+    fn follow_queue(&mut self, transition: Transition) {
+        let (id, gapless) = match transition {
+            Transition::Load(id) => (id, false),
+            Transition::Follow(id) => (id, true),
+            Transition::Stop => {
+                eprintln!("End of list reached. Stopping playback.");
+                self.stop_playback();
+                return;
             }
+        };
+        let Some(file) = self.find_track(id) else {
+            self.stop_playback();
+            return;
+        };
+        if !gapless {
+            eprintln!("Moving to next track: {}", file.track_title);
+            let uri = file.uri.clone();
+            self.switch_track(uri);
+            return;
         }
-        Task::none()
+
+        eprintln!("Moving to next track without a gap: {}", file.track_title);
+        let duration = file.duration;
+        let cues = cues::read(&file.saved_path);
+        self.current_track_duration = duration;
+        self.current_cues = cues;
+        self.seek_position = Duration::ZERO;
+        self.seeking = false;
+        self.playback.start(Some(id));
+        self.resume_position();
     }
 
-    /// Display a context drawer if the context page is requested.
-    fn context_drawer(&self) -> Option<context_drawer::ContextDrawer<Self::Message>> {
-        if !self.core.window.show_context {
-            return None;
+    /// Saves where the loaded track is, if it's one that resumes.
+    fn save_resume_position(&mut self) {
+        self.resume_saved_at = Instant::now();
+
+        let Some(file) = self.playback.current.and_then(|id| self.find_track(id)) else {
+            return;
+        };
+        if !self.resume_rules.applies(file) && !self.resume_positions.contains_key(&file.saved_path)
+        {
+            return;
         }
+        let (path, length, position) = (file.saved_path.clone(), file.duration, self.seek_position);
 
-        Some(match self.context_page {
-            ContextPage::About => context_drawer::context_drawer(
-                self.about(),
-                Message::ToggleContextPage(ContextPage::About),
-            )
-            .title(fl!("about")),
-        })
+        let previous = self.resume_positions.get(&path).copied();
+        resume::remember(&mut self.resume_positions, &path, length, position);
+        if self.resume_positions.get(&path).copied() == previous {
+            return;
+        }
+        if let Err(err) = resume::write_positions(&self.resume_positions) {
+            eprintln!("Failed to save where {} was left: {}", path.display(), err);
+        }
     }
 
-    /// Called when a nav item is selected.
-    fn on_nav_select(&mut self, id: nav_bar::Id) -> Task<Self::Message> {
-        // Activate the page in the model.
-        self.nav.activate(id);
-        self.update_titles()
+    /// Jumps to where the loaded track was left, if it's one that resumes.
+    fn resume_position(&mut self) {
+        let Some(file) = self.playback.current.and_then(|id| self.find_track(id)) else {
+            return;
+        };
+        if !self.resume_rules.applies(file) {
+            return;
+        }
+        let Some(position) = self.resume_positions.get(&file.saved_path).copied() else {
+            return;
+        };
+
+        self.seek_to(position);
     }
-}
 
-impl Jams {
-    /// The about page for this app.
-    pub fn about(&self) -> Element<Message> {
-        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+    /// The track [`Self::skip_next`] would move to.
+    fn upcoming(&self) -> Option<usize> {
+        match self.queue.peek_next() {
+            None if self.end_of_queue == EndOfQueue::Repeat => self.queue.tracks().first().copied(),
+            next => next,
+        }
+    }
 
-        let icon = widget::svg(widget::svg::Handle::from_memory(
-            &include_bytes!("../res/icons/hicolor/128x128/apps/com.example.CosmicAppTemplate.svg")
-                [..],
-        ));
+    /// Adds more tracks when the current one is the last, if the queue is set to carry on
+    /// into the library.
+    fn extend_queue(&mut self) {
+        if self.queue.current().is_none()
+            || self.queue.peek_next().is_some()
+            || matches!(self.end_of_queue, EndOfQueue::Stop | EndOfQueue::Repeat)
+        {
+            return;
+        }
 
-        let title = widget::text::title3(fl!("app-title"));
+        let library: Vec<&MusicFile> = self
+            .scanned_files
+            .iter()
+            .filter(|file| !self.local_only || file.source() == TrackSource::Local)
+            .collect();
+
+        let more = match self.end_of_queue {
+            EndOfQueue::Stop | EndOfQueue::Repeat => return,
+            EndOfQueue::ShuffleLibrary => autodj::shuffled(&library),
+            EndOfQueue::AutoDj => {
+                autodj::auto_dj(&library, self.queue.tracks(), autodj::AUTO_DJ_BATCH)
+            }
+        };
 
-        let link = widget::button::link(REPOSITORY)
-            .on_press(Message::LaunchUrl(REPOSITORY.to_string()))
-            .padding(0);
+        self.queue.append(more);
+    }
 
-        widget::column()
-            .push(icon)
-            .push(title)
-            .push(link)
-            //.align_items(Alignment::Center)
-            .spacing(space_xxs)
-            .into()
+    /// Queues the current track as a listen once enough of it has played, and sends it.
+    fn record_listen(&mut self) -> Task<Message> {
+        let current = self.queue.current();
+        if current != self.listen_track {
+            self.listen_track = current;
+            self.listen_started = SystemTime::now();
+            self.listened = Duration::ZERO;
+            self.listen_recorded = false;
+        }
+
+        if self.listen_recorded {
+            return Task::none();
+        }
+        let Some(file) = current.and_then(|id| self.find_track(id)) else {
+            return Task::none();
+        };
+        if !scrobble::counts(self.listened, file.duration) {
+            return Task::none();
+        }
+        let listen = Listen::new(file, self.listen_started);
+
+        self.listen_recorded = true;
+        // Kept even without an endpoint, so listens can be backfilled later.
+        if let Err(err) = scrobble::record_history(&listen) {
+            eprintln!("Failed to save the listening history: {}", err);
+        }
+        if !self.scrobble_endpoint.is_set() {
+            return Task::none();
+        }
+
+        self.scrobble_queue.push(listen);
+        if let Err(err) = scrobble::write_queue(&self.scrobble_queue) {
+            eprintln!("Failed to save the scrobble queue: {}", err);
+        }
+
+        self.submit_scrobbles()
     }
 
-    /// Updates the header and window titles.
-    pub fn update_titles(&mut self) -> Task<Message> {
-        let mut window_title = fl!("app-title");
-        let mut header_title = String::new();
+    /// Sends the oldest waiting listens, unless a submission is already on its way.
+    fn submit_scrobbles(&mut self) -> Task<Message> {
+        if self.scrobble_sending
+            || self.scrobble_queue.is_empty()
+            || !self.scrobble_endpoint.is_set()
+        {
+            return Task::none();
+        }
+        self.scrobble_sending = true;
+
+        let endpoint = self.scrobble_endpoint.clone();
+        let listens: Vec<Listen> = self
+            .scrobble_queue
+            .iter()
+            .take(scrobble::SUBMIT_BATCH)
+            .cloned()
+            .collect();
+
+        let task = cosmic::task::future(async move {
+            let count = listens.len();
+            let result = tokio::task::spawn_blocking(move || scrobble::submit(&endpoint, &listens))
+                .await
+                .unwrap_or_else(|err| Err(err.to_string()));
+            Message::ScrobblesSubmitted(count, result)
+        });
+        self.when_online(task)
+    }
 
-        if let Some(page) = self.nav.text(self.nav.active()) {
-            window_title.push_str(" — ");
-            window_title.push_str(page);
-            header_title.push_str(page);
+    /// Runs `task` now if network work is allowed, or holds it until the connection is back.
+    pub fn when_online(&mut self, task: Task<Message>) -> Task<Message> {
+        if self.network.is_online() {
+            task
+        } else {
+            self.pending_network_tasks.push(task);
+            Task::none()
         }
+    }
 
-        self.set_header_title(header_title);
-        self.set_window_title(window_title)
+    /// Starts looking up lyrics when the current track changed: the files and cache first,
+    /// then LRCLIB.
+    fn load_lyrics(&mut self) -> Task<Message> {
+        let current = self.queue.current();
+        if current == self.lyrics_track {
+            return Task::none();
+        }
+
+        // Edits to the previous track's lyrics are offered for saving rather than dropped.
+        if let Some(editor) = self.lyrics_editor.take() {
+            if let Some(file) = self.lyrics_track.and_then(|id| self.find_track(id)) {
+                self.lyrics_draft = Some(LyricsDraft {
+                    track: file.saved_path.clone(),
+                    text: editor.text(),
+                });
+            }
+        }
+
+        self.lyrics_track = current;
+        self.lyrics = None;
+        self.lyrics_status = None;
+
+        let Some((id, path)) = current
+            .and_then(|id| self.find_track(id))
+            .map(|file| (file.id, file.saved_path.clone()))
+        else {
+            return Task::none();
+        };
+
+        cosmic::task::future(async move {
+            let text = tokio::task::spawn_blocking(move || lyrics::read_local(&path))
+                .await
+                .unwrap_or_default();
+            Message::LyricsLoaded(id, text)
+        })
+    }
+
+    /// Releases held network work if the app just went from offline to online.
+    fn connectivity_changed(&mut self, was_online: bool) -> Task<Message> {
+        if !was_online && self.network.is_online() {
+            Task::batch(std::mem::take(&mut self.pending_network_tasks))
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Acts on what the player reported since the last tick. Returns whether the track
+    /// played to its end.
+    fn handle_player_events(&mut self) -> bool {
+        let mut ended = false;
+
+        for event in self.audio_player.poll_events() {
+            match event {
+                PlayerEvent::SeekDone => {
+                    self.seeking = false;
+                }
+                PlayerEvent::EndOfStream => {
+                    ended = true;
+                }
+                PlayerEvent::NextTrack => {
+                    self.continue_gapless();
+                }
+                PlayerEvent::Limiting => {
+                    self.limiting_at = Some(Instant::now());
+                }
+                PlayerEvent::Error(err) => {
+                    eprintln!("Playback error: {}", err);
+                }
+            }
+        }
+
+        // The position doesn't move while the pipeline is still flushing a seek.
+        if !self.seeking {
+            self.sync_position();
+        }
+
+        ended
+    }
+
+    /// Takes the position and length of the track from the pipeline. Counting time here
+    /// instead drifts while buffering, and tags get the length of VBR files wrong.
+    fn sync_position(&mut self) {
+        let duration = self.audio_player.duration();
+        if let Some(duration) = duration.filter(|duration| !duration.is_zero()) {
+            self.current_track_duration = duration;
+        }
+        if let Some(position) = self.audio_player.position() {
+            // A seek already moved `seek_position`, so only time actually played counts.
+            self.listened += position.saturating_sub(self.seek_position);
+            self.seek_position = position;
+        }
+    }
+
+    fn stop_playback(&mut self) {
+        self.save_resume_position();
+        self.cancel_crossfade();
+        self.audio_player.stop();
+        self.gapless_next = None;
+        if self.alarm_ramp.take().is_some() {
+            self.audio_player.set_volume(1.0);
+        }
+        self.seek_position = Duration::new(0, 0);
+        self.seeking = false;
+        self.current_cues.clear();
+        self.playback.stop();
+        self.current_track_duration = Duration::new(0, 0);
+        self.fire_hook(HookEvent::Stopped);
+        self.update_presence();
+        self.write_now_playing();
     }
 
     pub fn switch_track(&mut self, uri: String) {
-        self.audio_player.player.stop();
-
-        for file in &mut self.scanned_files {
-            file.paused = false;
-            if file.uri == uri {
-                println!("Switching to track: {}", uri);
-                file.playing = true;
-                self.current_track_duration = file.duration;
-            } else {
-                file.playing = false;
+        self.save_resume_position();
+        self.cancel_crossfade();
+        self.audio_player.stop();
+        self.gapless_next = None;
+
+        // The extracted copy of a track in an archive is gone if the cache was cleared since.
+        if let Some(file) = self.scanned_files.iter().find(|file| file.uri == uri) {
+            if let Some(entry) = &file.archive {
+                if let Err(err) = archive::extract(entry, &file.saved_path) {
+                    eprintln!("Failed to extract {}", err);
+                }
             }
         }
 
-        self.audio_player.player.set_uri(Some(uri.as_str()));
+        let current = self
+            .scanned_files
+            .iter()
+            .chain(&self.outside_files)
+            .find(|file| file.uri == uri);
+        if let Some(file) = current {
+            println!("Switching to track: {}", uri);
+            self.current_track_duration = file.duration;
+            self.current_cues = cues::read(&file.saved_path);
+        }
+        let current = current.map(|file| file.id);
+
+        self.audio_player.set_uri(uri.as_str());
 
-        self.audio_player.player.play();
+        self.audio_player.play();
 
-        self.last_tick = Instant::now();
         self.seek_position = Duration::default();
+        self.seeking = false;
 
-        self.global_play_state = PlayState::Playing;
+        self.playback.start(current);
+        self.resume_position();
     }
 
     pub fn scrub(&mut self, value: u8) {
@@ -1094,20 +8119,77 @@ impl Jams {
             pos,
             percent
         );
-        self.seek_position = Duration::from_secs(pos as u64);
-        self.audio_player
-            .player
-            .seek(ClockTime::from_seconds(pos as u64));
+        self.seek_to(Duration::from_secs(pos as u64));
+    }
+
+    /// Jumps to `position` in the current track.
+    pub fn seek_to(&mut self, position: Duration) {
+        self.seek_position = position;
+
+        // Nothing is loaded while idle, so no seek-done would ever arrive.
+        if !self.playback.is_idle() {
+            self.seeking = true;
+        }
+        self.audio_player.seek(position);
+        self.update_presence();
     }
 }
 
-fn is_audio_file(path: &Path) -> std::io::Result<bool> {
-    let mut file = File::open(path)?;
-    let mut buf = [0; 1024]; // Read first KB for detection
-    file.read_exact(&mut buf)?;
+/// Lets page content scroll and fill the space above the playback controls. The position is
+/// remembered, see [`Jams::restore_scroll`].
+fn scroll_page<'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    let scroll_list = Scrollable::new(content)
+        .id(PAGE_SCROLL.clone())
+        .on_scroll(Message::ListScrolled)
+        .height(Length::Fill)
+        .width(Length::Fill);
+
+    Container::new(scroll_list)
+        .height(Length::Fill)
+        .width(Length::Fill)
+        .into()
+}
+
+/// `content` on one line, cut off where its space ends, with the whole text in a tooltip
+/// if it doesn't fit. It's only cut off when drawn, so screen readers still read all of it.
+/// Text that fits is aligned as `align_x` says.
+fn clipped_text<'a>(content: String, align_x: Horizontal) -> Element<'a, Message> {
+    let line = widget::responsive(move |size| {
+        let clipped = content.chars().count() as f32 * AVERAGE_CHAR_WIDTH > size.width;
+        let label = text(content.clone())
+            .wrapping(Wrapping::None)
+            .width(Length::Fill);
+        if clipped {
+            widget::tooltip(label, text(content.clone()), widget::tooltip::Position::Top).into()
+        } else {
+            label.align_x(align_x).into()
+        }
+    });
+
+    Container::new(line)
+        .height(Length::Fixed(TRACK_CELL_HEIGHT))
+        .clip(true)
+        .into()
+}
+
+/// A text cell of a track row, `portion` wide.
+fn track_cell<'a>(content: String, portion: u16) -> Element<'a, Message> {
+    Container::new(clipped_text(content, Horizontal::Left))
+        .width(Length::FillPortion(portion))
+        .into()
+}
 
-    let info = Infer::new();
-    Ok(info.is_audio(&buf))
+/// The handle between two column headings, which resizes the column left of it.
+fn column_handle<'a>(column: TrackColumn) -> Element<'a, Message> {
+    let divider = Container::new(widget::divider::vertical::default())
+        .width(Length::Fixed(TRACK_CELL_SPACING))
+        .height(Length::Fill)
+        .align_x(Horizontal::Center);
+
+    widget::mouse_area(divider)
+        .interaction(mouse::Interaction::ResizingHorizontally)
+        .on_press(Message::StartColumnDrag(column))
+        .into()
 }
 
 fn send_value_as_str(v: &glib::SendValue) -> Option<String> {
@@ -1120,196 +8202,134 @@ fn send_value_as_str(v: &glib::SendValue) -> Option<String> {
     }
 }
 
-// fn get_all_files(url: Url, app_scope: &mut Jams) {
-fn get_all_files(url: Url, albums: &mut Vec<Album>, scanned_files: &mut Vec<MusicFile>) {
-    for (index, entry) in WalkDir::new(url.to_file_path().unwrap())
-        .into_iter()
-        .enumerate()
-    {
-        match entry {
-            Ok(entry) => {
-                let is_audio = is_audio_file(entry.path()).unwrap_or_else(|_| false);
-
-                if entry.file_type().is_file() && is_audio {
-                    let saved_path = entry.clone().into_path();
-                    println!("{}", entry.path().display());
-                    match Url::from_file_path(entry.clone().into_path()) {
-                        Ok(url) => {
-                            let tagged_file =
-                                match lofty::read_from_path(entry.clone().path()) {
-                                    Ok(file) => file,
-                                    Err(err) => {
-                                        eprintln!("Error reading file: {}", err);
-                                        continue;
-                                    }
-                                };
-
-                            if let Some(tag) = tagged_file.primary_tag() {
-                                let track_title = match tag
-                                    .get_string(&ItemKey::TrackTitle)
-                                    .map(|s| s.to_string())
-                                {
-                                    Some(title) => title,
-                                    None => {
-                                        // If there's no track tag, fall back to the file name.
-                                        match entry.path().file_name() {
-                                            Some(filename) => match filename.to_str() {
-                                                Some(filename) => filename.to_string(),
-                                                None => String::from(""),
-                                            },
-                                            None => String::from(""),
-                                        }
-                                    }
-                                };
-                                let album = tag
-                                    .album()
-                                    .map(|s| s.to_string())
-                                    .unwrap_or_else(|| String::from("Unknown Album"));
-                                let artist = tag
-                                    .artist()
-                                    .map(|s| s.to_string())
-                                    .unwrap_or_default();
-                                let album_artist = match tag
-                                    .get_string(&ItemKey::AlbumArtist)
-                                    .map(|s| s.to_string())
-                                {
-                                    Some(album_artist) => album_artist,
-                                    None => artist.clone(),
-                                };
-                                let date = tag
-                                    .year()
-                                    .map(|s| s.to_string())
-                                    .unwrap_or_default();
-                                let track_number = match tag
-                                    .track()
-                                    .map(|s| s.to_string())
-                                {
-                                    Some(track) => track.parse::<u16>().unwrap_or(0),
-                                    None => 0,
-                                };
-
-                                let properties =
-                                    lofty::prelude::AudioFile::properties(&tagged_file);
-                                let duration = Duration::from_secs(
-                                    properties.duration().as_secs(),
-                                );
+/// The file's format and channel layout, such as "FLAC · 5.1", and whether `downmix`
+/// mixes it down to stereo.
+fn format_badge(file: &MusicFile, downmix: Downmix) -> String {
+    let format = file
+        .saved_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_uppercase())
+        .unwrap_or_default();
+    let layout = match file.channels {
+        0 => return format,
+        1 => fl!("channels-mono"),
+        2 => fl!("channels-stereo"),
+        3 => String::from("2.1"),
+        4 => String::from("4.0"),
+        5 => String::from("5.0"),
+        6 => String::from("5.1"),
+        7 => String::from("6.1"),
+        8 => String::from("7.1"),
+        channels => fl!("channels-count", count = channels),
+    };
+
+    if file.channels > 2 && downmix != Downmix::Passthrough {
+        fl!("format-downmixed", format = format, layout = layout)
+    } else {
+        format!("{} · {}", format, layout)
+    }
+}
 
-                                // println!("{}", tag.picture_count());
-                                // let thing = tag.pictures();
-                                // for pic in tag.pictures() {
-                                //     println!("{:?}", pic.pic_type());
-                                // }
-
-                                let music_file = MusicFile {
-                                    album_artist: album_artist.clone(),
-                                    album: album.clone(),
-                                    track_number,
-                                    artist,
-                                    track_title,
-                                    duration,
-                                    date,
-                                    saved_path: saved_path.clone(),
-                                    uri: url.to_string(),
-                                    //metadata,
-                                    playing: false,
-                                    paused: false,
-                                    id: index,
-                                };
-
-                                match albums.iter_mut().find(|album| {
-                                    album.album == music_file.album
-                                        && album.album_artist == music_file.album_artist
-                                }) {
-                                    Some(album) => {
-                                        album.tracks.push(index);
-                                    }
-                                    None => {
-
-                                        let path_to_write = "~/.local/share/jams/covers/".to_string() + index.to_string().as_str();
-
-                                        match tag.pictures().first() {
-                                            None => {}
-                                            Some(picture) => {
-                                                let data = picture.data();
-
-                                                fs::create_dir_all("~/.local/share/jams/covers/").expect("TODO: panic message");
-
-                                                let mut file = fs::OpenOptions::new()
-                                                    .create(true) // To create a new file
-                                                    .write(true)
-                                                    // either use the ? operator or unwrap since it returns a Result
-                                                    .open(path_to_write.clone()).unwrap();
-
-                                                file.write_all(&data).unwrap();
-                                            }
-                                        }
-
-                                        let new_album = Album {
-                                            album_artist: album_artist.clone(),
-                                            album: album.clone(),
-                                            cached_cover_path: path_to_write.clone(),
-                                            tracks: vec![index],
-                                        };
-                                        albums.push(new_album);
-                                    }
-                                }
+/// What a running scan has done, and how many of how many files it looked at once they've
+/// been counted.
+fn scan_progress_text(progress: &ScanProgress) -> (String, Option<(usize, usize)>) {
+    let found = progress.found.load(Ordering::Relaxed);
+    let total = progress.total.load(Ordering::Relaxed);
+    if total == 0 {
+        return (fl!("scanning-found", count = found), None);
+    }
 
-                                scanned_files.push(music_file);
-                            } else {
-                                println!("No tags found in file");
-                                continue;
-                            };
-                        }
-                        Err(err) => eprintln!("Failed to run discovery: {err:?}"),
-                    }
-                }
-            }
-            Err(entry_error) => {
-                println!("URL {} could not be read", url);
-            }
-        }
+    // Files reached through several links are counted more than once.
+    let checked = progress.checked.load(Ordering::Relaxed).min(total);
+    let status = fl!(
+        "scanning-checked",
+        checked = checked,
+        total = total,
+        count = found
+    );
+    (status, Some((checked, total)))
+}
+
+/// How long `files` play for together.
+fn total_length(files: &[&MusicFile]) -> Duration {
+    files.iter().map(|file| file.duration).sum()
+}
+
+/// A length as `43:12` or `2:03:45`, or in days once it runs past one, like "9.4 days".
+fn format_length(length: Duration) -> String {
+    let seconds = length.as_secs();
+    if seconds >= 24 * 60 * 60 {
+        let days = (length.as_secs_f64() / (24.0 * 60.0 * 60.0) * 10.0).round() / 10.0;
+        return fl!("day-count", count = days);
     }
 
-    // https://rust-lang-nursery.github.io/rust-cookbook/algorithms/sorting.html#sort-a-vector-of-structs
-    // Sorts a vec of structs by its natural order (aka the order that was declared in the struct)
-    scanned_files.sort();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
 }
 
-fn write_loc_to_config(url: &Url) {
-    let home_dir = std::env::var("HOME").unwrap();
-    let config_file_loc = format!("{}/.config/jams/locations", home_dir);
-    // TODO: make this less horrifying
-    let path_to_write = url.clone().to_file_path().unwrap().as_os_str().to_str().unwrap().to_string();
+/// A size in bytes as "312 GB" or "4.7 MB", in powers of 1000 as file managers show them.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    if unit == 0 || size >= 10.0 {
+        format!("{:.0} {}", size, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
 
-    let mut file = File::create(&config_file_loc).unwrap();
-    file.write_all(path_to_write.as_bytes()).unwrap();
+/// Says why a track was picked for "More like this".
+fn match_reason_name(reason: MatchReason) -> String {
+    match reason {
+        MatchReason::SameArtist => fl!("match-same-artist"),
+        MatchReason::SameGenre => fl!("match-same-genre"),
+        MatchReason::SameLabel => fl!("match-same-label"),
+        MatchReason::SameDecade => fl!("match-same-decade"),
+        MatchReason::PlayedTogether => fl!("match-played-together"),
+    }
 }
 
-fn get_loc_from_config() -> Result<Url, String> {
-    // this could have a better result error type
-    let home_dir = std::env::var("HOME").unwrap();
-    let config_file_loc = format!("{}/.config/jams/locations", home_dir);
-
-    match fs::read_to_string(config_file_loc.clone()) {
-        Ok(contents) => {
-            let path = Path::new(contents.trim_end());
-            if path.exists() {
-                match Url::from_file_path(path) {
-                    Ok(url) => Ok(url),
-                    Err(_) => {
-                        let err_msg = format!("Failed to convert library path {} to URL.", path.display());
-                        Err(err_msg)
-                    }
-                }
-            } else {
-                let err_msg = format!("Library path {} does not exist.", path.display());
-                Err(err_msg)
-            }
-        }
-        Err(_) => {
-            let err_msg = format!("Config file at {} does not exist.", config_file_loc);
-            Err(String::from(""))
+/// What a picture embedded in a file shows, by its ID3 picture type.
+fn picture_type_name(pic_type: PictureType) -> String {
+    match pic_type {
+        PictureType::CoverFront => fl!("picture-front"),
+        PictureType::CoverBack => fl!("picture-back"),
+        PictureType::Leaflet => fl!("picture-booklet"),
+        PictureType::Media => fl!("picture-media"),
+        PictureType::LeadArtist | PictureType::Artist | PictureType::Band => {
+            fl!("picture-artist")
         }
+        _ => fl!("picture-other"),
     }
 }
 
+/// DR score, integrated loudness and album gain on one line, leaving out what's unknown.
+fn loudness_summary(measured: &AlbumLoudness) -> String {
+    let mut parts = Vec::new();
+    if let Some(dr) = measured.dr {
+        parts.push(fl!("loudness-dr", dr = dr));
+    }
+    if let Some(lufs) = measured.lufs {
+        parts.push(fl!("loudness-lufs", lufs = format!("{:.1}", lufs)));
+    }
+    if let Some(gain) = measured.album_gain() {
+        parts.push(fl!("loudness-album-gain", gain = format!("{:+.1}", gain)));
+    }
+
+    if parts.is_empty() {
+        fl!("loudness-silent")
+    } else {
+        parts.join(" · ")
+    }
+}
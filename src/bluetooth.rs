@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Codec and battery level of connected Bluetooth audio devices, read from BlueZ.
+
+use std::collections::HashMap;
+
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+/// Properties of one D-Bus interface.
+type Properties = HashMap<String, OwnedValue>;
+/// What `GetManagedObjects` returns: interfaces and their properties, per object.
+type ManagedObjects = HashMap<OwnedObjectPath, HashMap<String, Properties>>;
+
+/// A Bluetooth device audio is being streamed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioDevice {
+    pub name: String,
+    /// `None` when the codec isn't one we know.
+    pub codec: Option<&'static str>,
+    /// Charge in percent, if the device reports it.
+    pub battery: Option<u8>,
+    /// Whether audio is flowing right now, as opposed to the link just being set up.
+    pub active: bool,
+}
+
+/// A2DP codec IDs from the Bluetooth assigned numbers, plus LC3 for LE Audio.
+const CODEC_SBC: u8 = 0x00;
+const CODEC_MPEG12: u8 = 0x01;
+const CODEC_AAC: u8 = 0x02;
+const CODEC_LC3: u8 = 0x06;
+/// The codec is identified by a vendor and codec ID at the start of the configuration.
+const CODEC_VENDOR: u8 = 0xff;
+
+/// Known vendor codecs as (vendor ID, codec ID, name).
+const VENDOR_CODECS: [(u32, u16, &str); 5] = [
+    (0x0000_004f, 0x0001, "aptX"),
+    (0x0000_00d7, 0x0024, "aptX HD"),
+    (0x0000_000a, 0x0002, "aptX Low Latency"),
+    (0x0000_012d, 0x00aa, "LDAC"),
+    (0x0000_000a, 0x0001, "FastStream"),
+];
+
+/// Name of the codec described by a `MediaTransport1` `Codec` and `Configuration`.
+fn codec_name(codec: u8, configuration: &[u8]) -> Option<&'static str> {
+    match codec {
+        CODEC_SBC => Some("SBC"),
+        CODEC_MPEG12 => Some("MP3"),
+        CODEC_AAC => Some("AAC"),
+        CODEC_LC3 => Some("LC3"),
+        CODEC_VENDOR => {
+            let vendor = u32::from_le_bytes(configuration.get(0..4)?.try_into().ok()?);
+            let id = u16::from_le_bytes(configuration.get(4..6)?.try_into().ok()?);
+            VENDOR_CODECS
+                .iter()
+                .find(|(known_vendor, known_id, _)| *known_vendor == vendor && *known_id == id)
+                .map(|(_, _, name)| *name)
+        }
+        _ => None,
+    }
+}
+
+fn property<T>(properties: &Properties, name: &str) -> Option<T>
+where
+    T: TryFrom<OwnedValue>,
+{
+    let value = properties.get(name)?.try_clone().ok()?;
+    T::try_from(value).ok()
+}
+
+/// Asks BlueZ over the system bus for every device with an audio stream set up.
+/// Returns `None` when BlueZ can't be reached.
+pub async fn audio_devices() -> Option<Vec<AudioDevice>> {
+    let connection = zbus::Connection::system().await.ok()?;
+    let proxy = zbus::Proxy::new(
+        &connection,
+        "org.bluez",
+        "/",
+        "org.freedesktop.DBus.ObjectManager",
+    )
+    .await
+    .ok()?;
+
+    let objects: ManagedObjects = proxy.call("GetManagedObjects", &()).await.ok()?;
+
+    let mut devices = Vec::new();
+
+    // Each stream is a MediaTransport1 pointing back at the Device1 it belongs to.
+    for interfaces in objects.values() {
+        let Some(transport) = interfaces.get("org.bluez.MediaTransport1") else {
+            continue;
+        };
+        let Some(device_path) = property::<OwnedObjectPath>(transport, "Device") else {
+            continue;
+        };
+        let Some(device) = objects.get(&device_path) else {
+            continue;
+        };
+
+        let name = device
+            .get("org.bluez.Device1")
+            .and_then(|device| {
+                property::<String>(device, "Alias").or_else(|| property(device, "Name"))
+            })
+            .unwrap_or_else(|| device_path.to_string());
+        let codec = property::<u8>(transport, "Codec").and_then(|codec| {
+            let configuration = property::<Vec<u8>>(transport, "Configuration").unwrap_or_default();
+            codec_name(codec, &configuration)
+        });
+        let battery = device
+            .get("org.bluez.Battery1")
+            .and_then(|battery| property::<u8>(battery, "Percentage"));
+        let active = property::<String>(transport, "State").is_some_and(|state| state == "active");
+
+        devices.push(AudioDevice {
+            name,
+            codec,
+            battery,
+            active,
+        });
+    }
+
+    devices.sort_by(|a, b| b.active.cmp(&a.active).then_with(|| a.name.cmp(&b.name)));
+    devices.dedup_by(|a, b| a.name == b.name);
+
+    Some(devices)
+}
@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Settings kept with cosmic-config, under `~/.config/cosmic/com.benfuddled.Jams`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+use crate::app::Page;
+use crate::core::alarm::{Alarm, AlarmSource};
+use crate::core::flatpak;
+use crate::core::motion::MotionMode;
+use crate::core::touch::TouchMode;
+use crate::hooks::Hooks;
+use crate::library::collation;
+use crate::library::lyrics::LyricsDraft;
+use crate::library::musical_key::KeyNotation;
+use crate::library::persistence::get_loc_from_config;
+use crate::library::scan::ScanOptions;
+use crate::network::NetworkMode;
+use crate::now_playing::NowPlayingOutput;
+use crate::player::{DeviceProfile, Downmix, EndOfQueue, FilterSettings, ResumeRules};
+use crate::track_columns::ColumnWidths;
+
+/// Files under `~/.config/jams` settings were kept in before, carried over by [`migrate`].
+const FAVORITES: &str = "favorites";
+const LYRICS_DRAFT: &str = "drafts/lyrics";
+const HOOKS: &str = "hooks";
+const ALARM: &str = "alarm";
+const NOW_PLAYING: &str = "now-playing";
+const RESUME: &str = "resume";
+
+/// A folder whose music is part of the library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryFolder {
+    pub path: PathBuf,
+    /// Switched off, the folder's music is left out of the library without the folder
+    /// being forgotten.
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, CosmicConfigEntry)]
+#[version = 1]
+pub struct Config {
+    /// In the order they were added. Every enabled one is scanned into the same library.
+    pub library_folders: Vec<LibraryFolder>,
+    /// Whether the library folders are scanned when Jams starts. Otherwise the library
+    /// stays empty until scanned from the settings.
+    pub rescan_on_start: bool,
+    /// Folder the art cache and the caches next to it are kept in, instead of
+    /// `~/.local/share/jams`.
+    pub cache_folder: Option<PathBuf>,
+    /// The page shown when Jams starts.
+    pub default_page: Page,
+    pub crossfade_length: Duration,
+    pub end_of_queue: EndOfQueue,
+    /// How wide the columns of the track lists are.
+    pub track_columns: ColumnWidths,
+    pub favorites: BTreeSet<PathBuf>,
+    /// Lyrics being edited, kept as they're typed so quitting or crashing doesn't lose them.
+    pub lyrics_draft: Option<LyricsDraft>,
+    pub hooks: Hooks,
+    pub alarm: Alarm,
+    pub now_playing_output: NowPlayingOutput,
+    pub resume_rules: ResumeRules,
+    pub filter_settings: FilterSettings,
+    /// By the name of the output device.
+    pub device_profiles: BTreeMap<String, DeviceProfile>,
+    pub scan_options: ScanOptions,
+    /// Articles left out when sorting, separated by commas.
+    pub sort_articles: String,
+    /// Width of the album grid's covers, in logical pixels.
+    pub tile_size: f32,
+    /// Whether All Music and Songs are grouped under album headers.
+    pub group_by_album: bool,
+    pub key_notation: KeyNotation,
+    /// Audio buffered in the sink, `None` for the sink's own default.
+    pub output_buffer: Option<Duration>,
+    pub downmix: Downmix,
+    /// Whether track changes are announced to screen readers, as picked in the settings.
+    pub announce_tracks: bool,
+    /// Whether hovering a search result plays a bit of it.
+    pub preview_on_hover: bool,
+    pub motion_mode: MotionMode,
+    pub touch_mode: TouchMode,
+    pub network_mode: NetworkMode,
+    /// Whether tracks that would have to be streamed are hidden.
+    pub local_only: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            library_folders: Vec::new(),
+            rescan_on_start: true,
+            cache_folder: None,
+            default_page: Page::default(),
+            crossfade_length: Duration::ZERO,
+            end_of_queue: EndOfQueue::default(),
+            track_columns: ColumnWidths::default(),
+            favorites: BTreeSet::new(),
+            lyrics_draft: None,
+            hooks: Hooks::default(),
+            alarm: Alarm::default(),
+            now_playing_output: NowPlayingOutput::default(),
+            resume_rules: ResumeRules::default(),
+            filter_settings: FilterSettings::default(),
+            device_profiles: BTreeMap::new(),
+            scan_options: ScanOptions::default(),
+            sort_articles: collation::DEFAULT_ARTICLES.to_string(),
+            tile_size: 270.0,
+            group_by_album: false,
+            key_notation: KeyNotation::default(),
+            output_buffer: None,
+            downmix: Downmix::default(),
+            announce_tracks: false,
+            preview_on_hover: false,
+            motion_mode: MotionMode::default(),
+            touch_mode: TouchMode::default(),
+            network_mode: NetworkMode::default(),
+            local_only: false,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the settings for the app with `app_id`, along with the handle to change them
+    /// through. Settings that can't be read are left at their defaults.
+    pub fn load(app_id: &str) -> (Option<cosmic_config::Config>, Config) {
+        let handler = match cosmic_config::Config::new(app_id, Config::VERSION) {
+            Ok(handler) => handler,
+            Err(err) => {
+                eprintln!("Failed to open the settings: {}", err);
+                return (None, Config::default());
+            }
+        };
+
+        let mut config = match Config::get_entry(&handler) {
+            Ok(config) => config,
+            Err((errors, config)) => {
+                for err in errors.iter().filter(|err| err.is_err()) {
+                    eprintln!("Failed to read a setting: {}", err);
+                }
+                config
+            }
+        };
+
+        // Before there were several folders, the only one was kept in a file of its own.
+        if config.library_folders.is_empty() {
+            if let Some(path) = get_loc_from_config()
+                .ok()
+                .and_then(|url| url.to_file_path().ok())
+            {
+                let folders = vec![LibraryFolder {
+                    path,
+                    enabled: true,
+                }];
+                if let Err(err) = config.set_library_folders(&handler, folders) {
+                    eprintln!("Failed to save the library folders: {}", err);
+                }
+            }
+        }
+
+        // Other settings were kept in files of their own, removed once carried over.
+        migrate(FAVORITES, read_favorites(), |favorites| {
+            config.set_favorites(&handler, favorites)
+        });
+        migrate(LYRICS_DRAFT, read_lyrics_draft(), |draft| {
+            config.set_lyrics_draft(&handler, Some(draft))
+        });
+        migrate(HOOKS, read_hooks(), |hooks| {
+            config.set_hooks(&handler, hooks)
+        });
+        migrate(ALARM, read_alarm(), |alarm| {
+            config.set_alarm(&handler, alarm)
+        });
+        migrate(NOW_PLAYING, read_now_playing_output(), |output| {
+            config.set_now_playing_output(&handler, output)
+        });
+        migrate(RESUME, read_resume_rules(), |rules| {
+            config.set_resume_rules(&handler, rules)
+        });
+
+        (Some(handler), config)
+    }
+
+    /// The folders to scan.
+    pub fn enabled_folders(&self) -> Vec<PathBuf> {
+        self.library_folders
+            .iter()
+            .filter(|folder| folder.enabled)
+            .map(|folder| folder.path.clone())
+            .collect()
+    }
+
+    /// The enabled folder `path` is in, if any.
+    pub fn folder_of(&self, path: &Path) -> Option<&Path> {
+        self.library_folders
+            .iter()
+            .filter(|folder| folder.enabled)
+            .map(|folder| folder.path.as_path())
+            .find(|folder| path.starts_with(folder))
+    }
+}
+
+/// Saves `value`, read from the file `name` under `~/.config/jams`, with `save`, then removes
+/// the file so it isn't read again.
+fn migrate<T>(
+    name: &str,
+    value: Option<T>,
+    save: impl FnOnce(T) -> Result<bool, cosmic_config::Error>,
+) {
+    let Some(value) = value else {
+        return;
+    };
+
+    match save(value) {
+        Ok(_) => {
+            let _ = fs::remove_file(legacy_file(name));
+        }
+        Err(err) => eprintln!("Failed to carry over the {} setting: {}", name, err),
+    }
+}
+
+fn legacy_file(name: &str) -> PathBuf {
+    flatpak::config_dir().join(name)
+}
+
+/// Reads the favorite tracks once kept in `~/.config/jams/favorites`, one path per line.
+fn read_favorites() -> Option<BTreeSet<PathBuf>> {
+    let contents = fs::read_to_string(legacy_file(FAVORITES)).ok()?;
+
+    Some(
+        contents
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            // Library paths are canonical, so favorites saved before they were match again.
+            .map(|path| fs::canonicalize(&path).unwrap_or(path))
+            .collect(),
+    )
+}
+
+/// Reads the draft once kept in `~/.config/jams/drafts/lyrics`: the track's path on the
+/// first line, then the lyrics.
+fn read_lyrics_draft() -> Option<LyricsDraft> {
+    let contents = fs::read_to_string(legacy_file(LYRICS_DRAFT)).ok()?;
+    let (track, text) = contents.split_once('\n')?;
+
+    Some(LyricsDraft {
+        track: PathBuf::from(track),
+        text: text.to_string(),
+    })
+}
+
+/// Reads the hooks once kept in `~/.config/jams/hooks`: the command, then the URL.
+fn read_hooks() -> Option<Hooks> {
+    let contents = fs::read_to_string(legacy_file(HOOKS)).ok()?;
+    let mut lines = contents.lines();
+
+    Some(Hooks {
+        command: lines.next().unwrap_or_default().to_string(),
+        url: lines.next().unwrap_or_default().to_string(),
+    })
+}
+
+/// Reads the alarm once kept in `~/.config/jams/alarm`: `on` or `off`, the time, then what
+/// it plays.
+fn read_alarm() -> Option<Alarm> {
+    let contents = fs::read_to_string(legacy_file(ALARM)).ok()?;
+    let mut lines = contents.lines();
+
+    let mut alarm = Alarm::default();
+    alarm.enabled = lines.next() == Some("on");
+    alarm.set_time(lines.next().unwrap_or_default());
+    if lines.next() == Some("library") {
+        alarm.source = AlarmSource::Library;
+    }
+    Some(alarm)
+}
+
+/// Reads the now playing file settings once kept in `~/.config/jams/now-playing`: `on` or
+/// `off`, then the template.
+fn read_now_playing_output() -> Option<NowPlayingOutput> {
+    let contents = fs::read_to_string(legacy_file(NOW_PLAYING)).ok()?;
+    let mut lines = contents.lines();
+
+    Some(NowPlayingOutput {
+        enabled: lines.next() == Some("on"),
+        template: lines.next().unwrap_or_default().to_string(),
+    })
+}
+
+/// Reads the rules once kept in `~/.config/jams/resume`: the shortest length resumed in
+/// minutes, then the genres and folders it's limited to.
+fn read_resume_rules() -> Option<ResumeRules> {
+    let contents = fs::read_to_string(legacy_file(RESUME)).ok()?;
+    let mut lines = contents.lines();
+
+    let minutes = lines.next().and_then(|line| line.parse().ok()).unwrap_or(0);
+    Some(ResumeRules {
+        min_length: Duration::from_secs(minutes * 60),
+        only: lines.next().unwrap_or_default().to_string(),
+    })
+}
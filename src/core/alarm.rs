@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A wake-up alarm: music that starts at a set time, quietly at first, along with a
+//! notification that can stop it.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use gstreamer::glib;
+use serde::{Deserialize, Serialize};
+use zbus::export::futures_util::{stream, StreamExt};
+use zbus::zvariant::Value;
+
+/// How long the volume takes to rise from silence to full.
+pub const RAMP: Duration = Duration::from_secs(120);
+/// Key of the notification's stop button.
+const STOP_ACTION: &str = "stop";
+
+/// What the alarm plays.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlarmSource {
+    #[default]
+    Favorites,
+    /// The whole library.
+    Library,
+}
+
+impl AlarmSource {
+    pub const ALL: [AlarmSource; 2] = [AlarmSource::Favorites, AlarmSource::Library];
+
+    /// Labels for a dropdown, in the same order as [`AlarmSource::ALL`].
+    pub const NAMES: [&'static str; 2] = ["Favorites", "Whole library"];
+}
+
+/// When the alarm goes off, every day while it's on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alarm {
+    pub enabled: bool,
+    pub hour: u8,
+    pub minute: u8,
+    /// Played shuffled.
+    pub source: AlarmSource,
+}
+
+impl Default for Alarm {
+    fn default() -> Self {
+        Alarm {
+            enabled: false,
+            hour: 7,
+            minute: 0,
+            source: AlarmSource::default(),
+        }
+    }
+}
+
+impl Alarm {
+    /// The time as `07:30`.
+    pub fn time(&self) -> String {
+        format!("{:02}:{:02}", self.hour, self.minute)
+    }
+
+    /// Sets the time from text like `7:30` or `07:30`. Returns `false`, changing nothing,
+    /// for anything else.
+    pub fn set_time(&mut self, text: &str) -> bool {
+        let Some((hour, minute)) = text.trim().split_once(':') else {
+            return false;
+        };
+        let (Ok(hour), Ok(minute)) = (hour.parse::<u8>(), minute.parse::<u8>()) else {
+            return false;
+        };
+        if hour > 23 || minute > 59 {
+            return false;
+        }
+
+        self.hour = hour;
+        self.minute = minute;
+        true
+    }
+
+    /// Today, as year and day of the year, if the alarm should go off now and hasn't
+    /// already today. `rang` is the day it last went off.
+    pub fn due(&self, rang: Option<(i32, i32)>) -> Option<(i32, i32)> {
+        if !self.enabled {
+            return None;
+        }
+
+        let now = glib::DateTime::now_local().ok()?;
+        let today = (now.year(), now.day_of_year());
+        let on_time = now.hour() == i32::from(self.hour) && now.minute() == i32::from(self.minute);
+
+        (on_time && rang != Some(today)).then_some(today)
+    }
+}
+
+/// Volume for a ramp `elapsed` into [`RAMP`]. Rises slowly at first since hearing is more
+/// sensitive at low levels.
+pub fn ramp_volume(elapsed: Duration) -> f64 {
+    let progress = (elapsed.as_secs_f64() / RAMP.as_secs_f64()).clamp(0.0, 1.0);
+    progress * progress
+}
+
+/// Shows a notification with a button that stops the alarm, then waits until it's either
+/// pressed or the notification goes away. Returns whether the alarm should stop.
+pub async fn notify_and_wait(app_id: &str, summary: &str, body: &str, stop_label: &str) -> bool {
+    async fn run(app_id: &str, summary: &str, body: &str, stop_label: &str) -> zbus::Result<bool> {
+        let connection = zbus::Connection::session().await?;
+        let proxy = zbus::Proxy::new(
+            &connection,
+            "org.freedesktop.Notifications",
+            "/org/freedesktop/Notifications",
+            "org.freedesktop.Notifications",
+        )
+        .await?;
+
+        // Listen before notifying so a quick press isn't missed.
+        let invoked = proxy.receive_signal("ActionInvoked").await?;
+        let closed = proxy.receive_signal("NotificationClosed").await?;
+
+        let mut hints: HashMap<&str, Value> = HashMap::new();
+        hints.insert("urgency", Value::from(2u8));
+        hints.insert("category", Value::from("alarm"));
+
+        let actions = vec![STOP_ACTION, stop_label];
+        let id: u32 = proxy
+            .call(
+                "Notify",
+                &("Jams", 0u32, app_id, summary, body, actions, hints, 0i32),
+            )
+            .await?;
+
+        // Both signals are sent for every notification, not just this one.
+        let invoked = invoked.map(|message| {
+            let (id, action) = message.body().deserialize::<(u32, String)>()?;
+            Ok::<_, zbus::Error>((id, action == STOP_ACTION))
+        });
+        let closed = closed.map(|message| {
+            let (id, _reason) = message.body().deserialize::<(u32, u32)>()?;
+            // Dismissing the notification leaves the music playing.
+            Ok::<_, zbus::Error>((id, false))
+        });
+
+        let mut signals = stream::select(invoked, closed);
+        while let Some(signal) = signals.next().await {
+            let (signal_id, stop) = signal?;
+            if signal_id == id {
+                return Ok(stop);
+            }
+        }
+
+        Ok(false)
+    }
+
+    run(app_id, summary, body, stop_label)
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to show the alarm notification: {}", err);
+            false
+        })
+}
@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Spoken feedback for screen reader users, sent as desktop notifications since screen
+//! readers such as Orca read those out even when Jams isn't focused.
+
+use std::collections::HashMap;
+
+use zbus::zvariant::Value;
+
+/// How long the notification stays up, in milliseconds.
+const EXPIRE_TIMEOUT: i32 = 4000;
+
+/// Whether the session reports a running screen reader, through the AT-SPI status object.
+pub async fn screen_reader_enabled() -> bool {
+    async fn query() -> zbus::Result<bool> {
+        let connection = zbus::Connection::session().await?;
+        let proxy = zbus::Proxy::new(
+            &connection,
+            "org.a11y.Bus",
+            "/org/a11y/bus",
+            "org.a11y.Status",
+        )
+        .await?;
+        proxy.get_property("ScreenReaderEnabled").await
+    }
+
+    query().await.unwrap_or(false)
+}
+
+/// Posts `text` as a short-lived, low-priority notification, replacing the one with ID
+/// `replaces` so announcements don't pile up. Returns the new notification's ID.
+pub async fn announce(app_id: &str, text: &str, replaces: u32) -> Option<u32> {
+    let connection = zbus::Connection::session().await.ok()?;
+    let proxy = zbus::Proxy::new(
+        &connection,
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+    )
+    .await
+    .ok()?;
+
+    let mut hints: HashMap<&str, Value> = HashMap::new();
+    // Keeps announcements out of the notification history.
+    hints.insert("transient", Value::from(true));
+    hints.insert("urgency", Value::from(0u8));
+
+    let actions: Vec<&str> = Vec::new();
+    proxy
+        .call(
+            "Notify",
+            &(
+                "Jams",
+                replaces,
+                app_id,
+                text,
+                "",
+                actions,
+                hints,
+                EXPIRE_TIMEOUT,
+            ),
+        )
+        .await
+        .ok()
+}
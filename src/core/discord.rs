@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The current track as Discord Rich Presence, compiled in with `--features discord`.
+//!
+//! Talks to the Discord client over its local IPC socket. The application ID Discord shows
+//! the presence under is taken from `JAMS_DISCORD_CLIENT_ID` at build time. Without the
+//! feature or the ID, [`show`] does nothing so the calls can stay in place.
+
+#[cfg(feature = "discord")]
+use std::time::UNIX_EPOCH;
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "discord")]
+use std::io::{Read, Write};
+#[cfg(feature = "discord")]
+use std::os::unix::net::UnixStream;
+#[cfg(feature = "discord")]
+use std::path::PathBuf;
+#[cfg(feature = "discord")]
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(feature = "discord")]
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(feature = "discord")]
+use serde_json::json;
+
+const CLIENT_ID: Option<&str> = option_env!("JAMS_DISCORD_CLIENT_ID");
+/// Discord tries these sockets in turn, one per running client.
+#[cfg(feature = "discord")]
+const SOCKET_COUNT: usize = 10;
+#[cfg(feature = "discord")]
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+/// Frame opcodes.
+#[cfg(feature = "discord")]
+const OP_HANDSHAKE: u32 = 0;
+#[cfg(feature = "discord")]
+const OP_FRAME: u32 = 1;
+#[cfg(feature = "discord")]
+const OP_CLOSE: u32 = 2;
+/// Activity type shown as "Listening to".
+#[cfg(feature = "discord")]
+const ACTIVITY_LISTENING: u8 = 2;
+
+#[cfg(feature = "discord")]
+static UPDATES: OnceLock<Mutex<Sender<Option<NowPlaying>>>> = OnceLock::new();
+
+/// What's shown on the profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NowPlaying {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    /// A cover Discord can fetch itself. Files on this computer can't be shown.
+    pub cover_url: Option<String>,
+    /// When playback would have started had it never paused, and when it will end.
+    pub started: SystemTime,
+    pub length: Duration,
+}
+
+/// Shows `now_playing` on the profile, or clears it for `None`. Returns straight away; the
+/// update goes out on a thread that keeps the connection to Discord.
+pub fn show(now_playing: Option<NowPlaying>) {
+    #[cfg(feature = "discord")]
+    {
+        let Some(client_id) = CLIENT_ID else {
+            return;
+        };
+
+        let sender = UPDATES.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel();
+            std::thread::spawn(move || run(client_id, receiver));
+            Mutex::new(sender)
+        });
+        if let Ok(sender) = sender.lock() {
+            let _ = sender.send(now_playing);
+        }
+    }
+
+    #[cfg(not(feature = "discord"))]
+    {
+        let _ = now_playing;
+    }
+}
+
+/// Whether presence was compiled in and can be turned on.
+pub fn available() -> bool {
+    cfg!(feature = "discord") && CLIENT_ID.is_some()
+}
+
+/// Seconds since the Unix epoch, as Discord's timestamps take them.
+#[cfg(feature = "discord")]
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Sends every update that comes in, connecting again when Discord was restarted. Updates
+/// made while Discord isn't running are dropped.
+#[cfg(feature = "discord")]
+fn run(client_id: &str, updates: Receiver<Option<NowPlaying>>) {
+    let mut connection: Option<UnixStream> = None;
+    let mut nonce = 0u64;
+
+    for now_playing in updates {
+        let activity = now_playing.map(|now_playing| {
+            let started = unix_seconds(now_playing.started);
+            json!({
+                "type": ACTIVITY_LISTENING,
+                "details": now_playing.title,
+                "state": now_playing.artist,
+                "timestamps": {
+                    "start": started,
+                    "end": started + now_playing.length.as_secs(),
+                },
+                "assets": {
+                    "large_image": now_playing.cover_url.unwrap_or_else(|| String::from("jams")),
+                    "large_text": now_playing.album,
+                },
+            })
+        });
+
+        nonce += 1;
+        let command = json!({
+            "cmd": "SET_ACTIVITY",
+            "args": { "pid": std::process::id(), "activity": activity },
+            "nonce": nonce.to_string(),
+        });
+
+        // One retry, for a connection that went away since the last update.
+        for _ in 0..2 {
+            if connection.is_none() {
+                connection = connect(client_id);
+            }
+            let Some(stream) = &mut connection else {
+                break;
+            };
+
+            match send(stream, OP_FRAME, &command) {
+                Ok(()) => break,
+                Err(err) => {
+                    eprintln!("Failed to update Discord presence: {}", err);
+                    connection = None;
+                }
+            }
+        }
+    }
+}
+
+/// Sockets the Discord client may be listening on, including the Flatpak's.
+#[cfg(feature = "discord")]
+fn socket_paths() -> Vec<PathBuf> {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    let dirs = [
+        runtime_dir.clone(),
+        runtime_dir.join("app/com.discordapp.Discord"),
+    ];
+
+    dirs.iter()
+        .flat_map(|dir| {
+            (0..SOCKET_COUNT).map(move |index| dir.join(format!("discord-ipc-{index}")))
+        })
+        .collect()
+}
+
+/// Opens the first socket that answers and says hello.
+#[cfg(feature = "discord")]
+fn connect(client_id: &str) -> Option<UnixStream> {
+    let mut stream = socket_paths()
+        .iter()
+        .find_map(|path| UnixStream::connect(path).ok())?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT)).ok()?;
+
+    let handshake = json!({ "v": 1, "client_id": client_id });
+    match send(&mut stream, OP_HANDSHAKE, &handshake) {
+        Ok(()) => Some(stream),
+        Err(err) => {
+            eprintln!("Failed to connect to Discord: {}", err);
+            None
+        }
+    }
+}
+
+/// Writes one frame and reads the reply, so replies don't pile up unread. Discord closes
+/// the connection with a reason, such as an unknown application ID, instead of replying.
+#[cfg(feature = "discord")]
+fn send(stream: &mut UnixStream, opcode: u32, payload: &serde_json::Value) -> Result<(), String> {
+    let body = payload.to_string();
+    let mut frame = Vec::with_capacity(8 + body.len());
+    frame.extend_from_slice(&opcode.to_le_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    frame.extend_from_slice(body.as_bytes());
+    stream.write_all(&frame).map_err(|err| err.to_string())?;
+
+    let mut header = [0; 8];
+    stream
+        .read_exact(&mut header)
+        .map_err(|err| err.to_string())?;
+    let reply_opcode = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    let mut reply = vec![0; length as usize];
+    stream
+        .read_exact(&mut reply)
+        .map_err(|err| err.to_string())?;
+
+    if reply_opcode == OP_CLOSE {
+        return Err(String::from_utf8_lossy(&reply).to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Running inside a Flatpak sandbox. Jams can't see the home folder there, so settings go
+//! to the folders Flatpak hands out through XDG, and folders picked in the file chooser
+//! come in through the document portal, which needs asking to remember them.
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use zbus::zvariant::{OwnedFd, OwnedValue, Value};
+
+/// The document may be handed out again if it already was.
+const REUSE_EXISTING: u32 = 1;
+/// The document outlives the session instead of going away at logout.
+const PERSISTENT: u32 = 2;
+/// The path is a folder, and everything under it comes along.
+const EXPORT_DIRECTORY: u32 = 8;
+
+/// Whether Jams runs as a Flatpak.
+pub fn is_sandboxed() -> bool {
+    static SANDBOXED: OnceLock<bool> = OnceLock::new();
+    *SANDBOXED.get_or_init(|| Path::new("/.flatpak-info").exists())
+}
+
+/// Folder holding the settings, `~/.config/jams` unless XDG says otherwise. Flatpak points
+/// XDG at the app's own folder under `~/.var/app`.
+pub fn config_dir() -> PathBuf {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let home_dir = std::env::var("HOME").unwrap_or_default();
+            Path::new(&home_dir).join(".config")
+        });
+
+    config_home.join("jams")
+}
+
+/// Folder holding what Jams keeps apart from its settings, `~/.local/share/jams` unless XDG
+/// says otherwise. Flatpak points XDG at the app's own folder under `~/.var/app`.
+pub fn data_dir() -> PathBuf {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let home_dir = std::env::var("HOME").unwrap_or_default();
+            Path::new(&home_dir).join(".local/share")
+        });
+
+    data_home.join("jams")
+}
+
+/// Whether `path` was handed over by the document portal, as everything picked in the file
+/// chooser is under Flatpak.
+fn is_portal_path(path: &Path) -> bool {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").unwrap_or_default();
+    !runtime_dir.is_empty() && path.starts_with(Path::new(&runtime_dir).join("doc"))
+}
+
+/// Makes sure a folder picked in the file chooser can still be opened after a restart.
+/// The file chooser only grants access until the session ends, so the folder is exported
+/// again as a persistent document and its path within that document is returned. Outside
+/// a sandbox, or for paths the portal didn't hand over, `path` comes back as it is.
+pub async fn keep_access(path: PathBuf) -> Result<PathBuf, String> {
+    if !is_sandboxed() || !is_portal_path(&path) {
+        return Ok(path);
+    }
+
+    async fn run(path: &Path) -> zbus::Result<PathBuf> {
+        // The portal only takes descriptors opened with O_PATH.
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+            .open(path)
+            .map_err(|err| zbus::Error::Failure(err.to_string()))?;
+        let flags = REUSE_EXISTING | PERSISTENT | if path.is_dir() { EXPORT_DIRECTORY } else { 0 };
+
+        let connection = zbus::Connection::session().await?;
+        let proxy = zbus::Proxy::new(
+            &connection,
+            "org.freedesktop.portal.Documents",
+            "/org/freedesktop/portal/documents",
+            "org.freedesktop.portal.Documents",
+        )
+        .await?;
+
+        let fds = vec![OwnedFd::from(std::os::fd::OwnedFd::from(file))];
+        // Jams can always reach what it exports itself, so no other app is named.
+        let permissions: Vec<&str> = Vec::new();
+        let (doc_ids, extra): (Vec<String>, HashMap<String, OwnedValue>) = proxy
+            .call("AddFull", &(fds, flags, "", permissions))
+            .await?;
+
+        let doc_id = doc_ids
+            .into_iter()
+            .next()
+            .ok_or_else(|| zbus::Error::Failure("No document was created.".to_string()))?;
+        let mount_point = match extra.get("mountpoint") {
+            Some(value) => {
+                let mut bytes = Vec::<u8>::try_from(Value::from(value.try_clone()?))?;
+                // Sent as a C string.
+                if bytes.last() == Some(&0) {
+                    bytes.pop();
+                }
+                PathBuf::from(std::ffi::OsString::from_vec(bytes))
+            }
+            None => {
+                let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").unwrap_or_default();
+                Path::new(&runtime_dir).join("doc")
+            }
+        };
+
+        let name = path.file_name().unwrap_or_default();
+        Ok(mount_point.join(doc_id).join(name))
+    }
+
+    run(&path)
+        .await
+        .map_err(|err| format!("Failed to keep access to {}: {}", path.display(), err))
+}
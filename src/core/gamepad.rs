@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Gamepad buttons turned into the same moves as the keyboard's, for a couch or a Steam
+//! Deck. Compiled in with `--features gamepad`; without it no pad is ever connected.
+
+#[cfg(feature = "gamepad")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "gamepad")]
+use std::sync::mpsc::{self, Receiver};
+#[cfg(feature = "gamepad")]
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(feature = "gamepad")]
+use gilrs::{Button, EventType, Gilrs};
+
+#[cfg(feature = "gamepad")]
+static PRESSES: OnceLock<Mutex<Receiver<PadAction>>> = OnceLock::new();
+#[cfg(feature = "gamepad")]
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// What a button asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+pub enum PadAction {
+    /// D-pad up and down, through the track list.
+    Up,
+    Down,
+    /// D-pad left and right, through the pages.
+    Left,
+    Right,
+    /// A, or whichever button sits at the bottom.
+    Activate,
+    /// B, or whichever button sits on the right.
+    Back,
+    /// The shoulder buttons.
+    Previous,
+    Next,
+    /// Start.
+    PlayPause,
+}
+
+/// Starts listening for gamepads on a thread of its own. Calling it again does nothing.
+pub fn start() {
+    #[cfg(feature = "gamepad")]
+    PRESSES.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut gilrs = match Gilrs::new() {
+                Ok(gilrs) => gilrs,
+                Err(err) => {
+                    eprintln!("Failed to open gamepads: {}", err);
+                    return;
+                }
+            };
+            CONNECTED.store(gilrs.gamepads().next().is_some(), Ordering::Relaxed);
+
+            while let Some(event) = gilrs.next_event_blocking(None) {
+                let action = match event.event {
+                    EventType::Connected | EventType::Disconnected => {
+                        let connected = gilrs.gamepads().next().is_some();
+                        CONNECTED.store(connected, Ordering::Relaxed);
+                        None
+                    }
+                    EventType::ButtonPressed(button, _) => action_for(button),
+                    _ => None,
+                };
+
+                if let Some(action) = action {
+                    if sender.send(action).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Mutex::new(receiver)
+    });
+}
+
+/// Whether a gamepad is plugged in or paired.
+pub fn connected() -> bool {
+    #[cfg(feature = "gamepad")]
+    {
+        CONNECTED.load(Ordering::Relaxed)
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    {
+        false
+    }
+}
+
+/// Buttons pressed since the last call, oldest first.
+pub fn presses() -> Vec<PadAction> {
+    #[cfg(feature = "gamepad")]
+    {
+        PRESSES
+            .get()
+            .and_then(|presses| presses.lock().ok())
+            .map(|presses| presses.try_iter().collect())
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    {
+        Vec::new()
+    }
+}
+
+/// Gilrs names buttons by where they sit, so the same layout works on every brand of pad.
+#[cfg(feature = "gamepad")]
+fn action_for(button: Button) -> Option<PadAction> {
+    let action = match button {
+        Button::DPadUp => PadAction::Up,
+        Button::DPadDown => PadAction::Down,
+        Button::DPadLeft => PadAction::Left,
+        Button::DPadRight => PadAction::Right,
+        Button::South => PadAction::Activate,
+        Button::East => PadAction::Back,
+        Button::LeftTrigger => PadAction::Previous,
+        Button::RightTrigger => PadAction::Next,
+        Button::Start => PadAction::PlayPause,
+        _ => return None,
+    };
+    Some(action)
+}
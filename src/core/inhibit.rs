@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Keeps the session from going idle while music plays, so the screen doesn't blank and the
+//! computer doesn't suspend halfway through an album. Asked of the desktop portal, which
+//! passes it on to the compositor's idle-inhibit protocol under Wayland.
+
+use std::collections::HashMap;
+
+use zbus::zvariant::{OwnedObjectPath, Value};
+
+/// Inhibits the session going idle, from the portal's set of flags.
+const INHIBIT_IDLE: u32 = 8;
+
+/// Holds the session awake until dropped. The portal lifts the inhibition by itself once
+/// the connection that asked for it is closed.
+#[derive(Debug)]
+pub struct Inhibitor {
+    _connection: zbus::Connection,
+    _request: OwnedObjectPath,
+}
+
+/// Asks that the session stay awake, giving `reason` to anyone who wonders why. Returns
+/// `None` if there's no portal to ask.
+pub async fn inhibit_idle(reason: &str) -> Option<Inhibitor> {
+    async fn run(reason: &str) -> zbus::Result<Inhibitor> {
+        let connection = zbus::Connection::session().await?;
+        let proxy = zbus::Proxy::new(
+            &connection,
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Inhibit",
+        )
+        .await?;
+
+        let mut options: HashMap<&str, Value> = HashMap::new();
+        options.insert("reason", Value::from(reason));
+
+        let request: OwnedObjectPath = proxy.call("Inhibit", &("", INHIBIT_IDLE, options)).await?;
+        Ok(Inhibitor {
+            _connection: connection,
+            _request: request,
+        })
+    }
+
+    run(reason)
+        .await
+        .map_err(|err| eprintln!("Failed to keep the session awake: {}", err))
+        .ok()
+}
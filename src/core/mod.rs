@@ -1,3 +1,12 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+pub mod alarm;
+pub mod announce;
+pub mod discord;
+pub mod flatpak;
+pub mod gamepad;
+pub mod inhibit;
 pub mod localization;
+pub mod motion;
+pub mod profiling;
+pub mod touch;
@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Whether to cut down on animation and wakeups, from the user's choice, the desktop's
+//! animation setting and power-profiles-daemon.
+
+use serde::{Deserialize, Serialize};
+use zbus::zvariant::{OwnedValue, Value};
+
+/// What the user picked for reduced motion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MotionMode {
+    /// Reduce motion when the desktop has animations off or is saving power.
+    #[default]
+    Automatic,
+    Reduced,
+    Full,
+}
+
+impl MotionMode {
+    pub const ALL: [MotionMode; 3] = [MotionMode::Automatic, MotionMode::Reduced, MotionMode::Full];
+
+    /// Labels for a dropdown, in the same order as [`MotionMode::ALL`].
+    pub const NAMES: [&'static str; 3] = ["Automatic", "Always", "Never"];
+}
+
+/// What the desktop reported last time it was asked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DesktopMotion {
+    /// The desktop's own animations are turned off.
+    pub animations_off: bool,
+    /// power-profiles-daemon is in its power saver profile.
+    pub power_saver: bool,
+}
+
+/// The chosen mode combined with what was last detected.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Motion {
+    pub mode: MotionMode,
+    pub detected: DesktopMotion,
+}
+
+impl Motion {
+    /// Whether text should hold still and the position be polled less often.
+    pub fn is_reduced(&self) -> bool {
+        match self.mode {
+            MotionMode::Automatic => self.detected.animations_off || self.detected.power_saver,
+            MotionMode::Reduced => true,
+            MotionMode::Full => false,
+        }
+    }
+}
+
+/// Asks the desktop whether animations are off and whether it's saving power. Anything that
+/// can't be reached counts as no.
+pub async fn detect() -> DesktopMotion {
+    DesktopMotion {
+        animations_off: animations_enabled().await == Some(false),
+        power_saver: power_profile().await.as_deref() == Some("power-saver"),
+    }
+}
+
+/// The `enable-animations` setting, read through the settings portal.
+async fn animations_enabled() -> Option<bool> {
+    let connection = zbus::Connection::session().await.ok()?;
+    let proxy = zbus::Proxy::new(
+        &connection,
+        "org.freedesktop.portal.Desktop",
+        "/org/freedesktop/portal/desktop",
+        "org.freedesktop.portal.Settings",
+    )
+    .await
+    .ok()?;
+
+    // `Read` rather than `ReadOne`, which older portals lack. It wraps the value in a
+    // second variant.
+    let value: OwnedValue = proxy
+        .call(
+            "Read",
+            &("org.gnome.desktop.interface", "enable-animations"),
+        )
+        .await
+        .ok()?;
+
+    match &*value {
+        Value::Bool(enabled) => Some(*enabled),
+        Value::Value(inner) => match **inner {
+            Value::Bool(enabled) => Some(enabled),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The active profile of power-profiles-daemon, e.g. `power-saver`.
+async fn power_profile() -> Option<String> {
+    let connection = zbus::Connection::system().await.ok()?;
+
+    // The daemon moved under UPower's name in 0.20, but older versions are still around.
+    for (destination, path) in [
+        (
+            "org.freedesktop.UPower.PowerProfiles",
+            "/org/freedesktop/UPower/PowerProfiles",
+        ),
+        ("net.hadess.PowerProfiles", "/net/hadess/PowerProfiles"),
+    ] {
+        let Ok(proxy) = zbus::Proxy::new(&connection, destination, path, destination).await else {
+            continue;
+        };
+        if let Ok(profile) = proxy.get_property::<String>("ActiveProfile").await {
+            return Some(profile);
+        }
+    }
+
+    None
+}
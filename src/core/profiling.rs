@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Startup instrumentation, compiled in with `--features profiling`.
+//!
+//! Without the feature every function here is a no-op so the calls can stay in place.
+
+#[cfg(feature = "profiling")]
+use std::sync::{Once, OnceLock};
+#[cfg(feature = "profiling")]
+use std::time::Instant;
+
+#[cfg(feature = "profiling")]
+static PROCESS_START: OnceLock<Instant> = OnceLock::new();
+#[cfg(feature = "profiling")]
+static FIRST_FRAME: Once = Once::new();
+
+/// Records the moment the process started. Call this first thing in `main`.
+pub fn start() {
+    #[cfg(feature = "profiling")]
+    PROCESS_START.get_or_init(Instant::now);
+}
+
+/// Runs `f` and reports how long it took under `label`.
+pub fn measure<T>(label: &str, f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "profiling")]
+    {
+        let started = Instant::now();
+        let result = f();
+        eprintln!("[profiling] {label}: {:?}", started.elapsed());
+        result
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    {
+        let _ = label;
+        f()
+    }
+}
+
+/// Reports the time from process start to the first call, i.e. the first frame being built.
+pub fn first_frame() {
+    #[cfg(feature = "profiling")]
+    FIRST_FRAME.call_once(|| {
+        if let Some(start) = PROCESS_START.get() {
+            eprintln!("[profiling] first frame: {:?}", start.elapsed());
+        }
+    });
+}
@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A layout for touchscreens, with bigger rows and buttons, and the gestures it answers
+//! to: swiping across the playback controls to skip and holding a track for its details.
+
+use std::time::{Duration, Instant};
+
+use cosmic::iced::touch::{self, Finger};
+use cosmic::iced::{Point, Vector};
+use serde::{Deserialize, Serialize};
+
+/// How far a finger has to travel sideways for a swipe.
+const SWIPE_DISTANCE: f32 = 80.0;
+/// Swipes slower than this are taken for dragging.
+const SWIPE_TIME: Duration = Duration::from_millis(800);
+/// How long a finger has to stay down for a long press.
+const LONG_PRESS: Duration = Duration::from_millis(500);
+/// How far a finger may wander during a long press, since fingers are never quite still.
+const TAP_SLOP: f32 = 12.0;
+
+/// What the user picked for the touch layout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TouchMode {
+    /// Switch to the touch layout once the screen is touched.
+    #[default]
+    Automatic,
+    Always,
+    Never,
+}
+
+impl TouchMode {
+    pub const ALL: [TouchMode; 3] = [TouchMode::Automatic, TouchMode::Always, TouchMode::Never];
+
+    /// Labels for a dropdown, in the same order as [`TouchMode::ALL`].
+    pub const NAMES: [&'static str; 3] = ["Automatic", "Always", "Never"];
+}
+
+/// The chosen mode combined with whether a touchscreen was used.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Touch {
+    pub mode: TouchMode,
+    /// The screen was touched since Jams started.
+    pub detected: bool,
+}
+
+impl Touch {
+    /// Whether rows and buttons should be sized for fingers.
+    pub fn is_active(&self) -> bool {
+        match self.mode {
+            TouchMode::Automatic => self.detected,
+            TouchMode::Always => true,
+            TouchMode::Never => false,
+        }
+    }
+}
+
+/// A gesture made with one finger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    /// The finger moved right to left.
+    SwipeLeft,
+    SwipeRight,
+    LongPress,
+}
+
+/// The finger being followed, from where and when it went down.
+#[derive(Debug, Clone, Copy)]
+struct Stroke {
+    finger: Finger,
+    start: Point,
+    started: Instant,
+}
+
+/// Turns touch events into gestures. Only single fingers count; a second finger, as in a
+/// pinch, cancels the gesture.
+#[derive(Debug, Default)]
+pub struct Gestures {
+    stroke: Option<Stroke>,
+    /// More than one finger is down.
+    crowded: bool,
+}
+
+impl Gestures {
+    /// Follows `event`. When a finger is lifted at the end of a gesture, returns the
+    /// gesture and when it started, so it can be matched with what was pressed then.
+    pub fn handle(&mut self, event: touch::Event) -> Option<(Gesture, Instant)> {
+        match event {
+            touch::Event::FingerPressed { id, position } => {
+                if self.stroke.is_some() {
+                    self.crowded = true;
+                } else {
+                    self.stroke = Some(Stroke {
+                        finger: id,
+                        start: position,
+                        started: Instant::now(),
+                    });
+                }
+                None
+            }
+            // Only where the finger ends up matters.
+            touch::Event::FingerMoved { .. } => None,
+            touch::Event::FingerLifted { id, position } => {
+                let stroke = self.stroke.filter(|stroke| stroke.finger == id)?;
+                self.stroke = None;
+                if std::mem::take(&mut self.crowded) {
+                    return None;
+                }
+
+                let moved: Vector = position - stroke.start;
+                let held = stroke.started.elapsed();
+
+                let gesture = if moved.x.abs() >= SWIPE_DISTANCE
+                    && moved.x.abs() > 2.0 * moved.y.abs()
+                    && held <= SWIPE_TIME
+                {
+                    if moved.x < 0.0 {
+                        Gesture::SwipeLeft
+                    } else {
+                        Gesture::SwipeRight
+                    }
+                } else if held >= LONG_PRESS && moved.x.hypot(moved.y) <= TAP_SLOP {
+                    Gesture::LongPress
+                } else {
+                    return None;
+                };
+                Some((gesture, stroke.started))
+            }
+            touch::Event::FingerLost { id, .. } => {
+                if self.stroke.is_some_and(|stroke| stroke.finger == id) {
+                    self.stroke = None;
+                    self.crowded = false;
+                }
+                None
+            }
+        }
+    }
+}
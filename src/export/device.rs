@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! What syncing to a portable player has in common, whatever the player: tracks filed by
+//! album artist and album, converted when the player can't play them, and M3U8 playlists
+//! pointing at them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use super::folder::{ExportProgress, ExportReport};
+use super::transcode::{self, ConversionJob};
+use crate::library::organize::sanitize;
+
+/// A track to put on a player.
+#[derive(Debug, Clone)]
+pub struct DeviceTrack {
+    pub source: PathBuf,
+    pub album_artist: String,
+    pub album: String,
+    /// 0 when the file has no disc number.
+    pub disc_number: u16,
+    /// 0 when the file has no track number.
+    pub track_number: u16,
+    pub artist: String,
+    pub title: String,
+    pub duration: Duration,
+}
+
+/// A playlist to write next to the tracks, in playing order.
+#[derive(Debug, Clone)]
+pub struct DevicePlaylist {
+    pub name: String,
+    /// Indices into the tracks being synced.
+    pub tracks: Vec<usize>,
+}
+
+/// `Album Artist/Album/1-02 Title.ext` inside `music_dir`.
+fn track_path(music_dir: &Path, track: &DeviceTrack, extension: &str) -> PathBuf {
+    let number = match (track.disc_number, track.track_number) {
+        (0, 0) => String::new(),
+        (0, number) => format!("{:02} ", number),
+        (disc, number) => format!("{}-{:02} ", disc, number),
+    };
+
+    music_dir
+        .join(sanitize(&track.album_artist))
+        .join(sanitize(&track.album))
+        .join(format!(
+            "{}.{}",
+            sanitize(&format!("{}{}", number, track.title)),
+            extension
+        ))
+}
+
+/// Lowercase extension of `path`, empty if it has none.
+fn lowercase_extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default()
+}
+
+/// Puts `tracks` into `music_dir` on the player mounted at `root`, converting them with
+/// `job` when `convert` says so for their extension. Tracks already on the player are left
+/// alone, so syncing again only copies what's new. Returns where each track ended up,
+/// relative to `root`.
+pub(super) fn copy_tracks(
+    tracks: &[DeviceTrack],
+    root: &Path,
+    music_dir: &Path,
+    convert: impl Fn(&str) -> bool,
+    job: &ConversionJob,
+    progress: &ExportProgress,
+    report: &mut ExportReport,
+) -> Vec<Option<PathBuf>> {
+    let mut copied: Vec<Option<PathBuf>> = vec![None; tracks.len()];
+    progress.total.store(tracks.len(), Ordering::Relaxed);
+
+    for (index, track) in tracks.iter().enumerate() {
+        if !progress.control.checkpoint() {
+            report.cancelled = true;
+            break;
+        }
+
+        let extension = lowercase_extension(&track.source);
+        let converted = convert(&extension);
+
+        let relative = if converted {
+            track_path(music_dir, track, job.preset.extension())
+        } else {
+            track_path(music_dir, track, &extension)
+        };
+        let destination = root.join(&relative);
+
+        let result = if destination.exists() {
+            Ok(())
+        } else {
+            destination
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .map_err(|err| format!("{}: {}", destination.display(), err))
+                .and_then(|()| {
+                    if converted {
+                        convert_onto(&track.source, &destination, job)
+                    } else {
+                        fs::copy(&track.source, &destination)
+                            .map(|_| ())
+                            .map_err(|err| format!("{}: {}", track.source.display(), err))
+                    }
+                })
+        };
+
+        match result {
+            Ok(()) => {
+                report.exported += 1;
+                copied[index] = Some(relative);
+            }
+            Err(err) => report.failed.push(err),
+        }
+
+        progress.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    copied
+}
+
+/// Converts `source` next to this computer first and copies the result over, since players
+/// mounted through MTP can't be written to at random, which tagging needs.
+fn convert_onto(source: &Path, destination: &Path, job: &ConversionJob) -> Result<(), String> {
+    let file_name = destination.file_name().unwrap_or_default();
+    let staged = std::env::temp_dir().join(format!("jams-{}", file_name.to_string_lossy()));
+
+    let result = transcode::transcode(source, &staged, job).and_then(|()| {
+        fs::copy(&staged, destination)
+            .map(|_| ())
+            .map_err(|err| format!("{}: {}", destination.display(), err))
+    });
+
+    let _ = fs::remove_file(&staged);
+    result
+}
+
+/// Writes `playlist` as an M3U8 in `playlist_dir` on the player mounted at `root`, with
+/// paths relative to the playlist, and returns where it was written.
+pub(super) fn write_playlist(
+    root: &Path,
+    playlist_dir: &Path,
+    playlist: &DevicePlaylist,
+    tracks: &[DeviceTrack],
+    copied: &[Option<PathBuf>],
+) -> Result<PathBuf, String> {
+    let dir = root.join(playlist_dir);
+    let path = dir.join(format!("{}.m3u8", sanitize(&playlist.name)));
+
+    let mut contents = String::from("#EXTM3U\n");
+    for &index in &playlist.tracks {
+        let (Some(track), Some(Some(relative))) = (tracks.get(index), copied.get(index)) else {
+            continue;
+        };
+
+        // Players want forward slashes, which is all a Linux path has.
+        contents.push_str(&format!(
+            "#EXTINF:{},{} - {}\n{}\n",
+            track.duration.as_secs(),
+            track.artist,
+            track.title,
+            relative_to(playlist_dir, relative).display()
+        ));
+    }
+
+    fs::create_dir_all(&dir)
+        .and_then(|()| fs::write(&path, contents))
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(path)
+}
+
+/// `path` as seen from `dir`, both relative to the same root.
+fn relative_to(dir: &Path, path: &Path) -> PathBuf {
+    let mut relative = PathBuf::new();
+    let mut dir = dir.to_path_buf();
+    while !path.starts_with(&dir) {
+        relative.push("..");
+        if !dir.pop() {
+            break;
+        }
+    }
+    relative.join(path.strip_prefix(&dir).unwrap_or(path))
+}
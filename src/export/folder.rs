@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Copies a list of tracks into a folder as a numbered, self-contained playlist, the way
+//! car stereos and other simple players like them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use super::transcode::{self, ConversionJob};
+use crate::jobs::JobControl;
+use crate::library::organize::sanitize;
+
+/// Name of the playlist written next to the exported files.
+pub const PLAYLIST_NAME: &str = "playlist.m3u";
+
+/// A track to export, in playlist order.
+#[derive(Debug, Clone)]
+pub struct ExportEntry {
+    pub source: PathBuf,
+    pub artist: String,
+    pub title: String,
+    pub duration: Duration,
+}
+
+/// Progress of [`export_to_folder`], readable from another thread while it runs.
+#[derive(Debug, Default)]
+pub struct ExportProgress {
+    pub total: AtomicUsize,
+    pub done: AtomicUsize,
+    pub control: JobControl,
+}
+
+/// What [`export_to_folder`] did.
+#[derive(Debug, Clone, Default)]
+pub struct ExportReport {
+    pub exported: usize,
+    /// One message per file that could not be exported.
+    pub failed: Vec<String>,
+    /// Where the playlist was written, if it was.
+    pub playlist: Option<PathBuf>,
+    /// The export was stopped before every file was copied.
+    pub cancelled: bool,
+}
+
+/// `01 - Artist - Title.ext`, padded so the files sort in playlist order.
+fn file_name(number: usize, width: usize, entry: &ExportEntry, extension: &str) -> String {
+    let name = sanitize(&format!(
+        "{:0width$} - {} - {}",
+        number,
+        entry.artist,
+        entry.title,
+        width = width
+    ));
+
+    if extension.is_empty() {
+        name
+    } else {
+        format!("{}.{}", name, extension)
+    }
+}
+
+/// Copies `entries` into `target`, or converts them when `job` is given, numbering them in
+/// order, then writes an M3U of everything that made it.
+pub fn export_to_folder(
+    entries: Vec<ExportEntry>,
+    target: &Path,
+    job: Option<&ConversionJob>,
+    progress: &ExportProgress,
+) -> ExportReport {
+    let mut report = ExportReport::default();
+    progress.total.store(entries.len(), Ordering::Relaxed);
+
+    if let Err(err) = fs::create_dir_all(target) {
+        report.failed.push(format!("{}: {}", target.display(), err));
+        return report;
+    }
+
+    let width = entries.len().to_string().len().max(2);
+    let mut playlist = String::from("#EXTM3U\n");
+
+    for (index, entry) in entries.iter().enumerate() {
+        // The playlist is still written for the files copied so far.
+        if !progress.control.checkpoint() {
+            report.cancelled = true;
+            break;
+        }
+
+        let extension = match job {
+            Some(job) => job.preset.extension(),
+            None => entry
+                .source
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .unwrap_or_default(),
+        };
+        let name = file_name(index + 1, width, entry, extension);
+        let destination = target.join(&name);
+
+        let result = match job {
+            Some(job) => transcode::transcode(&entry.source, &destination, job),
+            None => fs::copy(&entry.source, &destination)
+                .map(|_| ())
+                .map_err(|err| format!("{}: {}", entry.source.display(), err)),
+        };
+
+        match result {
+            Ok(()) => {
+                report.exported += 1;
+                playlist.push_str(&format!(
+                    "#EXTINF:{},{} - {}\n{}\n",
+                    entry.duration.as_secs(),
+                    entry.artist,
+                    entry.title,
+                    name
+                ));
+            }
+            Err(err) => report.failed.push(err),
+        }
+
+        progress.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let playlist_path = target.join(PLAYLIST_NAME);
+    match fs::write(&playlist_path, playlist) {
+        Ok(()) => report.playlist = Some(playlist_path),
+        Err(err) => report
+            .failed
+            .push(format!("{}: {}", playlist_path.display(), err)),
+    }
+
+    report
+}
@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Copying tracks out of the library for other devices.
+
+pub mod device;
+pub mod folder;
+pub mod mtp;
+pub mod rockbox;
+pub mod transcode;
+
+pub use device::{DevicePlaylist, DeviceTrack};
+pub use folder::{ExportEntry, ExportProgress, ExportReport};
+pub use transcode::{ConversionJob, TranscodePreset};
@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Syncing to Android phones over MTP, through the mounts GVfs makes for them under
+//! `$XDG_RUNTIME_DIR/gvfs`. Lossless files are converted to Opus on the way, and playlists
+//! are written next to the music so Android players resolve their relative paths.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::device::{self, DevicePlaylist, DeviceTrack};
+use super::folder::{ExportProgress, ExportReport};
+use super::transcode::{ConversionJob, TranscodePreset};
+
+/// Prefix of the folders GVfs mounts MTP devices as.
+const MTP_PREFIX: &str = "mtp:host=";
+/// Where playlists are written, relative to the root of the phone's storage. Android
+/// players scan this folder anyway.
+const PLAYLIST_DIR: &str = "Music";
+/// Where tracks are copied to, inside [`PLAYLIST_DIR`] so playlists don't need `..`.
+const MUSIC_DIR: &str = "Music/Jams";
+
+/// Lossless formats, which take more space than a phone should spend on them.
+const LOSSLESS: [&str; 7] = ["flac", "wav", "aiff", "ape", "wv", "dsf", "dff"];
+
+/// Storage of a phone mounted through GVfs, such as its internal storage or an SD card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtpStorage {
+    /// The phone's name as it reports it over USB, with its storage.
+    pub name: String,
+    pub root: PathBuf,
+}
+
+/// Folder GVfs mounts devices in.
+fn gvfs_dir() -> PathBuf {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/run/user").join(uid()));
+    runtime_dir.join("gvfs")
+}
+
+/// The user ID, from `/proc`, for when `XDG_RUNTIME_DIR` isn't set.
+fn uid() -> String {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("Uid:"))
+                .and_then(|uids| uids.split_whitespace().next().map(str::to_string))
+        })
+        .unwrap_or_default()
+}
+
+/// Every storage of every phone GVfs has mounted, by name. A phone only shows up once it's
+/// unlocked and set to file transfer.
+pub fn storages() -> Vec<MtpStorage> {
+    let Ok(mounts) = fs::read_dir(gvfs_dir()) else {
+        return Vec::new();
+    };
+
+    let mut storages = Vec::new();
+    for mount in mounts.filter_map(|entry| entry.ok()) {
+        let file_name = mount.file_name();
+        let Some(host) = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix(MTP_PREFIX))
+        else {
+            continue;
+        };
+        // Hosts look like `SAMSUNG_Android_R58N12345`.
+        let phone = host.replace('_', " ");
+
+        let Ok(entries) = fs::read_dir(mount.path()) else {
+            continue;
+        };
+        for storage in entries.filter_map(|entry| entry.ok()) {
+            if storage.path().is_dir() {
+                storages.push(MtpStorage {
+                    name: format!("{} — {}", phone, storage.file_name().to_string_lossy()),
+                    root: storage.path(),
+                });
+            }
+        }
+    }
+
+    storages.sort_by(|a, b| a.name.cmp(&b.name));
+    storages
+}
+
+/// Copies `tracks` onto the phone storage at `root`, converting lossless files with `job`,
+/// or to Opus if `job` converts to FLAC, then writes `playlists`. Tracks already on the
+/// phone are left alone, so syncing again only copies what's new.
+pub fn sync_to_phone(
+    tracks: Vec<DeviceTrack>,
+    playlists: Vec<DevicePlaylist>,
+    root: &Path,
+    job: &ConversionJob,
+    progress: &ExportProgress,
+) -> ExportReport {
+    let mut report = ExportReport::default();
+
+    let mut job = job.clone();
+    if job.preset == TranscodePreset::Flac {
+        job.preset = TranscodePreset::default();
+    }
+
+    let copied = device::copy_tracks(
+        &tracks,
+        root,
+        Path::new(MUSIC_DIR),
+        |extension| LOSSLESS.contains(&extension),
+        &job,
+        progress,
+        &mut report,
+    );
+
+    for playlist in playlists {
+        match device::write_playlist(root, Path::new(PLAYLIST_DIR), &playlist, &tracks, &copied) {
+            Ok(path) => report.playlist = Some(path),
+            Err(err) => report.failed.push(err),
+        }
+    }
+
+    report
+}
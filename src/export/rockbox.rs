@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Copies tracks onto a player running Rockbox, filed by album artist and album, along with
+//! M3U8 playlists Rockbox can open. Formats Rockbox can't play are converted on the way.
+//!
+//! Rockbox builds its database on the device from the tags of the files, so that's left to
+//! the player: once the files are copied, it picks them up with "Update Now" or on its own
+//! with "Auto Update" turned on.
+
+use std::path::Path;
+
+use super::device::{self, DevicePlaylist, DeviceTrack};
+use super::folder::{ExportProgress, ExportReport};
+use super::transcode::ConversionJob;
+
+/// Folder Rockbox keeps its firmware and settings in, found at the root of the player.
+const ROCKBOX_DIR: &str = ".rockbox";
+/// Where tracks are copied to, relative to the root of the player.
+const MUSIC_DIR: &str = "Music";
+/// Where playlists are written to, relative to the root of the player.
+const PLAYLIST_DIR: &str = "Playlists";
+
+/// Extensions of the formats Rockbox decodes on every target.
+const PLAYABLE: [&str; 12] = [
+    "mp3", "mp2", "flac", "ogg", "oga", "opus", "m4a", "wav", "wv", "ape", "mpc", "aiff",
+];
+
+/// Whether `root` looks like the root of a player with Rockbox installed.
+pub fn is_rockbox(root: &Path) -> bool {
+    root.join(ROCKBOX_DIR).is_dir()
+}
+
+/// Copies `tracks` onto the player mounted at `root`, converting them with `job` when
+/// Rockbox can't play them as they are, then writes `playlists`. Tracks already on the
+/// player are left alone, so syncing again only copies what's new.
+pub fn export_to_rockbox(
+    tracks: Vec<DeviceTrack>,
+    playlists: Vec<DevicePlaylist>,
+    root: &Path,
+    job: &ConversionJob,
+    progress: &ExportProgress,
+) -> ExportReport {
+    let mut report = ExportReport::default();
+
+    if !is_rockbox(root) {
+        report.failed.push(format!(
+            "{}: No {} folder, Rockbox doesn't seem to be installed",
+            root.display(),
+            ROCKBOX_DIR
+        ));
+        return report;
+    }
+
+    let copied = device::copy_tracks(
+        &tracks,
+        root,
+        Path::new(MUSIC_DIR),
+        |extension| !PLAYABLE.contains(&extension),
+        job,
+        progress,
+        &mut report,
+    );
+
+    for playlist in playlists {
+        match device::write_playlist(root, Path::new(PLAYLIST_DIR), &playlist, &tracks, &copied) {
+            Ok(path) => report.playlist = Some(path),
+            Err(err) => report.failed.push(err),
+        }
+    }
+
+    report
+}
@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use gstreamer as gst;
+use gstreamer::glib;
+use gstreamer::prelude::*;
+use lofty::config::WriteOptions;
+use lofty::prelude::TaggedFileExt;
+use lofty::tag::{ItemKey, Tag, TagExt};
+
+use crate::fl;
+
+/// Tag keys carrying ReplayGain information.
+const REPLAYGAIN_KEYS: [ItemKey; 4] = [
+    ItemKey::ReplayGainTrackGain,
+    ItemKey::ReplayGainTrackPeak,
+    ItemKey::ReplayGainAlbumGain,
+    ItemKey::ReplayGainAlbumPeak,
+];
+
+/// Output formats offered when converting tracks for portable devices.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TranscodePreset {
+    Opus96,
+    #[default]
+    Opus128,
+    Opus160,
+    Mp3V0,
+    Mp3Cbr320,
+    Flac,
+}
+
+impl TranscodePreset {
+    /// Every preset, in the order shown to the user.
+    pub const ALL: [TranscodePreset; 6] = [
+        TranscodePreset::Opus96,
+        TranscodePreset::Opus128,
+        TranscodePreset::Opus160,
+        TranscodePreset::Mp3V0,
+        TranscodePreset::Mp3Cbr320,
+        TranscodePreset::Flac,
+    ];
+
+    /// Display names matching [`TranscodePreset::ALL`].
+    pub fn names() -> &'static [String] {
+        static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+        NAMES.get_or_init(|| {
+            vec![
+                fl!("conversion-preset-opus", bitrate = 96),
+                fl!("conversion-preset-opus", bitrate = 128),
+                fl!("conversion-preset-opus", bitrate = 160),
+                fl!("conversion-preset-mp3-v0"),
+                fl!("conversion-preset-mp3", bitrate = 320),
+                fl!("conversion-preset-flac"),
+            ]
+        })
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscodePreset::Opus96 | TranscodePreset::Opus128 | TranscodePreset::Opus160 => "opus",
+            TranscodePreset::Mp3V0 | TranscodePreset::Mp3Cbr320 => "mp3",
+            TranscodePreset::Flac => "flac",
+        }
+    }
+
+    /// Builds the encoder, followed by any muxer it needs, ready to be linked in order.
+    fn encoder_elements(&self) -> Result<Vec<gst::Element>, glib::BoolError> {
+        let opus = |bitrate: i32| -> Result<Vec<gst::Element>, glib::BoolError> {
+            Ok(vec![
+                gst::ElementFactory::make("opusenc")
+                    .property("bitrate", bitrate)
+                    .build()?,
+                gst::ElementFactory::make("oggmux").build()?,
+            ])
+        };
+
+        match self {
+            TranscodePreset::Opus96 => opus(96_000),
+            TranscodePreset::Opus128 => opus(128_000),
+            TranscodePreset::Opus160 => opus(160_000),
+            TranscodePreset::Mp3V0 => Ok(vec![
+                gst::ElementFactory::make("lamemp3enc")
+                    .property_from_str("target", "quality")
+                    .property("quality", 0f32)
+                    .build()?,
+                // Writes the VBR header players need for seeking and gapless playback.
+                gst::ElementFactory::make("xingmux").build()?,
+            ]),
+            TranscodePreset::Mp3Cbr320 => Ok(vec![gst::ElementFactory::make("lamemp3enc")
+                .property_from_str("target", "bitrate")
+                .property("bitrate", 320i32)
+                .property("cbr", true)
+                .build()?]),
+            TranscodePreset::Flac => Ok(vec![gst::ElementFactory::make("flacenc").build()?]),
+        }
+    }
+}
+
+/// Settings for converting a batch of tracks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionJob {
+    pub preset: TranscodePreset,
+    /// Copy ReplayGain tags into the converted files.
+    pub propagate_replaygain: bool,
+    /// Apply ReplayGain to the audio itself so every file comes out at the same loudness.
+    pub normalize_loudness: bool,
+}
+
+impl Default for ConversionJob {
+    fn default() -> Self {
+        ConversionJob {
+            preset: TranscodePreset::default(),
+            propagate_replaygain: true,
+            normalize_loudness: false,
+        }
+    }
+}
+
+/// Converts `source` into `destination` according to `job`, then copies the tags over.
+/// Blocks until the conversion is finished, so run it off the UI thread.
+pub fn transcode(source: &Path, destination: &Path, job: &ConversionJob) -> Result<(), String> {
+    run_pipeline(source, destination, job)
+        .map_err(|err| format!("Failed to convert {}: {}", source.display(), err))?;
+
+    copy_tags(source, destination, job)
+        .map_err(|err| format!("Failed to tag {}: {}", destination.display(), err))
+}
+
+fn run_pipeline(source: &Path, destination: &Path, job: &ConversionJob) -> Result<(), String> {
+    gst::init().map_err(|err| err.to_string())?;
+
+    let make = |name: &str| gst::ElementFactory::make(name).build().map_err(|err| err.to_string());
+
+    let filesrc = gst::ElementFactory::make("filesrc")
+        .property("location", source.to_string_lossy().to_string())
+        .build()
+        .map_err(|err| err.to_string())?;
+    let decodebin = make("decodebin")?;
+
+    // Everything after the decoder, linked in order.
+    let mut chain = vec![make("audioconvert")?];
+    if job.normalize_loudness {
+        chain.push(make("rgvolume")?);
+        chain.push(make("rglimiter")?);
+        chain.push(make("audioconvert")?);
+    }
+    chain.push(make("audioresample")?);
+    chain.extend(job.preset.encoder_elements().map_err(|err| err.to_string())?);
+    chain.push(
+        gst::ElementFactory::make("filesink")
+            .property("location", destination.to_string_lossy().to_string())
+            .build()
+            .map_err(|err| err.to_string())?,
+    );
+
+    let pipeline = gst::Pipeline::new();
+    pipeline
+        .add_many([&filesrc, &decodebin])
+        .map_err(|err| err.to_string())?;
+    pipeline.add_many(&chain).map_err(|err| err.to_string())?;
+    filesrc.link(&decodebin).map_err(|err| err.to_string())?;
+    gst::Element::link_many(&chain).map_err(|err| err.to_string())?;
+
+    // decodebin only knows its output pads once it has seen the stream.
+    let convert = chain[0].clone();
+    decodebin.connect_pad_added(move |_, pad| {
+        let Some(sink_pad) = convert.static_pad("sink") else {
+            return;
+        };
+        let is_audio = pad
+            .current_caps()
+            .and_then(|caps| caps.structure(0).map(|s| s.name().starts_with("audio/")))
+            .unwrap_or(false);
+
+        if is_audio && !sink_pad.is_linked() {
+            if let Err(err) = pad.link(&sink_pad) {
+                eprintln!("Failed to link decoder: {:?}", err);
+            }
+        }
+    });
+
+    pipeline
+        .set_state(gst::State::Playing)
+        .map_err(|err| err.to_string())?;
+
+    let bus = pipeline.bus().ok_or("Pipeline has no bus")?;
+    let mut result = Ok(());
+    for message in bus.iter_timed(gst::ClockTime::NONE) {
+        match message.view() {
+            gst::MessageView::Eos(..) => break,
+            gst::MessageView::Error(err) => {
+                result = Err(err.error().to_string());
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let _ = pipeline.set_state(gst::State::Null);
+    result
+}
+
+fn copy_tags(source: &Path, destination: &Path, job: &ConversionJob) -> Result<(), String> {
+    let source_file = lofty::read_from_path(source).map_err(|err| err.to_string())?;
+    let Some(source_tag) = source_file.primary_tag() else {
+        return Ok(());
+    };
+
+    let destination_file = lofty::read_from_path(destination).map_err(|err| err.to_string())?;
+    let mut tag = Tag::new(destination_file.primary_tag_type());
+
+    // Normalized audio already has the gain applied, so the tags would apply it twice.
+    let keep_replaygain = job.propagate_replaygain && !job.normalize_loudness;
+
+    for item in source_tag.items() {
+        if keep_replaygain || !REPLAYGAIN_KEYS.contains(item.key()) {
+            tag.insert(item.clone());
+        }
+    }
+
+    for picture in source_tag.pictures() {
+        tag.push_picture(picture.clone());
+    }
+
+    tag.save_to_path(destination, WriteOptions::default())
+        .map_err(|err| err.to_string())
+}
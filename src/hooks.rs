@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A command run and a URL posted to when something happens in the player, so home
+//! automation or a stream overlay can follow along without an integration of its own.
+
+use std::process::Command;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::library::MusicFile;
+
+const POST_TIMEOUT: Duration = Duration::from_secs(10);
+const USER_AGENT: &str = "Jams (https://github.com/benfuddled/Jams)";
+
+/// What the hooks are told about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    TrackStarted,
+    Paused,
+    Resumed,
+    Stopped,
+    /// The track was marked or unmarked as a favorite.
+    FavoriteChanged,
+}
+
+impl HookEvent {
+    /// How the event is named in the payload and in `JAMS_EVENT`.
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::TrackStarted => "track-start",
+            HookEvent::Paused => "pause",
+            HookEvent::Resumed => "resume",
+            HookEvent::Stopped => "stop",
+            HookEvent::FavoriteChanged => "favorite",
+        }
+    }
+}
+
+/// Where events go. Either part can be left empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hooks {
+    /// Run with `sh -c`.
+    pub command: String,
+    /// Receives the payload as a JSON `POST`.
+    pub url: String,
+}
+
+impl Hooks {
+    pub fn is_set(&self) -> bool {
+        !self.command.trim().is_empty() || !self.url.trim().is_empty()
+    }
+}
+
+/// What's sent along with an event: the event and the track it concerns, if any.
+pub fn payload(
+    event: HookEvent,
+    file: Option<&MusicFile>,
+    position: Duration,
+    favorite: bool,
+) -> serde_json::Value {
+    let track = file.map(|file| {
+        json!({
+            "title": file.track_title,
+            "artist": file.artist,
+            "album": file.album,
+            "album_artist": file.album_artist,
+            "duration_ms": file.duration.as_millis() as u64,
+            "path": file.saved_path.display().to_string(),
+            "favorite": favorite,
+        })
+    });
+
+    json!({
+        "event": event.name(),
+        "position_ms": position.as_millis() as u64,
+        "track": track,
+    })
+}
+
+/// Runs the command and posts to the URL. Blocks until both are done, so run it off the
+/// UI thread.
+///
+/// The command gets the payload in `JAMS_PAYLOAD`, and the event and the track's title,
+/// artist, album and path in variables of their own so simple scripts needn't parse JSON.
+fn run(hooks: &Hooks, event: HookEvent, payload: &serde_json::Value) -> Result<(), String> {
+    let mut errors = Vec::new();
+
+    if !hooks.command.trim().is_empty() {
+        let field = |name: &str| {
+            payload["track"][name]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(hooks.command.trim())
+            .env("JAMS_EVENT", event.name())
+            .env("JAMS_TITLE", field("title"))
+            .env("JAMS_ARTIST", field("artist"))
+            .env("JAMS_ALBUM", field("album"))
+            .env("JAMS_PATH", field("path"))
+            .env("JAMS_PAYLOAD", payload.to_string())
+            .status();
+
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => errors.push(format!("Hook command exited with {}", status)),
+            Err(err) => errors.push(format!("Failed to run hook command: {}", err)),
+        }
+    }
+
+    if !hooks.url.trim().is_empty() {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(POST_TIMEOUT)
+            .user_agent(USER_AGENT)
+            .build();
+
+        if let Err(err) = agent.post(hooks.url.trim()).send_json(payload.clone()) {
+            errors.push(format!("Failed to post to hook URL: {}", err));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Runs the hooks on a thread of their own, so a slow command or server holds nothing up.
+/// Failures are only logged.
+pub fn spawn(hooks: Hooks, event: HookEvent, payload: serde_json::Value) {
+    std::thread::spawn(move || {
+        if let Err(err) = run(&hooks, event, &payload) {
+            eprintln!("Hook for {} failed: {}", event.name(), err);
+        }
+    });
+}
@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Pausing and cancelling work that runs on another thread, such as scans and exports.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// How often a paused job wakes up to see whether it may go on.
+const PAUSE_POLL: Duration = Duration::from_millis(100);
+
+/// The kinds of background job, one of each at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Scan,
+    EmbedArtwork,
+    Export,
+    Verify,
+    Loudness,
+    Tempo,
+}
+
+/// Set from the UI, checked by the job between items.
+#[derive(Debug, Default)]
+pub struct JobControl {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl JobControl {
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Asks the job to stop at the next item. What it finished so far is kept.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Called by the job before each item. Waits while the job is paused and returns
+    /// `false` once it should stop.
+    pub fn checkpoint(&self) -> bool {
+        while self.is_paused() && !self.is_cancelled() {
+            thread::sleep(PAUSE_POLL);
+        }
+        !self.is_cancelled()
+    }
+}
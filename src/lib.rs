@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Everything but the entry point, so the tests and benchmarks can reach the library and
+//! player code.
+
+/// The `app` module is used by convention to indicate the main component of our application.
+pub mod app;
+mod bluetooth;
+mod config;
+pub mod core;
+mod export;
+mod hooks;
+mod icon_cache;
+mod jobs;
+pub mod library;
+mod marquee;
+mod network;
+mod now_playing;
+pub mod player;
+mod scrobble;
+mod share;
+mod track_columns;
+mod virtual_list;
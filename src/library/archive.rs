@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Albums kept in zip archives, the way stores like Bandcamp hand them out. Tracks are
+//! extracted into a cache as they're scanned, since the tag reader needs to seek, which
+//! entries compressed in a zip can't, and are played from there.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use super::artwork;
+use super::model::MusicFile;
+use super::scan::{self, TitleFallback};
+
+/// Extensions of the audio files looked for inside archives.
+const AUDIO_EXTENSIONS: [&str; 9] = [
+    "flac", "mp3", "ogg", "oga", "opus", "m4a", "wav", "aiff", "wv",
+];
+
+/// A file inside a zip archive.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ArchiveEntry {
+    pub archive: PathBuf,
+    /// The entry's name inside the archive.
+    pub name: String,
+}
+
+/// Whether the file at `path` is an archive albums can be read from.
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+}
+
+/// Folder the tracks of `archive` are extracted to, next to the art cache.
+fn extract_dir(archive: &Path) -> PathBuf {
+    artwork::cache_dir()
+        .parent()
+        .map(|data_dir| data_dir.join("archives"))
+        .unwrap_or_else(|| PathBuf::from("archives"))
+        .join(artwork::hash_key(&archive.display().to_string()))
+}
+
+/// Reads the tags of every audio file in the zip archive at `path`, numbering them from
+/// `first_id`, extracting the ones not in the cache yet. Returns nothing if the archive
+/// can't be read. Tracks without a title are named as `fallback` says.
+pub fn read_archive(path: &Path, first_id: usize, fallback: TitleFallback) -> Vec<MusicFile> {
+    let mut zip = match File::open(path)
+        .map_err(|err| err.to_string())
+        .and_then(|file| zip::ZipArchive::new(file).map_err(|err| err.to_string()))
+    {
+        Ok(zip) => zip,
+        Err(err) => {
+            eprintln!("Failed to open archive {}: {}", path.display(), err);
+            return Vec::new();
+        }
+    };
+
+    let mut files = Vec::new();
+    for index in 0..zip.len() {
+        let mut entry = match zip.by_index(index) {
+            Ok(entry) => entry,
+            Err(err) => {
+                eprintln!("Failed to read {} in {}: {}", index, path.display(), err);
+                continue;
+            }
+        };
+
+        // Names that would escape the extraction folder are skipped.
+        let Some(relative) = entry.enclosed_name() else {
+            continue;
+        };
+        let is_audio = relative
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| AUDIO_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+        if !entry.is_file() || !is_audio {
+            continue;
+        }
+
+        let extracted = extract_dir(path).join(&relative);
+        if !extracted.is_file() {
+            if let Err(err) = write_entry(&mut entry, &extracted) {
+                eprintln!(
+                    "Failed to extract {} in {}: {}",
+                    entry.name(),
+                    path.display(),
+                    err
+                );
+                continue;
+            }
+        }
+
+        let tagged_file = match lofty::read_from_path(&extracted) {
+            Ok(tagged_file) => tagged_file,
+            Err(err) => {
+                eprintln!(
+                    "Error reading {} in {}: {}",
+                    entry.name(),
+                    path.display(),
+                    err
+                );
+                continue;
+            }
+        };
+
+        let id = first_id + files.len();
+        if let Some(mut music_file) = scan::from_tagged_file(&tagged_file, &extracted, id, fallback)
+        {
+            music_file.size = entry.size();
+            // The extracted copy goes away with the cache, so it doesn't identify the track.
+            music_file.inode = None;
+            music_file.archive = Some(ArchiveEntry {
+                archive: path.to_path_buf(),
+                name: entry.name().to_string(),
+            });
+            files.push(music_file);
+        }
+    }
+
+    files
+}
+
+/// Extracts `entry` to `destination` unless it's there already, so it can be played.
+pub fn extract(entry: &ArchiveEntry, destination: &Path) -> Result<(), String> {
+    if destination.is_file() {
+        return Ok(());
+    }
+
+    let describe = |err: String| format!("{} in {}: {}", entry.name, entry.archive.display(), err);
+
+    let file = File::open(&entry.archive).map_err(|err| describe(err.to_string()))?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|err| describe(err.to_string()))?;
+    let mut source = zip
+        .by_name(&entry.name)
+        .map_err(|err| describe(err.to_string()))?;
+
+    write_entry(&mut source, destination).map_err(|err| describe(err.to_string()))
+}
+
+/// Streams `source` to `destination`, creating the folders it's in.
+fn write_entry(source: &mut impl Read, destination: &Path) -> io::Result<()> {
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Extract next to the destination first, so a cut-off extraction is never played.
+    let partial = destination.with_extension("partial");
+    let result = File::create(&partial)
+        .and_then(|mut target| io::copy(source, &mut target))
+        .and_then(|_| fs::rename(&partial, destination));
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+
+    result
+}
@@ -0,0 +1,523 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The art cache: covers pulled out of scanned files plus artwork picked by the user.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageReader};
+use lofty::config::WriteOptions;
+use lofty::picture::{Picture, PictureType};
+use lofty::prelude::TaggedFileExt;
+use lofty::tag::{Tag, TagExt};
+
+use super::archive::{self, ArchiveEntry};
+use super::journal::{self, Field};
+use crate::core::flatpak;
+use crate::jobs::JobControl;
+
+/// File names checked, case-insensitively, for cover art next to the audio files.
+const EXTERNAL_COVER_NAMES: [&str; 6] = [
+    "cover.jpg",
+    "cover.png",
+    "folder.jpg",
+    "folder.png",
+    "front.jpg",
+    "front.png",
+];
+
+lazy_static::lazy_static! {
+    static ref CACHE_FOLDER: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// Keeps the art cache, and the caches kept next to it, in `folder` from now on, or in
+/// the default place again with `None`. What was cached before stays where it was.
+pub fn set_cache_folder(folder: Option<PathBuf>) {
+    if let Ok(mut cache_folder) = CACHE_FOLDER.write() {
+        *cache_folder = folder;
+    }
+}
+
+/// Folder holding the caches, `~/.local/share/jams` unless XDG or [`set_cache_folder`]
+/// say otherwise.
+pub fn cache_folder() -> PathBuf {
+    if let Some(folder) = CACHE_FOLDER.read().ok().and_then(|folder| folder.clone()) {
+        return folder;
+    }
+
+    flatpak::data_dir()
+}
+
+/// Folder holding cached covers, `covers` in [`cache_folder`].
+pub fn cache_dir() -> PathBuf {
+    cache_folder().join("covers")
+}
+
+/// Where the cover pulled out of an album's files is cached.
+pub fn cached_cover(album_key: &str) -> PathBuf {
+    cache_dir().join(hash_key(album_key))
+}
+
+/// The cover shown for the album `album_key`: artwork the user picked, or else the cached
+/// one, if it's on disk.
+pub fn album_cover(album_key: &str) -> Option<PathBuf> {
+    let path = custom_cover(album_key).unwrap_or_else(|| cached_cover(album_key));
+    path.is_file().then_some(path)
+}
+
+/// Where the cover of an album scaled to `size` pixels is cached, see [`scale_covers`].
+fn scaled_cover(album_key: &str, size: u32) -> PathBuf {
+    cache_dir()
+        .join("scaled")
+        .join(format!("{}-{}.png", hash_key(album_key), size))
+}
+
+/// Where the picture picked to represent an album is recorded, as the picture type and
+/// which picture of that type it is.
+fn choice_path(album_key: &str) -> PathBuf {
+    cache_dir().join(format!("{}.picture", hash_key(album_key)))
+}
+
+/// Folder holding artwork the user assigned by hand.
+fn custom_dir() -> PathBuf {
+    cache_dir().join("custom")
+}
+
+/// Identifies an album in the art cache.
+pub fn album_key(album_artist: &str, album: &str) -> String {
+    format!("{}\u{1f}{}", album_artist, album)
+}
+
+/// Identifies a single track in the art cache.
+pub fn track_key(path: &Path) -> String {
+    path.display().to_string()
+}
+
+/// FNV-1a, so cache file names stay the same across runs and Rust versions.
+pub fn hash_key(key: &str) -> String {
+    hash_bytes(key.as_bytes())
+}
+
+/// [`hash_key`] for anything that isn't text, such as a picture.
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// The custom artwork assigned to `key`, if any.
+pub fn custom_cover(key: &str) -> Option<PathBuf> {
+    let prefix = format!("{}-", hash_key(key));
+
+    // Files are named `<hash>-<timestamp>`, so the newest sorts last.
+    fs::read_dir(custom_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix))
+        })
+        .max()
+}
+
+/// Stores `image` as the custom artwork for `key` and returns where it was written.
+///
+/// Every assignment gets a new file name so image caches keyed by path pick up the change.
+pub fn set_custom_cover(key: &str, image: &[u8]) -> Result<PathBuf, String> {
+    if !infer::is_image(image) {
+        return Err(String::from("The chosen file is not an image."));
+    }
+
+    let dir = custom_dir();
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+
+    let previous = custom_cover(key);
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = dir.join(format!("{}-{:016}", hash_key(key), timestamp));
+    fs::write(&path, image).map_err(|err| err.to_string())?;
+
+    if let Some(previous) = previous {
+        let _ = fs::remove_file(previous);
+    }
+
+    Ok(path)
+}
+
+/// Replaces the front cover embedded in the audio file at `path` with `image`.
+pub fn embed_cover(path: &Path, image: &[u8]) -> Result<(), String> {
+    let mut picture = Picture::from_reader(&mut &image[..]).map_err(|err| err.to_string())?;
+    picture.set_pic_type(PictureType::CoverFront);
+
+    let mut tagged_file = lofty::read_from_path(path).map_err(|err| err.to_string())?;
+
+    let tag = match tagged_file.primary_tag_mut() {
+        Some(tag) => tag,
+        None => {
+            let tag_type = tagged_file.primary_tag_type();
+            tagged_file.insert_tag(Tag::new(tag_type));
+            tagged_file
+                .primary_tag_mut()
+                .ok_or("Could not create a tag for this file.")?
+        }
+    };
+
+    tag.remove_picture_type(PictureType::CoverFront);
+    tag.push_picture(picture);
+
+    tag.save_to_path(path, WriteOptions::default())
+        .map_err(|err| err.to_string())
+}
+
+/// The front cover embedded in the primary tag of the audio file at `path`, which
+/// [`embed_cover`] replaces.
+pub fn front_cover(path: &Path) -> Result<Option<Vec<u8>>, String> {
+    let tagged_file = lofty::read_from_path(path).map_err(|err| err.to_string())?;
+    Ok(tagged_file.primary_tag().and_then(|tag| {
+        tag.pictures()
+            .iter()
+            .find(|picture| picture.pic_type() == PictureType::CoverFront)
+            .map(|picture| picture.data().to_vec())
+    }))
+}
+
+/// Takes the front cover out of the audio file at `path`, undoing [`embed_cover`].
+pub fn remove_cover(path: &Path) -> Result<(), String> {
+    let mut tagged_file = lofty::read_from_path(path).map_err(|err| err.to_string())?;
+    let Some(tag) = tagged_file.primary_tag_mut() else {
+        return Ok(());
+    };
+
+    tag.remove_picture_type(PictureType::CoverFront);
+    tag.save_to_path(path, WriteOptions::default())
+        .map_err(|err| err.to_string())
+}
+
+/// Whether the audio file at `path` carries any embedded picture.
+pub fn has_embedded_cover(path: &Path) -> Result<bool, String> {
+    let tagged_file = lofty::read_from_path(path).map_err(|err| err.to_string())?;
+    Ok(tagged_file.tags().iter().any(|tag| !tag.pictures().is_empty()))
+}
+
+/// A cover image sitting in `dir`, such as `cover.jpg`.
+pub fn external_cover(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| EXTERNAL_COVER_NAMES.contains(&name.to_lowercase().as_str()))
+        })
+}
+
+/// A picture embedded in an audio file.
+#[derive(Debug, Clone)]
+pub struct EmbeddedPicture {
+    pub pic_type: PictureType,
+    /// Which picture of this type it is, counting from 0, for files with several.
+    pub nth: usize,
+    pub data: Vec<u8>,
+}
+
+/// Every picture embedded in the audio file at `path`, in tag order.
+pub fn embedded_pictures(path: &Path) -> Result<Vec<EmbeddedPicture>, String> {
+    let tagged_file = lofty::read_from_path(path).map_err(|err| err.to_string())?;
+    let mut pictures: Vec<EmbeddedPicture> = Vec::new();
+
+    for picture in tagged_file.tags().iter().flat_map(|tag| tag.pictures()) {
+        // Files carrying several tag formats often repeat the same artwork in each.
+        if pictures.iter().any(|known| known.data == picture.data()) {
+            continue;
+        }
+
+        let nth = pictures
+            .iter()
+            .filter(|known| known.pic_type == picture.pic_type())
+            .count();
+        pictures.push(EmbeddedPicture {
+            pic_type: picture.pic_type(),
+            nth,
+            data: picture.data().to_vec(),
+        });
+    }
+
+    Ok(pictures)
+}
+
+/// The picture the user picked to represent the album `album_key`, as its type and which
+/// picture of that type it is.
+pub fn read_picture_choice(album_key: &str) -> Option<(PictureType, usize)> {
+    let choice = fs::read_to_string(choice_path(album_key)).ok()?;
+    let (pic_type, nth) = choice.trim().split_once(' ')?;
+    Some((
+        PictureType::from_u8(pic_type.parse().ok()?),
+        nth.parse().ok()?,
+    ))
+}
+
+/// Makes `picture` the cover of the album `album_key`, in place of the front cover or any
+/// artwork assigned by hand, and remembers the choice for the next scan. Returns where the
+/// cover was cached.
+pub fn choose_picture(album_key: &str, picture: &EmbeddedPicture) -> Result<PathBuf, String> {
+    fs::create_dir_all(cache_dir()).map_err(|err| err.to_string())?;
+    fs::write(
+        choice_path(album_key),
+        format!("{} {}", picture.pic_type.as_u8(), picture.nth),
+    )
+    .map_err(|err| err.to_string())?;
+
+    let path = cached_cover(album_key);
+    fs::write(&path, &picture.data).map_err(|err| err.to_string())?;
+
+    if let Some(custom) = custom_cover(album_key) {
+        fs::remove_file(custom).map_err(|err| err.to_string())?;
+    }
+
+    Ok(path)
+}
+
+/// An album whose embedded cover should be copied into the art cache.
+#[derive(Debug, Clone)]
+pub struct CoverSource {
+    /// See [`album_key`].
+    pub key: String,
+    /// The album's first file, which the cover is taken from.
+    pub file: PathBuf,
+    /// The archive `file` has to be extracted from first, if any.
+    pub archive: Option<ArchiveEntry>,
+}
+
+/// What [`cache_covers`] did.
+#[derive(Debug, Clone, Default)]
+pub struct CoverReport {
+    /// Albums whose cover is in the cache now.
+    pub cached: Vec<String>,
+    /// Covers that couldn't be written to the cache, by album key, to be shown from memory.
+    pub in_memory: Vec<(String, Vec<u8>)>,
+    /// One message per album whose cover couldn't be read or cached.
+    pub failed: Vec<String>,
+}
+
+/// Copies the embedded cover of every album in `sources` into the art cache. Blocks until
+/// done, so run it off the UI thread. Albums without an embedded cover are skipped.
+pub fn cache_covers(sources: Vec<CoverSource>) -> CoverReport {
+    let mut report = CoverReport::default();
+
+    for source in sources {
+        if let Some(entry) = &source.archive {
+            if let Err(err) = archive::extract(entry, &source.file) {
+                report.failed.push(err);
+                continue;
+            }
+        }
+
+        let data = match embedded_cover(&source.file, read_picture_choice(&source.key)) {
+            Ok(Some(data)) => data,
+            Ok(None) => continue,
+            Err(err) => {
+                report
+                    .failed
+                    .push(format!("{}: {}", source.file.display(), err));
+                continue;
+            }
+        };
+
+        let written = fs::create_dir_all(cache_dir())
+            .and_then(|()| fs::write(cached_cover(&source.key), &data));
+        match written {
+            Ok(()) => report.cached.push(source.key),
+            Err(err) => {
+                report.failed.push(format!(
+                    "Couldn't cache the cover of {}: {}",
+                    source.file.display(),
+                    err
+                ));
+                report.in_memory.push((source.key, data));
+            }
+        }
+    }
+
+    report
+}
+
+/// Scales each album's cover, given by album key and path, to `size` pixels on its longest
+/// side. Left to the GPU, covers are stretched to the screen's pixels and come out blurry
+/// under fractional scaling. Covers scaled earlier are reused unless the cover changed
+/// since, and covers already small enough are used as they are. Blocks until done, so run
+/// it off the UI thread. Returns where each album's cover to show is.
+pub fn scale_covers(covers: Vec<(String, PathBuf)>, size: u32) -> Vec<(String, PathBuf)> {
+    covers
+        .into_iter()
+        .filter_map(|(key, source)| match scale_cover(&key, &source, size) {
+            Ok(path) => Some((key, path)),
+            Err(err) => {
+                eprintln!("Failed to scale the cover of {}: {}", source.display(), err);
+                None
+            }
+        })
+        .collect()
+}
+
+fn scale_cover(key: &str, source: &Path, size: u32) -> Result<PathBuf, String> {
+    let scaled = scaled_cover(key, size);
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified());
+    if let (Ok(scaled_at), Ok(changed_at)) = (modified(&scaled), modified(source)) {
+        if scaled_at >= changed_at {
+            return Ok(scaled);
+        }
+    }
+
+    // Cached covers have no extension to tell the format by.
+    let picture = ImageReader::open(source)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|err| err.to_string())?
+        .decode()
+        .map_err(|err| err.to_string())?;
+    if picture.width().max(picture.height()) <= size {
+        return Ok(source.to_path_buf());
+    }
+
+    if let Some(parent) = scaled.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    picture
+        .resize(size, size, FilterType::Lanczos3)
+        .save_with_format(&scaled, ImageFormat::Png)
+        .map_err(|err| err.to_string())?;
+    Ok(scaled)
+}
+
+/// The cover embedded in the audio file at `path`: the `chosen` picture if the file has
+/// it, else the front cover, else whatever picture comes first.
+fn embedded_cover(
+    path: &Path,
+    chosen: Option<(PictureType, usize)>,
+) -> Result<Option<Vec<u8>>, String> {
+    let pictures = embedded_pictures(path)?;
+
+    let cover = chosen
+        .and_then(|(pic_type, nth)| {
+            pictures
+                .iter()
+                .find(|picture| picture.pic_type == pic_type && picture.nth == nth)
+        })
+        .or_else(|| {
+            pictures
+                .iter()
+                .find(|picture| picture.pic_type == PictureType::CoverFront)
+        })
+        .or_else(|| pictures.first());
+
+    Ok(cover.map(|picture| picture.data.clone()))
+}
+
+/// An album to check for files without embedded artwork.
+#[derive(Debug, Clone)]
+pub struct EmbedCandidate {
+    /// See [`album_key`].
+    pub key: String,
+    /// The cover cached while scanning, which may not exist.
+    pub cached_cover: PathBuf,
+    pub files: Vec<PathBuf>,
+}
+
+/// Progress of [`embed_missing_covers`], readable from another thread while it runs.
+#[derive(Debug, Default)]
+pub struct EmbedProgress {
+    pub total: AtomicUsize,
+    pub done: AtomicUsize,
+    pub control: JobControl,
+}
+
+/// What [`embed_missing_covers`] did.
+#[derive(Debug, Clone, Default)]
+pub struct EmbedReport {
+    pub embedded: usize,
+    /// One message per file that was skipped because of an error.
+    pub failed: Vec<String>,
+    /// Embedding was stopped before every album was done.
+    pub cancelled: bool,
+}
+
+/// Writes a cover into every file of `albums` that has none, taking the cover from custom
+/// artwork, an image next to the files or the scan cache, in that order. Every file written
+/// is recorded in the [`journal`], and keeps its modification time with `keep_modified`.
+pub fn embed_missing_covers(
+    albums: Vec<EmbedCandidate>,
+    progress: &EmbedProgress,
+    keep_modified: bool,
+) -> EmbedReport {
+    let mut report = EmbedReport::default();
+
+    progress.total.store(
+        albums.iter().map(|album| album.files.len()).sum(),
+        Ordering::Relaxed,
+    );
+
+    for album in albums {
+        if !progress.control.checkpoint() {
+            report.cancelled = true;
+            break;
+        }
+
+        let mut missing = Vec::new();
+        for file in &album.files {
+            match has_embedded_cover(file) {
+                Ok(true) => {
+                    progress.done.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(false) => missing.push(file),
+                Err(err) => {
+                    report.failed.push(format!("{}: {}", file.display(), err));
+                    progress.done.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        if missing.is_empty() {
+            continue;
+        }
+
+        let source = custom_cover(&album.key)
+            .or_else(|| missing[0].parent().and_then(external_cover))
+            .or_else(|| Some(album.cached_cover.clone()).filter(|path| path.is_file()));
+
+        let image = match source.map(fs::read) {
+            Some(Ok(image)) => image,
+            Some(Err(err)) => {
+                for file in &missing {
+                    report.failed.push(format!("{}: {}", file.display(), err));
+                }
+                progress.done.fetch_add(missing.len(), Ordering::Relaxed);
+                continue;
+            }
+            // Nothing to embed for this album.
+            None => {
+                progress.done.fetch_add(missing.len(), Ordering::Relaxed);
+                continue;
+            }
+        };
+
+        for file in missing {
+            match journal::edit(file, Field::FrontCover, Some(&image), keep_modified) {
+                Ok(()) => report.embedded += 1,
+                Err(err) => report.failed.push(format!("{}: {}", file.display(), err)),
+            }
+            progress.done.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    report
+}
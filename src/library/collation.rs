@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Sort keys for artist names. Leading articles are dropped, sort-name tags are honored
+//! and letters are compared the way the user's locale orders them, so "The National" sorts
+//! under N and "Ólafur Arnalds" next to "Oasis".
+
+use std::sync::RwLock;
+
+use icu_normalizer::DecomposingNormalizer;
+
+use super::model::MusicFile;
+
+/// Articles ignored at the start of artist names unless the user picks others.
+pub const DEFAULT_ARTICLES: &str = "The, A, An";
+
+lazy_static::lazy_static! {
+    static ref COLLATION: RwLock<Collation> = RwLock::new(Collation::new(DEFAULT_ARTICLES));
+}
+
+/// How names are turned into sort keys.
+#[derive(Debug, Clone)]
+struct Collation {
+    /// Lowercase articles, each followed by a space.
+    articles: Vec<String>,
+    /// Letters the locale sorts as letters of their own, with the key they sort as.
+    tailoring: &'static [(char, &'static str)],
+}
+
+// Keys compare as plain strings, and U+10FFFF sorts after every letter, so a tailored
+// letter lands after all names using the letter it's built on.
+
+/// Å, Ä and Ö come after Z, in that order. Æ and Ø are written for Ä and Ö.
+const SWEDISH: &[(char, &str)] = &[
+    ('å', "z\u{10FFFF}1"),
+    ('ä', "z\u{10FFFF}2"),
+    ('æ', "z\u{10FFFF}2"),
+    ('ö', "z\u{10FFFF}3"),
+    ('ø', "z\u{10FFFF}3"),
+];
+/// Æ, Ø and Å come after Z, in that order. Ä and Ö are written for Æ and Ø.
+const DANISH: &[(char, &str)] = &[
+    ('æ', "z\u{10FFFF}1"),
+    ('ä', "z\u{10FFFF}1"),
+    ('ø', "z\u{10FFFF}2"),
+    ('ö', "z\u{10FFFF}2"),
+    ('å', "z\u{10FFFF}3"),
+];
+/// Ñ comes right after N.
+const SPANISH: &[(char, &str)] = &[('ñ', "n\u{10FFFF}")];
+
+impl Collation {
+    fn new(articles: &str) -> Self {
+        Collation {
+            articles: articles
+                .split(',')
+                .map(|article| article.trim().to_lowercase())
+                .filter(|article| !article.is_empty())
+                .map(|article| article + " ")
+                .collect(),
+            tailoring: tailoring(&locale()),
+        }
+    }
+
+    /// Drops a leading article, unless the whole name is one.
+    fn strip_article<'a>(&self, name: &'a str) -> &'a str {
+        let lowercase = name.to_lowercase();
+        for article in &self.articles {
+            // Lowercasing can change lengths, so only cut where both agree.
+            if lowercase.starts_with(article.as_str()) && name.is_char_boundary(article.len()) {
+                let rest = name[article.len()..].trim_start();
+                if !rest.is_empty() {
+                    return rest;
+                }
+            }
+        }
+        name
+    }
+
+    /// Lowercases `name` and takes accents off letters the locale doesn't sort separately.
+    fn fold(&self, name: &str) -> String {
+        let normalizer = DecomposingNormalizer::new_nfd();
+        let mut key = String::with_capacity(name.len());
+
+        for letter in name.trim().chars().flat_map(char::to_lowercase) {
+            if let Some((_, tailored)) = self.tailoring.iter().find(|(c, _)| *c == letter) {
+                key.push_str(tailored);
+                continue;
+            }
+
+            let mut buffer = [0; 4];
+            for decomposed in normalizer
+                .normalize(letter.encode_utf8(&mut buffer))
+                .chars()
+            {
+                match decomposed {
+                    // Combining accents.
+                    '\u{0300}'..='\u{036F}' => {}
+                    'æ' => key.push_str("ae"),
+                    'œ' => key.push_str("oe"),
+                    'ß' => key.push_str("ss"),
+                    'þ' => key.push_str("th"),
+                    'ø' => key.push('o'),
+                    'đ' | 'ð' => key.push('d'),
+                    'ł' => key.push('l'),
+                    'ı' => key.push('i'),
+                    other => key.push(other),
+                }
+            }
+        }
+
+        key
+    }
+}
+
+/// The language names are sorted for: the first of `LC_ALL`, `LC_COLLATE` and `LANG` that's
+/// set, as the C library picks it.
+fn locale() -> String {
+    ["LC_ALL", "LC_COLLATE", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
+
+fn tailoring(locale: &str) -> &'static [(char, &'static str)] {
+    let language = locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default();
+
+    match language {
+        "sv" | "fi" => SWEDISH,
+        "da" | "nb" | "nn" | "no" => DANISH,
+        "es" => SPANISH,
+        _ => &[],
+    }
+}
+
+/// Sets the articles ignored at the start of names, as a comma separated list. Keys made
+/// before have to be made again with [`update_keys`].
+pub fn set_articles(articles: &str) {
+    if let Ok(mut collation) = COLLATION.write() {
+        *collation = Collation::new(articles);
+    }
+}
+
+/// Key `name` sorts by. An explicit `sort_name` from the tags wins and is taken as it is,
+/// articles and all.
+pub fn sort_key(name: &str, sort_name: &str) -> String {
+    let Ok(collation) = COLLATION.read() else {
+        return name.to_lowercase();
+    };
+
+    if sort_name.trim().is_empty() {
+        collation.fold(collation.strip_article(name.trim()))
+    } else {
+        collation.fold(sort_name)
+    }
+}
+
+/// Lowercases `text` and folds accents as [`sort_key`] does, keeping any leading article.
+/// For titles, which are sorted as written.
+pub fn fold(text: &str) -> String {
+    let Ok(collation) = COLLATION.read() else {
+        return text.to_lowercase();
+    };
+
+    collation.fold(text)
+}
+
+/// Fills in the sort keys of `file`.
+pub fn update_keys(file: &mut MusicFile) {
+    file.artist_key = sort_key(&file.artist, &file.artist_sort);
+    file.album_artist_key = sort_key(&file.album_artist, &file.album_artist_sort);
+    file.title_key = fold(&file.track_title);
+}
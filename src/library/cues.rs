@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Hot cues written into tags by Serato DJ, as the `Serato Markers2` object.
+//!
+//! MP3s carry it in an ID3v2 `GEOB` frame, FLAC and Ogg files in a `SERATO_MARKERS_V2`
+//! comment. Either way the payload is base64 wrapped around a list of named entries, of
+//! which only `CUE` is read here.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+use lofty::prelude::TaggedFileExt;
+use lofty::tag::ItemKey;
+
+/// Description of the `GEOB` frame holding the markers.
+const GEOB_DESCRIPTION: &str = "Serato Markers2";
+/// Comment holding the markers in Vorbis-style tags.
+const VORBIS_KEY: &str = "SERATO_MARKERS_V2";
+/// Version bytes in front of both the encoded and the decoded payload.
+const MARKERS2_VERSION: [u8; 2] = [0x01, 0x01];
+
+/// Serato leaves out padding and wraps lines, so decoding has to be forgiving.
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// A hot cue set in DJ software.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CueMarker {
+    /// The pad the cue is assigned to, from 0.
+    pub index: u8,
+    pub position: Duration,
+    pub color: [u8; 3],
+    /// May be empty.
+    pub name: String,
+}
+
+/// Every hot cue stored in the file at `path`, in pad order. Files without any, or with
+/// markers that can't be read, give an empty list.
+pub fn read(path: &Path) -> Vec<CueMarker> {
+    let payload = id3_geob(path, GEOB_DESCRIPTION).or_else(|| vorbis_markers(path));
+
+    let mut cues = payload
+        .and_then(|payload| decode_payload(&payload))
+        .map(|entries| parse_entries(&entries))
+        .unwrap_or_default();
+    cues.sort_by_key(|cue| cue.index);
+    cues
+}
+
+fn base64_decode(encoded: &[u8]) -> Option<Vec<u8>> {
+    let mut encoded: Vec<u8> = encoded
+        .iter()
+        .copied()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+
+    // A lone trailing character can't be decoded; Serato pads it with an `A`.
+    if encoded.len() % 4 == 1 {
+        encoded.push(b'A');
+    }
+
+    LENIENT_BASE64.decode(encoded).ok()
+}
+
+/// Splits off everything up to the first NUL, returning it and what follows the NUL.
+fn split_nul(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let end = data.iter().position(|byte| *byte == 0)?;
+    Some((&data[..end], &data[end + 1..]))
+}
+
+/// Turns the object's data into the decoded entry list.
+fn decode_payload(payload: &[u8]) -> Option<Vec<u8>> {
+    let encoded = payload.strip_prefix(&MARKERS2_VERSION)?;
+    // The base64 ends at the NUL padding.
+    let encoded = split_nul(encoded).map_or(encoded, |(encoded, _)| encoded);
+
+    let decoded = base64_decode(encoded)?;
+    decoded
+        .strip_prefix(&MARKERS2_VERSION)
+        .map(|entries| entries.to_vec())
+}
+
+/// Reads the `CUE` entries out of the decoded entry list.
+fn parse_entries(mut entries: &[u8]) -> Vec<CueMarker> {
+    let mut cues = Vec::new();
+
+    while let Some((name, rest)) = split_nul(entries) {
+        if name.is_empty() || rest.len() < 4 {
+            break;
+        }
+
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let Some(data) = rest.get(4..4 + length) else {
+            break;
+        };
+
+        if name == b"CUE" {
+            if let Some(cue) = parse_cue(data) {
+                cues.push(cue);
+            }
+        }
+
+        entries = &rest[4 + length..];
+    }
+
+    cues
+}
+
+/// Layout: 0x00, index, position in ms (u32 BE), 0x00, RGB, 0x00 0x00, NUL-terminated name.
+fn parse_cue(data: &[u8]) -> Option<CueMarker> {
+    let header = data.get(..12)?;
+    let name = split_nul(&data[12..]).map_or(&data[12..], |(name, _)| name);
+
+    Some(CueMarker {
+        index: header[1],
+        position: Duration::from_millis(u64::from(u32::from_be_bytes([
+            header[2], header[3], header[4], header[5],
+        ]))),
+        color: [header[7], header[8], header[9]],
+        name: String::from_utf8_lossy(name).into_owned(),
+    })
+}
+
+fn be_size(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |size, byte| (size << 8) | usize::from(*byte))
+}
+
+fn synchsafe_size(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0, |size, byte| (size << 7) | usize::from(*byte & 0x7f))
+}
+
+/// The data of the ID3v2.3/2.4 `GEOB` frame described as `description`.
+fn id3_geob(path: &Path, description: &str) -> Option<Vec<u8>> {
+    let mut file = File::open(path).ok()?;
+    let mut header = [0; 10];
+    file.read_exact(&mut header).ok()?;
+
+    let version = header[3];
+    let flags = header[5];
+    // Whole-tag unsynchronisation is rare and would need undoing first.
+    if &header[..3] != b"ID3" || !(3..=4).contains(&version) || flags & 0x80 != 0 {
+        return None;
+    }
+
+    let mut tag = vec![0; synchsafe_size(&header[6..10])];
+    file.read_exact(&mut tag).ok()?;
+
+    let frame_size = |bytes: &[u8]| {
+        if version == 4 {
+            synchsafe_size(bytes)
+        } else {
+            be_size(bytes)
+        }
+    };
+
+    let mut offset = 0;
+    if flags & 0x40 != 0 {
+        // The extended header's size covers its size field in 2.4 but not in 2.3.
+        offset = match version {
+            4 => synchsafe_size(tag.get(..4)?),
+            _ => be_size(tag.get(..4)?) + 4,
+        };
+    }
+
+    while let Some(frame_header) = tag.get(offset..offset + 10) {
+        // Padding after the last frame.
+        if frame_header[0] == 0 {
+            break;
+        }
+
+        let size = frame_size(&frame_header[4..8]);
+        let body = tag.get(offset + 10..offset + 10 + size)?;
+
+        if &frame_header[..4] == b"GEOB" {
+            if let Some(data) = geob_data(body, description) {
+                return Some(data.to_vec());
+            }
+        }
+
+        offset += 10 + size;
+    }
+
+    None
+}
+
+/// Layout: encoding, MIME type, file name, description, data. Strings are NUL-terminated,
+/// which only holds for the Latin-1 and UTF-8 encodings Serato writes.
+fn geob_data<'a>(body: &'a [u8], description: &str) -> Option<&'a [u8]> {
+    let (&encoding, rest) = body.split_first()?;
+    if encoding != 0 && encoding != 3 {
+        return None;
+    }
+
+    let (_mime, rest) = split_nul(rest)?;
+    let (_filename, rest) = split_nul(rest)?;
+    let (found, data) = split_nul(rest)?;
+
+    (found == description.as_bytes()).then_some(data)
+}
+
+/// The `SERATO_MARKERS_V2` comment, unwrapped down to the same data a `GEOB` frame holds.
+fn vorbis_markers(path: &Path) -> Option<Vec<u8>> {
+    let tagged_file = lofty::read_from_path(path).ok()?;
+    let key = ItemKey::Unknown(VORBIS_KEY.to_string());
+    let value = tagged_file
+        .tags()
+        .iter()
+        .find_map(|tag| tag.get_string(&key))?;
+
+    // The comment is base64 of a MIME type, the GEOB description and then the data.
+    let decoded = base64_decode(value.as_bytes())?;
+    let marker = format!("{}\0", GEOB_DESCRIPTION);
+    let start = decoded
+        .windows(marker.len())
+        .position(|window| window == marker.as_bytes())?;
+
+    Some(decoded[start + marker.len()..].to_vec())
+}
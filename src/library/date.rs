@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Release dates as found in tags, which range from a bare year to a full timestamp.
+
+use std::fmt;
+
+/// A date with as much precision as the tag provided. Orders chronologically, with a bare
+/// year sorting before any full date in that year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReleaseDate {
+    pub year: u16,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+}
+
+impl ReleaseDate {
+    /// Parses `YYYY`, `YYYY-MM` and `YYYY-MM-DD`, with `-`, `/` or `.` as separators and
+    /// anything after the day (such as a time) ignored. Out-of-range parts are dropped.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let mut parts = value.split(['-', '/', '.']);
+
+        let year = parts.next()?;
+        if year.len() != 4 || !year.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let year = year.parse().ok()?;
+
+        let month = parts
+            .next()
+            .and_then(|month| month.parse::<u8>().ok())
+            .filter(|month| (1..=12).contains(month));
+        let day = month
+            .and(parts.next())
+            .and_then(|day| day.get(..2).unwrap_or(day).parse::<u8>().ok())
+            .filter(|day| (1..=31).contains(day));
+
+        Some(ReleaseDate { year, month, day })
+    }
+
+    /// A bare year, as some tag formats only store that.
+    pub fn from_year(year: u32) -> Option<Self> {
+        Some(ReleaseDate {
+            year: u16::try_from(year).ok().filter(|year| *year > 0)?,
+            month: None,
+            day: None,
+        })
+    }
+
+    /// The first year of the decade, e.g. 1990 for 1994.
+    pub fn decade(&self) -> u16 {
+        self.year - self.year % 10
+    }
+}
+
+impl fmt::Display for ReleaseDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}", self.year)?;
+        if let Some(month) = self.month {
+            write!(f, "-{:02}", month)?;
+            if let Some(day) = self.day {
+                write!(f, "-{:02}", day)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: u16, month: Option<u8>, day: Option<u8>) -> Option<ReleaseDate> {
+        Some(ReleaseDate { year, month, day })
+    }
+
+    #[test]
+    fn parses_a_bare_year() {
+        assert_eq!(ReleaseDate::parse("1994"), date(1994, None, None));
+        assert_eq!(ReleaseDate::parse(" 1994 "), date(1994, None, None));
+    }
+
+    #[test]
+    fn parses_a_year_and_month() {
+        assert_eq!(ReleaseDate::parse("1994-06"), date(1994, Some(6), None));
+        assert_eq!(ReleaseDate::parse("1994/6"), date(1994, Some(6), None));
+    }
+
+    #[test]
+    fn parses_a_full_date_with_any_separator() {
+        for value in ["1994-06-21", "1994/06/21", "1994.06.21"] {
+            assert_eq!(ReleaseDate::parse(value), date(1994, Some(6), Some(21)));
+        }
+    }
+
+    #[test]
+    fn ignores_the_time_after_the_day() {
+        assert_eq!(
+            ReleaseDate::parse("1994-06-21T08:30:00Z"),
+            date(1994, Some(6), Some(21))
+        );
+    }
+
+    #[test]
+    fn drops_parts_out_of_range() {
+        assert_eq!(ReleaseDate::parse("1994-13-21"), date(1994, None, None));
+        assert_eq!(ReleaseDate::parse("1994-06-32"), date(1994, Some(6), None));
+        assert_eq!(ReleaseDate::parse("1994-00"), date(1994, None, None));
+        assert_eq!(ReleaseDate::parse("1994-06-??"), date(1994, Some(6), None));
+    }
+
+    #[test]
+    fn needs_a_four_digit_year() {
+        for value in ["", "94", "19940", "94-06-21", "June 1994", "n.d."] {
+            assert_eq!(ReleaseDate::parse(value), None, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn orders_a_bare_year_before_its_dates() {
+        let year = ReleaseDate::parse("1994").unwrap();
+        let month = ReleaseDate::parse("1994-01").unwrap();
+        let day = ReleaseDate::parse("1994-01-01").unwrap();
+
+        assert!(year < month && month < day);
+        assert!(day < ReleaseDate::parse("1995").unwrap());
+    }
+}
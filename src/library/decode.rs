@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Waiting on pipelines that decode a whole file as fast as they can, to check or measure
+//! it.
+
+use std::time::{Duration, Instant};
+
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+use crate::jobs::JobControl;
+
+/// How often a decode in progress checks whether the job was cancelled.
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+/// Decoding runs as fast as the CPU allows, so a file taking longer than this is stuck.
+const DECODE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Waits until `pipeline` has decoded everything, something went wrong, it got stuck or
+/// `control` is cancelled. Every other message is handed to `on_message`, which can fail
+/// the decode too.
+pub fn wait_for_end(
+    pipeline: &gst::Pipeline,
+    control: &JobControl,
+    mut on_message: impl FnMut(&gst::Message) -> Result<(), String>,
+) -> Result<(), String> {
+    let bus = pipeline.bus().ok_or("Pipeline has no bus")?;
+    let started = Instant::now();
+
+    loop {
+        if control.is_cancelled() {
+            return Err(String::from("Cancelled"));
+        }
+        if started.elapsed() > DECODE_TIMEOUT {
+            return Err(String::from("Decoding timed out"));
+        }
+
+        let timeout = gst::ClockTime::from_mseconds(CANCEL_POLL.as_millis() as u64);
+        let Some(message) = bus.timed_pop(timeout) else {
+            continue;
+        };
+
+        match message.view() {
+            gst::MessageView::Eos(..) => return Ok(()),
+            gst::MessageView::Error(err) => return Err(err.error().to_string()),
+            _ => on_message(&message)?,
+        }
+    }
+}
@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! DSD audio in DSF and DSDIFF (`.dff`) files, which lofty can't read. Both keep their tags
+//! in an ID3v2 block, which is parsed here into a lofty [`Tag`] so the scan can treat them
+//! like any other file. Playback goes through GStreamer, which decodes DSD to PCM.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use lofty::tag::{ItemKey, Tag, TagType};
+
+/// ID3v2 text frames and what they're read as. `TYER` and `TORY` are the ID3v2.3 versions
+/// of `TDRC` and `TDOR`.
+const TEXT_FRAMES: [(&[u8; 4], ItemKey); 15] = [
+    (b"TIT2", ItemKey::TrackTitle),
+    (b"TPE1", ItemKey::TrackArtist),
+    (b"TPE2", ItemKey::AlbumArtist),
+    (b"TALB", ItemKey::AlbumTitle),
+    (b"TRCK", ItemKey::TrackNumber),
+    (b"TPOS", ItemKey::DiscNumber),
+    (b"TDRC", ItemKey::RecordingDate),
+    (b"TYER", ItemKey::RecordingDate),
+    (b"TDOR", ItemKey::OriginalReleaseDate),
+    (b"TORY", ItemKey::OriginalReleaseDate),
+    (b"TSOP", ItemKey::TrackArtistSortOrder),
+    (b"TSO2", ItemKey::AlbumArtistSortOrder),
+    (b"TLAN", ItemKey::Language),
+    (b"TBPM", ItemKey::Bpm),
+    (b"TKEY", ItemKey::InitialKey),
+];
+/// Description of the `TXXX` frame MusicBrainz Picard stores the release group in.
+const RELEASE_GROUP_ID: &str = "MusicBrainz Release Group Id";
+
+/// What's read from a DSD file.
+#[derive(Debug, Clone)]
+pub struct DsdFile {
+    pub duration: Duration,
+    pub channels: u8,
+    /// Empty when the file has no ID3v2 block.
+    pub tag: Tag,
+}
+
+/// Whether the file at `path` is a DSF or DSDIFF file, going by its header.
+pub fn is_dsd(path: &Path) -> bool {
+    let mut header = [0; 16];
+    let read = File::open(path).and_then(|mut file| file.read_exact(&mut header));
+
+    read.is_ok()
+        && (&header[..4] == b"DSD " || (&header[..4] == b"FRM8" && &header[12..] == b"DSD "))
+}
+
+/// Reads the length and tags of the DSF or DSDIFF file at `path`.
+pub fn read(path: &Path) -> Result<DsdFile, String> {
+    let mut file = File::open(path).map_err(|err| err.to_string())?;
+    let mut magic = [0; 4];
+    file.read_exact(&mut magic).map_err(|err| err.to_string())?;
+
+    let (duration, channels, id3) = match &magic {
+        b"DSD " => read_dsf(&mut file)?,
+        b"FRM8" => read_dff(&mut file)?,
+        _ => return Err(format!("{} is not a DSD file", path.display())),
+    };
+
+    Ok(DsdFile {
+        duration,
+        channels: u8::try_from(channels).unwrap_or(0),
+        tag: id3
+            .map(|id3| parse_id3v2(&id3))
+            .unwrap_or_else(|| Tag::new(TagType::Id3v2)),
+    })
+}
+
+/// DSF is little-endian: a `DSD ` chunk pointing at the ID3v2 block at the end of the
+/// file, then a `fmt ` chunk with the channel count, the sample rate and the number of
+/// samples per channel.
+fn read_dsf(file: &mut File) -> Result<(Duration, u32, Option<Vec<u8>>), String> {
+    let mut header = [0; 76];
+    file.read_exact(&mut header)
+        .map_err(|err| err.to_string())?;
+
+    if &header[24..28] != b"fmt " {
+        return Err(String::from("DSF file without a format chunk"));
+    }
+    let metadata = u64::from_le_bytes(header[16..24].try_into().unwrap_or_default());
+    let channels = u32::from_le_bytes(header[48..52].try_into().unwrap_or_default());
+    let sample_rate = u32::from_le_bytes(header[52..56].try_into().unwrap_or_default());
+    let samples = u64::from_le_bytes(header[60..68].try_into().unwrap_or_default());
+
+    let duration = match sample_rate {
+        0 => Duration::ZERO,
+        rate => Duration::from_secs_f64(samples as f64 / f64::from(rate)),
+    };
+
+    let id3 = match metadata {
+        0 => None,
+        offset => {
+            let mut id3 = Vec::new();
+            file.seek(SeekFrom::Start(offset))
+                .and_then(|_| file.read_to_end(&mut id3))
+                .map_err(|err| err.to_string())?;
+            Some(id3)
+        }
+    };
+
+    Ok((duration, channels, id3))
+}
+
+/// DSDIFF is big-endian chunks inside a `FRM8` form. The length comes from the size of the
+/// `DSD ` sound data, or from the frame count for DST compressed files. The `ID3 ` chunk
+/// isn't in the specification, but it's where every tagger puts tags.
+fn read_dff(file: &mut File) -> Result<(Duration, u32, Option<Vec<u8>>), String> {
+    let mut form = [0; 12];
+    file.read_exact(&mut form).map_err(|err| err.to_string())?;
+
+    let mut sample_rate = 0;
+    let mut channels = 0;
+    let mut duration = Duration::ZERO;
+    let mut id3 = None;
+
+    let mut chunk = [0; 12];
+    while file.read_exact(&mut chunk).is_ok() {
+        let size = u64::from_be_bytes(chunk[4..12].try_into().unwrap_or_default());
+        let start = file.stream_position().map_err(|err| err.to_string())?;
+
+        match &chunk[..4] {
+            b"PROP" => (sample_rate, channels) = read_dff_properties(file, size)?,
+            b"DSD " if sample_rate > 0 && channels > 0 => {
+                // One bit per sample.
+                let samples = size * 8 / u64::from(channels);
+                duration = Duration::from_secs_f64(samples as f64 / f64::from(sample_rate));
+            }
+            b"DST " => {
+                let mut frames = [0; 18];
+                file.read_exact(&mut frames)
+                    .map_err(|err| err.to_string())?;
+                if &frames[..4] == b"FRTE" {
+                    let count = u32::from_be_bytes(frames[12..16].try_into().unwrap_or_default());
+                    let rate = u16::from_be_bytes(frames[16..18].try_into().unwrap_or_default());
+                    if rate > 0 {
+                        duration = Duration::from_secs_f64(f64::from(count) / f64::from(rate));
+                    }
+                }
+            }
+            b"ID3 " => {
+                let mut data = vec![0; usize::try_from(size).map_err(|err| err.to_string())?];
+                file.read_exact(&mut data).map_err(|err| err.to_string())?;
+                id3 = Some(data);
+            }
+            _ => {}
+        }
+
+        // Chunks are padded to an even length.
+        let next = start + size + size % 2;
+        file.seek(SeekFrom::Start(next))
+            .map_err(|err| err.to_string())?;
+    }
+
+    Ok((duration, u32::from(channels), id3))
+}
+
+/// Sample rate and channel count from the `PROP` chunk.
+fn read_dff_properties(file: &mut File, size: u64) -> Result<(u32, u16), String> {
+    let mut kind = [0; 4];
+    file.read_exact(&mut kind).map_err(|err| err.to_string())?;
+    if &kind != b"SND " {
+        return Ok((0, 0));
+    }
+
+    let mut sample_rate = 0;
+    let mut channels = 0;
+    let mut read = 4;
+    let mut chunk = [0; 12];
+
+    while read + 12 <= size && file.read_exact(&mut chunk).is_ok() {
+        let chunk_size = u64::from_be_bytes(chunk[4..12].try_into().unwrap_or_default());
+        let start = file.stream_position().map_err(|err| err.to_string())?;
+
+        match &chunk[..4] {
+            b"FS  " => {
+                let mut rate = [0; 4];
+                file.read_exact(&mut rate).map_err(|err| err.to_string())?;
+                sample_rate = u32::from_be_bytes(rate);
+            }
+            b"CHNL" => {
+                let mut count = [0; 2];
+                file.read_exact(&mut count).map_err(|err| err.to_string())?;
+                channels = u16::from_be_bytes(count);
+            }
+            _ => {}
+        }
+
+        let padded = chunk_size + chunk_size % 2;
+        file.seek(SeekFrom::Start(start + padded))
+            .map_err(|err| err.to_string())?;
+        read += 12 + padded;
+    }
+
+    Ok((sample_rate, channels))
+}
+
+/// Reads the text frames of an ID3v2.3 or ID3v2.4 tag. Pictures and everything else are
+/// left out. Returns an empty tag for anything that doesn't parse.
+fn parse_id3v2(data: &[u8]) -> Tag {
+    let mut tag = Tag::new(TagType::Id3v2);
+
+    if data.len() < 10 || &data[..3] != b"ID3" || !matches!(data[3], 3 | 4) {
+        return tag;
+    }
+    let version = data[3];
+    let flags = data[5];
+    let size = synchsafe(&data[6..10]) as usize;
+    let Some(body) = data.get(10..10 + size) else {
+        return tag;
+    };
+
+    // Unsynchronisation puts a zero after every 0xFF, so take them out again.
+    let body: Vec<u8> = if flags & 0x80 != 0 {
+        let mut bytes = Vec::with_capacity(body.len());
+        for (i, &byte) in body.iter().enumerate() {
+            if !(byte == 0 && i > 0 && body[i - 1] == 0xFF) {
+                bytes.push(byte);
+            }
+        }
+        bytes
+    } else {
+        body.to_vec()
+    };
+
+    let mut position = 0;
+    // The extended header is only there to be skipped.
+    if flags & 0x40 != 0 && body.len() >= 4 {
+        position = match version {
+            3 => 4 + u32::from_be_bytes(body[..4].try_into().unwrap_or_default()) as usize,
+            _ => synchsafe(&body[..4]) as usize,
+        };
+    }
+
+    while let Some(header) = body.get(position..position + 10) {
+        // Padding fills the rest of the tag with zeros.
+        if header[0] == 0 {
+            break;
+        }
+        let frame_size = match version {
+            3 => u32::from_be_bytes(header[4..8].try_into().unwrap_or_default()) as usize,
+            _ => synchsafe(&header[4..8]) as usize,
+        };
+        let Some(frame) = body.get(position + 10..position + 10 + frame_size) else {
+            break;
+        };
+        position += 10 + frame_size;
+
+        let Some((&encoding, text)) = frame.split_first() else {
+            continue;
+        };
+
+        if &header[..4] == b"TXXX" {
+            let (description, value) = split_terminated(encoding, text);
+            if decode_text(encoding, description).eq_ignore_ascii_case(RELEASE_GROUP_ID) {
+                tag.insert_text(
+                    ItemKey::MusicBrainzReleaseGroupId,
+                    decode_text(encoding, value),
+                );
+            }
+            continue;
+        }
+
+        let Some((_, key)) = TEXT_FRAMES.iter().find(|(id, _)| id[..] == header[..4]) else {
+            continue;
+        };
+        // ID3v2.4 separates several values with terminators, only the first is used.
+        let value = decode_text(encoding, split_terminated(encoding, text).0);
+
+        // The disc is written as "1/2", which lofty won't read as a number.
+        let value = match key {
+            ItemKey::DiscNumber => value
+                .split('/')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            _ => value,
+        };
+        if !value.is_empty() {
+            tag.insert_text(key.clone(), value);
+        }
+    }
+
+    tag
+}
+
+/// A 28 bit number stored in the low 7 bits of each byte.
+fn synchsafe(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |value, &byte| (value << 7) | u32::from(byte & 0x7F))
+}
+
+/// Splits `text` at its first terminator, which is two zero bytes for UTF-16.
+fn split_terminated(encoding: u8, text: &[u8]) -> (&[u8], &[u8]) {
+    let terminator = match encoding {
+        1 | 2 => text
+            .chunks_exact(2)
+            .position(|pair| pair == [0, 0])
+            .map(|pair| (pair * 2, 2)),
+        _ => text
+            .iter()
+            .position(|&byte| byte == 0)
+            .map(|index| (index, 1)),
+    };
+
+    match terminator {
+        Some((index, length)) => (&text[..index], &text[index + length..]),
+        None => (text, &[]),
+    }
+}
+
+/// Decodes text in one of the four ID3v2 encodings: Latin-1, UTF-16 with a byte order
+/// mark, UTF-16 big-endian and UTF-8.
+fn decode_text(encoding: u8, bytes: &[u8]) -> String {
+    let text: String = match encoding {
+        0 => bytes.iter().map(|&byte| char::from(byte)).collect(),
+        1 | 2 => {
+            let (little_endian, bytes) = match bytes {
+                [0xFF, 0xFE, rest @ ..] => (true, rest),
+                [0xFE, 0xFF, rest @ ..] => (false, rest),
+                _ => (false, bytes),
+            };
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| {
+                    if little_endian {
+                        u16::from_le_bytes([pair[0], pair[1]])
+                    } else {
+                        u16::from_be_bytes([pair[0], pair[1]])
+                    }
+                })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    };
+
+    text.trim_end_matches('\0').trim().to_string()
+}
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Telling editions of the same album apart from different albums, so "Album",
+//! "Album (Remastered 2011)" and "Album [Deluxe Edition]" can be shown as one.
+
+/// Words that mark a bracketed or dashed suffix as an edition note rather than part of
+/// the title, as in "(What's the Story) Morning Glory?".
+const EDITION_WORDS: [&str; 13] = [
+    "remaster",
+    "deluxe",
+    "edition",
+    "anniversary",
+    "expanded",
+    "bonus",
+    "reissue",
+    "version",
+    "mono",
+    "stereo",
+    "special",
+    "collector",
+    "legacy",
+];
+
+fn is_edition_note(note: &str) -> bool {
+    let note = note.to_lowercase();
+    EDITION_WORDS.iter().any(|word| note.contains(word))
+}
+
+/// Splits `title` into the album title and its edition notes, such as
+/// `("Abbey Road", Some("Remastered 2009"))`. Only notes at the end are taken off.
+pub fn split_edition(title: &str) -> (&str, Option<String>) {
+    let mut base = title.trim_end();
+    let mut notes: Vec<&str> = Vec::new();
+
+    loop {
+        let close = match base.chars().last() {
+            Some(')') => '(',
+            Some(']') => '[',
+            _ => {
+                // "Album - Remastered 2011", as some stores tag it.
+                if let Some((rest, note)) = base.rsplit_once(" - ") {
+                    if is_edition_note(note) {
+                        notes.push(note.trim());
+                        base = rest.trim_end();
+                        continue;
+                    }
+                }
+                break;
+            }
+        };
+
+        let Some(open) = base.rfind(close) else {
+            break;
+        };
+        let note = &base[open + 1..base.len() - 1];
+        if open == 0 || !is_edition_note(note) {
+            break;
+        }
+
+        notes.push(note.trim());
+        base = base[..open].trim_end();
+    }
+
+    if notes.is_empty() {
+        (title, None)
+    } else {
+        notes.reverse();
+        (base, Some(notes.join(", ")))
+    }
+}
+
+/// `title` without edition notes, lowercase, with letters and digits only.
+pub fn comparable(title: &str) -> String {
+    split_edition(title)
+        .0
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// What editions of the same album have in common: the MusicBrainz release group when the
+/// files are tagged with one, else the album artist and the title without edition notes.
+pub fn version_group(album_artist_key: &str, album: &str, release_group_id: &str) -> String {
+    if release_group_id.is_empty() {
+        format!("{}\u{1f}{}", album_artist_key, comparable(album))
+    } else {
+        format!("mb:{}", release_group_id)
+    }
+}
@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The edit journal: every change Jams makes to the tags of an audio file, with the value
+//! before and after, so edits can be looked over and taken back later. Values are kept as
+//! files of their own next to the journal, since a single cover can run to megabytes.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::artwork;
+use crate::core::flatpak;
+
+/// A tag Jams writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Field {
+    FrontCover,
+}
+
+/// One change to one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edit {
+    pub path: PathBuf,
+    /// Seconds since the Unix epoch.
+    pub edited_at: u64,
+    pub field: Field,
+    /// Where the value is kept in the journal, or `None` if the file had none.
+    pub before: Option<PathBuf>,
+    pub after: Option<PathBuf>,
+}
+
+/// Folder holding the journal, `~/.local/share/jams/journal` unless XDG says otherwise.
+fn journal_dir() -> PathBuf {
+    flatpak::data_dir().join("journal")
+}
+
+/// Every edit, one JSON object per line so recording one only appends.
+fn edits_file() -> PathBuf {
+    journal_dir().join("edits.jsonl")
+}
+
+/// Keeps `value` in the journal and returns where. Named after its contents, so a cover
+/// embedded into a whole album is only stored once.
+fn store_value(value: &[u8]) -> Result<PathBuf, String> {
+    let dir = journal_dir().join("values");
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+
+    let path = dir.join(artwork::hash_bytes(value));
+    if !path.is_file() {
+        fs::write(&path, value).map_err(|err| err.to_string())?;
+    }
+    Ok(path)
+}
+
+fn record(edit: &Edit) -> Result<(), String> {
+    fs::create_dir_all(journal_dir()).map_err(|err| err.to_string())?;
+
+    let line = serde_json::to_string(edit).map_err(|err| err.to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(edits_file())
+        .map_err(|err| err.to_string())?;
+    writeln!(file, "{}", line).map_err(|err| err.to_string())
+}
+
+fn read_field(path: &Path, field: Field) -> Result<Option<Vec<u8>>, String> {
+    match field {
+        Field::FrontCover => artwork::front_cover(path),
+    }
+}
+
+fn write_field(path: &Path, field: Field, value: Option<&[u8]>) -> Result<(), String> {
+    match (field, value) {
+        (Field::FrontCover, Some(image)) => artwork::embed_cover(path, image),
+        (Field::FrontCover, None) => artwork::remove_cover(path),
+    }
+}
+
+/// Sets `field` of the audio file at `path` to `value`, or clears it for `None`, and
+/// records the change. With `keep_modified` the file keeps its modification time, so tools
+/// that sort or sync by it don't take a tag edit for new music.
+pub fn edit(
+    path: &Path,
+    field: Field,
+    value: Option<&[u8]>,
+    keep_modified: bool,
+) -> Result<(), String> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|err| err.to_string())?;
+
+    // Stored before the file is touched, so nothing is written that couldn't be undone.
+    let before = read_field(path, field)?
+        .map(|before| store_value(&before))
+        .transpose()?;
+    let after = value.map(store_value).transpose()?;
+
+    write_field(path, field, value)?;
+
+    if keep_modified {
+        let restored = fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(modified));
+        if let Err(err) = restored {
+            eprintln!(
+                "Failed to keep the modification time of {}: {}",
+                path.display(),
+                err
+            );
+        }
+    }
+
+    record(&Edit {
+        path: path.to_path_buf(),
+        edited_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        field,
+        before,
+        after,
+    })
+}
+
+/// The edits made to the file at `path`, newest first.
+pub fn history(path: &Path) -> Vec<Edit> {
+    let contents = fs::read_to_string(edits_file()).unwrap_or_default();
+    let mut edits: Vec<Edit> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Edit>(line).ok())
+        .filter(|edit| edit.path == path)
+        .collect();
+    edits.reverse();
+    edits
+}
+
+/// Puts back the value `undone` replaced. Reverting is an edit too and lands in the journal,
+/// so it can be reverted in turn.
+pub fn revert(undone: &Edit, keep_modified: bool) -> Result<(), String> {
+    let before = match &undone.before {
+        Some(value) => Some(fs::read(value).map_err(|err| err.to_string())?),
+        None => None,
+    };
+
+    edit(&undone.path, undone.field, before.as_deref(), keep_modified)
+}
+
+/// Points the edits of files that were moved at where they are now. `moved` maps the old
+/// paths to the new ones.
+pub fn follow_moves(moved: &HashMap<PathBuf, PathBuf>) -> Result<(), String> {
+    let Ok(contents) = fs::read_to_string(edits_file()) else {
+        return Ok(());
+    };
+
+    let mut changed = false;
+    let mut rewritten = String::with_capacity(contents.len());
+    for line in contents.lines() {
+        let edit = serde_json::from_str::<Edit>(line)
+            .ok()
+            .and_then(|edit| Some((moved.get(&edit.path)?.clone(), edit)));
+        match edit {
+            Some((path, edit)) => {
+                let edit = Edit { path, ..edit };
+                rewritten.push_str(&serde_json::to_string(&edit).map_err(|err| err.to_string())?);
+                changed = true;
+            }
+            None => rewritten.push_str(line),
+        }
+        rewritten.push('\n');
+    }
+
+    if !changed {
+        return Ok(());
+    }
+    fs::write(edits_file(), rewritten).map_err(|err| err.to_string())
+}
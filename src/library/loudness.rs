@@ -0,0 +1,462 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Loudness and dynamic range of whole albums: integrated loudness as in EBU R128 and the
+//! DR score of the Dynamic Range Meter, measured by decoding every track.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+use super::artwork;
+use super::decode;
+use crate::jobs::JobControl;
+
+/// Loudness ReplayGain 2.0 brings tracks to, in LUFS.
+pub const REFERENCE_LUFS: f64 = -18.0;
+
+/// Gating blocks are 400 ms long and overlap by 75%, so they start every 100 ms.
+const STEPS_PER_BLOCK: usize = 4;
+const STEPS_PER_SECOND: u32 = 10;
+/// Blocks quieter than this are left out of the integrated loudness entirely.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks this far below the loudness of the remaining ones are left out as well.
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// The Dynamic Range Meter looks at the signal in blocks of this many seconds.
+const DR_BLOCK_SECONDS: u32 = 3;
+/// Only the loudest fifth of those blocks counts towards the RMS level.
+const DR_LOUDEST_SHARE: f64 = 0.2;
+
+/// What was measured for an album.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AlbumLoudness {
+    /// Integrated loudness in LUFS, `None` for an album that's silent throughout.
+    pub lufs: Option<f64>,
+    /// The album's DR score: the rounded average of its tracks' scores.
+    pub dr: Option<u32>,
+}
+
+impl AlbumLoudness {
+    /// The gain that brings the album to [`REFERENCE_LUFS`], in dB.
+    pub fn album_gain(&self) -> Option<f64> {
+        self.lufs.map(|lufs| REFERENCE_LUFS - lufs)
+    }
+}
+
+/// An analysis running on another thread. Counts tracks.
+#[derive(Debug, Default)]
+pub struct LoudnessProgress {
+    pub total: AtomicUsize,
+    pub done: AtomicUsize,
+    pub control: JobControl,
+}
+
+/// Decodes every file of an album and measures it. Blocks until done, so run it off the UI
+/// thread.
+pub fn analyze_album(
+    files: Vec<PathBuf>,
+    progress: &LoudnessProgress,
+) -> Result<AlbumLoudness, String> {
+    progress.total.store(files.len(), Ordering::Relaxed);
+
+    let mut blocks = Vec::new();
+    let mut scores = Vec::new();
+
+    for file in files {
+        if !progress.control.checkpoint() {
+            return Err(String::from("Cancelled"));
+        }
+
+        let track = measure(&file, &progress.control)
+            .map_err(|err| format!("Failed to measure {}: {}", file.display(), err))?;
+        blocks.extend(track.blocks);
+        scores.extend(track.dr);
+
+        progress.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let dr = (!scores.is_empty()).then(|| {
+        (scores.iter().sum::<f64>() / scores.len() as f64)
+            .round()
+            .max(0.0) as u32
+    });
+
+    Ok(AlbumLoudness {
+        lufs: integrated(&blocks),
+        dr,
+    })
+}
+
+/// Integrated loudness of gating block powers, after both gates.
+fn integrated(blocks: &[f64]) -> Option<f64> {
+    let mean = |blocks: &[f64]| blocks.iter().sum::<f64>() / blocks.len() as f64;
+
+    let audible: Vec<f64> = blocks
+        .iter()
+        .copied()
+        .filter(|power| to_lufs(*power) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if audible.is_empty() {
+        return None;
+    }
+
+    let threshold = to_lufs(mean(&audible)) + RELATIVE_GATE_LU;
+    let gated: Vec<f64> = audible
+        .into_iter()
+        .filter(|power| to_lufs(*power) > threshold)
+        .collect();
+
+    (!gated.is_empty()).then(|| to_lufs(mean(&gated)))
+}
+
+fn to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// What one track contributes to its album's measurements.
+#[derive(Debug, Default)]
+struct TrackLoudness {
+    /// Mean square power of every gating block, K-weighted and summed over channels.
+    blocks: Vec<f64>,
+    dr: Option<f64>,
+}
+
+/// Decodes `path` to 32-bit float samples and feeds them through a [`Meter`].
+fn measure(path: &Path, control: &JobControl) -> Result<TrackLoudness, String> {
+    gst::init().map_err(|err| err.to_string())?;
+
+    let make = |name: &str| {
+        gst::ElementFactory::make(name)
+            .build()
+            .map_err(|err| err.to_string())
+    };
+
+    let filesrc = gst::ElementFactory::make("filesrc")
+        .property("location", path.to_string_lossy().to_string())
+        .build()
+        .map_err(|err| err.to_string())?;
+    let decodebin = make("decodebin")?;
+    let convert = make("audioconvert")?;
+    let filter = gst::ElementFactory::make("capsfilter")
+        .property(
+            "caps",
+            gst::Caps::builder("audio/x-raw")
+                .field("format", "F32LE")
+                .field("layout", "interleaved")
+                .build(),
+        )
+        .build()
+        .map_err(|err| err.to_string())?;
+    let sink = gst::ElementFactory::make("fakesink")
+        .property("sync", false)
+        .property("signal-handoffs", true)
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    let pipeline = gst::Pipeline::new();
+    pipeline
+        .add_many([&filesrc, &decodebin, &convert, &filter, &sink])
+        .map_err(|err| err.to_string())?;
+    filesrc.link(&decodebin).map_err(|err| err.to_string())?;
+    gst::Element::link_many([&convert, &filter, &sink]).map_err(|err| err.to_string())?;
+
+    // decodebin only knows its output pads once it has seen the stream.
+    let audio = convert.clone();
+    decodebin.connect_pad_added(move |_, pad| {
+        let Some(sink_pad) = audio.static_pad("sink") else {
+            return;
+        };
+        let is_audio = pad
+            .current_caps()
+            .and_then(|caps| caps.structure(0).map(|s| s.name().starts_with("audio/")))
+            .unwrap_or(false);
+
+        if is_audio && !sink_pad.is_linked() {
+            if let Err(err) = pad.link(&sink_pad) {
+                eprintln!("Failed to link decoder: {:?}", err);
+            }
+        }
+    });
+
+    // The meter is set up from the first buffer, once the format is known.
+    let meter: Arc<Mutex<Option<Meter>>> = Arc::new(Mutex::new(None));
+    let handoff_meter = meter.clone();
+    sink.connect("handoff", false, move |values| {
+        let buffer = values.get(1)?.get::<gst::Buffer>().ok()?;
+        let pad = values.get(2)?.get::<gst::Pad>().ok()?;
+        let mut meter = handoff_meter.lock().ok()?;
+
+        if meter.is_none() {
+            let caps = pad.current_caps()?;
+            let structure = caps.structure(0)?;
+            let rate = structure.get::<i32>("rate").ok()?;
+            let channels = structure.get::<i32>("channels").ok()?;
+            *meter = Some(Meter::new(rate.try_into().ok()?, channels.try_into().ok()?));
+        }
+
+        let map = buffer.map_readable().ok()?;
+        let samples = map
+            .as_slice()
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        meter.as_mut()?.push(samples);
+        None
+    });
+
+    pipeline
+        .set_state(gst::State::Playing)
+        .map_err(|err| err.to_string())?;
+    let result = decode::wait_for_end(&pipeline, control, |_| Ok(()));
+    let _ = pipeline.set_state(gst::State::Null);
+    result?;
+
+    let meter = meter
+        .lock()
+        .map_err(|err| err.to_string())?
+        .take()
+        .ok_or("No audio was decoded")?;
+    Ok(meter.finish())
+}
+
+/// A second order IIR filter.
+#[derive(Debug, Clone, Copy, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// The two stages of the K-weighting filter from ITU-R BS.1770, for any sample rate.
+fn k_weighting(rate: u32) -> [Biquad; 2] {
+    let rate = f64::from(rate);
+
+    // A high shelf modelling the acoustic effect of the head.
+    let (f0, gain, q) = (
+        1681.974_450_955_533,
+        3.999_843_853_973_347,
+        0.707_175_236_955_419_6,
+    );
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ..Default::default()
+    };
+
+    // A high pass cutting out what's felt rather than heard.
+    let (f0, q) = (38.135_470_876_024_44, 0.500_327_037_323_877_3);
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ..Default::default()
+    };
+
+    [shelf, high_pass]
+}
+
+/// Weight of a channel in the loudness sum. Surround channels count a little more, and the
+/// LFE channel of 5.1 audio not at all.
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channels, channel) {
+        (6, 3) => 0.0,
+        (5.., 3..) => 1.41,
+        _ => 1.0,
+    }
+}
+
+/// Collects what's needed for both measurements from interleaved samples.
+struct Meter {
+    channels: usize,
+    filters: Vec<[Biquad; 2]>,
+    /// Samples per channel in a 100 ms step.
+    step_length: usize,
+    step_position: usize,
+    step_energy: f64,
+    /// Energy of the steps making up the current gating block.
+    recent_steps: VecDeque<f64>,
+    blocks: Vec<f64>,
+    /// Samples per channel in a DR block.
+    dr_block_length: usize,
+    dr_position: usize,
+    /// Per channel: the running sum of squares and peak of the current DR block, and the
+    /// RMS and peak of every finished one.
+    dr_sums: Vec<f64>,
+    dr_peak: Vec<f64>,
+    dr_rms: Vec<Vec<f64>>,
+    dr_peaks: Vec<Vec<f64>>,
+    channel: usize,
+}
+
+impl Meter {
+    fn new(rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        Meter {
+            channels,
+            filters: vec![k_weighting(rate); channels],
+            step_length: (rate / STEPS_PER_SECOND).max(1) as usize,
+            step_position: 0,
+            step_energy: 0.0,
+            recent_steps: VecDeque::with_capacity(STEPS_PER_BLOCK),
+            blocks: Vec::new(),
+            dr_block_length: (rate * DR_BLOCK_SECONDS).max(1) as usize,
+            dr_position: 0,
+            dr_sums: vec![0.0; channels],
+            dr_peak: vec![0.0; channels],
+            dr_rms: vec![Vec::new(); channels],
+            dr_peaks: vec![Vec::new(); channels],
+            channel: 0,
+        }
+    }
+
+    fn push(&mut self, samples: impl Iterator<Item = f32>) {
+        for sample in samples {
+            let sample = f64::from(sample);
+            let channel = self.channel;
+
+            let [shelf, high_pass] = &mut self.filters[channel];
+            let weighted = high_pass.process(shelf.process(sample));
+            self.step_energy += channel_weight(channel, self.channels) * weighted * weighted;
+
+            self.dr_sums[channel] += sample * sample;
+            self.dr_peak[channel] = self.dr_peak[channel].max(sample.abs());
+
+            self.channel += 1;
+            if self.channel == self.channels {
+                self.channel = 0;
+                self.end_frame();
+            }
+        }
+    }
+
+    fn end_frame(&mut self) {
+        self.step_position += 1;
+        if self.step_position == self.step_length {
+            self.end_step();
+        }
+
+        self.dr_position += 1;
+        if self.dr_position == self.dr_block_length {
+            self.end_dr_block();
+        }
+    }
+
+    fn end_step(&mut self) {
+        if self.recent_steps.len() == STEPS_PER_BLOCK {
+            self.recent_steps.pop_front();
+        }
+        self.recent_steps.push_back(self.step_energy);
+        if self.recent_steps.len() == STEPS_PER_BLOCK {
+            let energy: f64 = self.recent_steps.iter().sum();
+            self.blocks
+                .push(energy / (STEPS_PER_BLOCK * self.step_length) as f64);
+        }
+
+        self.step_position = 0;
+        self.step_energy = 0.0;
+    }
+
+    fn end_dr_block(&mut self) {
+        for channel in 0..self.channels {
+            let rms = (2.0 * self.dr_sums[channel] / self.dr_position as f64).sqrt();
+            self.dr_rms[channel].push(rms);
+            self.dr_peaks[channel].push(self.dr_peak[channel]);
+        }
+
+        self.dr_position = 0;
+        self.dr_sums.fill(0.0);
+        self.dr_peak.fill(0.0);
+    }
+
+    fn finish(mut self) -> TrackLoudness {
+        if self.dr_position > 0 {
+            self.end_dr_block();
+        }
+
+        let scores: Vec<f64> = (0..self.channels)
+            .filter_map(|channel| dr_score(&mut self.dr_rms[channel], &mut self.dr_peaks[channel]))
+            .collect();
+        let dr = (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64);
+
+        TrackLoudness {
+            blocks: self.blocks,
+            dr,
+        }
+    }
+}
+
+/// DR of one channel: the second highest block peak over the RMS of the loudest blocks, in dB.
+fn dr_score(rms: &mut [f64], peaks: &mut [f64]) -> Option<f64> {
+    rms.sort_by(|a, b| b.total_cmp(a));
+    peaks.sort_by(|a, b| b.total_cmp(a));
+
+    let loudest = ((rms.len() as f64 * DR_LOUDEST_SHARE).round() as usize).max(1);
+    let loudest = rms.get(..loudest)?;
+    let level = (loudest.iter().map(|rms| rms * rms).sum::<f64>() / loudest.len() as f64).sqrt();
+    let peak = peaks.get(1).or(peaks.first()).copied()?;
+
+    (level > 0.0 && peak > 0.0).then(|| 20.0 * (peak / level).log10())
+}
+
+/// Folder holding measured albums, next to the art cache.
+fn cache_dir() -> PathBuf {
+    artwork::cache_dir()
+        .parent()
+        .map(|data_dir| data_dir.join("loudness"))
+        .unwrap_or_else(|| PathBuf::from("loudness"))
+}
+
+fn cache_path(album_key: &str) -> PathBuf {
+    cache_dir().join(artwork::hash_key(album_key))
+}
+
+/// What was measured for the album with `album_key` before, if anything.
+pub fn read_cached(album_key: &str) -> Option<AlbumLoudness> {
+    let contents = fs::read_to_string(cache_path(album_key)).ok()?;
+    let (lufs, dr) = contents.trim().split_once(' ')?;
+
+    Some(AlbumLoudness {
+        lufs: lufs.parse().ok(),
+        dr: dr.parse().ok(),
+    })
+}
+
+/// Keeps the measurements of the album with `album_key` so they're only taken once.
+pub fn cache(album_key: &str, loudness: &AlbumLoudness) -> Result<(), String> {
+    let field = |value: Option<String>| value.unwrap_or_else(|| String::from("-"));
+
+    fs::create_dir_all(cache_dir()).map_err(|err| err.to_string())?;
+    fs::write(
+        cache_path(album_key),
+        format!(
+            "{} {}\n",
+            field(loudness.lufs.map(|lufs| lufs.to_string())),
+            field(loudness.dr.map(|dr| dr.to_string()))
+        ),
+    )
+    .map_err(|err| err.to_string())
+}
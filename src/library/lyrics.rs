@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Lyrics from `.lrc` files, tags, the lyrics cache or LRCLIB, plain or synced.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use lofty::prelude::TaggedFileExt;
+use lofty::tag::ItemKey;
+use serde::{Deserialize, Serialize};
+
+use super::artwork;
+use super::model::MusicFile;
+
+/// LRCLIB's lookup endpoint, which matches on artist, title, album and duration.
+const LRCLIB_GET: &str = "https://lrclib.net/api/get";
+/// LRCLIB asks clients to identify themselves.
+const USER_AGENT: &str = "Jams (https://github.com/benfuddled/Jams)";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A line of lyrics. Synced lyrics have a time on every line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LyricLine {
+    pub time: Option<Duration>,
+    pub text: String,
+}
+
+/// Parsed lyrics, in the order they're sung.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lyrics {
+    pub lines: Vec<LyricLine>,
+}
+
+impl Lyrics {
+    /// Parses LRC, falling back to plain text for lines without timestamps.
+    /// Metadata tags such as `[ar:Artist]` are skipped.
+    pub fn parse(text: &str) -> Self {
+        let mut lines = Vec::new();
+
+        for line in text.lines() {
+            let mut rest = line.trim();
+            let mut times = Vec::new();
+            let mut tagged = false;
+
+            // A line may carry several timestamps when it's sung more than once.
+            while let Some(tag) = rest.strip_prefix('[') {
+                let Some(close) = tag.find(']') else {
+                    break;
+                };
+                tagged = true;
+                if let Some(time) = parse_timestamp(&tag[..close]) {
+                    times.push(time);
+                }
+                rest = &tag[close + 1..];
+            }
+
+            if tagged && times.is_empty() {
+                continue;
+            }
+
+            if times.is_empty() {
+                lines.push(LyricLine {
+                    time: None,
+                    text: rest.to_string(),
+                });
+            }
+            for time in times {
+                lines.push(LyricLine {
+                    time: Some(time),
+                    text: rest.trim().to_string(),
+                });
+            }
+        }
+
+        // Untimed lines in an LRC file are blank spacers, not lyrics.
+        if lines.iter().any(|line| line.time.is_some()) {
+            lines.retain(|line| line.time.is_some());
+            lines.sort_by_key(|line| line.time);
+        }
+
+        Lyrics { lines }
+    }
+
+    pub fn is_synced(&self) -> bool {
+        !self.lines.is_empty() && self.lines.iter().all(|line| line.time.is_some())
+    }
+
+    /// Index of the line being sung at `position`, for synced lyrics.
+    pub fn line_at(&self, position: Duration) -> Option<usize> {
+        if !self.is_synced() {
+            return None;
+        }
+
+        self.lines
+            .iter()
+            .rposition(|line| line.time.is_some_and(|time| time <= position))
+    }
+}
+
+/// `mm:ss`, `mm:ss.xx` or `mm:ss.xxx`.
+fn parse_timestamp(tag: &str) -> Option<Duration> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: u64 = minutes.trim().parse().ok()?;
+    let seconds: f64 = seconds.trim().parse().ok()?;
+
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+
+    Some(Duration::from_secs(minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+/// Folder holding lyrics fetched online, next to the art cache.
+fn cache_dir() -> PathBuf {
+    artwork::cache_dir()
+        .parent()
+        .map(|data_dir| data_dir.join("lyrics"))
+        .unwrap_or_else(|| PathBuf::from("lyrics"))
+}
+
+fn cache_path(file: &Path) -> PathBuf {
+    cache_dir().join(format!(
+        "{}.lrc",
+        artwork::hash_key(&artwork::track_key(file))
+    ))
+}
+
+/// Lyrics edited by hand but not saved yet, kept so they survive a crash or a quit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LyricsDraft {
+    /// The audio file the lyrics are for.
+    pub track: PathBuf,
+    pub text: String,
+}
+
+/// The `.lrc` file that sits next to the audio file at `file`.
+pub fn sidecar_path(file: &Path) -> PathBuf {
+    file.with_extension("lrc")
+}
+
+/// Lyrics that don't need the network: an `.lrc` next to the file, then the tags, then
+/// whatever was fetched before.
+pub fn read_local(file: &Path) -> Option<String> {
+    if let Ok(text) = fs::read_to_string(sidecar_path(file)) {
+        return Some(text);
+    }
+
+    let embedded = lofty::read_from_path(file).ok().and_then(|tagged_file| {
+        tagged_file
+            .tags()
+            .iter()
+            .find_map(|tag| tag.get_string(&ItemKey::Lyrics).map(str::to_string))
+    });
+    if embedded
+        .as_ref()
+        .is_some_and(|text| !text.trim().is_empty())
+    {
+        return embedded;
+    }
+
+    fs::read_to_string(cache_path(file)).ok()
+}
+
+/// Keeps lyrics fetched for `file` so they're only downloaded once.
+pub fn cache(file: &Path, text: &str) -> Result<(), String> {
+    fs::create_dir_all(cache_dir()).map_err(|err| err.to_string())?;
+    fs::write(cache_path(file), text).map_err(|err| err.to_string())
+}
+
+/// Keeps the lyrics fetched for the audio file at `from` once it moved to `to`.
+pub fn move_cached(from: &Path, to: &Path) -> std::io::Result<()> {
+    let cached = cache_path(from);
+    if !cached.is_file() {
+        return Ok(());
+    }
+    fs::rename(cached, cache_path(to))
+}
+
+/// Saves lyrics edited by the user as an `.lrc` next to the audio file, where they take
+/// precedence over everything else.
+pub fn save_sidecar(file: &Path, text: &str) -> Result<PathBuf, String> {
+    let path = sidecar_path(file);
+    fs::write(&path, text).map_err(|err| err.to_string())?;
+    Ok(path)
+}
+
+/// What a lookup is matched on.
+#[derive(Debug, Clone)]
+pub struct LyricsQuery {
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    pub duration: Duration,
+}
+
+impl From<&MusicFile> for LyricsQuery {
+    fn from(file: &MusicFile) -> Self {
+        LyricsQuery {
+            artist: file.artist.clone(),
+            title: file.track_title.clone(),
+            album: file.album.clone(),
+            duration: file.duration,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LrclibRecord {
+    synced_lyrics: Option<String>,
+    plain_lyrics: Option<String>,
+}
+
+/// Looks the track up on LRCLIB, preferring synced lyrics. Blocks until the request is
+/// done, and returns `Ok(None)` when LRCLIB has nothing for the track.
+pub fn fetch(query: &LyricsQuery) -> Result<Option<String>, String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build();
+
+    let response = agent
+        .get(LRCLIB_GET)
+        .query("artist_name", &query.artist)
+        .query("track_name", &query.title)
+        .query("album_name", &query.album)
+        .query("duration", &query.duration.as_secs().to_string())
+        .call();
+
+    let record: LrclibRecord = match response {
+        Ok(response) => response.into_json().map_err(|err| err.to_string())?,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(err) => return Err(err.to_string()),
+    };
+
+    Ok(record
+        .synced_lyrics
+        .or(record.plain_lyrics)
+        .filter(|text| !text.trim().is_empty()))
+}
@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The music library: scanning folders, the track and album model, search and
+//! persisting where the library lives. Nothing in here depends on the GUI.
+
+pub mod archive;
+pub mod artwork;
+pub mod collation;
+pub mod cues;
+pub mod date;
+pub mod decode;
+pub mod dsd;
+pub mod edition;
+pub mod journal;
+pub mod loudness;
+pub mod lyrics;
+pub mod model;
+pub mod musical_key;
+pub mod musicbrainz;
+pub mod organize;
+pub mod persistence;
+pub mod playlist;
+pub mod scan;
+pub mod search;
+pub mod similar;
+pub mod tempo;
+pub mod track_number;
+pub mod verify;
+pub mod watch;
+
+pub use model::{Album, MusicFile, TrackSource};
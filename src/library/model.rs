@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use url::Url;
+
+use super::archive::ArchiveEntry;
+use super::date::ReleaseDate;
+use super::musical_key::MusicalKey;
+
+/// A single audio file found while scanning, along with the tags read from it.
+#[derive(Debug, Clone, Eq, Ord, PartialEq, PartialOrd)]
+pub struct MusicFile {
+    pub album_artist: String,
+    /// Album artist as it should be sorted, from the `ALBUMARTISTSORT` tag. Often empty.
+    pub album_artist_sort: String,
+    /// What the album artist sorts by, from [`super::collation::sort_key`].
+    pub album_artist_key: String,
+    pub album: String,
+    /// 0 when the file has no disc number.
+    pub disc_number: u16,
+    /// 0 when the file has no track number.
+    pub track_number: u16,
+    /// Side of a record, for positions tagged like `B2`. Numbers start over on every side.
+    pub side: Option<char>,
+    /// Tagged as part of a gapless sequence, as iTunes does with `ITUNESGAPLESS`.
+    pub gapless: bool,
+    pub artist: String,
+    /// Artist as it should be sorted, from the `ARTISTSORT` or `TSOP` tag. Often empty.
+    pub artist_sort: String,
+    /// What the artist sorts by, from [`super::collation::sort_key`].
+    pub artist_key: String,
+    pub track_title: String,
+    /// The title folded for sorting and comparing, from [`super::collation::fold`].
+    pub title_key: String,
+    pub duration: Duration,
+    /// Number of audio channels, 0 when the file doesn't say.
+    pub channels: u8,
+    /// When this edition was released.
+    pub release_date: Option<ReleaseDate>,
+    /// When the music was first released, for reissues and remasters.
+    pub original_date: Option<ReleaseDate>,
+    /// MusicBrainz release group, shared by every edition of an album. Often empty.
+    pub release_group_id: String,
+    /// Genre as tagged, empty when the file doesn't say.
+    pub genre: String,
+    /// Record label or publisher, such as `Warp`. Empty when the file doesn't say.
+    pub label: String,
+    /// Language of the lyrics, usually as an ISO 639-2 code like `jpn`. Lowercase, and empty
+    /// when the file doesn't say.
+    pub language: String,
+    /// Tempo in beats per minute, from the `BPM` tag or measured with
+    /// [`super::tempo::detect_all`].
+    pub bpm: Option<u16>,
+    /// Key the track is in, from the `INITIALKEY` tag.
+    pub key: Option<MusicalKey>,
+    /// Where the file plays from. For tracks in an archive, that's where it's extracted to
+    /// before it's played, see [`super::archive::extract`].
+    pub saved_path: PathBuf,
+    /// Device and inode of the file, which are the same whichever path it's reached by.
+    /// `None` for files that aren't on disk yet, such as tracks in archives.
+    pub inode: Option<(u64, u64)>,
+    /// Size of the file in bytes, 0 when unknown.
+    pub size: u64,
+    /// The zip archive the track comes from, if it isn't a plain file.
+    pub archive: Option<ArchiveEntry>,
+    pub uri: String,
+    pub id: usize,
+}
+
+/// Tracks grouped by album and album artist.
+#[derive(Debug, Clone)]
+pub struct Album {
+    pub album_artist: String,
+    /// [`MusicFile::album_artist_key`] of the album's tracks.
+    pub album_artist_key: String,
+    pub album: String,
+    pub cached_cover_path: String,
+    /// The file at `cached_cover_path` exists. Covers are cached in the background, and
+    /// one that failed to load isn't tried again.
+    pub cover_ready: bool,
+    /// The earliest [`MusicFile::sort_date`] of the album's tracks.
+    pub date: Option<ReleaseDate>,
+    /// Albums with the same group are editions of each other, see
+    /// [`super::edition::version_group`].
+    pub version_group: String,
+    /// [`MusicFile::language`] of the album's tracks, each once. Untagged tracks add none.
+    pub languages: Vec<String>,
+    pub tracks: Vec<usize>, // TODO: refactor to use arc
+}
+
+/// Where a track is played from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackSource {
+    /// A file on this device.
+    Local,
+    /// A stream that needs the network to play.
+    Network,
+}
+
+impl MusicFile {
+    /// Whether playing this track goes over the network, going by the scheme of its URI.
+    pub fn source(&self) -> TrackSource {
+        match Url::parse(&self.uri) {
+            Ok(url) if url.scheme() != "file" => TrackSource::Network,
+            _ => TrackSource::Local,
+        }
+    }
+
+    /// The date used for sorting and browsing: the original release date when known.
+    pub fn sort_date(&self) -> Option<ReleaseDate> {
+        self.original_date.or(self.release_date)
+    }
+}
+
+// TODO: MAKE THESE SOME()
+impl Default for MusicFile {
+    fn default() -> Self {
+        MusicFile {
+            saved_path: PathBuf::new(),
+            inode: None,
+            size: 0,
+            archive: None,
+            uri: "/uri-does-not-exist".to_string(),
+            track_title: "Invalid Title".to_string(),
+            title_key: String::new(),
+            disc_number: 0,
+            track_number: 0,
+            side: None,
+            gapless: false,
+            duration: Duration::new(0, 0),
+            channels: 0,
+            artist: "Invalid Artist".to_string(),
+            artist_sort: String::new(),
+            artist_key: String::new(),
+            album: "Invalid Album".to_string(),
+            album_artist: "Invalid Album Artist".to_string(),
+            album_artist_sort: String::new(),
+            album_artist_key: String::new(),
+            release_date: None,
+            original_date: None,
+            release_group_id: String::new(),
+            genre: String::new(),
+            label: String::new(),
+            language: String::new(),
+            bpm: None,
+            key: None,
+            id: 0,
+        }
+    }
+}
@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Musical keys from `INITIALKEY` tags, shown the way musicians write them or in the
+//! Camelot notation DJs use to find tracks that mix well.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fl;
+
+/// Names of the pitch classes from C, spelled the way keys usually are.
+const MAJOR_NAMES: [&str; 12] = [
+    "C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+];
+const MINOR_NAMES: [&str; 12] = [
+    "C", "C#", "D", "Eb", "E", "F", "F#", "G", "G#", "A", "Bb", "B",
+];
+
+/// A key: its tonic as a pitch class, 0 for C up to 11 for B, and its mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MusicalKey {
+    pub tonic: u8,
+    pub minor: bool,
+}
+
+/// How keys are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyNotation {
+    /// Like `Am` or `F#`.
+    #[default]
+    Standard,
+    /// Like `8A`. Neighbouring numbers, and the same number with the other letter, mix
+    /// well.
+    Camelot,
+}
+
+impl KeyNotation {
+    pub const ALL: [KeyNotation; 2] = [KeyNotation::Standard, KeyNotation::Camelot];
+
+    /// Labels for a dropdown, in the same order as [`KeyNotation::ALL`].
+    pub fn names() -> &'static [String] {
+        static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+        NAMES.get_or_init(|| vec![fl!("key-notation-standard"), fl!("key-notation-camelot")])
+    }
+}
+
+impl MusicalKey {
+    /// Reads a key tag. Taggers write keys like `Am`, `A minor`, `Bbm`, `F♯` or, in
+    /// Camelot notation, `8A`.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let tag = tag.trim();
+
+        if let Some(key) = Self::parse_camelot(tag) {
+            return Some(key);
+        }
+
+        let mut chars = tag.chars();
+        let letter = chars.next()?.to_ascii_uppercase();
+        let natural: i8 = match letter {
+            'C' => 0,
+            'D' => 2,
+            'E' => 4,
+            'F' => 5,
+            'G' => 7,
+            'A' => 9,
+            'B' => 11,
+            _ => return None,
+        };
+
+        let rest = chars.as_str();
+        let (shift, mode) = if let Some(mode) = rest.strip_prefix(['#', '♯']) {
+            (1, mode)
+        } else if let Some(mode) = rest.strip_prefix(['b', '♭']) {
+            (-1, mode)
+        } else {
+            (0, rest)
+        };
+
+        let minor = match mode.trim().to_lowercase().as_str() {
+            "" | "maj" | "major" | "dur" => false,
+            "m" | "min" | "minor" | "moll" => true,
+            _ => return None,
+        };
+
+        Some(MusicalKey {
+            tonic: (natural + shift).rem_euclid(12) as u8,
+            minor,
+        })
+    }
+
+    /// Reads Camelot notation: a number from 1 to 12, then `A` for minor or `B` for major.
+    fn parse_camelot(tag: &str) -> Option<Self> {
+        let split = tag.find(|c: char| !c.is_ascii_digit())?;
+        let (number, letter) = tag.split_at(split);
+        let number: i32 = number.parse().ok().filter(|n| (1..=12).contains(n))?;
+        let minor = match letter {
+            "A" | "a" => true,
+            "B" | "b" => false,
+            _ => return None,
+        };
+
+        // Each step around the wheel is a fifth, and 8B is C major. Sevens undo
+        // themselves, since 7 × 7 is 1 more than a multiple of 12.
+        let major = ((number - 8) * 7).rem_euclid(12);
+        let tonic = if minor { (major + 9) % 12 } else { major };
+
+        Some(MusicalKey {
+            tonic: tonic as u8,
+            minor,
+        })
+    }
+
+    /// The key written in `notation`.
+    pub fn display(self, notation: KeyNotation) -> String {
+        match notation {
+            KeyNotation::Standard if self.minor => {
+                format!("{}m", MINOR_NAMES[usize::from(self.tonic % 12)])
+            }
+            KeyNotation::Standard => MAJOR_NAMES[usize::from(self.tonic % 12)].to_string(),
+            KeyNotation::Camelot => {
+                // Minor keys sit next to their relative major, three semitones up.
+                let major = if self.minor {
+                    (self.tonic + 3) % 12
+                } else {
+                    self.tonic % 12
+                };
+                let number = (u32::from(major) * 7 + 7) % 12 + 1;
+                format!("{}{}", number, if self.minor { 'A' } else { 'B' })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(tonic: u8, minor: bool) -> Option<MusicalKey> {
+        Some(MusicalKey { tonic, minor })
+    }
+
+    #[test]
+    fn reads_standard_notation() {
+        assert_eq!(MusicalKey::parse("C"), key(0, false));
+        assert_eq!(MusicalKey::parse("Am"), key(9, true));
+        assert_eq!(MusicalKey::parse("F#"), key(6, false));
+        assert_eq!(MusicalKey::parse("Bbm"), key(10, true));
+        assert_eq!(MusicalKey::parse(" ebm "), key(3, true));
+    }
+
+    #[test]
+    fn reads_written_out_modes_and_signs() {
+        assert_eq!(MusicalKey::parse("A minor"), key(9, true));
+        assert_eq!(MusicalKey::parse("D Major"), key(2, false));
+        assert_eq!(MusicalKey::parse("F♯ moll"), key(6, true));
+        assert_eq!(MusicalKey::parse("E♭ dur"), key(3, false));
+    }
+
+    #[test]
+    fn wraps_around_the_octave() {
+        assert_eq!(MusicalKey::parse("Cb"), key(11, false));
+        assert_eq!(MusicalKey::parse("B#"), key(0, false));
+    }
+
+    #[test]
+    fn reads_camelot_notation() {
+        assert_eq!(MusicalKey::parse("8B"), key(0, false));
+        assert_eq!(MusicalKey::parse("8A"), key(9, true));
+        assert_eq!(MusicalKey::parse("1B"), key(11, false));
+        assert_eq!(MusicalKey::parse("5a"), key(0, true));
+        assert_eq!(MusicalKey::parse("12B"), key(4, false));
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        for tag in ["", "H", "Am7", "C minor-ish", "0A", "13B", "8C", "B8"] {
+            assert_eq!(MusicalKey::parse(tag), None, "{:?}", tag);
+        }
+    }
+
+    #[test]
+    fn writes_keys_in_either_notation() {
+        let a_minor = MusicalKey {
+            tonic: 9,
+            minor: true,
+        };
+        assert_eq!(a_minor.display(KeyNotation::Standard), "Am");
+        assert_eq!(a_minor.display(KeyNotation::Camelot), "8A");
+
+        let d_flat = MusicalKey {
+            tonic: 1,
+            minor: false,
+        };
+        assert_eq!(d_flat.display(KeyNotation::Standard), "Db");
+        assert_eq!(d_flat.display(KeyNotation::Camelot), "3B");
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        for tonic in 0..12 {
+            for minor in [false, true] {
+                let key = MusicalKey { tonic, minor };
+                for notation in KeyNotation::ALL {
+                    assert_eq!(MusicalKey::parse(&key.display(notation)), Some(key));
+                }
+            }
+        }
+    }
+}
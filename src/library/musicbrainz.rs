@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! An artist's albums as MusicBrainz lists them, to spot the ones missing from the library.
+
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::edition;
+
+const ARTIST_SEARCH: &str = "https://musicbrainz.org/ws/2/artist";
+const RELEASE_GROUPS: &str = "https://musicbrainz.org/ws/2/release-group";
+/// MusicBrainz blocks clients that don't say who they are.
+const USER_AGENT: &str = "Jams (https://github.com/benfuddled/Jams)";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// MusicBrainz allows one request per second from each client.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+/// The most release groups MusicBrainz returns for one request.
+const PAGE_SIZE: usize = 100;
+
+/// A release group: an album, EP or single, whatever editions it came out in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub title: String,
+    /// "Album", "EP" or "Single", followed by qualifiers such as "Live" or "Compilation".
+    pub kind: String,
+    pub year: Option<u16>,
+}
+
+/// The releases of an artist, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discography {
+    pub releases: Vec<Release>,
+}
+
+/// Whether `release` matches one of `owned`, a list of album titles from the library.
+/// Edition notes like "(Deluxe Edition)" and differences in case and punctuation are
+/// ignored.
+pub fn is_owned(release: &Release, owned: &[String]) -> bool {
+    let title = edition::comparable(&release.title);
+    owned
+        .iter()
+        .any(|album| edition::comparable(album) == title)
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtistSearch {
+    artists: Vec<ArtistRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtistRecord {
+    id: String,
+    score: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseGroupBrowse {
+    #[serde(rename = "release-groups")]
+    release_groups: Vec<ReleaseGroupRecord>,
+    #[serde(rename = "release-group-count")]
+    count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseGroupRecord {
+    title: String,
+    #[serde(rename = "primary-type")]
+    primary_type: Option<String>,
+    #[serde(rename = "secondary-types", default)]
+    secondary_types: Vec<String>,
+    #[serde(rename = "first-release-date", default)]
+    first_release_date: String,
+}
+
+/// Looks `artist` up on MusicBrainz and lists their albums and EPs. Blocks until every
+/// request is done, and returns `Ok(None)` when MusicBrainz doesn't know the artist.
+pub fn fetch_discography(artist: &str) -> Result<Option<Discography>, String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build();
+
+    let search: ArtistSearch = agent
+        .get(ARTIST_SEARCH)
+        .query("query", &format!("artist:\"{}\"", artist.replace('"', "")))
+        .query("limit", "1")
+        .query("fmt", "json")
+        .call()
+        .map_err(|err| err.to_string())?
+        .into_json()
+        .map_err(|err| err.to_string())?;
+
+    // Anything below a near-certain match is likely a different artist with a similar name.
+    let Some(record) = search.artists.into_iter().find(|record| record.score >= 90) else {
+        return Ok(None);
+    };
+
+    let mut releases = Vec::new();
+    loop {
+        thread::sleep(REQUEST_INTERVAL);
+
+        let page: ReleaseGroupBrowse = agent
+            .get(RELEASE_GROUPS)
+            .query("artist", &record.id)
+            .query("type", "album|ep")
+            .query("limit", &PAGE_SIZE.to_string())
+            .query("offset", &releases.len().to_string())
+            .query("fmt", "json")
+            .call()
+            .map_err(|err| err.to_string())?
+            .into_json()
+            .map_err(|err| err.to_string())?;
+
+        let fetched = page.release_groups.len();
+        releases.extend(page.release_groups.into_iter().map(|group| {
+            let kind = group
+                .primary_type
+                .into_iter()
+                .chain(group.secondary_types)
+                .collect::<Vec<String>>()
+                .join(", ");
+
+            Release {
+                title: group.title,
+                kind,
+                year: group
+                    .first_release_date
+                    .get(..4)
+                    .and_then(|year| year.parse().ok()),
+            }
+        }));
+
+        if fetched == 0 || releases.len() >= page.count {
+            break;
+        }
+    }
+
+    releases.sort_by(|a, b| a.year.cmp(&b.year).then_with(|| a.title.cmp(&b.title)));
+
+    Ok(Some(Discography { releases }))
+}
@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Renames and moves library files into a folder layout built from their tags.
+
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::lyrics;
+use super::model::MusicFile;
+
+/// Layout used until the user picks another one. The file extension is always kept.
+pub const DEFAULT_PATTERN: &str = "{album_artist}/{album}/{track} - {title}";
+
+/// A file that would end up somewhere else when the library is organized.
+#[derive(Debug, Clone)]
+pub struct PlannedMove {
+    pub id: usize,
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Fills in the placeholders of a single path segment. Unknown placeholders are kept as is,
+/// and separators left dangling by empty ones, like the dash after a missing track number,
+/// are trimmed off.
+fn render_segment(segment: &str, file: &MusicFile) -> String {
+    let mut rendered = String::new();
+    let mut rest = segment;
+    let mut left_empty = false;
+
+    while let Some(open) = rest.find('{') {
+        rendered.push_str(&rest[..open]);
+
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        let placeholder = &rest[open + 1..open + close];
+        let length = rendered.len();
+
+        match placeholder {
+            "album_artist" => rendered.push_str(&file.album_artist),
+            "artist" => rendered.push_str(&file.artist),
+            "album" => rendered.push_str(&file.album),
+            "title" => rendered.push_str(&file.track_title),
+            "track" => match (file.side, file.track_number) {
+                (Some(side), 0) => rendered.push(side),
+                (Some(side), number) => rendered.push_str(&format!("{}{}", side, number)),
+                (None, 0) => {}
+                (None, number) => rendered.push_str(&format!("{:02}", number)),
+            },
+            "year" => {
+                if let Some(date) = file.release_date {
+                    rendered.push_str(&date.year.to_string());
+                }
+            }
+            "original_year" => {
+                if let Some(date) = file.sort_date() {
+                    rendered.push_str(&date.year.to_string());
+                }
+            }
+            _ => rendered.push_str(&rest[open..=open + close]),
+        }
+        left_empty |= rendered.len() == length;
+
+        rest = &rest[open + close + 1..];
+    }
+
+    rendered.push_str(rest);
+    if left_empty {
+        let trimmed =
+            rendered.trim_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '–' | '—'));
+        return trimmed.to_string();
+    }
+    rendered
+}
+
+/// Makes a tag value safe to use as a single file or folder name, on the FAT and exFAT
+/// drives and Windows shares libraries are often kept on too.
+pub fn sanitize(component: &str) -> String {
+    let cleaned: String = component
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    // Leading dots would hide the file or turn it into `.`/`..`, and Windows drops trailing
+    // ones.
+    let cleaned = cleaned.trim().trim_matches('.').trim();
+
+    if cleaned.is_empty() {
+        String::from("Unknown")
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// Where `file` belongs relative to the library root under `pattern`.
+pub fn render(pattern: &str, file: &MusicFile) -> PathBuf {
+    let mut path = PathBuf::new();
+
+    for segment in pattern.split('/').filter(|segment| !segment.is_empty()) {
+        path.push(sanitize(&render_segment(segment, file)));
+    }
+
+    // Appended rather than `set_extension`, which would eat anything after a dot in the title.
+    if let (Some(name), Some(extension)) = (path.file_name(), file.saved_path.extension()) {
+        let mut name = name.to_os_string();
+        name.push(".");
+        name.push(extension);
+        path.set_file_name(name);
+    }
+
+    path
+}
+
+/// `target` with " (n)" inserted before the extension.
+fn with_suffix(target: &Path, n: usize) -> PathBuf {
+    let mut name = OsString::from(target.file_stem().unwrap_or_default());
+    name.push(format!(" ({})", n));
+
+    if let Some(extension) = target.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+
+    target.with_file_name(name)
+}
+
+/// Works out every move needed to bring `files` under `root` in line with `pattern`,
+/// without touching the disk. Targets that are already taken get a numbered suffix.
+pub fn plan(root: &Path, pattern: &str, files: &[MusicFile]) -> Vec<PlannedMove> {
+    let mut taken: HashSet<PathBuf> = HashSet::new();
+    let mut moves = Vec::new();
+
+    for file in files {
+        let target = root.join(render(pattern, file));
+        let mut candidate = target.clone();
+        let mut n = 2;
+
+        while taken.contains(&candidate) || (candidate != file.saved_path && candidate.exists()) {
+            candidate = with_suffix(&target, n);
+            n += 1;
+        }
+
+        taken.insert(candidate.clone());
+
+        if candidate != file.saved_path {
+            moves.push(PlannedMove {
+                id: file.id,
+                from: file.saved_path.clone(),
+                to: candidate,
+            });
+        }
+    }
+
+    moves
+}
+
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    // The plan steered clear of taken paths, but something may have turned up there since,
+    // and renaming over it would lose it.
+    if to.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Renaming fails across filesystems, in which case the file is copied instead.
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+
+    Ok(())
+}
+
+/// Takes the lyrics kept for the file at `from` along to `to`: the `.lrc` next to it and
+/// the ones fetched for it.
+fn move_lyrics(from: &Path, to: &Path) -> io::Result<()> {
+    let sidecar = lyrics::sidecar_path(from);
+    if sidecar.is_file() {
+        move_file(&sidecar, &lyrics::sidecar_path(to))?;
+    }
+    lyrics::move_cached(from, to)
+}
+
+/// Removes the folders `path` was in, up to `root`, for as long as they're empty.
+fn remove_empty_parents(path: &Path, root: &Path) {
+    let mut parent = path.parent();
+
+    while let Some(dir) = parent {
+        if dir == root || !dir.starts_with(root) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        parent = dir.parent();
+    }
+}
+
+/// Carries out `moves`, taking each file's lyrics along. Returns the moves that succeeded
+/// and a message for each that didn't.
+pub fn apply(root: &Path, moves: Vec<PlannedMove>) -> (Vec<PlannedMove>, Vec<String>) {
+    let mut done = Vec::new();
+    let mut errors = Vec::new();
+
+    for planned in moves {
+        match move_file(&planned.from, &planned.to) {
+            Ok(()) => {
+                if let Err(err) = move_lyrics(&planned.from, &planned.to) {
+                    errors.push(format!("Lyrics of {}: {}", planned.from.display(), err));
+                }
+                remove_empty_parents(&planned.from, root);
+                done.push(planned);
+            }
+            Err(err) => {
+                errors.push(format!("{}: {}", planned.from.display(), err));
+            }
+        }
+    }
+
+    (done, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::date::ReleaseDate;
+
+    fn track(id: usize, track_number: u16, title: &str) -> MusicFile {
+        MusicFile {
+            album_artist: "The Hollows".to_string(),
+            artist: "The Hollows".to_string(),
+            album: "Low Tide".to_string(),
+            track_number,
+            track_title: title.to_string(),
+            saved_path: PathBuf::from(format!("/downloads/{}.flac", id)),
+            id,
+            ..MusicFile::default()
+        }
+    }
+
+    #[test]
+    fn renders_the_default_pattern() {
+        assert_eq!(
+            render(DEFAULT_PATTERN, &track(0, 3, "Undertow")),
+            Path::new("The Hollows/Low Tide/03 - Undertow.flac")
+        );
+    }
+
+    #[test]
+    fn renders_sides_and_years() {
+        let file = MusicFile {
+            side: Some('B'),
+            release_date: ReleaseDate::parse("2004-06-21"),
+            original_date: ReleaseDate::parse("1994"),
+            ..track(0, 2, "Undertow")
+        };
+
+        assert_eq!(
+            render("{year} ({original_year})/{track} {title}", &file),
+            Path::new("2004 (1994)/B2 Undertow.flac")
+        );
+    }
+
+    #[test]
+    fn leaves_out_a_missing_track_number() {
+        assert_eq!(
+            render(DEFAULT_PATTERN, &track(0, 0, "Undertow")),
+            Path::new("The Hollows/Low Tide/Undertow.flac")
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_placeholders() {
+        assert_eq!(
+            render("{genre}/{title}", &track(0, 1, "Undertow")),
+            Path::new("{genre}/Undertow.flac")
+        );
+    }
+
+    #[test]
+    fn keeps_dots_in_the_title() {
+        assert_eq!(
+            render("{title}", &track(0, 1, "Vol. 2")),
+            Path::new("Vol. 2.flac")
+        );
+    }
+
+    #[test]
+    fn replaces_characters_file_systems_refuse() {
+        assert_eq!(sanitize("AC/DC"), "AC_DC");
+        assert_eq!(sanitize("Why? Because: *"), "Why_ Because_ _");
+        assert_eq!(sanitize("\"Quoted\" <a|b>"), "_Quoted_ _a_b_");
+        assert_eq!(sanitize("Tab\there"), "Tab_here");
+    }
+
+    #[test]
+    fn trims_dots_that_would_hide_or_lose_the_name() {
+        assert_eq!(sanitize(".hidden"), "hidden");
+        assert_eq!(sanitize("..."), "Unknown");
+        assert_eq!(sanitize("Etc."), "Etc");
+    }
+
+    #[test]
+    fn names_empty_values_unknown() {
+        assert_eq!(sanitize(""), "Unknown");
+        assert_eq!(sanitize("   "), "Unknown");
+        assert_eq!(
+            render("{album_artist}/{title}", &track(0, 1, "")),
+            Path::new("The Hollows/Unknown.flac")
+        );
+    }
+
+    #[test]
+    fn numbers_targets_that_are_taken() {
+        let root = Path::new("/nonexistent/music");
+        let files = [track(0, 1, "Undertow"), track(1, 1, "Undertow")];
+        let moves = plan(root, "{title}", &files);
+
+        let targets: Vec<PathBuf> = moves.iter().map(|planned| planned.to.clone()).collect();
+        assert_eq!(
+            targets,
+            vec![root.join("Undertow.flac"), root.join("Undertow (2).flac")]
+        );
+    }
+
+    #[test]
+    fn leaves_files_already_in_place() {
+        let root = Path::new("/nonexistent/music");
+        let file = MusicFile {
+            saved_path: root.join("Undertow.flac"),
+            ..track(0, 1, "Undertow")
+        };
+
+        assert!(plan(root, "{title}", &[file]).is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Where the library folder was kept before [`crate::config::Config`] took over.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use url::Url;
+
+use crate::core::flatpak;
+
+fn locations_file() -> PathBuf {
+    flatpak::config_dir().join("locations")
+}
+
+/// Reads the library folder from `~/.config/jams/locations`, where it was kept before
+/// there could be several, see [`crate::config::Config`].
+pub fn get_loc_from_config() -> Result<Url, String> {
+    // this could have a better result error type
+    let config_file_loc = locations_file();
+
+    match fs::read_to_string(&config_file_loc) {
+        Ok(contents) => {
+            let path = Path::new(contents.trim_end());
+            if path.exists() {
+                match Url::from_file_path(path) {
+                    Ok(url) => Ok(url),
+                    Err(_) => {
+                        let err_msg = format!("Failed to convert library path {} to URL.", path.display());
+                        Err(err_msg)
+                    }
+                }
+            } else if flatpak::is_sandboxed() {
+                // The portal forgot the folder, which only picking it again fixes.
+                let err_msg = format!(
+                    "Library path {} can no longer be opened. Add the folder again.",
+                    path.display()
+                );
+                Err(err_msg)
+            } else {
+                let err_msg = format!("Library path {} does not exist.", path.display());
+                Err(err_msg)
+            }
+        }
+        Err(_) => {
+            let err_msg = format!(
+                "Config file at {} does not exist.",
+                config_file_loc.display()
+            );
+            Err(err_msg)
+        }
+    }
+}
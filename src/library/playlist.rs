@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Reading M3U and PLS playlists, such as one someone sent over, to play from the queue.
+//! Tracks are matched against the library, and ones outside it are read for playing
+//! without being added to it.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use url::Url;
+
+use super::scan::{self, TitleFallback};
+use super::MusicFile;
+
+/// Extensions of the playlists Jams reads, lowercase.
+const EXTENSIONS: [&str; 3] = ["m3u", "m3u8", "pls"];
+
+/// Whether `path` looks like a playlist, going by its extension.
+pub fn is_playlist(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// A playlist ready to be queued.
+#[derive(Debug, Clone, Default)]
+pub struct LoadedPlaylist {
+    /// Every track that could be found, by ID, in playlist order.
+    pub tracks: Vec<usize>,
+    /// The tracks that aren't in the library, read from their files.
+    pub outside: Vec<MusicFile>,
+    /// Entries that were skipped, as written in the playlist.
+    pub missing: Vec<String>,
+}
+
+/// Reads the playlist at `path`. Tracks found in `library`, by canonical path, keep their
+/// IDs, and the rest are read from their files and numbered from `first_id`. Entries that
+/// don't lead to a local audio file are reported as missing.
+pub fn load(
+    path: &Path,
+    library: &HashMap<PathBuf, usize>,
+    first_id: usize,
+    fallback: TitleFallback,
+) -> Result<LoadedPlaylist, String> {
+    let bytes = fs::read(path).map_err(|err| err.to_string())?;
+    // M3U files from older software are often Latin-1 rather than UTF-8.
+    let text = String::from_utf8(bytes)
+        .unwrap_or_else(|err| err.into_bytes().into_iter().map(char::from).collect());
+
+    let is_pls = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pls"));
+    let base = path.parent().unwrap_or(Path::new("/"));
+
+    let mut playlist = LoadedPlaylist::default();
+    let mut id = first_id;
+    for line in text.lines() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        let entry = if is_pls {
+            pls_entry(line)
+        } else {
+            m3u_entry(line)
+        };
+        let Some(entry) = entry else {
+            continue;
+        };
+
+        let Some(file) = resolve(entry, base) else {
+            playlist.missing.push(entry.to_string());
+            continue;
+        };
+        if let Some(known) = library.get(&file) {
+            playlist.tracks.push(*known);
+        } else if let Some(music_file) = scan::reread(&file, id, fallback) {
+            playlist.tracks.push(id);
+            playlist.outside.push(music_file);
+            id += 1;
+        } else {
+            playlist.missing.push(entry.to_string());
+        }
+    }
+
+    Ok(playlist)
+}
+
+/// The track in a line of an M3U playlist. Everything else is a comment or an extension
+/// like `#EXTINF`.
+fn m3u_entry(line: &str) -> Option<&str> {
+    (!line.is_empty() && !line.starts_with('#')).then_some(line)
+}
+
+/// The track in a line of a PLS playlist, which are written as `File1=…`.
+fn pls_entry(line: &str) -> Option<&str> {
+    let (key, value) = line.split_once('=')?;
+    let is_file = key
+        .trim()
+        .get(..4)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("file"));
+    is_file.then_some(value.trim())
+}
+
+/// The canonical path of the local file `entry` points at, relative to the playlist's
+/// folder `base`, if it exists. Playlists made on Windows separate folders with
+/// backslashes.
+fn resolve(entry: &str, base: &Path) -> Option<PathBuf> {
+    let path = if entry.contains("://") {
+        Url::parse(entry).ok()?.to_file_path().ok()?
+    } else if entry.contains('\\') && !entry.contains('/') {
+        PathBuf::from(entry.replace('\\', "/"))
+    } else {
+        PathBuf::from(entry)
+    };
+
+    fs::canonicalize(base.join(path))
+        .ok()
+        .filter(|path| path.is_file())
+}
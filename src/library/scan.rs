@@ -0,0 +1,751 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use infer::Infer;
+use lofty::file::TaggedFile;
+use lofty::prelude::{Accessor, TaggedFileExt};
+use lofty::tag::{ItemKey, Tag};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use walkdir::{DirEntry, WalkDir};
+
+use super::archive;
+use super::artwork::{self, CoverSource};
+use super::collation;
+use super::date::ReleaseDate;
+use super::dsd;
+use super::edition;
+use super::model::{Album, MusicFile};
+use super::musical_key::MusicalKey;
+use super::tempo;
+use super::track_number;
+use crate::fl;
+use crate::jobs::JobControl;
+
+fn is_audio_file(path: &Path) -> std::io::Result<bool> {
+    let mut file = File::open(path)?;
+    let mut buf = [0; 1024]; // Read first KB for detection
+    file.read_exact(&mut buf)?;
+
+    let info = Infer::new();
+    Ok(info.is_audio(&buf))
+}
+
+/// How the scan treats links.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanOptions {
+    /// Scan what symbolic links point to. Otherwise links are skipped.
+    pub follow_symlinks: bool,
+    /// Take hard links, and symbolic links, to a file already found as the same track
+    /// rather than adding it twice.
+    pub merge_hardlinks: bool,
+    pub title_fallback: TitleFallback,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            follow_symlinks: false,
+            merge_hardlinks: true,
+            title_fallback: TitleFallback::default(),
+        }
+    }
+}
+
+/// How tracks without a title tag are named after their file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TitleFallback {
+    /// The whole file name, extension included.
+    FileName,
+    /// The file name without its extension or a leading track number, as in
+    /// `03 - Title.flac`, and with underscores as spaces.
+    #[default]
+    Cleaned,
+    /// Cleaned up, and with every word capitalized.
+    TitleCase,
+}
+
+impl TitleFallback {
+    pub const ALL: [TitleFallback; 3] = [
+        TitleFallback::FileName,
+        TitleFallback::Cleaned,
+        TitleFallback::TitleCase,
+    ];
+
+    /// Labels for a dropdown, in the same order as [`TitleFallback::ALL`].
+    pub fn names() -> &'static [String] {
+        static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+        NAMES.get_or_init(|| {
+            vec![
+                fl!("title-fallback-file-name"),
+                fl!("title-fallback-cleaned"),
+                fl!("title-fallback-title-case"),
+            ]
+        })
+    }
+
+    /// The title for the file at `path`.
+    pub fn title(self, path: &Path) -> String {
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        if self == TitleFallback::FileName {
+            return file_name.to_string();
+        }
+
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(file_name)
+            .replace('_', " ");
+        let title = strip_track_number(stem.trim());
+
+        if self == TitleFallback::TitleCase {
+            title_case(title)
+        } else {
+            title.to_string()
+        }
+    }
+}
+
+/// `name` without a leading track number and the separator after it, as in `03 - Title`,
+/// `3. Title`, `1-03 Title` or `03 Title`. A number followed only by a space must have a
+/// leading zero, so titles like `99 Luftballons` stay whole.
+fn strip_track_number(name: &str) -> &str {
+    let number_end = |text: &str| {
+        text.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len())
+    };
+
+    let mut digits = number_end(name);
+    if digits == 0 || digits > 3 {
+        return name;
+    }
+    // A disc number before the track number.
+    if let Some(track) = name[digits..].strip_prefix('-') {
+        let track_digits = number_end(track);
+        if (1..=3).contains(&track_digits) && track[track_digits..].starts_with(' ') {
+            digits += 1 + track_digits;
+        }
+    }
+
+    let rest = &name[digits..];
+    let title = rest.trim_start_matches([' ', '-', '.', ')']).trim_start();
+    let separated = rest.trim_start().starts_with(['-', '.', ')']);
+    let padded = name.starts_with('0') && rest.starts_with(' ');
+
+    if title.is_empty() || !(separated || padded || name[..digits].contains('-')) {
+        name
+    } else {
+        title
+    }
+}
+
+/// `text` with the first letter of every word capitalized. The rest of each word is left
+/// alone so names like `DJ` or `iPod` keep their case.
+fn title_case(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Files handed to the UI at a time by [`scan_incrementally`].
+const SCAN_BATCH_SIZE: usize = 50;
+/// Files modified more recently than this may still be being copied or downloaded, so
+/// they're held back to be scanned again later rather than read half-written.
+pub const SETTLE_TIME: Duration = Duration::from_secs(10);
+
+/// A scan running on another thread. Files are handed over in batches as they're found.
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    /// Audio files found so far, including ones not handed over yet.
+    pub found: AtomicUsize,
+    /// Files to look at, audio or not. Zero until they've been counted.
+    pub total: AtomicUsize,
+    /// Files looked at so far, audio or not.
+    pub checked: AtomicUsize,
+    pub control: JobControl,
+    batch: Mutex<Vec<MusicFile>>,
+    held_back: Mutex<Vec<PathBuf>>,
+}
+
+impl ScanProgress {
+    /// Takes the files found since the last call.
+    pub fn take_found(&self) -> Vec<MusicFile> {
+        self.batch
+            .lock()
+            .map(|mut batch| std::mem::take(&mut *batch))
+            .unwrap_or_default()
+    }
+
+    /// Takes the files skipped because they were still changing.
+    pub fn take_held_back(&self) -> Vec<PathBuf> {
+        self.held_back
+            .lock()
+            .map(|mut held_back| std::mem::take(&mut *held_back))
+            .unwrap_or_default()
+    }
+
+    fn hand_over(&self, files: &mut Vec<MusicFile>) {
+        if let Ok(mut batch) = self.batch.lock() {
+            batch.append(files);
+        }
+    }
+}
+
+/// The ID after the highest one in `scanned_files`, so newly scanned files never collide
+/// with ones already in the library.
+pub fn next_id(scanned_files: &[MusicFile]) -> usize {
+    scanned_files
+        .iter()
+        .map(|file| file.id + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// What tells library files apart.
+#[derive(PartialEq, Eq, Hash)]
+enum Identity {
+    /// Files on disk, by device and inode, so one reached under another spelling, as on
+    /// filesystems that ignore case, isn't added twice.
+    Inode((u64, u64)),
+    /// Hard links to the same file, each under its own path.
+    Link((u64, u64), String),
+    /// Files whose inode couldn't be read.
+    Path(String),
+    /// Tracks inside an archive, by archive and entry name. Their extracted copy comes and
+    /// goes with the cache, so its inode says nothing.
+    Entry(PathBuf, String),
+}
+
+/// What tells `file` apart from others. Unless `merge_links` is set, hard links to the
+/// same file keep their own entries.
+fn identity(file: &MusicFile, merge_links: bool) -> Identity {
+    if let Some(entry) = &file.archive {
+        return Identity::Entry(entry.archive.clone(), entry.name.clone());
+    }
+
+    match file.inode {
+        Some(inode) if merge_links => Identity::Inode(inode),
+        Some(inode) => Identity::Link(inode, file.saved_path.to_string_lossy().to_lowercase()),
+        None => Identity::Path(file.saved_path.to_string_lossy().to_string()),
+    }
+}
+
+/// Drops the files in `found` that are already in `library`, or earlier in `found`, under
+/// another path.
+pub fn drop_duplicates(found: &mut Vec<MusicFile>, library: &[MusicFile], options: ScanOptions) {
+    let mut known: HashSet<_> = library
+        .iter()
+        .map(|file| identity(file, options.merge_hardlinks))
+        .collect();
+
+    found.retain(|file| known.insert(identity(file, options.merge_hardlinks)));
+}
+
+/// The folder behind `url`, if it's a local one.
+fn local_root(url: &Url) -> Option<PathBuf> {
+    match url.to_file_path() {
+        Ok(root) => Some(root),
+        Err(()) => {
+            println!("URL {} is not a local folder", url);
+            None
+        }
+    }
+}
+
+/// Walks the folder behind `url` for tagged audio files, handing them over through
+/// `progress` every [`SCAN_BATCH_SIZE`] files so they can be shown while the scan goes on.
+/// Files still changing are held back, see [`SETTLE_TIME`]. IDs start at `first_id` and
+/// never change afterwards. Batches are not sorted. Cancelling keeps the files found so far.
+pub fn scan_incrementally(
+    url: Url,
+    first_id: usize,
+    options: ScanOptions,
+    progress: &ScanProgress,
+) {
+    if let Some(root) = local_root(&url) {
+        scan_paths(vec![root], first_id, options, progress);
+    }
+}
+
+/// Like [`scan_incrementally`], for a list of files and folders, such as the ones held
+/// back by an earlier scan.
+pub fn scan_paths(
+    paths: Vec<PathBuf>,
+    first_id: usize,
+    options: ScanOptions,
+    progress: &ScanProgress,
+) {
+    let mut pending = Vec::with_capacity(SCAN_BATCH_SIZE);
+    let mut held_back = Vec::new();
+
+    let total = count_files(&paths, options, &progress.control);
+    progress.total.store(total, Ordering::Relaxed);
+
+    visit_files(
+        &paths,
+        first_id,
+        options,
+        &progress.control,
+        &progress.checked,
+        |music_file| {
+            pending.push(music_file);
+            progress.found.fetch_add(1, Ordering::Relaxed);
+
+            if pending.len() >= SCAN_BATCH_SIZE {
+                progress.hand_over(&mut pending);
+            }
+        },
+        |path| held_back.push(path),
+    );
+
+    progress.hand_over(&mut pending);
+    if let Ok(mut held) = progress.held_back.lock() {
+        held.append(&mut held_back);
+    }
+}
+
+/// Whether the file at `path` changed within the last [`SETTLE_TIME`]. Files dated in the
+/// future are taken as settled, or a wrong clock would hold them back forever.
+fn is_settling(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < SETTLE_TIME)
+}
+
+/// How many files [`visit_files`] looks at under `roots`, for a progress bar. Nothing is
+/// read, so this is quick, but files reached through several links are counted each time.
+fn count_files(roots: &[PathBuf], options: ScanOptions, control: &JobControl) -> usize {
+    let mut count = 0;
+
+    for root in roots {
+        for entry in WalkDir::new(root).follow_links(options.follow_symlinks) {
+            if !control.checkpoint() {
+                return count;
+            }
+            if entry.is_ok_and(|entry| !entry.file_type().is_dir()) {
+                count += 1;
+            }
+        }
+    }
+
+    count
+}
+
+/// Calls `found` with every tagged audio file under `roots`, including the ones in zip
+/// archives, numbering them from `first_id`, until `control` is cancelled. Roots can also
+/// be single files or archives. Files still changing are passed to `held_back` instead.
+/// Links are followed and merged as `options` says. Every file looked at, audio or not,
+/// adds to `checked`.
+fn visit_files(
+    roots: &[PathBuf],
+    first_id: usize,
+    options: ScanOptions,
+    control: &JobControl,
+    checked: &AtomicUsize,
+    mut found: impl FnMut(MusicFile),
+    mut held_back: impl FnMut(PathBuf),
+) {
+    let mut id = first_id;
+    // Links can lead to the same folder or file more than once, and a link back up the
+    // tree would otherwise be followed forever.
+    let mut visited_dirs = HashSet::new();
+    let mut visited_files = HashSet::new();
+
+    for root in roots {
+        let mut walker = WalkDir::new(root)
+            .follow_links(options.follow_symlinks)
+            .into_iter();
+
+        while let Some(entry) = walker.next() {
+            if !control.checkpoint() {
+                println!("Scan cancelled");
+                return;
+            }
+            if entry
+                .as_ref()
+                .is_ok_and(|entry| !entry.file_type().is_dir())
+            {
+                checked.fetch_add(1, Ordering::Relaxed);
+            }
+
+            match entry {
+                Ok(entry) if entry.file_type().is_dir() => {
+                    if !is_first_visit(&entry, &mut visited_dirs) {
+                        walker.skip_current_dir();
+                    }
+                }
+                Ok(entry)
+                    if entry.file_type().is_file()
+                        && options.merge_hardlinks
+                        && !is_first_visit(&entry, &mut visited_files) =>
+                {
+                    println!(
+                        "{} is a link to a file already scanned",
+                        entry.path().display()
+                    );
+                }
+                Ok(entry) if entry.file_type().is_file() && is_settling(entry.path()) => {
+                    println!(
+                        "{} is still changing, scanning it later",
+                        entry.path().display()
+                    );
+                    held_back(entry.path().to_path_buf());
+                }
+                Ok(entry) if entry.file_type().is_file() && archive::is_archive(entry.path()) => {
+                    let archived = archive::read_archive(entry.path(), id, options.title_fallback);
+                    for music_file in archived {
+                        id = music_file.id + 1;
+                        found(music_file);
+                    }
+                }
+                Ok(entry) => {
+                    if let Some(music_file) = read_music_file(&entry, id, options.title_fallback) {
+                        id += 1;
+                        found(music_file);
+                    }
+                }
+                Err(entry_error) => {
+                    println!("{} could not be read", entry_error);
+                }
+            }
+        }
+    }
+}
+
+/// Records the device and inode `entry` lives at in `visited`. Returns `false` if it was
+/// there already, meaning `entry` is another link to something scanned before.
+fn is_first_visit(entry: &DirEntry, visited: &mut HashSet<(u64, u64)>) -> bool {
+    match entry.metadata() {
+        Ok(metadata) => visited.insert((metadata.dev(), metadata.ino())),
+        Err(_) => true,
+    }
+}
+
+/// Reads the tags of `entry` if it's an audio file.
+fn read_music_file(entry: &DirEntry, id: usize, fallback: TitleFallback) -> Option<MusicFile> {
+    if !entry.file_type().is_file() {
+        return None;
+    }
+
+    read_tags(entry.path(), id, fallback)
+}
+
+/// Reads the file at `path` again, for when its tags were changed outside of Jams. The
+/// file keeps `id`. Returns `None` if it's gone or no longer a tagged audio file.
+pub fn reread(path: &Path, id: usize, fallback: TitleFallback) -> Option<MusicFile> {
+    if !path.is_file() {
+        return None;
+    }
+
+    read_tags(path, id, fallback)
+}
+
+/// Reads the tags of the file at `path` if it's an audio file. Files without a title are
+/// named as `fallback` says.
+fn read_tags(path: &Path, id: usize, fallback: TitleFallback) -> Option<MusicFile> {
+    // Neither lofty nor infer know DSD files.
+    if dsd::is_dsd(path) {
+        println!("{}", path.display());
+        return match dsd::read(path) {
+            Ok(file) => from_tag(&file.tag, file.duration, file.channels, path, id, fallback),
+            Err(err) => {
+                eprintln!("Error reading file: {}", err);
+                None
+            }
+        };
+    }
+
+    let is_audio = is_audio_file(path).unwrap_or_else(|_| false);
+
+    if !is_audio {
+        return None;
+    }
+
+    println!("{}", path.display());
+    let tagged_file = match lofty::read_from_path(path) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Error reading file: {}", err);
+            return None;
+        }
+    };
+
+    from_tagged_file(&tagged_file, path, id, fallback)
+}
+
+/// Makes the library entry for the file at `path` out of its tags, already read into
+/// `tagged_file`. Returns `None` if the file has no tags.
+pub(super) fn from_tagged_file(
+    tagged_file: &TaggedFile,
+    path: &Path,
+    id: usize,
+    fallback: TitleFallback,
+) -> Option<MusicFile> {
+    let Some(tag) = tagged_file.primary_tag() else {
+        println!("No tags found in file");
+        return None;
+    };
+    let properties = lofty::prelude::AudioFile::properties(tagged_file);
+    let channels = properties.channels().unwrap_or(0);
+
+    from_tag(tag, properties.duration(), channels, path, id, fallback)
+}
+
+/// Makes the library entry for the file at `path` out of `tag`.
+fn from_tag(
+    tag: &Tag,
+    duration: Duration,
+    channels: u8,
+    path: &Path,
+    id: usize,
+    fallback: TitleFallback,
+) -> Option<MusicFile> {
+    // Links and `..` are resolved so every file has a single path in the library.
+    let saved_path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let metadata = std::fs::metadata(&saved_path).ok();
+    let inode = metadata
+        .as_ref()
+        .map(|metadata| (metadata.dev(), metadata.ino()));
+    let size = metadata.map_or(0, |metadata| metadata.len());
+    match Url::from_file_path(&saved_path) {
+        Ok(url) => {
+            let track_title = match tag.get_string(&ItemKey::TrackTitle).map(|s| s.to_string()) {
+                Some(title) => title,
+                // If there's no track tag, fall back to the file name.
+                None => fallback.title(path),
+            };
+            let album = tag
+                .album()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| String::from("Unknown Album"));
+            let artist = tag.artist().map(|s| s.to_string()).unwrap_or_default();
+            let artist_sort = tag
+                .get_string(&ItemKey::TrackArtistSortOrder)
+                .map(str::to_string)
+                .unwrap_or_default();
+            let album_artist_sort = tag
+                .get_string(&ItemKey::AlbumArtistSortOrder)
+                .map(str::to_string)
+                .unwrap_or_default();
+            let release_group_id = tag
+                .get_string(&ItemKey::MusicBrainzReleaseGroupId)
+                .map(str::to_string)
+                .unwrap_or_default();
+            let genre = tag
+                .genre()
+                .map(|genre| genre.trim().to_string())
+                .unwrap_or_default();
+            // Vorbis comments call it LABEL, ID3 and MP4 the publisher.
+            let label = [ItemKey::Label, ItemKey::Publisher]
+                .iter()
+                .filter_map(|key| tag.get_string(key))
+                .map(str::trim)
+                .find(|label| !label.is_empty())
+                .map(str::to_string)
+                .unwrap_or_default();
+            let language = tag
+                .get_string(&ItemKey::Language)
+                .map(|language| language.trim().to_lowercase())
+                .unwrap_or_default();
+            let bpm = [ItemKey::Bpm, ItemKey::IntegerBpm]
+                .iter()
+                .filter_map(|key| tag.get_string(key))
+                .find_map(tempo::parse);
+            let key = tag
+                .get_string(&ItemKey::InitialKey)
+                .and_then(MusicalKey::parse);
+            let album_artist = match tag.get_string(&ItemKey::AlbumArtist).map(|s| s.to_string()) {
+                Some(album_artist) => album_artist,
+                None => artist.clone(),
+            };
+            // Full dates live in different fields depending on the
+            // format, with a bare year as the last resort.
+            let release_date = [ItemKey::RecordingDate, ItemKey::ReleaseDate]
+                .iter()
+                .filter_map(|key| tag.get_string(key))
+                .find_map(ReleaseDate::parse)
+                .or_else(|| tag.year().and_then(ReleaseDate::from_year));
+            let original_date = tag
+                .get_string(&ItemKey::OriginalReleaseDate)
+                .and_then(ReleaseDate::parse);
+            // Read as text first, since vinyl rips tag positions like "B2".
+            let (side, track_number) = match tag.get_string(&ItemKey::TrackNumber) {
+                Some(position) => track_number::parse_position(position),
+                None => (
+                    None,
+                    tag.track()
+                        .and_then(|track| u16::try_from(track).ok())
+                        .unwrap_or(0),
+                ),
+            };
+            let disc_number = tag
+                .disk()
+                .and_then(|disc| u16::try_from(disc).ok())
+                .unwrap_or(0);
+
+            let gapless = ["ITUNESGAPLESS", "GAPLESS"].iter().any(|key| {
+                tag.get_string(&ItemKey::Unknown(key.to_string()))
+                    .is_some_and(|value| value.trim() == "1")
+            });
+
+            let duration = Duration::from_secs(duration.as_secs());
+
+            // println!("{}", tag.picture_count());
+            // let thing = tag.pictures();
+            // for pic in tag.pictures() {
+            //     println!("{:?}", pic.pic_type());
+            // }
+
+            let mut music_file = MusicFile {
+                album_artist,
+                album_artist_sort,
+                album_artist_key: String::new(),
+                album,
+                disc_number,
+                track_number,
+                side,
+                gapless,
+                artist,
+                artist_sort,
+                artist_key: String::new(),
+                track_title,
+                title_key: String::new(),
+                duration,
+                channels,
+                release_date,
+                original_date,
+                release_group_id,
+                genre,
+                label,
+                language,
+                bpm,
+                key,
+                saved_path,
+                inode,
+                size,
+                archive: None,
+                uri: url.to_string(),
+                //metadata,
+                id,
+            };
+            collation::update_keys(&mut music_file);
+
+            Some(music_file)
+        }
+        Err(err) => {
+            eprintln!("Failed to run discovery: {err:?}");
+            None
+        }
+    }
+}
+
+/// Groups scanned files into albums. Also returns the albums whose cover still has to be
+/// cached with [`artwork::cache_covers`].
+pub fn build_albums(scanned_files: &[MusicFile]) -> (Vec<Album>, Vec<CoverSource>) {
+    let mut albums: Vec<Album> = Vec::new();
+    let covers = add_to_albums(&mut albums, scanned_files);
+    sort_albums(&mut albums);
+    (albums, covers)
+}
+
+/// Adds `files` to the albums they belong to. Albums seen for the first time are appended,
+/// so indices into `albums` stay valid until [`sort_albums`] is called. Returns the new
+/// albums whose cover still has to be cached with [`artwork::cache_covers`]. Nothing is
+/// written here, so this is cheap enough for the UI thread.
+pub fn add_to_albums(albums: &mut Vec<Album>, files: &[MusicFile]) -> Vec<CoverSource> {
+    let mut covers = Vec::new();
+
+    for music_file in files {
+        match albums.iter_mut().find(|album| {
+            album.album == music_file.album && album.album_artist == music_file.album_artist
+        }) {
+            Some(album) => {
+                album.tracks.push(music_file.id);
+                album.date = match (album.date, music_file.sort_date()) {
+                    (Some(current), Some(date)) => Some(current.min(date)),
+                    (current, date) => current.or(date),
+                };
+                if !music_file.language.is_empty()
+                    && !album.languages.contains(&music_file.language)
+                {
+                    album.languages.push(music_file.language.clone());
+                }
+            }
+            None => {
+                let album_key = artwork::album_key(&music_file.album_artist, &music_file.album);
+                let custom_cover = artwork::custom_cover(&album_key);
+
+                // Artwork picked by the user wins over whatever is embedded. A cover cached
+                // in an earlier session can be shown while it's refreshed.
+                let cover_path = match custom_cover {
+                    Some(path) => path,
+                    None => {
+                        covers.push(CoverSource {
+                            key: album_key.clone(),
+                            file: music_file.saved_path.clone(),
+                            archive: music_file.archive.clone(),
+                        });
+                        artwork::cached_cover(&album_key)
+                    }
+                };
+                let cover_ready = cover_path.is_file();
+
+                let new_album = Album {
+                    album_artist: music_file.album_artist.clone(),
+                    album_artist_key: music_file.album_artist_key.clone(),
+                    album: music_file.album.clone(),
+                    cached_cover_path: cover_path.display().to_string(),
+                    cover_ready,
+                    date: music_file.sort_date(),
+                    version_group: edition::version_group(
+                        &music_file.album_artist_key,
+                        &music_file.album,
+                        &music_file.release_group_id,
+                    ),
+                    languages: [&music_file.language]
+                        .into_iter()
+                        .filter(|language| !language.is_empty())
+                        .cloned()
+                        .collect(),
+                    tracks: vec![music_file.id],
+                };
+                albums.push(new_album);
+            }
+        }
+    }
+
+    covers
+}
+
+/// Sorts albums by artist, ignoring leading articles, then each artist's albums in the
+/// order they first came out, undated ones last.
+pub fn sort_albums(albums: &mut [Album]) {
+    albums.sort_by(|a, b| {
+        a.album_artist_key
+            .cmp(&b.album_artist_key)
+            .then_with(|| a.album_artist.cmp(&b.album_artist))
+            .then_with(|| a.date.is_none().cmp(&b.date.is_none()))
+            .then_with(|| a.date.cmp(&b.date))
+            .then_with(|| a.album.cmp(&b.album))
+    });
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::collections::HashSet;
+
+use super::model::{Album, MusicFile};
+
+/// A lowercased copy of everything searchable about a single item, built once
+/// per scan so that filtering doesn't allocate on every keystroke.
+#[derive(Debug, Clone)]
+struct SearchEntry {
+    id: usize,
+    haystack: String,
+}
+
+impl SearchEntry {
+    fn new(id: usize, fields: &[&str]) -> Self {
+        // Fields are separated by a newline so a term can never match across
+        // the boundary of two fields (search input is always a single line).
+        let haystack = fields
+            .iter()
+            .map(|field| field.to_lowercase())
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        SearchEntry { id, haystack }
+    }
+}
+
+/// Pre-computed search data for the library.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    tracks: Vec<SearchEntry>,
+    albums: Vec<SearchEntry>,
+}
+
+/// IDs of the tracks and indices of the albums that matched a search term.
+#[derive(Debug, Clone, Default)]
+pub struct SearchResults {
+    pub tracks: HashSet<usize>,
+    pub albums: HashSet<usize>,
+}
+
+impl SearchIndex {
+    /// Indexes tracks by their ID and albums by their position in `albums`.
+    pub fn build(scanned_files: &[MusicFile], albums: &[Album]) -> Self {
+        let tracks = scanned_files
+            .iter()
+            .map(|file| {
+                SearchEntry::new(
+                    file.id,
+                    &[&file.album, &file.artist, &file.track_title, &file.album_artist],
+                )
+            })
+            .collect();
+
+        let albums = albums
+            .iter()
+            .enumerate()
+            .map(|(index, album)| SearchEntry::new(index, &[&album.album, &album.album_artist]))
+            .collect();
+
+        SearchIndex { tracks, albums }
+    }
+
+    /// Filters the whole index. This walks every entry, so call it off the UI thread.
+    pub fn search(&self, term: &str) -> SearchResults {
+        let term = term.to_lowercase();
+
+        let matching = |entries: &[SearchEntry]| {
+            entries
+                .iter()
+                .filter(|entry| entry.haystack.contains(&term))
+                .map(|entry| entry.id)
+                .collect::<HashSet<usize>>()
+        };
+
+        SearchResults {
+            tracks: matching(&self.tracks),
+            albums: matching(&self.albums),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! "More like this": tracks in the library close to a given one, scored by what their tags
+//! have in common and how often they were listened to in the same sitting.
+
+use std::collections::{HashMap, HashSet};
+
+use super::MusicFile;
+use crate::scrobble::Listen;
+
+/// Listens that start further apart than this, in seconds, belong to different sittings.
+const SESSION_GAP: u64 = 60 * 60;
+/// Sittings shared with the seed only add to the score up to this many.
+const MAX_SHARED_SESSIONS: u32 = 3;
+
+/// Why a track counts as similar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchReason {
+    SameArtist,
+    SameGenre,
+    /// Released on the same record label.
+    SameLabel,
+    SameDecade,
+    /// Listened to in the same sitting as the seed.
+    PlayedTogether,
+}
+
+/// A track picked by [`similar`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Similar {
+    pub id: usize,
+    pub score: u32,
+    pub reasons: Vec<MatchReason>,
+}
+
+/// In how many sittings of `history` each track, by artist and title, was listened to
+/// along with `seed`.
+fn co_plays<'a>(seed: &MusicFile, history: &'a [Listen]) -> HashMap<(&'a str, &'a str), u32> {
+    let mut listens: Vec<&Listen> = history.iter().collect();
+    listens.sort_by_key(|listen| listen.listened_at);
+
+    let mut sessions: Vec<Vec<&Listen>> = Vec::new();
+    let mut last_start = None;
+    for listen in listens {
+        match (last_start, sessions.last_mut()) {
+            (Some(start), Some(session))
+                if listen.listened_at.saturating_sub(start) <= SESSION_GAP =>
+            {
+                session.push(listen)
+            }
+            _ => sessions.push(vec![listen]),
+        }
+        last_start = Some(listen.listened_at);
+    }
+
+    let is_seed =
+        |listen: &Listen| listen.artist == seed.artist && listen.title == seed.track_title;
+    let mut counts = HashMap::new();
+    for session in sessions {
+        if !session.iter().any(|listen| is_seed(listen)) {
+            continue;
+        }
+
+        // A track played twice in one sitting still counts once.
+        let others: HashSet<(&str, &str)> = session
+            .iter()
+            .filter(|listen| !is_seed(listen))
+            .map(|listen| (listen.artist.as_str(), listen.title.as_str()))
+            .collect();
+        for other in others {
+            *counts.entry(other).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Up to `limit` tracks of `files` most like `seed`, best first. Tracks that share nothing
+/// with it are left out, and so are other copies of the seed.
+pub fn similar(
+    seed: &MusicFile,
+    files: &[MusicFile],
+    history: &[Listen],
+    limit: usize,
+) -> Vec<Similar> {
+    let co_plays = co_plays(seed, history);
+    let seed_decade = seed.sort_date().map(|date| date.decade());
+
+    let mut matches: Vec<Similar> = files
+        .iter()
+        .filter(|file| file.id != seed.id)
+        .filter(|file| file.artist_key != seed.artist_key || file.title_key != seed.title_key)
+        .filter_map(|file| {
+            let mut score = 0;
+            let mut reasons = Vec::new();
+
+            if !seed.artist.is_empty() && file.artist_key == seed.artist_key {
+                score += 4;
+                reasons.push(MatchReason::SameArtist);
+            }
+            if !seed.genre.is_empty() && file.genre.to_lowercase() == seed.genre.to_lowercase() {
+                score += 3;
+                reasons.push(MatchReason::SameGenre);
+            }
+            if !seed.label.is_empty() && file.label.to_lowercase() == seed.label.to_lowercase() {
+                score += 2;
+                reasons.push(MatchReason::SameLabel);
+            }
+            if seed_decade.is_some() && file.sort_date().map(|date| date.decade()) == seed_decade {
+                score += 1;
+                reasons.push(MatchReason::SameDecade);
+            }
+
+            let together = co_plays
+                .get(&(file.artist.as_str(), file.track_title.as_str()))
+                .copied()
+                .unwrap_or(0)
+                .min(MAX_SHARED_SESSIONS);
+            if together > 0 {
+                score += 2 * together;
+                reasons.push(MatchReason::PlayedTogether);
+            }
+
+            // Half the library shares a decade, so that alone doesn't make a match.
+            (score > 1).then_some(Similar {
+                id: file.id,
+                score,
+                reasons,
+            })
+        })
+        .collect();
+
+    // Stable, so equal scores stay in library order.
+    matches.sort_by(|a, b| b.score.cmp(&a.score));
+    matches.truncate(limit);
+    matches
+}
@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Tempo of tracks in beats per minute, read from their tags or, for files without a `BPM`
+//! tag, measured with GStreamer's `bpmdetect` and kept in a cache of its own.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+use super::artwork;
+use super::decode;
+use super::model::MusicFile;
+use crate::jobs::JobControl;
+
+/// Tempos outside this range are taken for misdetections or typos and ignored.
+const PLAUSIBLE_BPM: (u16, u16) = (30, 300);
+
+/// A detection running on another thread. Counts tracks.
+#[derive(Debug, Default)]
+pub struct TempoProgress {
+    pub total: AtomicUsize,
+    pub done: AtomicUsize,
+    pub control: JobControl,
+}
+
+/// Reads a `BPM` tag. Some taggers write fractions, which are rounded.
+pub fn parse(tag: &str) -> Option<u16> {
+    let bpm = tag.trim().replace(',', ".").parse::<f64>().ok()?.round();
+    plausible(bpm)
+}
+
+fn plausible(bpm: f64) -> Option<u16> {
+    let (min, max) = PLAUSIBLE_BPM;
+    (f64::from(min)..=f64::from(max))
+        .contains(&bpm)
+        .then_some(bpm as u16)
+}
+
+/// Measures the tempo of every file in turn. Files that fail are skipped, and whatever was
+/// measured before the job is cancelled is returned. Blocks until done, so run it off the
+/// UI thread.
+pub fn detect_all(files: Vec<PathBuf>, progress: &TempoProgress) -> Vec<(PathBuf, u16)> {
+    progress.total.store(files.len(), Ordering::Relaxed);
+
+    let mut detected = Vec::new();
+    for file in files {
+        if !progress.control.checkpoint() {
+            break;
+        }
+
+        match detect(&file, &progress.control) {
+            Ok(bpm) => detected.push((file, bpm)),
+            Err(err) => eprintln!("Failed to detect the tempo of {}: {}", file.display(), err),
+        }
+
+        progress.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    detected
+}
+
+/// Decodes `path` through `bpmdetect`, which posts its estimate as a tag while it goes.
+/// The last estimate, made from the most audio, is the one kept.
+fn detect(path: &Path, control: &JobControl) -> Result<u16, String> {
+    gst::init().map_err(|err| err.to_string())?;
+
+    let make = |name: &str| {
+        gst::ElementFactory::make(name)
+            .build()
+            .map_err(|err| err.to_string())
+    };
+
+    let filesrc = gst::ElementFactory::make("filesrc")
+        .property("location", path.to_string_lossy().to_string())
+        .build()
+        .map_err(|err| err.to_string())?;
+    let decodebin = make("decodebin")?;
+    let convert = make("audioconvert")?;
+    let bpmdetect = make("bpmdetect")?;
+    let sink = gst::ElementFactory::make("fakesink")
+        .property("sync", false)
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    let pipeline = gst::Pipeline::new();
+    pipeline
+        .add_many([&filesrc, &decodebin, &convert, &bpmdetect, &sink])
+        .map_err(|err| err.to_string())?;
+    filesrc.link(&decodebin).map_err(|err| err.to_string())?;
+    gst::Element::link_many([&convert, &bpmdetect, &sink]).map_err(|err| err.to_string())?;
+
+    // decodebin only knows its output pads once it has seen the stream.
+    let audio = convert.clone();
+    decodebin.connect_pad_added(move |_, pad| {
+        let Some(sink_pad) = audio.static_pad("sink") else {
+            return;
+        };
+        let is_audio = pad
+            .current_caps()
+            .and_then(|caps| caps.structure(0).map(|s| s.name().starts_with("audio/")))
+            .unwrap_or(false);
+
+        if is_audio && !sink_pad.is_linked() {
+            if let Err(err) = pad.link(&sink_pad) {
+                eprintln!("Failed to link decoder: {:?}", err);
+            }
+        }
+    });
+
+    pipeline
+        .set_state(gst::State::Playing)
+        .map_err(|err| err.to_string())?;
+    // The estimate is posted as a tag, refined as more of the file is decoded.
+    let mut estimate = None;
+    let result = decode::wait_for_end(&pipeline, control, |message| {
+        if let gst::MessageView::Tag(tag) = message.view() {
+            if let Some(bpm) = tag.tags().get::<gst::tags::BeatsPerMinute>() {
+                estimate = Some(bpm.get());
+            }
+        }
+        Ok(())
+    });
+    let _ = pipeline.set_state(gst::State::Null);
+    result?;
+
+    estimate
+        .and_then(plausible)
+        .ok_or_else(|| String::from("No tempo was found"))
+}
+
+/// Gives files without a `BPM` tag the tempo measured for them, if there is one.
+pub fn fill_in(files: &mut [MusicFile], tempos: &HashMap<PathBuf, u16>) {
+    for file in files.iter_mut().filter(|file| file.bpm.is_none()) {
+        file.bpm = tempos.get(&file.saved_path).copied();
+    }
+}
+
+/// File holding measured tempos, next to the art cache.
+fn cache_path() -> PathBuf {
+    artwork::cache_dir()
+        .parent()
+        .map(|data_dir| data_dir.join("tempo"))
+        .unwrap_or_else(|| PathBuf::from("tempo"))
+}
+
+/// Every tempo measured before, by file.
+pub fn read_cache() -> HashMap<PathBuf, u16> {
+    let Ok(contents) = fs::read_to_string(cache_path()) else {
+        return HashMap::new();
+    };
+
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter_map(|(bpm, path)| Some((PathBuf::from(path), bpm.parse().ok()?)))
+        .collect()
+}
+
+/// Keeps measured tempos so files are only measured once. One line per file, the tempo
+/// first so tabs in paths don't get in the way.
+pub fn write_cache(tempos: &HashMap<PathBuf, u16>) -> Result<(), String> {
+    let contents: String = tempos
+        .iter()
+        .map(|(path, bpm)| format!("{}\t{}\n", bpm, path.display()))
+        .collect();
+
+    let path = cache_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    fs::write(path, contents).map_err(|err| err.to_string())
+}
@@ -0,0 +1,289 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! How track numbers are shown in lists and how tracks are ordered within an album.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use super::model::MusicFile;
+
+/// Splits a track number tag into side and number. Vinyl positions such as `A1` or `b12`
+/// give a side, plain numbers and `3/12` don't. Anything unreadable is number 0.
+pub fn parse_position(position: &str) -> (Option<char>, u16) {
+    let position = position.split('/').next().unwrap_or_default().trim();
+    let mut chars = position.chars();
+
+    match chars.next() {
+        Some(side) if side.is_ascii_alphabetic() => {
+            let number = chars.as_str().trim();
+            if number.chars().all(|c| c.is_ascii_digit()) {
+                (Some(side.to_ascii_uppercase()), number.parse().unwrap_or(0))
+            } else {
+                (None, 0)
+            }
+        }
+        _ => (None, position.parse().unwrap_or(0)),
+    }
+}
+
+/// Orders two tracks of the same album: by disc and side, then by track number. Tracks
+/// without a number go after the numbered ones of their disc, sorted by title.
+pub fn album_order(a: &MusicFile, b: &MusicFile) -> Ordering {
+    a.disc_number
+        .cmp(&b.disc_number)
+        .then_with(|| a.side.cmp(&b.side))
+        .then_with(|| (a.track_number == 0).cmp(&(b.track_number == 0)))
+        .then_with(|| a.track_number.cmp(&b.track_number))
+        .then_with(|| a.track_title.cmp(&b.track_title))
+        .then_with(|| a.saved_path.cmp(&b.saved_path))
+}
+
+/// What the whole library is ordered by: album artist, going by its sort key, and album,
+/// then as in [`album_order`].
+pub type LibraryKey = (
+    String,
+    String,
+    String,
+    u16,
+    Option<char>,
+    bool,
+    u16,
+    String,
+    PathBuf,
+);
+
+/// The [`LibraryKey`] of `file`.
+pub fn library_key(file: &MusicFile) -> LibraryKey {
+    (
+        file.album_artist_key.clone(),
+        file.album_artist.clone(),
+        file.album.clone(),
+        file.disc_number,
+        file.side,
+        file.track_number == 0,
+        file.track_number,
+        file.track_title.clone(),
+        file.saved_path.clone(),
+    )
+}
+
+/// Sorts `files` in library order. Each key is made once, instead of the fields being
+/// compared one by one on every comparison.
+pub fn sort_library(files: &mut [MusicFile]) {
+    files.sort_by_cached_key(library_key);
+}
+
+/// Indices of each song in `files` that `include` lets through, once, sorted by artist and
+/// title. Copies are told apart by title, artist and length; the first one is kept. Only
+/// compares the keys made during the scan.
+pub fn distinct_songs(files: &[MusicFile], include: impl Fn(&MusicFile) -> bool) -> Vec<usize> {
+    let mut seen = HashSet::new();
+    let mut songs: Vec<usize> = (0..files.len())
+        .filter(|&index| {
+            let file = &files[index];
+            include(file)
+                && seen.insert((
+                    file.title_key.as_str(),
+                    file.artist_key.as_str(),
+                    file.duration.as_secs(),
+                ))
+        })
+        .collect();
+
+    songs.sort_unstable_by_key(|&index| (&files[index].artist_key, &files[index].title_key));
+    songs
+}
+
+/// Per-album settings for [`format`].
+#[derive(Debug, Clone, Copy, Default)]
+struct AlbumLayout {
+    /// Digits in the album's highest track number.
+    width: usize,
+    /// Whether the album spans more than one disc.
+    multi_disc: bool,
+}
+
+fn format(file: &MusicFile, layout: AlbumLayout) -> String {
+    // The side already tells records apart, and its tracks are never padded.
+    if let Some(side) = file.side {
+        return match file.track_number {
+            0 => side.to_string(),
+            number => format!("{}{}", side, number),
+        };
+    }
+
+    if file.track_number == 0 {
+        return String::new();
+    }
+
+    if layout.multi_disc && file.disc_number > 0 {
+        format!(
+            "{}-{:0width$}",
+            file.disc_number,
+            file.track_number,
+            width = layout.width
+        )
+    } else {
+        format!("{:0width$}", file.track_number, width = layout.width)
+    }
+}
+
+/// The track number column for every file, keyed by ID. Untracked files get an empty label,
+/// numbers are padded to the width of the highest one on the same album and vinyl positions
+/// are shown as tagged, like `A1`.
+pub fn labels(files: &[MusicFile]) -> HashMap<usize, String> {
+    let mut layouts: HashMap<(&str, &str), AlbumLayout> = HashMap::new();
+    let mut first_disc: HashMap<(&str, &str), u16> = HashMap::new();
+
+    for file in files {
+        let key = (file.album_artist.as_str(), file.album.as_str());
+        let layout = layouts.entry(key).or_default();
+        layout.width = layout.width.max(file.track_number.to_string().len());
+
+        if file.disc_number > 0 {
+            let disc = *first_disc.entry(key).or_insert(file.disc_number);
+            layout.multi_disc |= disc != file.disc_number || file.disc_number > 1;
+        }
+    }
+
+    files
+        .iter()
+        .map(|file| {
+            let layout = layouts[&(file.album_artist.as_str(), file.album.as_str())];
+            (file.id, format(file, layout))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn track(id: usize, disc_number: u16, position: &str, title: &str) -> MusicFile {
+        let (side, track_number) = parse_position(position);
+
+        MusicFile {
+            album_artist: "The Hollows".to_string(),
+            album_artist_sort: String::new(),
+            album_artist_key: "hollows".to_string(),
+            album: "Low Tide".to_string(),
+            disc_number,
+            track_number,
+            side,
+            gapless: false,
+            artist: "The Hollows".to_string(),
+            artist_sort: String::new(),
+            artist_key: "hollows".to_string(),
+            track_title: title.to_string(),
+            title_key: title.to_lowercase(),
+            duration: Duration::from_secs(180),
+            channels: 2,
+            release_date: None,
+            original_date: None,
+            release_group_id: String::new(),
+            genre: String::new(),
+            label: String::new(),
+            language: String::new(),
+            bpm: None,
+            key: None,
+            saved_path: PathBuf::from(format!("/music/Low Tide/{}.flac", title)),
+            inode: None,
+            size: 0,
+            archive: None,
+            uri: format!("file:///music/Low Tide/{}.flac", title),
+            id,
+        }
+    }
+
+    #[test]
+    fn reads_plain_numbers() {
+        assert_eq!(parse_position("7"), (None, 7));
+        assert_eq!(parse_position(" 07 "), (None, 7));
+    }
+
+    #[test]
+    fn reads_the_number_out_of_a_total() {
+        assert_eq!(parse_position("3/12"), (None, 3));
+        assert_eq!(parse_position("A3/B6"), (Some('A'), 3));
+    }
+
+    #[test]
+    fn reads_vinyl_sides() {
+        assert_eq!(parse_position("A1"), (Some('A'), 1));
+        assert_eq!(parse_position("b12"), (Some('B'), 12));
+        assert_eq!(parse_position("C 2"), (Some('C'), 2));
+        assert_eq!(parse_position("D"), (Some('D'), 0));
+    }
+
+    #[test]
+    fn reads_anything_else_as_no_number() {
+        for position in ["", "/12", "Track 3", "A1b", "3a", "-1"] {
+            assert_eq!(parse_position(position), (None, 0), "{:?}", position);
+        }
+    }
+
+    #[test]
+    fn pads_numbers_to_the_widest_on_the_album() {
+        let files = [
+            track(0, 0, "1", "Undertow"),
+            track(1, 0, "10", "Breakwater"),
+        ];
+        let labels = labels(&files);
+
+        assert_eq!(labels[&0], "01");
+        assert_eq!(labels[&1], "10");
+    }
+
+    #[test]
+    fn shows_the_disc_of_albums_with_several() {
+        let files = [track(0, 1, "1", "Undertow"), track(1, 2, "1", "Breakwater")];
+        let labels = labels(&files);
+
+        assert_eq!(labels[&0], "1-1");
+        assert_eq!(labels[&1], "2-1");
+    }
+
+    #[test]
+    fn shows_sides_as_tagged() {
+        let files = [
+            track(0, 0, "A1", "Undertow"),
+            track(1, 0, "B12", "Breakwater"),
+            track(2, 0, "", "Hidden Track"),
+        ];
+        let labels = labels(&files);
+
+        assert_eq!(labels[&0], "A1");
+        assert_eq!(labels[&1], "B12");
+        assert_eq!(labels[&2], "");
+    }
+
+    #[test]
+    fn sorts_untracked_files_after_the_numbered_ones() {
+        let mut files = vec![
+            track(0, 0, "", "Hidden Track"),
+            track(1, 0, "2", "Shoals"),
+            track(2, 0, "1", "Driftwood"),
+        ];
+        sort_library(&mut files);
+
+        let order: Vec<usize> = files.iter().map(|file| file.id).collect();
+        assert_eq!(order, [2, 1, 0]);
+    }
+
+    #[test]
+    fn sorts_by_side_then_number() {
+        let mut files = vec![
+            track(0, 0, "B1", "Breakwater"),
+            track(1, 0, "A2", "Undertow"),
+            track(2, 0, "A10", "Shoals"),
+            track(3, 0, "A1", "Driftwood"),
+        ];
+        sort_library(&mut files);
+
+        let order: Vec<usize> = files.iter().map(|file| file.id).collect();
+        assert_eq!(order, [3, 1, 2, 0]);
+    }
+}
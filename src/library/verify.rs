@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Checking that library files decode from start to end, to find corrupt and truncated
+//! files before they cut out halfway through playback.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+use super::decode;
+use crate::jobs::JobControl;
+
+/// Decoded audio may come up this much shorter than the tagged length before the file
+/// counts as truncated. Tagged lengths are estimates for some formats.
+const TRUNCATION_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// A file to check, along with the length read from its tags.
+#[derive(Debug, Clone)]
+pub struct VerifyCandidate {
+    pub id: usize,
+    pub path: PathBuf,
+    pub duration: Duration,
+}
+
+/// A check running on another thread.
+#[derive(Debug, Default)]
+pub struct VerifyProgress {
+    pub total: AtomicUsize,
+    pub done: AtomicUsize,
+    pub control: JobControl,
+}
+
+/// What [`verify_files`] found.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    /// Tracks that decoded cleanly.
+    pub passed: Vec<usize>,
+    /// Track ID and what's wrong, for every file that didn't decode cleanly.
+    pub damaged: Vec<(usize, String)>,
+    /// One message per damaged file, naming the file.
+    pub failed: Vec<String>,
+    /// Checking was stopped before every file was done.
+    pub cancelled: bool,
+}
+
+impl VerifyReport {
+    pub fn checked(&self) -> usize {
+        self.passed.len() + self.damaged.len()
+    }
+}
+
+/// Decodes every file in `files` in full. Blocks until done, so run it off the UI thread.
+pub fn verify_files(files: Vec<VerifyCandidate>, progress: &VerifyProgress) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    progress.total.store(files.len(), Ordering::Relaxed);
+
+    for file in files {
+        if !progress.control.checkpoint() {
+            report.cancelled = true;
+            break;
+        }
+
+        match decode_file(&file.path, file.duration, &progress.control) {
+            Ok(()) => report.passed.push(file.id),
+            Err(_) if progress.control.is_cancelled() => {
+                report.cancelled = true;
+                break;
+            }
+            Err(err) => {
+                report
+                    .failed
+                    .push(format!("{}: {}", file.path.display(), err));
+                report.damaged.push((file.id, err));
+            }
+        }
+
+        progress.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    report
+}
+
+/// Decodes `path` without playing it. Fails on decoder errors and warnings, which is how
+/// broken frames and bad checksums show up, and when the audio ends well before `expected`.
+fn decode_file(path: &Path, expected: Duration, control: &JobControl) -> Result<(), String> {
+    gst::init().map_err(|err| err.to_string())?;
+
+    let filesrc = gst::ElementFactory::make("filesrc")
+        .property("location", path.to_string_lossy().to_string())
+        .build()
+        .map_err(|err| err.to_string())?;
+    let decodebin = gst::ElementFactory::make("decodebin")
+        .build()
+        .map_err(|err| err.to_string())?;
+    let convert = gst::ElementFactory::make("audioconvert")
+        .build()
+        .map_err(|err| err.to_string())?;
+    let sink = gst::ElementFactory::make("fakesink")
+        .property("sync", false)
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    let pipeline = gst::Pipeline::new();
+    pipeline
+        .add_many([&filesrc, &decodebin, &convert, &sink])
+        .map_err(|err| err.to_string())?;
+    filesrc.link(&decodebin).map_err(|err| err.to_string())?;
+    convert.link(&sink).map_err(|err| err.to_string())?;
+
+    // decodebin only knows its output pads once it has seen the stream.
+    let audio = convert.clone();
+    decodebin.connect_pad_added(move |_, pad| {
+        let Some(sink_pad) = audio.static_pad("sink") else {
+            return;
+        };
+        let is_audio = pad
+            .current_caps()
+            .and_then(|caps| caps.structure(0).map(|s| s.name().starts_with("audio/")))
+            .unwrap_or(false);
+
+        if is_audio && !sink_pad.is_linked() {
+            if let Err(err) = pad.link(&sink_pad) {
+                eprintln!("Failed to link decoder: {:?}", err);
+            }
+        }
+    });
+
+    pipeline
+        .set_state(gst::State::Playing)
+        .map_err(|err| err.to_string())?;
+
+    // Decoders warn about the damage they conceal, which is what's looked for here.
+    let result = decode::wait_for_end(&pipeline, control, |message| match message.view() {
+        gst::MessageView::Warning(warning) => Err(warning.error().to_string()),
+        _ => Ok(()),
+    });
+    let result = result.and_then(|()| {
+        let decoded = pipeline
+            .query_position::<gst::ClockTime>()
+            .map(|position| Duration::from_nanos(position.nseconds()))
+            .unwrap_or_default();
+
+        if decoded + TRUNCATION_TOLERANCE < expected {
+            Err(format!(
+                "Truncated, ends after {} of {} seconds",
+                decoded.as_secs(),
+                expected.as_secs()
+            ))
+        } else {
+            Ok(())
+        }
+    });
+
+    let _ = pipeline.set_state(gst::State::Null);
+    result
+}
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Noticing files being added, changed or removed under the library folder, so the library
+//! follows along without being scanned again.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify::event::{AccessKind, AccessMode};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// Changes are handed over once nothing else changed for this long, so copying an album in
+/// makes one batch rather than one per write.
+const QUIET_TIME: Duration = Duration::from_secs(2);
+
+/// Watches a folder and everything under it until dropped.
+pub struct LibraryWatcher {
+    _watcher: RecommendedWatcher,
+    changes: UnboundedReceiver<PathBuf>,
+}
+
+impl LibraryWatcher {
+    /// Starts watching `root`. Fails if the system can't watch any more folders, which
+    /// on Linux is limited by `fs.inotify.max_user_watches`.
+    pub fn new(root: &Path) -> Result<Self, String> {
+        let (sender, changes) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<_>| {
+            let event: notify::Event = match event {
+                Ok(event) => event,
+                Err(err) => {
+                    eprintln!("Failed to watch the library: {}", err);
+                    return;
+                }
+            };
+
+            // Playing a track opens and reads it, which isn't a change.
+            let changed = match event.kind {
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => true,
+                EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+                _ => false,
+            };
+            if changed {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+        })
+        .map_err(|err| err.to_string())?;
+
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            _watcher: watcher,
+            changes,
+        })
+    }
+
+    /// Waits for files to change and returns their paths, each once, after [`QUIET_TIME`]
+    /// without further changes. Paths may be of files or folders and may no longer exist.
+    pub async fn next_changes(&mut self) -> Option<Vec<PathBuf>> {
+        let mut paths = BTreeSet::new();
+        paths.insert(self.changes.recv().await?);
+
+        while let Ok(Some(path)) = tokio::time::timeout(QUIET_TIME, self.changes.recv()).await {
+            paths.insert(path);
+        }
+
+        Some(paths.into_iter().collect())
+    }
+}
@@ -1,11 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use app::Jams;
-
-/// The `app` module is used by convention to indicate the main component of our application.
-mod app;
-mod core;
-mod icon_cache;
+use jams::app::Jams;
+use jams::core;
 
 /// The `cosmic::app::run()` function is the starting point of your application.
 /// It takes two arguments:
@@ -13,7 +9,9 @@ mod icon_cache;
 /// - `()` is the flags that your app needs to use before it starts.
 ///  If your app does not need any flags, you can pass in `()`.
 fn main() -> cosmic::iced::Result {
+    core::profiling::start();
+
     // For any error, return an exit code -1. Otherwise, return the exit code provided.
     let settings = cosmic::app::Settings::default();
-    cosmic::app::run::<Jams>(settings, ())
+    cosmic::app::run_single_instance::<Jams>(settings, ())
 }
\ No newline at end of file
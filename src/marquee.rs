@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Text too long for its space, scrolled through a fixed-width window of characters.
+
+use std::time::Duration;
+
+/// Time spent on each step of the scroll.
+const STEP: Duration = Duration::from_millis(250);
+/// How long the start of the text stays put before scrolling, and again after every loop.
+const PAUSE: Duration = Duration::from_secs(2);
+/// Put between the end of the text and its start coming round again.
+const GAP: &str = "   •   ";
+
+/// The `width` characters of `text` to show `elapsed` into the scroll. Text that fits is
+/// returned as is.
+pub fn scroll(text: &str, width: usize, elapsed: Duration) -> String {
+    let chars: Vec<char> = text.chars().chain(GAP.chars()).collect();
+    let length = text.chars().count();
+    if length <= width {
+        return text.to_string();
+    }
+
+    // One loop is a pause followed by a step for every character.
+    let loop_length = PAUSE + STEP * chars.len() as u32;
+    let into_loop = Duration::from_nanos((elapsed.as_nanos() % loop_length.as_nanos()) as u64);
+    let offset = match into_loop.checked_sub(PAUSE) {
+        Some(scrolled) => (scrolled.as_millis() / STEP.as_millis()) as usize,
+        None => 0,
+    };
+
+    chars.iter().cycle().skip(offset).take(width).collect()
+}
+
+/// `text` cut to `width` characters, ending in an ellipsis if anything was left out.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+
+    let mut short: String = text.chars().take(width.saturating_sub(1)).collect();
+    short.push('…');
+    short
+}
@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Whether Jams should reach out to the network, from the user's choice and NetworkManager.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fl;
+
+/// What the user picked for network access.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NetworkMode {
+    /// Follow NetworkManager.
+    #[default]
+    Automatic,
+    Online,
+    Offline,
+}
+
+impl NetworkMode {
+    pub const ALL: [NetworkMode; 3] = [
+        NetworkMode::Automatic,
+        NetworkMode::Online,
+        NetworkMode::Offline,
+    ];
+
+    /// Labels for a dropdown, in the same order as [`NetworkMode::ALL`].
+    pub fn names() -> &'static [String] {
+        static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+        NAMES.get_or_init(|| {
+            vec![
+                fl!("network-mode-automatic"),
+                fl!("network-mode-online"),
+                fl!("network-mode-offline"),
+            ]
+        })
+    }
+}
+
+/// The chosen mode combined with what was last detected.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Connectivity {
+    pub mode: NetworkMode,
+    /// The last answer from NetworkManager, `None` until one arrives or if it isn't running.
+    pub detected: Option<bool>,
+}
+
+impl Connectivity {
+    /// Whether network work should run now. Without NetworkManager, automatic mode assumes
+    /// a connection so requests fail on their own instead of waiting forever.
+    pub fn is_online(&self) -> bool {
+        match self.mode {
+            NetworkMode::Automatic => self.detected.unwrap_or(true),
+            NetworkMode::Online => true,
+            NetworkMode::Offline => false,
+        }
+    }
+}
+
+/// `NM_CONNECTIVITY_FULL`: the internet is reachable.
+const NM_CONNECTIVITY_FULL: u32 = 4;
+/// `NM_CONNECTIVITY_UNKNOWN`, reported when connectivity checks are turned off.
+const NM_CONNECTIVITY_UNKNOWN: u32 = 0;
+/// `NM_STATE_CONNECTED_GLOBAL`.
+const NM_STATE_CONNECTED_GLOBAL: u32 = 70;
+
+/// Asks NetworkManager over the system bus whether the internet is reachable.
+/// Returns `None` when NetworkManager can't be reached.
+pub async fn detect() -> Option<bool> {
+    let connection = zbus::Connection::system().await.ok()?;
+    let proxy = zbus::Proxy::new(
+        &connection,
+        "org.freedesktop.NetworkManager",
+        "/org/freedesktop/NetworkManager",
+        "org.freedesktop.NetworkManager",
+    )
+    .await
+    .ok()?;
+
+    let connectivity: u32 = proxy.get_property("Connectivity").await.ok()?;
+    if connectivity != NM_CONNECTIVITY_UNKNOWN {
+        return Some(connectivity == NM_CONNECTIVITY_FULL);
+    }
+
+    // Connectivity checking is off, so fall back to the overall state.
+    let state: u32 = proxy.get_property("State").await.ok()?;
+    Some(state == NM_STATE_CONNECTED_GLOBAL)
+}
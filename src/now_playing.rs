@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The playing track written to files that stay at the same place, so streaming software
+//! such as OBS can show it through its text and image sources.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::flatpak;
+use crate::library::MusicFile;
+
+/// What the text file holds unless the listener writes their own template.
+pub const DEFAULT_TEMPLATE: &str = "{artist} – {title}";
+const TEXT_FILE_NAME: &str = "now-playing.txt";
+/// The cover is copied as it is. OBS looks at the contents, so PNG covers show up too.
+const COVER_FILE_NAME: &str = "now-playing-cover.jpg";
+
+/// Whether the files are written, and how the text is laid out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NowPlayingOutput {
+    pub enabled: bool,
+    /// Text with `{title}`, `{artist}`, `{album}` and `{album_artist}` filled in.
+    pub template: String,
+}
+
+impl Default for NowPlayingOutput {
+    fn default() -> Self {
+        NowPlayingOutput {
+            enabled: false,
+            template: DEFAULT_TEMPLATE.to_string(),
+        }
+    }
+}
+
+/// Folder the files are written to, `~/.local/share/jams/now-playing` unless XDG says
+/// otherwise.
+pub fn output_dir() -> PathBuf {
+    flatpak::data_dir().join("now-playing")
+}
+
+/// `template` filled in for `file`. Separators left dangling by empty tags, like the dash
+/// before a missing artist, are trimmed off.
+pub fn render(template: &str, file: &MusicFile) -> String {
+    let text = template
+        .replace("{title}", &file.track_title)
+        .replace("{artist}", &file.artist)
+        .replace("{album}", &file.album)
+        .replace("{album_artist}", &file.album_artist);
+
+    text.trim_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '–' | '—' | '/' | '|'))
+        .to_string()
+}
+
+/// Writes `text` and copies `cover` into [`output_dir`]. No cover removes the old one, and
+/// empty text leaves an empty file, so sources go blank when nothing plays.
+pub fn write(text: &str, cover: Option<&Path>) -> Result<(), String> {
+    let dir = output_dir();
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+
+    replace(&dir.join(TEXT_FILE_NAME), text.as_bytes())?;
+
+    let cover_path = dir.join(COVER_FILE_NAME);
+    match cover {
+        Some(cover) => {
+            let image = fs::read(cover).map_err(|err| err.to_string())?;
+            replace(&cover_path, &image)
+        }
+        None => match fs::remove_file(&cover_path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.to_string()),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Writes next to `path` and renames over it, so a source reading the file never sees it
+/// half written.
+fn replace(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let partial = path.with_extension("partial");
+    fs::write(&partial, contents).map_err(|err| err.to_string())?;
+    fs::rename(&partial, path).map_err(|err| err.to_string())
+}
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Tracks to carry on with once the queue runs out: the library shuffled, or auto-DJ picks
+//! that stay close to what was just played.
+
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::library::MusicFile;
+
+/// How many tracks auto-DJ adds to the queue at a time.
+pub const AUTO_DJ_BATCH: usize = 10;
+/// How far back in the queue auto-DJ looks to avoid playing a track again.
+const RECENT_TRACKS: usize = 50;
+
+/// A small xorshift generator. Shuffling doesn't need anything stronger.
+struct Rng(u64);
+
+impl Rng {
+    fn from_clock() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        // Xorshift gets stuck on zero.
+        Rng(nanos | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..bound`. `bound` must not be zero.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+/// The IDs of `files` in random order.
+pub fn shuffled(files: &[&MusicFile]) -> Vec<usize> {
+    let mut ids: Vec<usize> = files.iter().map(|file| file.id).collect();
+    let mut rng = Rng::from_clock();
+
+    // Fisher-Yates.
+    for i in (1..ids.len()).rev() {
+        let j = rng.below(i as u64 + 1) as usize;
+        ids.swap(i, j);
+    }
+
+    ids
+}
+
+/// How much `b` has in common with `a`: the same artist counts most, then the same album
+/// artist, then the same decade.
+fn closeness(a: &MusicFile, b: &MusicFile) -> u64 {
+    let mut score = 0;
+    if a.artist == b.artist {
+        score += 4;
+    }
+    if a.album_artist == b.album_artist {
+        score += 2;
+    }
+    if let (Some(a_date), Some(b_date)) = (a.sort_date(), b.sort_date()) {
+        if a_date.decade() == b_date.decade() {
+            score += 1;
+        }
+    }
+    score
+}
+
+/// Picks up to `count` tracks from `files` to follow `queue`. Each pick leans towards tracks
+/// close to the one before it, so the mix drifts rather than jumps, and nothing played in
+/// the last [`RECENT_TRACKS`] of the queue comes back.
+pub fn auto_dj(files: &[&MusicFile], queue: &[usize], count: usize) -> Vec<usize> {
+    let recent: HashSet<usize> = queue.iter().rev().take(RECENT_TRACKS).copied().collect();
+    let mut candidates: Vec<&MusicFile> = files
+        .iter()
+        .filter(|file| !recent.contains(&file.id))
+        .copied()
+        .collect();
+
+    // A library smaller than the recent window would run dry; allow repeats then.
+    if candidates.is_empty() {
+        candidates = files.to_vec();
+    }
+
+    let mut previous = queue
+        .last()
+        .and_then(|id| files.iter().find(|file| file.id == *id))
+        .copied();
+    let mut rng = Rng::from_clock();
+    let mut picks = Vec::new();
+
+    while picks.len() < count && !candidates.is_empty() {
+        // Every candidate stays possible, close ones are just a lot more likely.
+        let weights: Vec<u64> = candidates
+            .iter()
+            .map(|file| 1 + previous.map_or(0, |previous| closeness(previous, file) * 3))
+            .collect();
+
+        let mut target = rng.below(weights.iter().sum());
+        let mut index = 0;
+        while target >= weights[index] {
+            target -= weights[index];
+            index += 1;
+        }
+
+        let pick = candidates.swap_remove(index);
+        picks.push(pick.id);
+        previous = Some(pick);
+    }
+
+    picks
+}
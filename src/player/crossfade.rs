@@ -0,0 +1,291 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Overlapping the end of one track with the start of the next.
+
+use std::f64::consts::FRAC_PI_2;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use super::PlayerBackend;
+use crate::fl;
+use crate::library::MusicFile;
+
+/// Lengths offered in the settings, `Duration::ZERO` meaning off.
+pub const LENGTHS: [Duration; 6] = [
+    Duration::ZERO,
+    Duration::from_secs(2),
+    Duration::from_secs(4),
+    Duration::from_secs(6),
+    Duration::from_secs(8),
+    Duration::from_secs(12),
+];
+
+/// Labels for a dropdown, in the same order as [`LENGTHS`].
+pub fn length_names() -> &'static [String] {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+    NAMES.get_or_init(|| {
+        LENGTHS
+            .iter()
+            .map(|length| match length.as_secs() {
+                0 => fl!("crossfade-off"),
+                seconds => fl!("crossfade-seconds", count = seconds),
+            })
+            .collect()
+    })
+}
+
+/// Whether `next` should follow `current` without any overlap: it's the next track of the
+/// same album, so the album may have been mastered to flow into it, or either one is tagged
+/// as gapless.
+pub fn is_seamless(current: &MusicFile, next: &MusicFile) -> bool {
+    if current.gapless || next.gapless {
+        return true;
+    }
+
+    let same_album = current.album == next.album && current.album_artist == next.album_artist;
+    let follows = if next.disc_number != current.disc_number {
+        current.disc_number.checked_add(1) == Some(next.disc_number) && next.track_number <= 1
+    } else if next.side != current.side {
+        let next_side = current
+            .side
+            .and_then(|side| char::from_u32(u32::from(side) + 1));
+        next_side.is_some() && next_side == next.side && next.track_number <= 1
+    } else {
+        current.track_number.checked_add(1) == Some(next.track_number)
+    };
+
+    same_album && follows
+}
+
+/// A fade in progress. The outgoing track keeps playing on its own backend until the fade
+/// is over, while the app's backend already plays the incoming one.
+pub struct Crossfade {
+    /// Taken once the fade is over.
+    outgoing: Option<Box<dyn PlayerBackend>>,
+    started: Instant,
+    length: Duration,
+}
+
+impl Crossfade {
+    pub fn new(outgoing: Box<dyn PlayerBackend>, length: Duration) -> Self {
+        Crossfade {
+            outgoing: Some(outgoing),
+            started: Instant::now(),
+            length,
+        }
+    }
+
+    /// Sets both volumes for this moment of the fade. Returns `false` once the fade is
+    /// over and the outgoing track has been stopped.
+    pub fn step(&mut self, incoming: &mut dyn PlayerBackend) -> bool {
+        let progress = if self.length.is_zero() {
+            1.0
+        } else {
+            (self.started.elapsed().as_secs_f64() / self.length.as_secs_f64()).min(1.0)
+        };
+
+        if progress >= 1.0 {
+            self.finish(incoming);
+            return false;
+        }
+
+        // Equal-power curves, so the overlap doesn't dip in loudness. Each step is ramped
+        // to by the backends, see `declick`.
+        if let Some(outgoing) = &mut self.outgoing {
+            outgoing.set_volume((progress * FRAC_PI_2).cos());
+        }
+        incoming.set_volume((progress * FRAC_PI_2).sin());
+        true
+    }
+
+    /// Ends the fade straight away. The outgoing track is rarely silent yet at the last
+    /// step, and cut short when the fade is, so it's faded out rather than stopped.
+    pub fn finish(&mut self, incoming: &mut dyn PlayerBackend) {
+        if let Some(outgoing) = self.outgoing.take() {
+            outgoing.fade_out();
+        }
+        incoming.set_volume(1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::player::MockPlayer;
+
+    fn track(album: &str, disc_number: u16, track_number: u16) -> MusicFile {
+        MusicFile {
+            album_artist: "The Hollows".to_string(),
+            album_artist_sort: String::new(),
+            album_artist_key: "hollows".to_string(),
+            album: album.to_string(),
+            disc_number,
+            track_number,
+            side: None,
+            gapless: false,
+            artist: "The Hollows".to_string(),
+            artist_sort: String::new(),
+            artist_key: "hollows".to_string(),
+            track_title: format!("Track {}", track_number),
+            title_key: format!("track {}", track_number),
+            duration: Duration::from_secs(180),
+            channels: 2,
+            release_date: None,
+            original_date: None,
+            release_group_id: String::new(),
+            genre: String::new(),
+            label: String::new(),
+            language: String::new(),
+            bpm: None,
+            key: None,
+            saved_path: PathBuf::from(format!("/music/{}/{}.flac", album, track_number)),
+            inode: None,
+            size: 0,
+            archive: None,
+            uri: format!("file:///music/{}/{}.flac", album, track_number),
+            id: 0,
+        }
+    }
+
+    fn on_side(side: char, track_number: u16) -> MusicFile {
+        MusicFile {
+            side: Some(side),
+            ..track("Low Tide", 0, track_number)
+        }
+    }
+
+    /// A fade from a playing mock to `incoming`, started `ago`.
+    fn fade(length: Duration, ago: Duration, incoming: &mut MockPlayer) -> Crossfade {
+        let mut outgoing = MockPlayer::default();
+        outgoing.set_uri("file:///music/outgoing.flac");
+        outgoing.play();
+        incoming.set_uri("file:///music/incoming.flac");
+        incoming.set_volume(0.0);
+        incoming.play();
+
+        let mut fade = Crossfade::new(Box::new(outgoing), length);
+        fade.started = Instant::now() - ago;
+        fade
+    }
+
+    #[test]
+    fn the_next_track_of_an_album_is_seamless() {
+        assert!(is_seamless(
+            &track("Low Tide", 1, 3),
+            &track("Low Tide", 1, 4)
+        ));
+    }
+
+    #[test]
+    fn skipping_a_track_is_not_seamless() {
+        assert!(!is_seamless(
+            &track("Low Tide", 1, 3),
+            &track("Low Tide", 1, 5)
+        ));
+        assert!(!is_seamless(
+            &track("Low Tide", 1, 4),
+            &track("Low Tide", 1, 3)
+        ));
+    }
+
+    #[test]
+    fn another_album_is_not_seamless() {
+        assert!(!is_seamless(
+            &track("Low Tide", 1, 3),
+            &track("High Water", 1, 4)
+        ));
+
+        let mut other_artist = track("Low Tide", 1, 4);
+        other_artist.album_artist = "Amber Static".to_string();
+        assert!(!is_seamless(&track("Low Tide", 1, 3), &other_artist));
+    }
+
+    #[test]
+    fn the_first_track_of_the_next_disc_is_seamless() {
+        assert!(is_seamless(
+            &track("Low Tide", 1, 12),
+            &track("Low Tide", 2, 1)
+        ));
+        assert!(!is_seamless(
+            &track("Low Tide", 1, 12),
+            &track("Low Tide", 2, 2)
+        ));
+        assert!(!is_seamless(
+            &track("Low Tide", 1, 12),
+            &track("Low Tide", 3, 1)
+        ));
+    }
+
+    #[test]
+    fn the_first_track_of_the_next_side_is_seamless() {
+        assert!(is_seamless(&on_side('A', 5), &on_side('B', 1)));
+        assert!(!is_seamless(&on_side('A', 5), &on_side('C', 1)));
+        assert!(!is_seamless(&on_side('A', 5), &on_side('B', 2)));
+    }
+
+    #[test]
+    fn tracks_tagged_gapless_are_seamless() {
+        let mut next = track("High Water", 1, 1);
+        next.gapless = true;
+
+        assert!(is_seamless(&track("Low Tide", 1, 3), &next));
+    }
+
+    #[test]
+    fn fades_in_from_silence() {
+        let mut incoming = MockPlayer::default();
+        let mut fade = fade(Duration::from_secs(60 * 60), Duration::ZERO, &mut incoming);
+
+        assert!(fade.step(&mut incoming));
+        assert!(incoming.volume() < 0.01);
+        assert!(fade.outgoing.is_some());
+    }
+
+    #[test]
+    fn keeps_equal_power_halfway() {
+        let mut incoming = MockPlayer::default();
+        let length = Duration::from_secs(10);
+        let mut fade = fade(length, length / 2, &mut incoming);
+
+        assert!(fade.step(&mut incoming));
+        assert!((incoming.volume() - (FRAC_PI_2 / 2.0).sin()).abs() < 0.01);
+    }
+
+    #[test]
+    fn ends_once_the_length_has_passed() {
+        let mut incoming = MockPlayer::default();
+        let length = Duration::from_secs(4);
+        let mut fade = fade(length, length, &mut incoming);
+
+        assert!(!fade.step(&mut incoming));
+        assert_eq!(incoming.volume(), 1.0);
+        assert!(fade.outgoing.is_none());
+    }
+
+    #[test]
+    fn a_fade_of_no_length_ends_at_once() {
+        let mut incoming = MockPlayer::default();
+        let mut fade = fade(Duration::ZERO, Duration::ZERO, &mut incoming);
+
+        assert!(!fade.step(&mut incoming));
+        assert_eq!(incoming.volume(), 1.0);
+    }
+
+    #[test]
+    fn finishing_early_brings_the_incoming_track_up() {
+        let mut incoming = MockPlayer::default();
+        let mut fade = fade(
+            Duration::from_secs(12),
+            Duration::from_secs(1),
+            &mut incoming,
+        );
+        assert!(fade.step(&mut incoming));
+
+        fade.finish(&mut incoming);
+        assert_eq!(incoming.volume(), 1.0);
+        assert!(fade.outgoing.is_none());
+        assert!(incoming.position().is_some());
+    }
+}
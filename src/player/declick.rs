@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Volume applied to the samples themselves, moving to a new level over a few milliseconds
+//! rather than jumping there between two buffers. A jump in the middle of a waveform is
+//! heard as a click, which crossfades, which change the volume in steps, and tracks
+//! starting or seeked mid-waveform would otherwise be full of.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+/// How long the gain takes to go from silence to full. Too short to be heard as a fade.
+pub const FADE: Duration = Duration::from_millis(10);
+
+/// A gain shared with the streaming thread, which ramps towards whatever was last set.
+#[derive(Debug)]
+pub struct Ramp {
+    /// The gain asked for, as the bits of an `f32`.
+    target: AtomicU32,
+    /// The gain the last frame got.
+    current: AtomicU32,
+}
+
+impl Ramp {
+    pub fn new(gain: f32) -> Self {
+        Ramp {
+            target: AtomicU32::new(gain.to_bits()),
+            current: AtomicU32::new(gain.to_bits()),
+        }
+    }
+
+    /// Ramps to `gain` from wherever the audio is now.
+    pub fn set(&self, gain: f32) {
+        self.target.store(gain.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Starts the next samples from silence, for audio that was cut to play something
+    /// else, such as a new track or another spot in this one.
+    pub fn restart(&self) {
+        self.current.store(0f32.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Scales interleaved F32LE samples, `channels` to a frame at `rate` frames a second.
+    fn apply(&self, data: &mut [u8], channels: usize, rate: u32) {
+        let target = f32::from_bits(self.target.load(Ordering::Relaxed));
+        let mut gain = f32::from_bits(self.current.load(Ordering::Relaxed));
+        if gain == 1.0 && target == 1.0 {
+            return;
+        }
+
+        let step = 1.0 / (FADE.as_secs_f32() * rate as f32);
+        for frame in data.chunks_exact_mut(4 * channels) {
+            gain = if gain < target {
+                (gain + step).min(target)
+            } else {
+                (gain - step).max(target)
+            };
+
+            for sample in frame.chunks_exact_mut(4) {
+                let value = f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+                sample.copy_from_slice(&(value * gain).to_le_bytes());
+            }
+        }
+
+        self.current.store(gain.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Applies `ramp` to every buffer reaching `pad`, which has to take F32LE samples.
+pub fn attach(pad: &gst::Pad, ramp: Arc<Ramp>) {
+    pad.add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+        let format = pad.current_caps().and_then(|caps| {
+            let structure = caps.structure(0)?;
+            let channels = structure.get::<i32>("channels").ok()?;
+            let rate = structure.get::<i32>("rate").ok()?;
+            Some((usize::try_from(channels).ok()?, u32::try_from(rate).ok()?))
+        });
+        let Some((channels, rate)) = format.filter(|(channels, rate)| *channels > 0 && *rate > 0)
+        else {
+            return gst::PadProbeReturn::Ok;
+        };
+
+        if let Some(buffer) = info.buffer_mut() {
+            if let Ok(mut map) = buffer.make_mut().map_writable() {
+                ramp.apply(map.as_mut_slice(), channels, rate);
+            }
+        }
+
+        gst::PadProbeReturn::Ok
+    });
+}
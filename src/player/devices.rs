@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The output device audio goes to, so the EQ and level can follow the listener from
+//! laptop speakers to headphones and back.
+
+use gstreamer as gst;
+use gstreamer::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::equalizer::ParametricEq;
+use super::filters::FilterSettings;
+
+/// What's remembered for one output device.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceProfile {
+    pub equalizer: Option<ParametricEq>,
+    /// See [`FilterSettings::gain`].
+    pub gain: f64,
+}
+
+impl DeviceProfile {
+    /// The parts of `settings` that depend on the device.
+    pub fn from_filters(settings: &FilterSettings) -> Self {
+        DeviceProfile {
+            equalizer: settings.equalizer.clone(),
+            gain: settings.gain,
+        }
+    }
+
+    /// Puts this profile into `settings`, leaving the other filters alone.
+    pub fn apply(&self, settings: &mut FilterSettings) {
+        settings.equalizer = self.equalizer.clone();
+        settings.gain = self.gain;
+    }
+}
+
+/// Name of the device the sound server plays to by default, if it tells. Blocks while
+/// devices are listed, so run it off the UI thread.
+pub fn default_output() -> Option<String> {
+    gst::init().ok()?;
+
+    let monitor = gst::DeviceMonitor::new();
+    monitor.add_filter(Some("Audio/Sink"), None)?;
+    monitor.start().ok()?;
+    let devices = monitor.devices();
+    monitor.stop();
+
+    // PulseAudio and PipeWire mark the default sink; other providers don't say.
+    devices
+        .iter()
+        .find(|device| {
+            device
+                .properties()
+                .and_then(|properties| properties.get::<bool>("is-default").ok())
+                .unwrap_or(false)
+        })
+        .map(|device| device.display_name().to_string())
+}
@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A test signal played through the default audio output, reporting what the output
+//! negotiated and whether it keeps up. Used from the diagnostics page in the Debug menu.
+
+use std::time::{Duration, Instant};
+
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+/// Frequency of the steady test tone.
+const TONE_HZ: f64 = 440.0;
+/// Range covered by the sweep, low to high.
+const SWEEP_HZ: (f64, f64) = (20.0, 20_000.0);
+/// How long one pass of the sweep takes before it starts over.
+const SWEEP_LENGTH: Duration = Duration::from_secs(10);
+/// Kept well below full scale, this is meant to be heard, not felt.
+const TEST_VOLUME: f64 = 0.2;
+
+/// What the test plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestSignal {
+    Tone,
+    /// A logarithmic sweep across the audible range, repeated.
+    Sweep,
+}
+
+/// What the output negotiated, filled in as the test runs.
+#[derive(Debug, Clone, Default)]
+pub struct OutputReport {
+    /// The sink `autoaudiosink` picked, e.g. `pulsesink`.
+    pub sink: Option<String>,
+    pub sample_rate: Option<i32>,
+    pub channels: Option<i32>,
+    pub format: Option<String>,
+    /// Size of the sink's ring buffer.
+    pub buffer_time: Option<Duration>,
+    /// Size of a single segment of the ring buffer.
+    pub latency_time: Option<Duration>,
+    /// Latency reported for the whole pipeline.
+    pub latency: Option<Duration>,
+    /// Quality-of-service messages from the sink, each one meaning audio arrived late
+    /// and was dropped or played with a gap.
+    pub underruns: u64,
+    pub errors: Vec<String>,
+}
+
+/// A running test. The output stops when this is dropped.
+pub struct ToneTest {
+    pipeline: gst::Pipeline,
+    source: gst::Element,
+    sink: gst::Element,
+    bus: gst::Bus,
+    signal: TestSignal,
+    started: Instant,
+    report: OutputReport,
+}
+
+impl ToneTest {
+    /// Starts playing `signal` through the default output.
+    pub fn start(signal: TestSignal) -> Result<Self, String> {
+        gst::init().map_err(|err| err.to_string())?;
+
+        let pipeline = gst::Pipeline::with_name("jams-tone-test");
+        let source = gst::ElementFactory::make("audiotestsrc")
+            .property("freq", TONE_HZ)
+            .property("volume", TEST_VOLUME)
+            .build()
+            .map_err(|err| err.to_string())?;
+        let convert = gst::ElementFactory::make("audioconvert")
+            .build()
+            .map_err(|err| err.to_string())?;
+        let resample = gst::ElementFactory::make("audioresample")
+            .build()
+            .map_err(|err| err.to_string())?;
+        // The same sink playbin picks when nothing else is configured.
+        let sink = gst::ElementFactory::make("autoaudiosink")
+            .build()
+            .map_err(|err| err.to_string())?;
+
+        let chain = [&source, &convert, &resample, &sink];
+        pipeline.add_many(chain).map_err(|err| err.to_string())?;
+        gst::Element::link_many(chain).map_err(|err| err.to_string())?;
+
+        let bus = pipeline
+            .bus()
+            .ok_or_else(|| String::from("The test pipeline has no bus."))?;
+
+        pipeline
+            .set_state(gst::State::Playing)
+            .map_err(|err| err.to_string())?;
+
+        Ok(ToneTest {
+            pipeline,
+            source,
+            sink,
+            bus,
+            signal,
+            started: Instant::now(),
+            report: OutputReport::default(),
+        })
+    }
+
+    pub fn signal(&self) -> TestSignal {
+        self.signal
+    }
+
+    /// The frequency currently playing.
+    pub fn frequency(&self) -> f64 {
+        match self.signal {
+            TestSignal::Tone => TONE_HZ,
+            TestSignal::Sweep => {
+                let progress =
+                    (self.started.elapsed().as_secs_f64() / SWEEP_LENGTH.as_secs_f64()).fract();
+                SWEEP_HZ.0 * (SWEEP_HZ.1 / SWEEP_HZ.0).powf(progress)
+            }
+        }
+    }
+
+    /// Moves the sweep along and collects what the output has negotiated so far. Call this
+    /// often for a smooth sweep.
+    pub fn poll(&mut self) -> &OutputReport {
+        if self.signal == TestSignal::Sweep {
+            self.source.set_property("freq", self.frequency());
+        }
+
+        while let Some(message) = self.bus.pop() {
+            match message.view() {
+                gst::MessageView::Qos(_) => self.report.underruns += 1,
+                gst::MessageView::Error(err) => self.report.errors.push(err.error().to_string()),
+                gst::MessageView::Warning(warning) => {
+                    self.report.errors.push(warning.error().to_string())
+                }
+                _ => {}
+            }
+        }
+
+        // autoaudiosink only creates the real sink once it starts, so look it up every time.
+        if let Some(device) = self.device_sink() {
+            self.report.sink = device.factory().map(|factory| factory.name().to_string());
+
+            if let Some(caps) = device.static_pad("sink").and_then(|pad| pad.current_caps()) {
+                if let Some(structure) = caps.structure(0) {
+                    self.report.sample_rate = structure.get::<i32>("rate").ok();
+                    self.report.channels = structure.get::<i32>("channels").ok();
+                    self.report.format = structure.get::<String>("format").ok();
+                }
+            }
+
+            // Both are in microseconds on every sink based on GstAudioBaseSink.
+            for (name, field) in [
+                ("buffer-time", &mut self.report.buffer_time),
+                ("latency-time", &mut self.report.latency_time),
+            ] {
+                if device.find_property(name).is_some() {
+                    *field = u64::try_from(device.property::<i64>(name))
+                        .ok()
+                        .map(Duration::from_micros);
+                }
+            }
+        }
+
+        let mut query = gst::query::Latency::new();
+        if self.pipeline.query(&mut query) {
+            let (_live, min, _max) = query.result();
+            self.report.latency = Some(Duration::from_nanos(min.nseconds()));
+        }
+
+        &self.report
+    }
+
+    /// The sink inside `autoaudiosink` that actually talks to the sound server.
+    fn device_sink(&self) -> Option<gst::Element> {
+        match self.sink.downcast_ref::<gst::Bin>() {
+            Some(bin) => bin.iterate_sinks().into_iter().flatten().next(),
+            None => Some(self.sink.clone()),
+        }
+    }
+
+    /// Stops the output and hands back everything collected.
+    pub fn finish(mut self) -> OutputReport {
+        self.poll();
+        std::mem::take(&mut self.report)
+    }
+}
+
+impl Drop for ToneTest {
+    fn drop(&mut self) {
+        let _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Parametric EQ loaded from the `ParametricEQ.txt` files AutoEq publishes for headphones,
+//! played through GStreamer's `equalizer-nbands`.
+
+use std::path::Path;
+
+use gstreamer as gst;
+use gstreamer::glib;
+use gstreamer::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The most bands `equalizer-nbands` takes.
+const MAX_BANDS: usize = 64;
+/// Band gains `equalizer-nbands` accepts, in dB.
+const GAIN_RANGE: (f64, f64) = (-24.0, 12.0);
+
+/// The shape of a band.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BandKind {
+    Peak,
+    LowShelf,
+    HighShelf,
+}
+
+impl BandKind {
+    /// Reads AutoEq's filter type, such as `PK` or `LSC`.
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "PK" | "PEQ" => Some(BandKind::Peak),
+            "LS" | "LSC" | "LSQ" => Some(BandKind::LowShelf),
+            "HS" | "HSC" | "HSQ" => Some(BandKind::HighShelf),
+            _ => None,
+        }
+    }
+
+    /// The band's `type` as `equalizer-nbands` names it.
+    fn nick(self) -> &'static str {
+        match self {
+            BandKind::Peak => "peak",
+            BandKind::LowShelf => "low-shelf",
+            BandKind::HighShelf => "high-shelf",
+        }
+    }
+}
+
+/// One filter of a parametric EQ.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EqBand {
+    pub kind: BandKind,
+    /// Center or corner frequency in Hz.
+    pub frequency: f64,
+    /// In dB.
+    pub gain: f64,
+    pub q: f64,
+}
+
+/// A correction profile, usually for one model of headphones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParametricEq {
+    /// Shown in the settings, taken from the file name.
+    pub name: String,
+    /// Gain applied before the bands, in dB, so boosts don't clip.
+    pub preamp: f64,
+    pub bands: Vec<EqBand>,
+}
+
+impl ParametricEq {
+    /// Reads the profile at `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Self::parse(name, &text)
+    }
+
+    /// Parses AutoEq's format, a preamp line followed by one line per filter:
+    ///
+    /// ```text
+    /// Preamp: -6.2 dB
+    /// Filter 1: ON LSC Fc 105 Hz Gain 6.5 dB Q 0.70
+    /// Filter 2: ON PK Fc 160 Hz Gain -2.3 dB Q 0.55
+    /// ```
+    pub fn parse(name: String, text: &str) -> Result<Self, String> {
+        let mut preamp = 0.0;
+        let mut bands = Vec::new();
+
+        for line in text.lines() {
+            let Some((label, rest)) = line.split_once(':') else {
+                continue;
+            };
+            let words: Vec<&str> = rest.split_whitespace().collect();
+
+            if label.trim().eq_ignore_ascii_case("preamp") {
+                preamp = words
+                    .first()
+                    .and_then(|gain| gain.parse().ok())
+                    .ok_or_else(|| format!("Could not read the preamp in \"{}\"", line))?;
+            } else if label.trim_start().starts_with("Filter") {
+                // Filters switched off are kept in the file but do nothing.
+                if words.first() != Some(&"ON") {
+                    continue;
+                }
+
+                let kind = words.get(1).copied().unwrap_or_default();
+                let kind = BandKind::parse(kind)
+                    .ok_or_else(|| format!("Filter type {} isn't supported", kind))?;
+                let value = |key: &str| {
+                    words
+                        .iter()
+                        .position(|word| *word == key)
+                        .and_then(|index| words.get(index + 1))
+                        .and_then(|value| value.parse::<f64>().ok())
+                };
+
+                let (Some(frequency), Some(gain), Some(q)) =
+                    (value("Fc"), value("Gain"), value("Q"))
+                else {
+                    return Err(format!("Could not read the filter in \"{}\"", line));
+                };
+                if frequency <= 0.0 || q <= 0.0 {
+                    return Err(format!("Could not read the filter in \"{}\"", line));
+                }
+
+                bands.push(EqBand {
+                    kind,
+                    frequency,
+                    gain,
+                    q,
+                });
+            }
+        }
+
+        if bands.is_empty() {
+            return Err(String::from("The file has no filters"));
+        }
+        if bands.len() > MAX_BANDS {
+            return Err(format!("Only up to {} filters are supported", MAX_BANDS));
+        }
+
+        Ok(ParametricEq {
+            name,
+            preamp,
+            bands,
+        })
+    }
+
+    /// The preamp and the equalizer, to go into the filter chain in that order.
+    pub(super) fn elements(&self) -> Result<[gst::Element; 2], glib::BoolError> {
+        let preamp = gst::ElementFactory::make("volume")
+            .property("volume", 10f64.powf(self.preamp / 20.0))
+            .build()?;
+        let equalizer = gst::ElementFactory::make("equalizer-nbands")
+            .property("num-bands", self.bands.len() as u32)
+            .build()?;
+
+        let proxy = equalizer
+            .dynamic_cast_ref::<gst::ChildProxy>()
+            .ok_or_else(|| glib::bool_error!("equalizer-nbands has no bands"))?;
+        for (index, band) in self.bands.iter().enumerate() {
+            let Some(child) = proxy.child_by_index(index as u32) else {
+                continue;
+            };
+
+            // Bands are sized by their width in Hz rather than Q.
+            child.set_property("freq", band.frequency);
+            child.set_property("bandwidth", band.frequency / band.q);
+            child.set_property("gain", band.gain.clamp(GAIN_RANGE.0, GAIN_RANGE.1));
+            child.set_property_from_str("type", band.kind.nick());
+        }
+
+        Ok([preamp, equalizer])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = "Preamp: -6.2 dB
+Filter 1: ON LSC Fc 105 Hz Gain 6.5 dB Q 0.70
+Filter 2: ON PK Fc 160 Hz Gain -2.3 dB Q 0.55
+Filter 3: OFF PK Fc 2000 Hz Gain 4.0 dB Q 1.00
+Filter 4: ON HSC Fc 10000 Hz Gain -4.1 dB Q 0.70
+";
+
+    fn parse(text: &str) -> Result<ParametricEq, String> {
+        ParametricEq::parse(String::from("Headphones"), text)
+    }
+
+    #[test]
+    fn reads_the_preamp_and_the_filters_switched_on() {
+        let eq = parse(PROFILE).unwrap();
+
+        assert_eq!(eq.name, "Headphones");
+        assert_eq!(eq.preamp, -6.2);
+        assert_eq!(
+            eq.bands,
+            [
+                EqBand {
+                    kind: BandKind::LowShelf,
+                    frequency: 105.0,
+                    gain: 6.5,
+                    q: 0.7,
+                },
+                EqBand {
+                    kind: BandKind::Peak,
+                    frequency: 160.0,
+                    gain: -2.3,
+                    q: 0.55,
+                },
+                EqBand {
+                    kind: BandKind::HighShelf,
+                    frequency: 10000.0,
+                    gain: -4.1,
+                    q: 0.7,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_windows_line_endings() {
+        assert_eq!(parse(&PROFILE.replace('\n', "\r\n")), parse(PROFILE));
+    }
+
+    #[test]
+    fn goes_without_a_preamp() {
+        let eq = parse("Filter 1: ON PK Fc 1000 Hz Gain 3 dB Q 1").unwrap();
+        assert_eq!(eq.preamp, 0.0);
+        assert_eq!(eq.bands.len(), 1);
+    }
+
+    #[test]
+    fn rejects_unreadable_lines() {
+        assert!(parse("Preamp: loud\nFilter 1: ON PK Fc 1000 Hz Gain 3 dB Q 1").is_err());
+        assert!(parse("Filter 1: ON BP Fc 1000 Hz Gain 3 dB Q 1").is_err());
+        assert!(parse("Filter 1: ON PK Fc 1000 Hz Gain 3 dB").is_err());
+        assert!(parse("Filter 1: ON PK Fc 0 Hz Gain 3 dB Q 1").is_err());
+        assert!(parse("Filter 1: ON PK Fc 1000 Hz Gain 3 dB Q 0").is_err());
+    }
+
+    #[test]
+    fn needs_a_filter() {
+        assert!(parse("").is_err());
+        assert!(parse("Preamp: -3 dB\nFilter 1: OFF PK Fc 1000 Hz Gain 3 dB Q 1").is_err());
+    }
+
+    #[test]
+    fn takes_as_many_filters_as_the_equalizer_does() {
+        let filters = |count: usize| {
+            (1..=count)
+                .map(|n| format!("Filter {}: ON PK Fc {} Hz Gain 1 dB Q 1\n", n, n * 100))
+                .collect::<String>()
+        };
+
+        assert_eq!(parse(&filters(MAX_BANDS)).unwrap().bands.len(), MAX_BANDS);
+        assert!(parse(&filters(MAX_BANDS + 1)).is_err());
+    }
+}
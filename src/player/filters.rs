@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Processing applied to the decoded audio before it reaches the sink.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use gstreamer as gst;
+use gstreamer::glib;
+use gstreamer::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::declick::{self, Ramp};
+use super::equalizer::ParametricEq;
+use crate::fl;
+
+/// Level above which `rglimiter` starts compressing, i.e. -6 dBFS.
+const LIMITER_THRESHOLD: f32 = 0.5;
+
+/// How much of each side of a stereo recording is fed to the other ear, the way it
+/// would be heard from speakers. Uses the presets of the bs2b library.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Crossfeed {
+    #[default]
+    Off,
+    /// 700 Hz cut, 4.5 dB feed.
+    Light,
+    /// 700 Hz cut, 6 dB feed, like Chu Moy's headphone amplifier.
+    Medium,
+    /// 650 Hz cut, 9.5 dB feed, like Jan Meier's.
+    Strong,
+}
+
+impl Crossfeed {
+    pub const ALL: [Crossfeed; 4] = [
+        Crossfeed::Off,
+        Crossfeed::Light,
+        Crossfeed::Medium,
+        Crossfeed::Strong,
+    ];
+
+    /// Labels for a dropdown, in the same order as [`Crossfeed::ALL`].
+    pub fn names() -> &'static [String] {
+        static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+        NAMES.get_or_init(|| {
+            vec![
+                fl!("crossfeed-off"),
+                fl!("crossfeed-light"),
+                fl!("crossfeed-medium"),
+                fl!("crossfeed-strong"),
+            ]
+        })
+    }
+
+    /// Cut frequency in Hz and feed level in tenths of a dB, as `bs2b` takes them.
+    fn parameters(self) -> Option<(i32, i32)> {
+        match self {
+            Crossfeed::Off => None,
+            Crossfeed::Light => Some((700, 45)),
+            Crossfeed::Medium => Some((700, 60)),
+            Crossfeed::Strong => Some((650, 95)),
+        }
+    }
+}
+
+/// Which filters are switched on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FilterSettings {
+    /// Cancels what's panned to the center, which is usually the lead vocal.
+    pub vocal_reduction: bool,
+    /// Makes headphones sound less like the two sides are inside the head.
+    pub crossfeed: Crossfeed,
+    /// Headphone correction imported from AutoEq, kept so the file isn't needed again.
+    pub equalizer: Option<ParametricEq>,
+    /// Level in dB, 0 leaving it as it is. Evens out devices that play louder than others.
+    pub gain: f64,
+    /// Soft-knee limiter at the end of the chain, so boosts can't clip.
+    pub limiter: bool,
+}
+
+/// Builds the bin set as playbin's `audio-filter`. It's needed even with every filter off,
+/// since it ends in `ramp`, which sets the volume.
+///
+/// `limiting` is raised from the streaming thread whenever the limiter has to act.
+pub fn build(
+    settings: &FilterSettings,
+    limiting: &Arc<AtomicBool>,
+    ramp: &Arc<Ramp>,
+) -> Result<gst::Element, glib::BoolError> {
+    let bin = gst::Bin::builder().name("jams-audio-filter").build();
+    let mut chain = vec![gst::ElementFactory::make("audioconvert").build()?];
+
+    if settings.gain != 0.0 {
+        chain.push(
+            gst::ElementFactory::make("volume")
+                .property("volume", 10f64.powf(settings.gain / 20.0))
+                .build()?,
+        );
+    }
+
+    if settings.vocal_reduction {
+        chain.push(gst::ElementFactory::make("audiokaraoke").build()?);
+    }
+
+    if let Some((cut, feed)) = settings.crossfeed.parameters() {
+        chain.push(
+            gst::ElementFactory::make("bs2b")
+                .property("fcut", cut)
+                .property("feed", feed)
+                .build()?,
+        );
+    }
+
+    if let Some(equalizer) = &settings.equalizer {
+        chain.extend(equalizer.elements()?);
+    }
+
+    if settings.limiter {
+        // The probe below reads samples directly, so pin the format.
+        chain.push(float_samples()?);
+
+        let limiter = gst::ElementFactory::make("rglimiter").build()?;
+        watch_limiter(&limiter, Arc::clone(limiting));
+        chain.push(limiter);
+    }
+
+    // Last, so nothing after it can undo the ramp.
+    chain.push(float_samples()?);
+    let output = gst::ElementFactory::make("audioconvert").build()?;
+    if let Some(pad) = output.static_pad("sink") {
+        declick::attach(&pad, Arc::clone(ramp));
+    }
+    chain.push(output);
+
+    bin.add_many(&chain)?;
+    gst::Element::link_many(&chain)?;
+
+    let sink = chain[0]
+        .static_pad("sink")
+        .ok_or_else(|| glib::bool_error!("Filter chain has no sink pad"))?;
+    let src = chain[chain.len() - 1]
+        .static_pad("src")
+        .ok_or_else(|| glib::bool_error!("Filter chain has no source pad"))?;
+    bin.add_pad(&gst::GhostPad::with_target(&sink)?)?;
+    bin.add_pad(&gst::GhostPad::with_target(&src)?)?;
+
+    Ok(bin.upcast())
+}
+
+/// A capsfilter making samples F32LE, for probes that read them.
+fn float_samples() -> Result<gst::Element, glib::BoolError> {
+    let caps = gst::Caps::builder("audio/x-raw")
+        .field("format", "F32LE")
+        .build();
+    gst::ElementFactory::make("capsfilter")
+        .property("caps", caps)
+        .build()
+}
+
+/// Raises `limiting` whenever a buffer going into `limiter` peaks above its threshold.
+fn watch_limiter(limiter: &gst::Element, limiting: Arc<AtomicBool>) {
+    let Some(pad) = limiter.static_pad("sink") else {
+        return;
+    };
+
+    pad.add_probe(gst::PadProbeType::BUFFER, move |_, info| {
+        if let Some(buffer) = info.buffer() {
+            if let Ok(map) = buffer.map_readable() {
+                let peaked = map.as_slice().chunks_exact(4).any(|sample| {
+                    let sample = f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+                    sample.abs() > LIMITER_THRESHOLD
+                });
+
+                if peaked {
+                    limiting.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+
+        gst::PadProbeReturn::Ok
+    });
+}
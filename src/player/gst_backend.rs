@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gstreamer as gst;
+use gstreamer::prelude::*;
+use gstreamer::ClockTime;
+use gstreamer_play as gst_play;
+
+use super::declick::Ramp;
+use super::filters::{self, FilterSettings};
+use super::inspect::{self, PipelineSnapshot};
+use super::output::{self, OutputSettings};
+use super::{PlayerBackend, PlayerEvent};
+
+/// How long a backend that's done with keeps running after fading out, for the faded
+/// samples to get through the sink's buffer, which can hold up to half a second.
+const FADE_OUT_TAIL: Duration = Duration::from_millis(600);
+
+pub struct GStreamerPlayer {
+    /// The sink responsible for managing the audio playback.
+    player: gst_play::Play,
+    /// Where `player` posts state changes, seek completion and errors.
+    bus: gst::Bus,
+    /// Raised by the limiter from the streaming thread, cleared when polled.
+    limiting: Arc<AtomicBool>,
+    /// The volume, applied by the filters at the end of the chain rather than by playbin,
+    /// so changing it doesn't click.
+    ramp: Arc<Ramp>,
+    /// Recent bus messages, for the pipeline inspector.
+    recent_messages: VecDeque<String>,
+    /// Handed to playbin once the loaded track is about to run out.
+    next_uri: Arc<Mutex<Option<String>>>,
+    /// The next track after playbin took it, until it starts playing.
+    handed_over: Arc<Mutex<Option<String>>>,
+}
+
+impl GStreamerPlayer {
+    pub fn new() -> Self {
+        gst::init().expect("Could not initialize GStreamer.");
+
+        let player = gst_play::Play::new(None::<gst_play::PlayVideoRenderer>);
+        let bus = player.message_bus();
+
+        let next_uri = Arc::new(Mutex::new(None::<String>));
+        let handed_over = Arc::new(Mutex::new(None::<String>));
+
+        // Emitted from the streaming thread once the whole track has been read, early
+        // enough for the next one to be decoded straight after it without a gap.
+        let (next, handed) = (next_uri.clone(), handed_over.clone());
+        player
+            .pipeline()
+            .connect("about-to-finish", false, move |values| {
+                let playbin = values[0].get::<gst::Element>().ok()?;
+                let uri = next.lock().ok()?.take()?;
+                playbin.set_property("uri", uri.as_str());
+                *handed.lock().ok()? = Some(uri);
+                None
+            });
+
+        let mut gstreamer_player = GStreamerPlayer {
+            player,
+            bus,
+            limiting: Arc::new(AtomicBool::new(false)),
+            ramp: Arc::new(Ramp::new(1.0)),
+            recent_messages: VecDeque::with_capacity(inspect::RECENT_MESSAGES),
+            next_uri,
+            handed_over,
+        };
+        // The ramp sits in the filter bin, so there has to be one before anything plays.
+        gstreamer_player.set_filters(FilterSettings::default());
+        gstreamer_player
+    }
+
+    fn forget_next(&self) {
+        for uri in [&self.next_uri, &self.handed_over] {
+            if let Ok(mut uri) = uri.lock() {
+                *uri = None;
+            }
+        }
+    }
+
+    /// Whether playbin moved on to the track it was handed, which it does without posting
+    /// an end of stream.
+    fn took_next(&self) -> bool {
+        let Ok(mut handed) = self.handed_over.lock() else {
+            return false;
+        };
+        if handed.is_none() {
+            return false;
+        }
+
+        let current = self
+            .player
+            .pipeline()
+            .property::<Option<String>>("current-uri");
+        if current.is_some() && *handed == current {
+            *handed = None;
+            return true;
+        }
+        false
+    }
+}
+
+impl Default for GStreamerPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlayerBackend for GStreamerPlayer {
+    fn set_uri(&mut self, uri: &str) {
+        self.forget_next();
+        self.ramp.restart();
+        self.player.set_uri(Some(uri));
+    }
+
+    fn set_next_uri(&mut self, uri: Option<&str>) {
+        if let Ok(mut next) = self.next_uri.lock() {
+            *next = uri.map(String::from);
+        }
+    }
+
+    fn play(&mut self) {
+        self.player.play();
+    }
+
+    fn pause(&mut self) {
+        self.player.pause();
+    }
+
+    fn stop(&mut self) {
+        self.forget_next();
+        self.player.stop();
+    }
+
+    fn fade_out(self: Box<Self>) {
+        self.forget_next();
+        self.ramp.set(0.0);
+
+        let player = self.player.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(FADE_OUT_TAIL);
+            player.stop();
+        });
+    }
+
+    fn seek(&mut self, position: Duration) {
+        self.ramp.restart();
+        self.player
+            .seek(ClockTime::from_nseconds(position.as_nanos() as u64));
+    }
+
+    fn position(&self) -> Option<Duration> {
+        self.player
+            .position()
+            .map(|position| Duration::from_nanos(position.nseconds()))
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.player
+            .duration()
+            .map(|duration| Duration::from_nanos(duration.nseconds()))
+    }
+
+    fn set_volume(&mut self, volume: f64) {
+        self.ramp.set(volume as f32);
+    }
+
+    fn set_filters(&mut self, settings: FilterSettings) {
+        match filters::build(&settings, &self.limiting, &self.ramp) {
+            // playbin picks up a new filter when the next track is loaded.
+            Ok(filter) => self.player.pipeline().set_property("audio-filter", &filter),
+            Err(err) => eprintln!("Could not build the audio filters: {}", err),
+        }
+    }
+
+    fn set_output(&mut self, settings: OutputSettings) {
+        match output::build(&settings) {
+            // Like the filters, a new sink is only used from the next track.
+            Ok(sink) => self
+                .player
+                .pipeline()
+                .set_property("audio-sink", sink.as_ref()),
+            Err(err) => eprintln!("Could not build the audio output: {}", err),
+        }
+    }
+
+    fn poll_events(&mut self) -> Vec<PlayerEvent> {
+        let mut events = Vec::new();
+
+        if self.limiting.swap(false, Ordering::Relaxed) {
+            events.push(PlayerEvent::Limiting);
+        }
+        if self.took_next() {
+            inspect::remember(
+                &mut self.recent_messages,
+                String::from("Next track started"),
+            );
+            events.push(PlayerEvent::NextTrack);
+        }
+
+        // Everything is drained, even messages nobody cares about, so the bus doesn't fill up.
+        while let Some(message) = self.bus.pop() {
+            let Ok(message) = gst_play::PlayMessage::parse(&message) else {
+                continue;
+            };
+
+            // Position updates arrive several times a second and would drown everything else.
+            let description = match &message {
+                gst_play::PlayMessage::UriLoaded { .. } => Some(String::from("URI loaded")),
+                gst_play::PlayMessage::StateChanged { state } => {
+                    Some(format!("State changed to {:?}", state))
+                }
+                gst_play::PlayMessage::Buffering { percent } => {
+                    Some(format!("Buffering {}%", percent))
+                }
+                gst_play::PlayMessage::EndOfStream { .. } => Some(String::from("End of stream")),
+                gst_play::PlayMessage::SeekDone => Some(String::from("Seek done")),
+                gst_play::PlayMessage::Error { error, .. } => Some(format!("Error: {}", error)),
+                gst_play::PlayMessage::Warning { error, .. } => Some(format!("Warning: {}", error)),
+                _ => None,
+            };
+            if let Some(description) = description {
+                inspect::remember(&mut self.recent_messages, description);
+            }
+
+            match message {
+                gst_play::PlayMessage::SeekDone => events.push(PlayerEvent::SeekDone),
+                gst_play::PlayMessage::EndOfStream { .. } => events.push(PlayerEvent::EndOfStream),
+                gst_play::PlayMessage::Error { error, .. } => {
+                    events.push(PlayerEvent::Error(error.to_string()))
+                }
+                _ => {}
+            }
+        }
+
+        events
+    }
+
+    fn inspect(&self) -> Option<PipelineSnapshot> {
+        Some(inspect::snapshot(
+            &self.player.pipeline(),
+            &self.recent_messages,
+        ))
+    }
+
+    fn write_graph(&self) -> Result<PathBuf, String> {
+        inspect::write_graph(&self.player.pipeline())
+    }
+}
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A look inside the playback pipeline for bug reports: element states, negotiated caps
+//! and a Graphviz dump.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+/// How many bus messages a backend keeps around for [`PipelineSnapshot::messages`].
+pub const RECENT_MESSAGES: usize = 50;
+
+/// One element of the pipeline.
+#[derive(Debug, Clone)]
+pub struct ElementInfo {
+    pub name: String,
+    /// Factory the element was made from, e.g. `pulsesink`.
+    pub factory: String,
+    pub state: String,
+    /// `pad: caps` for every pad that has negotiated caps.
+    pub caps: Vec<String>,
+}
+
+/// The pipeline as it was when the snapshot was taken.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineSnapshot {
+    pub state: String,
+    /// The state the pipeline is changing to, if it's in the middle of a change.
+    pub pending: Option<String>,
+    pub elements: Vec<ElementInfo>,
+    /// Recent bus messages, oldest first.
+    pub messages: Vec<String>,
+}
+
+/// Folder pipeline graphs are written to, `~/.cache/jams` unless XDG says otherwise.
+pub fn graph_dir() -> PathBuf {
+    let cache_home = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let home_dir = std::env::var("HOME").unwrap_or_default();
+            Path::new(&home_dir).join(".cache")
+        });
+
+    cache_home.join("jams")
+}
+
+/// Appends `message` to `recent`, dropping the oldest once there are [`RECENT_MESSAGES`].
+pub fn remember(recent: &mut VecDeque<String>, message: String) {
+    if recent.len() == RECENT_MESSAGES {
+        recent.pop_front();
+    }
+    recent.push_back(message);
+}
+
+/// Describes `pipeline` and every element nested in it.
+pub fn snapshot(pipeline: &gst::Element, recent: &VecDeque<String>) -> PipelineSnapshot {
+    let (_, current, pending) = pipeline.state(Some(gst::ClockTime::ZERO));
+
+    let elements = match pipeline.downcast_ref::<gst::Bin>() {
+        Some(bin) => bin
+            .iterate_recurse()
+            .into_iter()
+            .flatten()
+            .map(|element| describe(&element))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    PipelineSnapshot {
+        state: format!("{:?}", current),
+        pending: (pending != gst::State::VoidPending).then(|| format!("{:?}", pending)),
+        elements,
+        messages: recent.iter().cloned().collect(),
+    }
+}
+
+fn describe(element: &gst::Element) -> ElementInfo {
+    let caps = element
+        .pads()
+        .iter()
+        .filter_map(|pad| {
+            pad.current_caps()
+                .map(|caps| format!("{}: {}", pad.name(), caps))
+        })
+        .collect();
+
+    ElementInfo {
+        name: element.name().to_string(),
+        factory: element
+            .factory()
+            .map(|factory| factory.name().to_string())
+            .unwrap_or_default(),
+        state: format!("{:?}", element.current_state()),
+        caps,
+    }
+}
+
+/// Writes `pipeline` as a Graphviz DOT file into [`graph_dir`] and returns its path.
+pub fn write_graph(pipeline: &gst::Element) -> Result<PathBuf, String> {
+    let bin = pipeline
+        .downcast_ref::<gst::Bin>()
+        .ok_or("The playback pipeline is not a bin.")?;
+    let dot = gst::debug_bin_to_dot_data(bin, gst::DebugGraphDetails::all());
+
+    let dir = graph_dir();
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = dir.join(format!("pipeline-{}.dot", timestamp));
+    fs::write(&path, dot.as_bytes()).map_err(|err| err.to_string())?;
+
+    Ok(path)
+}
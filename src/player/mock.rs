@@ -0,0 +1,278 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use super::{FilterSettings, OutputSettings, PipelineSnapshot, PlayerBackend, PlayerEvent};
+
+/// Length reported for every track unless told otherwise.
+const DEFAULT_TRACK_DURATION: Duration = Duration::from_secs(180);
+
+/// A backend that plays nothing but keeps time like a real one: the position advances
+/// with the wall clock while playing and stops at the end of the track.
+#[derive(Debug)]
+pub struct MockPlayer {
+    uri: Option<String>,
+    /// Follows `uri` once it ends, see [`PlayerBackend::set_next_uri`].
+    next_uri: Option<String>,
+    track_duration: Duration,
+    /// Position at the moment playback last started or the track was seeked.
+    position: Duration,
+    /// Set while playing.
+    playing_since: Option<Instant>,
+    /// Last set with [`PlayerBackend::set_volume`].
+    volume: f64,
+    /// Events waiting for the next [`PlayerBackend::poll_events`].
+    pending_events: Vec<PlayerEvent>,
+}
+
+impl Default for MockPlayer {
+    fn default() -> Self {
+        MockPlayer::with_duration(DEFAULT_TRACK_DURATION)
+    }
+}
+
+impl MockPlayer {
+    pub fn with_duration(track_duration: Duration) -> Self {
+        MockPlayer {
+            uri: None,
+            next_uri: None,
+            track_duration,
+            position: Duration::ZERO,
+            playing_since: None,
+            volume: 1.0,
+            pending_events: Vec::new(),
+        }
+    }
+
+    /// The volume last set, for checking fades.
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    fn current_position(&self) -> Duration {
+        let elapsed = self
+            .playing_since
+            .map_or(Duration::ZERO, |since| since.elapsed());
+
+        (self.position + elapsed).min(self.track_duration)
+    }
+}
+
+impl PlayerBackend for MockPlayer {
+    fn set_uri(&mut self, uri: &str) {
+        self.uri = Some(uri.to_string());
+        self.next_uri = None;
+        self.position = Duration::ZERO;
+        self.playing_since = None;
+    }
+
+    fn set_next_uri(&mut self, uri: Option<&str>) {
+        self.next_uri = uri.map(String::from);
+    }
+
+    fn play(&mut self) {
+        if self.uri.is_some() && self.playing_since.is_none() {
+            self.playing_since = Some(Instant::now());
+        }
+    }
+
+    fn pause(&mut self) {
+        self.position = self.current_position();
+        self.playing_since = None;
+    }
+
+    fn stop(&mut self) {
+        self.next_uri = None;
+        self.position = Duration::ZERO;
+        self.playing_since = None;
+    }
+
+    fn fade_out(mut self: Box<Self>) {
+        self.stop();
+    }
+
+    fn seek(&mut self, position: Duration) {
+        self.position = position.min(self.track_duration);
+
+        if self.playing_since.is_some() {
+            self.playing_since = Some(Instant::now());
+        }
+
+        // Seeks complete instantly, but are still reported the way the pipeline does.
+        self.pending_events.push(PlayerEvent::SeekDone);
+    }
+
+    fn position(&self) -> Option<Duration> {
+        self.uri.as_ref().map(|_| self.current_position())
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.uri.as_ref().map(|_| self.track_duration)
+    }
+
+    /// Only remembered, as the mock produces no audio.
+    fn set_volume(&mut self, volume: f64) {
+        self.volume = volume;
+    }
+
+    /// The mock produces no audio, so there is nothing to filter.
+    fn set_filters(&mut self, _settings: FilterSettings) {}
+
+    /// The mock has no output to set up.
+    fn set_output(&mut self, _settings: OutputSettings) {}
+
+    fn poll_events(&mut self) -> Vec<PlayerEvent> {
+        // Like the pipeline, moves on to the next track if it has one, or stops at the end
+        // and says so once.
+        if self.playing_since.is_some() && self.current_position() >= self.track_duration {
+            if let Some(next) = self.next_uri.take() {
+                self.uri = Some(next);
+                self.position = Duration::ZERO;
+                self.playing_since = Some(Instant::now());
+                self.pending_events.push(PlayerEvent::NextTrack);
+            } else {
+                self.position = self.track_duration;
+                self.playing_since = None;
+                self.pending_events.push(PlayerEvent::EndOfStream);
+            }
+        }
+
+        std::mem::take(&mut self.pending_events)
+    }
+
+    fn inspect(&self) -> Option<PipelineSnapshot> {
+        None
+    }
+
+    fn write_graph(&self) -> Result<PathBuf, String> {
+        Err(String::from("The mock backend has no pipeline."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    const URI: &str = "file:///music/one.flac";
+
+    /// Driven through the trait, the way the app holds it.
+    fn player(track_duration: Duration) -> Box<dyn PlayerBackend> {
+        Box::new(MockPlayer::with_duration(track_duration))
+    }
+
+    #[test]
+    fn knows_nothing_until_a_track_is_loaded() {
+        let mut player = player(DEFAULT_TRACK_DURATION);
+        player.play();
+
+        assert_eq!(player.position(), None);
+        assert_eq!(player.duration(), None);
+        assert!(player.poll_events().is_empty());
+    }
+
+    #[test]
+    fn keeps_time_only_while_playing() {
+        let mut player = player(DEFAULT_TRACK_DURATION);
+        player.set_uri(URI);
+        assert_eq!(player.position(), Some(Duration::ZERO));
+        assert_eq!(player.duration(), Some(DEFAULT_TRACK_DURATION));
+
+        player.play();
+        thread::sleep(Duration::from_millis(20));
+        player.pause();
+        let paused_at = player.position().unwrap();
+        assert!(paused_at >= Duration::from_millis(20));
+
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(player.position(), Some(paused_at));
+    }
+
+    #[test]
+    fn reports_seeks_when_done() {
+        let mut player = player(DEFAULT_TRACK_DURATION);
+        player.set_uri(URI);
+
+        player.seek(Duration::from_secs(60));
+        assert_eq!(player.poll_events(), [PlayerEvent::SeekDone]);
+        assert_eq!(player.position(), Some(Duration::from_secs(60)));
+        assert!(player.poll_events().is_empty());
+    }
+
+    #[test]
+    fn seeks_no_further_than_the_end() {
+        let mut player = player(DEFAULT_TRACK_DURATION);
+        player.set_uri(URI);
+
+        player.seek(DEFAULT_TRACK_DURATION * 2);
+        assert_eq!(player.position(), Some(DEFAULT_TRACK_DURATION));
+    }
+
+    #[test]
+    fn reports_the_end_of_the_track_once() {
+        let mut player = player(Duration::from_millis(10));
+        player.set_uri(URI);
+        player.play();
+        thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(player.poll_events(), [PlayerEvent::EndOfStream]);
+        assert_eq!(player.position(), Some(Duration::from_millis(10)));
+        assert!(player.poll_events().is_empty());
+    }
+
+    #[test]
+    fn seeking_to_the_end_ends_the_track() {
+        let mut player = player(DEFAULT_TRACK_DURATION);
+        player.set_uri(URI);
+        player.play();
+
+        player.seek(DEFAULT_TRACK_DURATION);
+        assert_eq!(
+            player.poll_events(),
+            [PlayerEvent::SeekDone, PlayerEvent::EndOfStream]
+        );
+    }
+
+    #[test]
+    fn a_track_not_playing_does_not_end() {
+        let mut player = player(Duration::ZERO);
+        player.set_uri(URI);
+
+        assert!(player.poll_events().is_empty());
+    }
+
+    #[test]
+    fn moves_on_to_the_queued_track() {
+        let mut player = player(Duration::ZERO);
+        player.set_uri(URI);
+        player.set_next_uri(Some("file:///music/two.flac"));
+        player.play();
+
+        assert_eq!(player.poll_events(), [PlayerEvent::NextTrack]);
+        assert_eq!(player.position(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn loading_a_track_drops_the_queued_one() {
+        let mut player = player(Duration::ZERO);
+        player.set_uri(URI);
+        player.set_next_uri(Some("file:///music/two.flac"));
+        player.set_uri(URI);
+        player.play();
+
+        assert_eq!(player.poll_events(), [PlayerEvent::EndOfStream]);
+    }
+
+    #[test]
+    fn stopping_goes_back_to_the_start() {
+        let mut player = player(DEFAULT_TRACK_DURATION);
+        player.set_uri(URI);
+        player.seek(Duration::from_secs(60));
+        player.play();
+
+        player.stop();
+        assert_eq!(player.position(), Some(Duration::ZERO));
+    }
+}
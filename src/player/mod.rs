@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Audio playback. The app talks to a [`PlayerBackend`] so the GStreamer pipeline can be
+//! swapped for [`mock::MockPlayer`] when running headless.
+
+pub mod autodj;
+pub mod crossfade;
+pub mod declick;
+pub mod devices;
+pub mod diagnostics;
+pub mod equalizer;
+pub mod filters;
+pub mod gst_backend;
+pub mod inspect;
+pub mod mock;
+pub mod output;
+pub mod plugins;
+pub mod preview;
+pub mod queue;
+pub mod resume;
+pub mod state;
+pub mod transport;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub use crossfade::Crossfade;
+pub use devices::DeviceProfile;
+pub use equalizer::ParametricEq;
+pub use filters::{Crossfeed, FilterSettings};
+pub use gst_backend::GStreamerPlayer;
+pub use inspect::PipelineSnapshot;
+pub use mock::MockPlayer;
+pub use output::{Downmix, OutputSettings};
+pub use preview::Preview;
+pub use queue::{EndOfQueue, Queue};
+pub use resume::ResumeRules;
+pub use state::{PlayState, PlaybackState};
+
+/// Environment variable selecting the playback backend. Set it to `mock` to play nothing.
+const BACKEND_ENV: &str = "JAMS_PLAYER_BACKEND";
+
+/// Something that happened in a backend, collected with [`PlayerBackend::poll_events`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerEvent {
+    /// A seek requested earlier has finished and the reported position is accurate again.
+    SeekDone,
+    /// The loaded track played to its very end.
+    EndOfStream,
+    /// The track queued with [`PlayerBackend::set_next_uri`] took over from the one that
+    /// ended, without a gap.
+    NextTrack,
+    /// The limiter had to hold back peaks since the last poll.
+    Limiting,
+    Error(String),
+}
+
+/// Everything the app needs from something that plays audio.
+pub trait PlayerBackend {
+    /// Loads a new track, replacing whatever was loaded before.
+    fn set_uri(&mut self, uri: &str);
+    /// Queues a track to follow the loaded one without a gap, or cancels that with `None`.
+    /// Loading or stopping a track cancels it too.
+    fn set_next_uri(&mut self, uri: Option<&str>);
+    fn play(&mut self);
+    fn pause(&mut self);
+    fn stop(&mut self);
+    /// Stops after a fade too short to hear, for a backend that's done with, without
+    /// holding up the caller. Cutting the audio off would click.
+    fn fade_out(self: Box<Self>);
+    fn seek(&mut self, position: Duration);
+    /// Current playback position, if the backend knows it.
+    fn position(&self) -> Option<Duration>;
+    /// Duration of the loaded track, if the backend knows it.
+    fn duration(&self) -> Option<Duration>;
+    /// Linear volume from 0.0 to 1.0.
+    fn set_volume(&mut self, volume: f64);
+    /// Replaces the processing applied to the decoded audio.
+    fn set_filters(&mut self, settings: FilterSettings);
+    /// Replaces the setup of the output sink.
+    fn set_output(&mut self, settings: OutputSettings);
+    /// Drains the events that happened since the last call.
+    fn poll_events(&mut self) -> Vec<PlayerEvent>;
+    /// Describes the playback pipeline, for backends that have one.
+    fn inspect(&self) -> Option<PipelineSnapshot>;
+    /// Writes the playback pipeline as a Graphviz DOT file and returns where it went.
+    fn write_graph(&self) -> Result<PathBuf, String>;
+}
+
+/// Picks the backend named by `JAMS_PLAYER_BACKEND`, defaulting to GStreamer.
+pub fn backend_from_env() -> Box<dyn PlayerBackend> {
+    match std::env::var(BACKEND_ENV).as_deref() {
+        Ok("mock") => {
+            eprintln!("Using the mock player backend.");
+            Box::new(MockPlayer::default())
+        }
+        _ => Box::new(GStreamerPlayer::new()),
+    }
+}
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The audio sink playback goes through.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use gstreamer as gst;
+use gstreamer::glib;
+use gstreamer::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::fl;
+
+/// Buffer sizes offered in the settings, `None` leaving the sink's own default alone.
+pub const BUFFER_SIZES: [Option<Duration>; 6] = [
+    None,
+    Some(Duration::from_millis(50)),
+    Some(Duration::from_millis(100)),
+    Some(Duration::from_millis(200)),
+    Some(Duration::from_millis(300)),
+    Some(Duration::from_millis(500)),
+];
+
+/// Labels for a dropdown, in the same order as [`BUFFER_SIZES`].
+pub fn buffer_names() -> &'static [String] {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+    NAMES.get_or_init(|| {
+        BUFFER_SIZES
+            .iter()
+            .map(|size| match size {
+                Some(size) => {
+                    let count = size.as_millis() as u64;
+                    fl!("output-buffer-milliseconds", count = count)
+                }
+                None => fl!("output-buffer-default"),
+            })
+            .collect()
+    })
+}
+
+/// The ring buffer is split into this many segments, each one written in a single go.
+const SEGMENTS: u32 = 4;
+
+/// -3 dB, what the center and surround channels are mixed in at.
+const MINUS_3_DB: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// What's done with audio that has more than two channels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Downmix {
+    /// Every channel goes to the sink, which mixes it down itself if it has to.
+    #[default]
+    Passthrough,
+    /// Mixed to stereo as ITU-R BS.775 does it: center and surrounds at -3 dB, no LFE.
+    Stereo,
+    /// Like [`Downmix::Stereo`], with the LFE mixed into both sides at -3 dB as well.
+    StereoWithLfe,
+}
+
+impl Downmix {
+    pub const ALL: [Downmix; 3] = [
+        Downmix::Passthrough,
+        Downmix::Stereo,
+        Downmix::StereoWithLfe,
+    ];
+
+    /// Labels for a dropdown, in the same order as [`Downmix::ALL`].
+    pub fn names() -> &'static [String] {
+        static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+        NAMES.get_or_init(|| {
+            vec![
+                fl!("downmix-passthrough"),
+                fl!("downmix-stereo"),
+                fl!("downmix-stereo-lfe"),
+            ]
+        })
+    }
+
+    /// How much of the channel at `position`, a bit of GStreamer's channel mask, goes to the
+    /// left and to the right.
+    fn weights(self, position: u32) -> (f32, f32) {
+        let lfe = match self {
+            Downmix::StereoWithLfe => MINUS_3_DB,
+            _ => 0.0,
+        };
+
+        match position {
+            // Front left and right, and left and right of center.
+            0 | 6 => (1.0, 0.0),
+            1 | 7 => (0.0, 1.0),
+            // Front center.
+            2 => (MINUS_3_DB, MINUS_3_DB),
+            // Both LFE channels.
+            3 | 9 => (lfe, lfe),
+            // Rear and side left and right.
+            4 | 10 => (MINUS_3_DB, 0.0),
+            5 | 11 => (0.0, MINUS_3_DB),
+            // Rear center, and height and other channels nobody lays out the same way.
+            _ => (0.5, 0.5),
+        }
+    }
+
+    /// `audioconvert`'s `mix-matrix` for audio laid out as `channel_mask`: one row per
+    /// output channel, one column per input channel. Rows are scaled so a signal on every
+    /// channel at once can't clip.
+    fn matrix(self, channel_mask: u64) -> gst::Array {
+        let weights: Vec<(f32, f32)> = (0..64)
+            .filter(|position| channel_mask & (1 << position) != 0)
+            .map(|position| self.weights(position))
+            .collect();
+        let left_sum: f32 = weights.iter().map(|(left, _)| left).sum();
+        let right_sum: f32 = weights.iter().map(|(_, right)| right).sum();
+
+        let left = weights.iter().map(|(left, _)| left / left_sum.max(1.0));
+        let right = weights.iter().map(|(_, right)| right / right_sum.max(1.0));
+        gst::Array::new([gst::Array::new(left), gst::Array::new(right)])
+    }
+}
+
+/// How the output sink is set up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputSettings {
+    /// Audio buffered ahead in the sink. Larger buffers ride out hiccups, such as a
+    /// Bluetooth link dropping packets, at the cost of reacting later to pause and seek.
+    pub buffer: Option<Duration>,
+    pub downmix: Downmix,
+}
+
+/// Builds the sink set as playbin's `audio-sink`, or `None` to let playbin pick its own.
+pub fn build(settings: &OutputSettings) -> Result<Option<gst::Element>, glib::BoolError> {
+    if settings.buffer.is_none() && settings.downmix == Downmix::Passthrough {
+        return Ok(None);
+    }
+
+    let sink = gst::ElementFactory::make("autoaudiosink").build()?;
+
+    if let Some(buffer) = settings.buffer {
+        let bin = sink
+            .downcast_ref::<gst::Bin>()
+            .ok_or_else(|| glib::bool_error!("autoaudiosink is not a bin"))?;
+
+        // autoaudiosink only creates the real sink when it starts, so size its buffer then.
+        let buffer_time = i64::try_from(buffer.as_micros()).unwrap_or(i64::MAX);
+        bin.connect_element_added(move |_, element| {
+            if element.find_property("buffer-time").is_some() {
+                element.set_property("buffer-time", buffer_time);
+            }
+            if element.find_property("latency-time").is_some() {
+                element.set_property("latency-time", buffer_time / i64::from(SEGMENTS));
+            }
+        });
+    }
+
+    match settings.downmix {
+        Downmix::Passthrough => Ok(Some(sink)),
+        downmix => with_downmix(sink, downmix).map(Some),
+    }
+}
+
+/// Puts a stereo downmix in front of `sink`.
+fn with_downmix(sink: gst::Element, downmix: Downmix) -> Result<gst::Element, glib::BoolError> {
+    let bin = gst::Bin::builder().name("jams-audio-output").build();
+    let convert = gst::ElementFactory::make("audioconvert").build()?;
+    let stereo = gst::Caps::builder("audio/x-raw")
+        .field("channels", 2)
+        .build();
+    let capsfilter = gst::ElementFactory::make("capsfilter")
+        .property("caps", stereo)
+        .build()?;
+
+    // The matrix depends on the channel layout, which is only known once a track's caps
+    // arrive. audioconvert reads it when it takes the caps, right after this probe.
+    let sink_pad = convert
+        .static_pad("sink")
+        .ok_or_else(|| glib::bool_error!("audioconvert has no sink pad"))?;
+    let matrix_target = convert.clone();
+    sink_pad.add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, move |_, info| {
+        let Some(gst::PadProbeData::Event(event)) = &info.data else {
+            return gst::PadProbeReturn::Ok;
+        };
+        let gst::EventView::Caps(caps) = event.view() else {
+            return gst::PadProbeReturn::Ok;
+        };
+
+        let structure = caps.caps().structure(0);
+        let channels = structure.and_then(|s| s.get::<i32>("channels").ok());
+        let mask = structure.and_then(|s| s.get::<gst::Bitmask>("channel-mask").ok());
+
+        // Mono and stereo are left to audioconvert, as is audio without positions.
+        let matrix = match mask {
+            Some(mask) if channels.is_some_and(|channels| channels > 2) => downmix.matrix(mask.0),
+            _ => gst::Array::new(Vec::<gst::Array>::new()),
+        };
+        matrix_target.set_property("mix-matrix", matrix);
+
+        gst::PadProbeReturn::Ok
+    });
+
+    let chain = [&convert, &capsfilter, &sink];
+    bin.add_many(chain)?;
+    gst::Element::link_many(chain)?;
+    bin.add_pad(&gst::GhostPad::with_target(&sink_pad)?)?;
+
+    Ok(bin.upcast())
+}
@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Checks that GStreamer can decode the formats in the library. Without the right plugin a
+//! track fails to play with nothing more than an error on stderr, so the listener is told
+//! up front which package to install.
+
+use std::collections::BTreeSet;
+
+use gstreamer as gst;
+
+/// What a format needs: every group must have at least one of its elements installed.
+struct Requirement {
+    extensions: &'static [&'static str],
+    /// Shown to the listener, such as "FLAC".
+    format: &'static str,
+    elements: &'static [&'static [&'static str]],
+    /// The package on Debian and Ubuntu based systems, which Pop!_OS is.
+    package: &'static str,
+}
+
+const GOOD: &str = "gstreamer1.0-plugins-good";
+const BASE: &str = "gstreamer1.0-plugins-base";
+const LIBAV: &str = "gstreamer1.0-libav";
+
+const REQUIREMENTS: &[Requirement] = &[
+    Requirement {
+        extensions: &["flac"],
+        format: "FLAC",
+        elements: &[&["flacparse"], &["flacdec", "avdec_flac"]],
+        package: GOOD,
+    },
+    Requirement {
+        extensions: &["mp3"],
+        format: "MP3",
+        elements: &[&["mpegaudioparse"], &["mpg123audiodec", "avdec_mp3"]],
+        package: GOOD,
+    },
+    Requirement {
+        extensions: &["ogg", "oga"],
+        format: "Ogg Vorbis",
+        elements: &[&["oggdemux"], &["vorbisdec", "avdec_vorbis"]],
+        package: BASE,
+    },
+    Requirement {
+        extensions: &["opus"],
+        format: "Opus",
+        elements: &[&["oggdemux"], &["opusdec", "avdec_opus"]],
+        package: BASE,
+    },
+    Requirement {
+        extensions: &["m4a", "aac"],
+        format: "AAC",
+        elements: &[&["qtdemux"], &["avdec_aac", "fdkaacdec", "faad"]],
+        package: LIBAV,
+    },
+    Requirement {
+        extensions: &["wav"],
+        format: "WAV",
+        elements: &[&["wavparse"]],
+        package: GOOD,
+    },
+    Requirement {
+        extensions: &["aiff", "aif"],
+        format: "AIFF",
+        elements: &[&["aiffparse"]],
+        package: "gstreamer1.0-plugins-bad",
+    },
+    Requirement {
+        extensions: &["wv"],
+        format: "WavPack",
+        elements: &[&["wavpackparse"], &["wavpackdec", "avdec_wavpack"]],
+        package: GOOD,
+    },
+    Requirement {
+        extensions: &["ape"],
+        format: "Monkey's Audio",
+        elements: &[&["avdec_ape"]],
+        package: LIBAV,
+    },
+    Requirement {
+        extensions: &["wma"],
+        format: "Windows Media Audio",
+        elements: &[&["asfdemux"], &["avdec_wmav2"]],
+        package: LIBAV,
+    },
+    Requirement {
+        extensions: &["dsf", "dff"],
+        format: "DSD",
+        elements: &[&["avdec_dsd_lsbf", "avdec_dsd_msbf"]],
+        package: LIBAV,
+    },
+];
+
+/// A format in the library that GStreamer can't play yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingPlugin {
+    pub format: &'static str,
+    pub package: &'static str,
+}
+
+/// The formats among `extensions`, lowercase and without the dot, that lack a plugin.
+/// Loads the plugin registry the first time, so run it off the UI thread.
+pub fn missing(extensions: &BTreeSet<String>) -> Vec<MissingPlugin> {
+    if let Err(err) = gst::init() {
+        eprintln!("Failed to initialize GStreamer: {}", err);
+        return Vec::new();
+    }
+
+    REQUIREMENTS
+        .iter()
+        .filter(|requirement| {
+            requirement
+                .extensions
+                .iter()
+                .any(|extension| extensions.contains(*extension))
+        })
+        .filter(|requirement| {
+            !requirement.elements.iter().all(|any_of| {
+                any_of
+                    .iter()
+                    .any(|name| gst::ElementFactory::find(name).is_some())
+            })
+        })
+        .map(|requirement| MissingPlugin {
+            format: requirement.format,
+            package: requirement.package,
+        })
+        .collect()
+}
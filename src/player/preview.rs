@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Short snippets of tracks, played quietly on a backend of their own so whatever's
+//! playing carries on. Used to tell search results apart by hovering them.
+
+use std::time::{Duration, Instant};
+
+use super::PlayerBackend;
+use crate::library::MusicFile;
+
+/// How long a snippet plays.
+const SNIPPET_LENGTH: Duration = Duration::from_secs(10);
+/// Where in the track the snippet starts, as a fraction of its length. Far enough in to
+/// skip intros.
+const SNIPPET_START: f64 = 0.3;
+/// Quiet enough to talk over whatever's playing.
+const SNIPPET_VOLUME: f64 = 0.3;
+
+/// A snippet that's playing. It stops when this is dropped.
+pub struct Preview {
+    track: usize,
+    backend: Box<dyn PlayerBackend>,
+    start: Duration,
+    /// When playback reached `start`, once the track had loaded far enough to seek.
+    started: Option<Instant>,
+}
+
+impl Preview {
+    pub fn start(file: &MusicFile) -> Self {
+        let mut backend = super::backend_from_env();
+        backend.set_uri(&file.uri);
+        backend.set_volume(SNIPPET_VOLUME);
+        backend.play();
+
+        Preview {
+            track: file.id,
+            backend,
+            start: file.duration.mul_f64(SNIPPET_START),
+            started: None,
+        }
+    }
+
+    /// ID of the track being previewed.
+    pub fn track(&self) -> usize {
+        self.track
+    }
+
+    /// Seeks to the start of the snippet once the track has loaded. Returns `false` once
+    /// the snippet is over.
+    pub fn step(&mut self) -> bool {
+        match self.started {
+            // The backend only knows the duration once it can seek.
+            None if self.backend.duration().is_some() => {
+                self.backend.seek(self.start);
+                self.started = Some(Instant::now());
+                true
+            }
+            None => true,
+            Some(started) => started.elapsed() < SNIPPET_LENGTH,
+        }
+    }
+}
+
+impl Drop for Preview {
+    fn drop(&mut self) {
+        self.backend.stop();
+    }
+}
@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fl;
+
+/// What happens once the last track of the queue has played.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EndOfQueue {
+    #[default]
+    Stop,
+    /// Starts the queue over from its first track.
+    Repeat,
+    /// Carries on with the whole library in random order.
+    ShuffleLibrary,
+    /// Carries on with tracks picked to go with what was just played.
+    AutoDj,
+}
+
+impl EndOfQueue {
+    pub const ALL: [EndOfQueue; 4] = [
+        EndOfQueue::Stop,
+        EndOfQueue::Repeat,
+        EndOfQueue::ShuffleLibrary,
+        EndOfQueue::AutoDj,
+    ];
+
+    /// Labels for a dropdown, in the same order as [`EndOfQueue::ALL`].
+    pub fn names() -> &'static [String] {
+        static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+        NAMES.get_or_init(|| {
+            vec![
+                fl!("end-of-queue-stop"),
+                fl!("end-of-queue-repeat"),
+                fl!("end-of-queue-shuffle"),
+                fl!("end-of-queue-auto-dj"),
+            ]
+        })
+    }
+}
+
+/// The ordered list of track IDs being played and where playback is within it.
+#[derive(Debug, Clone, Default)]
+pub struct Queue {
+    tracks: Vec<usize>,
+    position: Option<usize>,
+}
+
+impl Queue {
+    /// Creates a queue over `tracks`, positioned at `start` if it's in the list.
+    pub fn new(tracks: Vec<usize>, start: usize) -> Self {
+        let position = tracks.iter().position(|&id| id == start);
+        Queue { tracks, position }
+    }
+
+    /// Every track ID in play order.
+    pub fn tracks(&self) -> &[usize] {
+        &self.tracks
+    }
+
+    /// The track currently being played.
+    pub fn current(&self) -> Option<usize> {
+        self.position.and_then(|position| self.tracks.get(position).copied())
+    }
+
+    /// The track that follows the current one, without moving to it.
+    pub fn peek_next(&self) -> Option<usize> {
+        self.tracks.get(self.position? + 1).copied()
+    }
+
+    /// Moves to the following track. Stays put and returns `None` at the end of the queue.
+    pub fn advance(&mut self) -> Option<usize> {
+        let next = self.position? + 1;
+        let id = *self.tracks.get(next)?;
+        self.position = Some(next);
+        Some(id)
+    }
+
+    /// Moves back to the first track, for repeating the queue.
+    pub fn restart(&mut self) -> Option<usize> {
+        let id = *self.tracks.first()?;
+        self.position = Some(0);
+        Some(id)
+    }
+
+    /// Adds `ids` after the last track, leaving the current one playing.
+    pub fn append(&mut self, ids: impl IntoIterator<Item = usize>) {
+        self.tracks.extend(ids);
+    }
+
+    /// Where the current track is in [`Queue::tracks`].
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Takes the track at `index` out of the queue. The current track stays, so returns
+    /// `false` for it and for indices past the end.
+    pub fn remove(&mut self, index: usize) -> bool {
+        if index >= self.tracks.len() || self.position == Some(index) {
+            return false;
+        }
+
+        self.tracks.remove(index);
+        if let Some(position) = self.position.as_mut() {
+            if index < *position {
+                *position -= 1;
+            }
+        }
+        true
+    }
+
+    /// Moves the track at `from` to `to`, shifting the ones in between. The current track
+    /// stays current wherever it ends up.
+    pub fn move_track(&mut self, from: usize, to: usize) {
+        if from >= self.tracks.len() || to >= self.tracks.len() || from == to {
+            return;
+        }
+
+        let id = self.tracks.remove(from);
+        self.tracks.insert(to, id);
+
+        if let Some(position) = self.position.as_mut() {
+            if *position == from {
+                *position = to;
+            } else if from < *position && to >= *position {
+                *position -= 1;
+            } else if from > *position && to <= *position {
+                *position += 1;
+            }
+        }
+    }
+
+    /// Moves to the preceding track. Stays put and returns `None` at the start of the queue.
+    pub fn go_back(&mut self) -> Option<usize> {
+        let previous = self.position?.checked_sub(1)?;
+        let id = *self.tracks.get(previous)?;
+        self.position = Some(previous);
+        Some(id)
+    }
+}
@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Picking long tracks, such as DJ mixes or audiobooks dropped into the library, back up
+//! where they were left instead of from the start.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::flatpak;
+use crate::fl;
+use crate::library::MusicFile;
+
+/// Lengths offered in the settings, `Duration::ZERO` meaning off.
+pub const MIN_LENGTHS: [Duration; 5] = [
+    Duration::ZERO,
+    Duration::from_secs(10 * 60),
+    Duration::from_secs(20 * 60),
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(60 * 60),
+];
+
+/// Labels for a dropdown, in the same order as [`MIN_LENGTHS`].
+pub fn min_length_names() -> &'static [String] {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+    NAMES.get_or_init(|| {
+        MIN_LENGTHS
+            .iter()
+            .map(|length| match length.as_secs() / 60 {
+                0 => fl!("resume-length-off"),
+                minutes if minutes % 60 == 0 => {
+                    fl!("resume-length-hours", count = minutes / 60)
+                }
+                minutes => fl!("resume-length-minutes", count = minutes),
+            })
+            .collect()
+    })
+}
+
+/// How often the position of a playing track is saved, so a crash loses little of it.
+pub const SAVE_EVERY: Duration = Duration::from_secs(30);
+
+/// Left this close to either end, a track starts over next time.
+const NEAR_END: Duration = Duration::from_secs(30);
+
+/// Which tracks are resumed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeRules {
+    /// Tracks at least this long are resumed, none if zero.
+    pub min_length: Duration,
+    /// Genres and folders, separated by commas, limiting which long tracks are resumed.
+    /// Folders start with `/` or `~/`. Empty for every long track.
+    pub only: String,
+}
+
+impl ResumeRules {
+    /// Whether `file` picks up where it was left.
+    pub fn applies(&self, file: &MusicFile) -> bool {
+        if self.min_length.is_zero() || file.duration < self.min_length {
+            return false;
+        }
+
+        let mut entries = self
+            .only
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .peekable();
+        if entries.peek().is_none() {
+            return true;
+        }
+
+        entries.any(|entry| match folder(entry) {
+            Some(folder) => file.saved_path.starts_with(folder),
+            None => file.genre.eq_ignore_ascii_case(entry),
+        })
+    }
+}
+
+/// The folder `entry` names, if it's a path rather than a genre.
+fn folder(entry: &str) -> Option<PathBuf> {
+    if let Some(relative) = entry.strip_prefix("~/") {
+        let home_dir = std::env::var("HOME").unwrap_or_default();
+        Some(Path::new(&home_dir).join(relative))
+    } else if entry.starts_with('/') {
+        Some(PathBuf::from(entry))
+    } else {
+        None
+    }
+}
+
+/// Where each track was left, `~/.local/share/jams/resume.json` unless XDG says otherwise.
+fn positions_file() -> PathBuf {
+    flatpak::data_dir().join("resume.json")
+}
+
+/// The positions saved by [`write_positions`], by file.
+pub fn read_positions() -> HashMap<PathBuf, Duration> {
+    let positions: HashMap<PathBuf, u64> = fs::read_to_string(positions_file())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    positions
+        .into_iter()
+        .map(|(path, secs)| (path, Duration::from_secs(secs)))
+        .collect()
+}
+
+/// Keeps `positions` in whole seconds, which is close enough to carry on listening.
+pub fn write_positions(positions: &HashMap<PathBuf, Duration>) -> Result<(), String> {
+    let path = positions_file();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+
+    let positions: HashMap<&PathBuf, u64> = positions
+        .iter()
+        .map(|(path, position)| (path, position.as_secs()))
+        .collect();
+    let contents = serde_json::to_string(&positions).map_err(|err| err.to_string())?;
+    fs::write(path, contents).map_err(|err| err.to_string())
+}
+
+/// Notes that the file at `path`, `length` long, was left at `position`. Barely started or
+/// nearly finished, it's forgotten instead.
+pub fn remember(
+    positions: &mut HashMap<PathBuf, Duration>,
+    path: &Path,
+    length: Duration,
+    position: Duration,
+) {
+    if position < NEAR_END || position + NEAR_END >= length {
+        positions.remove(path);
+    } else {
+        positions.insert(path.to_path_buf(), position);
+    }
+}
@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! What the player is doing, kept in one place so the track rows, the controls and the
+//! integrations can't disagree about it.
+
+/// Whether the loaded track is playing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlayState {
+    /// Nothing is loaded.
+    #[default]
+    Idle,
+    Paused,
+    Playing,
+}
+
+/// The track loaded into the player, by ID, and whether it plays.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlaybackState {
+    /// `None` while idle, and for tracks played from outside the library.
+    pub current: Option<usize>,
+    pub status: PlayState,
+}
+
+impl PlaybackState {
+    /// Track `current` was loaded and plays from the start.
+    pub fn start(&mut self, current: Option<usize>) {
+        self.current = current;
+        self.status = PlayState::Playing;
+    }
+
+    /// Pauses a playing track. Does nothing while idle.
+    pub fn pause(&mut self) {
+        if self.status == PlayState::Playing {
+            self.status = PlayState::Paused;
+        }
+    }
+
+    /// Resumes a paused track. Does nothing while idle.
+    pub fn resume(&mut self) {
+        if self.status == PlayState::Paused {
+            self.status = PlayState::Playing;
+        }
+    }
+
+    pub fn stop(&mut self) {
+        *self = PlaybackState::default();
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.status == PlayState::Playing
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.status == PlayState::Idle
+    }
+
+    /// How the row for track `id` should show it: idle unless it's the loaded track.
+    pub fn track_state(&self, id: usize) -> PlayState {
+        if self.current == Some(id) {
+            self.status
+        } else {
+            PlayState::Idle
+        }
+    }
+}
@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Where the queue goes when a track ends, is skipped or the player moves on by itself,
+//! apart from the app so the transitions can be tested against [`super::MockPlayer`].
+
+use super::{EndOfQueue, Queue};
+
+/// What the app does once the queue has moved on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// Load this track and play it.
+    Load(usize),
+    /// The player is already playing this track, so only the app's state has to follow.
+    Follow(usize),
+    /// Nothing is left to play.
+    Stop,
+}
+
+/// Moves `queue` on to the next track once the loaded one ended or was skipped. At the end,
+/// starts over if `end` is [`EndOfQueue::Repeat`]; tracks other actions add have to be
+/// appended before this.
+///
+/// `handed_over` is the track given to the player with
+/// [`super::PlayerBackend::set_next_uri`] when the player already went on to it. If the
+/// queue changed since, the track it leads to now is loaded instead.
+pub fn move_on(queue: &mut Queue, end: EndOfQueue, handed_over: Option<usize>) -> Transition {
+    let next = match queue.advance() {
+        Some(id) => Some(id),
+        None if end == EndOfQueue::Repeat => queue.restart(),
+        None => None,
+    };
+
+    match next {
+        Some(id) if handed_over == Some(id) => Transition::Follow(id),
+        Some(id) => Transition::Load(id),
+        None => Transition::Stop,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::player::{MockPlayer, PlayerBackend, PlayerEvent};
+
+    fn uri(id: usize) -> String {
+        format!("file:///music/{}.flac", id)
+    }
+
+    /// Tracks 1 to 3 queued, with `current` loaded and playing. The player ends its track
+    /// as soon as it's polled.
+    fn playing(current: usize) -> (Queue, MockPlayer) {
+        let queue = Queue::new(vec![1, 2, 3], current);
+        let mut player = MockPlayer::with_duration(Duration::ZERO);
+
+        player.set_uri(&uri(current));
+        player.play();
+        (queue, player)
+    }
+
+    #[test]
+    fn track_ended_loads_the_next_track() {
+        let (mut queue, mut player) = playing(1);
+
+        assert_eq!(player.poll_events(), [PlayerEvent::EndOfStream]);
+        assert_eq!(
+            move_on(&mut queue, EndOfQueue::Stop, None),
+            Transition::Load(2)
+        );
+        assert_eq!(queue.current(), Some(2));
+    }
+
+    #[test]
+    fn track_ended_stops_at_the_end_of_the_queue() {
+        let (mut queue, mut player) = playing(3);
+
+        assert_eq!(player.poll_events(), [PlayerEvent::EndOfStream]);
+        assert_eq!(
+            move_on(&mut queue, EndOfQueue::Stop, None),
+            Transition::Stop
+        );
+        // The last track stays current, so going back still works.
+        assert_eq!(queue.current(), Some(3));
+    }
+
+    #[test]
+    fn track_ended_repeats_the_queue() {
+        let (mut queue, mut player) = playing(3);
+
+        assert_eq!(player.poll_events(), [PlayerEvent::EndOfStream]);
+        assert_eq!(
+            move_on(&mut queue, EndOfQueue::Repeat, None),
+            Transition::Load(1)
+        );
+        assert_eq!(queue.current(), Some(1));
+    }
+
+    #[test]
+    fn next_track_follows_the_hand_over() {
+        let (mut queue, mut player) = playing(1);
+        player.set_next_uri(Some(&uri(2)));
+
+        assert_eq!(player.poll_events(), [PlayerEvent::NextTrack]);
+        assert_eq!(
+            move_on(&mut queue, EndOfQueue::Stop, Some(2)),
+            Transition::Follow(2)
+        );
+        assert_eq!(queue.current(), Some(2));
+    }
+
+    #[test]
+    fn next_track_follows_a_repeat() {
+        let (mut queue, mut player) = playing(3);
+        player.set_next_uri(Some(&uri(1)));
+
+        assert_eq!(player.poll_events(), [PlayerEvent::NextTrack]);
+        assert_eq!(
+            move_on(&mut queue, EndOfQueue::Repeat, Some(1)),
+            Transition::Follow(1)
+        );
+    }
+
+    #[test]
+    fn next_track_loads_what_the_queue_leads_to_after_a_change() {
+        let (mut queue, mut player) = playing(1);
+        player.set_next_uri(Some(&uri(2)));
+        // Track 3 is moved up after track 2 was handed over.
+        queue.move_track(2, 1);
+
+        assert_eq!(player.poll_events(), [PlayerEvent::NextTrack]);
+        assert_eq!(
+            move_on(&mut queue, EndOfQueue::Stop, Some(2)),
+            Transition::Load(3)
+        );
+        assert_eq!(queue.current(), Some(3));
+    }
+
+    #[test]
+    fn next_track_stops_when_the_rest_was_removed() {
+        let (mut queue, mut player) = playing(2);
+        player.set_next_uri(Some(&uri(3)));
+        queue.remove(2);
+
+        assert_eq!(player.poll_events(), [PlayerEvent::NextTrack]);
+        assert_eq!(
+            move_on(&mut queue, EndOfQueue::Stop, Some(3)),
+            Transition::Stop
+        );
+    }
+
+    #[test]
+    fn seek_done_leaves_the_queue_alone() {
+        let queue = Queue::new(vec![1, 2, 3], 2);
+        let mut player = MockPlayer::default();
+        player.set_uri(&uri(2));
+        player.play();
+
+        player.seek(Duration::from_secs(30));
+        assert_eq!(player.poll_events(), [PlayerEvent::SeekDone]);
+
+        assert!(player.position().unwrap() >= Duration::from_secs(30));
+        assert_eq!(queue.current(), Some(2));
+    }
+
+    #[test]
+    fn skipping_with_nothing_playing_stops() {
+        let mut queue = Queue::default();
+
+        assert_eq!(
+            move_on(&mut queue, EndOfQueue::Stop, None),
+            Transition::Stop
+        );
+        assert_eq!(
+            move_on(&mut queue, EndOfQueue::Repeat, Some(1)),
+            Transition::Stop
+        );
+    }
+}
@@ -0,0 +1,222 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Listens sent to a ListenBrainz-compatible endpoint: ListenBrainz itself, or a self-hosted
+//! Maloja or multi-scrobbler. Listens that can't be sent yet wait in a queue kept on disk
+//! and are sent with the next ones. Every listen is also kept in a local history, which can
+//! be exported for ListenBrainz to import.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::core::flatpak;
+use crate::library::MusicFile;
+
+/// Tracks shorter than this are never submitted, as ListenBrainz asks.
+const MIN_TRACK_LENGTH: Duration = Duration::from_secs(30);
+/// A track counts as listened to after half its length or this long, whichever is first.
+const MAX_LISTEN_THRESHOLD: Duration = Duration::from_secs(4 * 60);
+/// Listens sent in one request when catching up on the queue.
+pub const SUBMIT_BATCH: usize = 50;
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(10);
+const USER_AGENT: &str = "Jams (https://github.com/benfuddled/Jams)";
+/// Name of the file [`export_history`] writes.
+pub const EXPORT_FILE_NAME: &str = "jams-listens.json";
+
+/// Where listens go. Nothing is submitted until a URL is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Endpoint {
+    /// The full `submit-listens` URL, e.g. `https://api.listenbrainz.org/1/submit-listens`.
+    pub url: String,
+    /// Sent as `Authorization: Token …`.
+    pub token: String,
+}
+
+impl Endpoint {
+    pub fn is_set(&self) -> bool {
+        !self.url.trim().is_empty()
+    }
+}
+
+/// A track that was listened to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Listen {
+    /// When playback of the track started, in seconds since the Unix epoch.
+    pub listened_at: u64,
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    pub duration_ms: u64,
+}
+
+impl Listen {
+    pub fn new(file: &MusicFile, started: SystemTime) -> Self {
+        Listen {
+            listened_at: started
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            artist: file.artist.clone(),
+            title: file.track_title.clone(),
+            album: file.album.clone(),
+            duration_ms: file.duration.as_millis() as u64,
+        }
+    }
+}
+
+/// Whether playing `listened` of a track `length` long makes a listen.
+pub fn counts(listened: Duration, length: Duration) -> bool {
+    length >= MIN_TRACK_LENGTH && listened >= (length / 2).min(MAX_LISTEN_THRESHOLD)
+}
+
+/// A listen the way ListenBrainz takes it, both when submitted and when imported.
+fn to_json(listen: &Listen) -> serde_json::Value {
+    json!({
+        "listened_at": listen.listened_at,
+        "track_metadata": {
+            "artist_name": listen.artist,
+            "track_name": listen.title,
+            "release_name": listen.album,
+            "additional_info": {
+                "duration_ms": listen.duration_ms,
+                "media_player": "Jams",
+                "submission_client": "Jams",
+            },
+        },
+    })
+}
+
+/// Sends `listens` in one request. Blocks until the endpoint answers.
+pub fn submit(endpoint: &Endpoint, listens: &[Listen]) -> Result<(), String> {
+    let payload: Vec<_> = listens.iter().map(to_json).collect();
+
+    // ListenBrainz only takes more than one listen at a time as an import.
+    let listen_type = if listens.len() == 1 {
+        "single"
+    } else {
+        "import"
+    };
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout(SUBMIT_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build();
+
+    agent
+        .post(endpoint.url.trim())
+        .set("Authorization", &format!("Token {}", endpoint.token.trim()))
+        .send_json(json!({ "listen_type": listen_type, "payload": payload }))
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// `name` in `~/.local/share/jams` unless XDG says otherwise.
+fn data_file(name: &str) -> PathBuf {
+    flatpak::data_dir().join(name)
+}
+
+/// Kept out of cosmic-config, as this file can be made unreadable to other users and the
+/// token is as good as a password.
+fn endpoint_file() -> PathBuf {
+    flatpak::config_dir().join("scrobble")
+}
+
+/// Reads the endpoint saved by [`write_endpoint`]: the URL, then the token.
+pub fn read_endpoint() -> Endpoint {
+    let contents = fs::read_to_string(endpoint_file()).unwrap_or_default();
+    let mut lines = contents.lines();
+
+    Endpoint {
+        url: lines.next().unwrap_or_default().to_string(),
+        token: lines.next().unwrap_or_default().to_string(),
+    }
+}
+
+/// Remembers where listens are submitted in `~/.config/jams/scrobble`.
+pub fn write_endpoint(endpoint: &Endpoint) -> Result<(), String> {
+    let path = endpoint_file();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+
+    fs::write(&path, format!("{}\n{}", endpoint.url, endpoint.token))
+        .map_err(|err| err.to_string())?;
+
+    // The token is as good as a password.
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|err| err.to_string())
+}
+
+fn queue_file() -> PathBuf {
+    data_file("scrobble-queue.json")
+}
+
+/// Every listen, one JSON object per line so recording one only appends.
+fn history_file() -> PathBuf {
+    data_file("history.jsonl")
+}
+
+/// Listens that weren't sent before Jams was last closed.
+pub fn read_queue() -> Vec<Listen> {
+    fs::read_to_string(queue_file())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Keeps the listens still waiting to be sent, removing the file once there are none.
+pub fn write_queue(listens: &[Listen]) -> Result<(), String> {
+    let path = queue_file();
+    if listens.is_empty() {
+        let _ = fs::remove_file(path);
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let contents = serde_json::to_string(listens).map_err(|err| err.to_string())?;
+    fs::write(path, contents).map_err(|err| err.to_string())
+}
+
+/// Adds `listen` to the local history, whether or not it's ever submitted.
+pub fn record_history(listen: &Listen) -> Result<(), String> {
+    let path = history_file();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+
+    let line = serde_json::to_string(listen).map_err(|err| err.to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| err.to_string())?;
+    writeln!(file, "{}", line).map_err(|err| err.to_string())
+}
+
+/// Every listen in the local history, oldest first. Lines that can't be read, such as one
+/// cut short by a crash, are skipped.
+pub fn read_history() -> Vec<Listen> {
+    fs::read_to_string(history_file())
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Writes the whole local history to [`EXPORT_FILE_NAME`] in `folder`, as the JSON array of
+/// listens ListenBrainz imports. Returns how many listens were written.
+pub fn export_history(folder: &Path) -> Result<usize, String> {
+    let listens: Vec<_> = read_history().iter().map(to_json).collect();
+    let contents = serde_json::to_string_pretty(&listens).map_err(|err| err.to_string())?;
+    fs::write(folder.join(EXPORT_FILE_NAME), contents).map_err(|err| err.to_string())?;
+    Ok(listens.len())
+}
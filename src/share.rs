@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Text for sharing what's playing, and `jams://` links that open an album or track in Jams.
+//!
+//! Links name the album and track by their tags rather than by path, so they work on
+//! another computer with the same music.
+
+use url::Url;
+
+use crate::library::{Album, MusicFile};
+
+/// The URI scheme Jams is registered to handle.
+pub const SCHEME: &str = "jams";
+
+/// What a `jams://` link points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShareLink {
+    /// `jams://album?artist=…&album=…`
+    Album { artist: String, album: String },
+    /// `jams://track?artist=…&album=…&title=…`, where `artist` is the album artist.
+    Track {
+        artist: String,
+        album: String,
+        title: String,
+    },
+}
+
+impl ShareLink {
+    pub fn album(album: &Album) -> Self {
+        ShareLink::Album {
+            artist: album.album_artist.clone(),
+            album: album.album.clone(),
+        }
+    }
+
+    pub fn track(file: &MusicFile) -> Self {
+        ShareLink::Track {
+            artist: file.album_artist.clone(),
+            album: file.album.clone(),
+            title: file.track_title.clone(),
+        }
+    }
+
+    /// Reads a `jams://` link. Returns `None` for other schemes and malformed links.
+    pub fn parse(url: &Url) -> Option<Self> {
+        if url.scheme() != SCHEME {
+            return None;
+        }
+
+        let query = |key: &str| {
+            url.query_pairs()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.into_owned())
+        };
+
+        match url.host_str()? {
+            "album" => Some(ShareLink::Album {
+                artist: query("artist")?,
+                album: query("album")?,
+            }),
+            "track" => Some(ShareLink::Track {
+                artist: query("artist")?,
+                album: query("album")?,
+                title: query("title")?,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn to_url(&self) -> String {
+        let (kind, pairs) = match self {
+            ShareLink::Album { artist, album } => {
+                ("album", vec![("artist", artist), ("album", album)])
+            }
+            ShareLink::Track {
+                artist,
+                album,
+                title,
+            } => (
+                "track",
+                vec![("artist", artist), ("album", album), ("title", title)],
+            ),
+        };
+
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs)
+            .finish();
+        format!("{}://{}?{}", SCHEME, kind, query)
+    }
+}
+
+/// `Artist – Title`, then the album and its year.
+pub fn track_text(file: &MusicFile) -> String {
+    let mut text = format!("{} – {}\n{}", file.artist, file.track_title, file.album);
+    if let Some(date) = file.sort_date() {
+        text.push_str(&format!(" ({})", date.year));
+    }
+    text
+}
+
+/// `Album Artist – Album (year)`.
+pub fn album_text(album: &Album) -> String {
+    let mut text = format!("{} – {}", album.album_artist, album.album);
+    if let Some(date) = album.date {
+        text.push_str(&format!(" ({})", date.year));
+    }
+    text
+}
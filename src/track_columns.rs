@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The columns of the track lists, which are made wider or narrower by dragging the
+//! boundaries between their headings.
+
+use serde::{Deserialize, Serialize};
+
+/// Share of a row taken by the track number, which isn't resized.
+pub const NUMBER_PORTION: u16 = 10;
+/// Any narrower and a column would be hard to grab to widen it again.
+const MIN_PORTION: u16 = 20;
+
+/// A column that can be resized, in the order they're shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackColumn {
+    Title,
+    Artist,
+    Album,
+    Bpm,
+    Key,
+}
+
+impl TrackColumn {
+    pub const ALL: [TrackColumn; 5] = [
+        TrackColumn::Title,
+        TrackColumn::Artist,
+        TrackColumn::Album,
+        TrackColumn::Bpm,
+        TrackColumn::Key,
+    ];
+}
+
+/// How much of a row each column takes, as portions of what the play button leaves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnWidths([u16; 5]);
+
+impl Default for ColumnWidths {
+    fn default() -> Self {
+        ColumnWidths([400, 200, 200, 40, 40])
+    }
+}
+
+impl ColumnWidths {
+    pub fn portion(&self, column: TrackColumn) -> u16 {
+        self.0[column as usize]
+    }
+
+    /// The portions of every column and the track number together.
+    pub fn total(&self) -> u16 {
+        NUMBER_PORTION + self.0.iter().sum::<u16>()
+    }
+
+    /// These widths with the boundary after `column` moved right by `delta` portions,
+    /// left if negative. The column after it makes up the difference, so the others stay.
+    pub fn dragged(&self, column: TrackColumn, delta: f32) -> ColumnWidths {
+        let index = column as usize;
+        let mut widths = *self;
+        let Some(next) = widths.0.get(index + 1) else {
+            return widths;
+        };
+
+        let pair = widths.0[index] + next;
+        if pair < 2 * MIN_PORTION {
+            return widths;
+        }
+        let (min, max) = (f32::from(MIN_PORTION), f32::from(pair - MIN_PORTION));
+        let moved = (f32::from(widths.0[index]) + delta).round().clamp(min, max) as u16;
+        widths.0[index] = moved;
+        widths.0[index + 1] = pair - moved;
+        widths
+    }
+}
@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A scrolling list that only builds the rows in view, so libraries with thousands of
+//! tracks don't rebuild every row on each redraw. Row heights must be known up front,
+//! either one for all rows or one per row, such as taller headers between groups.
+
+use std::ops::Range;
+
+use cosmic::iced::widget::scrollable::{self, Viewport};
+use cosmic::iced::Length;
+use cosmic::iced_widget::Scrollable;
+use cosmic::widget::{self, Column, Container};
+use cosmic::Element;
+
+/// Rows built beyond each edge of the viewport, so fast scrolling doesn't show gaps.
+const OVERSCAN: usize = 8;
+/// Assumed viewport height until the list has been scrolled once.
+const INITIAL_HEIGHT: f32 = 1200.0;
+
+/// Where a [`VirtualList`] is scrolled to, fed from its `on_scroll` messages.
+#[derive(Debug, Clone, Copy)]
+pub struct ListScroll {
+    offset: f32,
+    height: f32,
+}
+
+impl Default for ListScroll {
+    fn default() -> Self {
+        ListScroll {
+            offset: 0.0,
+            height: INITIAL_HEIGHT,
+        }
+    }
+}
+
+impl ListScroll {
+    pub fn update(&mut self, viewport: Viewport) {
+        self.offset = viewport.absolute_offset().y;
+        self.height = viewport.bounds().height;
+    }
+
+    /// How far down the list is scrolled.
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+
+    /// Where to scroll so the span from `top` to `bottom` is in view, or `None` if it
+    /// already is.
+    pub fn reveal(&self, top: f32, bottom: f32) -> Option<f32> {
+        if top < self.offset {
+            Some(top)
+        } else if bottom > self.offset + self.height {
+            Some(bottom - self.height)
+        } else {
+            None
+        }
+    }
+
+    /// Builds the rows at `offset` straight away, for when the scrollable is about to be
+    /// moved there, rather than waiting for it to report the scroll.
+    pub fn restore(&mut self, offset: f32) {
+        self.offset = offset;
+    }
+
+    /// Rows that have to be built for a list with `heights`.
+    fn visible_range(&self, heights: &RowHeights) -> Range<usize> {
+        let len = heights.len();
+        let first = heights.index_at(self.offset);
+        let last = heights.index_at(self.offset + self.height);
+
+        let start = first.saturating_sub(OVERSCAN).min(len);
+        let end = (last + 1 + OVERSCAN).min(len);
+        start..end
+    }
+}
+
+/// How tall the rows of a [`VirtualList`] are.
+enum RowHeights {
+    /// `len` rows of one height.
+    Uniform { len: usize, height: f32 },
+    /// Where each row starts, followed by where the last one ends.
+    Varying(Vec<f32>),
+}
+
+impl RowHeights {
+    fn len(&self) -> usize {
+        match self {
+            RowHeights::Uniform { len, .. } => *len,
+            RowHeights::Varying(tops) => tops.len() - 1,
+        }
+    }
+
+    /// Where the row at `index` starts. `len()` gives the end of the list.
+    fn top(&self, index: usize) -> f32 {
+        match self {
+            RowHeights::Uniform { height, .. } => index as f32 * height,
+            RowHeights::Varying(tops) => tops[index],
+        }
+    }
+
+    /// The row at `y`, found by binary search for varying heights.
+    fn index_at(&self, y: f32) -> usize {
+        match self {
+            RowHeights::Uniform { height, .. } => (y.max(0.0) / height).floor() as usize,
+            RowHeights::Varying(tops) => tops.partition_point(|top| *top <= y).saturating_sub(1),
+        }
+    }
+}
+
+/// A list of rows whose heights are known before they're built.
+pub struct VirtualList<Message, F> {
+    heights: RowHeights,
+    row: F,
+    on_scroll: fn(Viewport) -> Message,
+    id: Option<scrollable::Id>,
+}
+
+impl<Message, F> VirtualList<Message, F> {
+    /// A list of `len` rows, each `row_height` tall. `row` builds the row at an index; it's
+    /// only called for rows near the viewport.
+    pub fn new(len: usize, row_height: f32, row: F, on_scroll: fn(Viewport) -> Message) -> Self {
+        VirtualList {
+            heights: RowHeights::Uniform {
+                len,
+                height: row_height,
+            },
+            row,
+            on_scroll,
+            id: None,
+        }
+    }
+
+    /// A list with one row for each of `heights`, for rows of different kinds such as
+    /// group headers and their items. `row` is called as in [`VirtualList::new`].
+    pub fn with_heights(
+        heights: impl IntoIterator<Item = f32>,
+        row: F,
+        on_scroll: fn(Viewport) -> Message,
+    ) -> Self {
+        let mut tops = vec![0.0];
+        for height in heights {
+            let top = tops[tops.len() - 1];
+            tops.push(top + height);
+        }
+
+        VirtualList {
+            heights: RowHeights::Varying(tops),
+            row,
+            on_scroll,
+            id: None,
+        }
+    }
+
+    /// Gives the scrollable `id`, so it can be scrolled from a task.
+    pub fn id(mut self, id: scrollable::Id) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn view<'a>(self, scroll: &ListScroll) -> Element<'a, Message>
+    where
+        Message: Clone + 'static,
+        F: Fn(usize) -> Element<'a, Message>,
+    {
+        let heights = &self.heights;
+        let range = scroll.visible_range(heights);
+
+        // Spacers stand in for the rows that aren't built, keeping the scrollbar honest.
+        let mut column = Column::new().push(widget::Space::new(
+            Length::Fill,
+            Length::Fixed(heights.top(range.start)),
+        ));
+
+        for index in range.clone() {
+            column = column.push(
+                Container::new((self.row)(index))
+                    .width(Length::Fill)
+                    .height(Length::Fixed(heights.top(index + 1) - heights.top(index))),
+            );
+        }
+
+        column = column.push(widget::Space::new(
+            Length::Fill,
+            Length::Fixed(heights.top(heights.len()) - heights.top(range.end)),
+        ));
+
+        let mut scrollable = Scrollable::new(column);
+        if let Some(id) = self.id {
+            scrollable = scrollable.id(id);
+        }
+
+        scrollable
+            .on_scroll(self.on_scroll)
+            .height(Length::Fill)
+            .width(Length::Fill)
+            .into()
+    }
+}
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Tag reading and scanning over the small WAV files in `tests/fixtures/library`. Each has
+//! an ID3 chunk: two tracks of one album, and a track with only an artist.
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use jams::library::archive::ArchiveEntry;
+use jams::library::scan::{self, ScanOptions, ScanProgress, TitleFallback};
+use jams::library::{track_number, MusicFile};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/library")
+}
+
+fn album_folder(root: &Path) -> PathBuf {
+    root.join("The Hollows").join("Low Tide")
+}
+
+/// A copy of the fixtures in a folder of its own. The files are dated an hour back, or the
+/// scan would hold them back as still being copied.
+fn library_copy(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("jams-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    copy_dir(&fixtures(), &root);
+    root
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), &target).unwrap();
+            set_modified(&target, SystemTime::now() - Duration::from_secs(60 * 60));
+        }
+    }
+}
+
+fn set_modified(path: &Path, time: SystemTime) {
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+/// Scans `root` the way the app does, returning the files in library order and the ones
+/// held back.
+fn scan(root: &Path) -> (Vec<MusicFile>, Vec<PathBuf>) {
+    let progress = ScanProgress::default();
+    scan::scan_paths(
+        vec![root.to_path_buf()],
+        0,
+        ScanOptions::default(),
+        &progress,
+    );
+
+    let mut files = progress.take_found();
+    track_number::sort_library(&mut files);
+    (files, progress.take_held_back())
+}
+
+#[test]
+fn reads_the_tags() {
+    let path = album_folder(&fixtures()).join("01 Undertow.wav");
+    let file = scan::reread(&path, 7, TitleFallback::default()).unwrap();
+
+    assert_eq!(file.id, 7);
+    assert_eq!(file.track_title, "Undertow");
+    assert_eq!(file.artist, "The Hollows");
+    assert_eq!(file.album_artist, "The Hollows");
+    assert_eq!(file.album, "Low Tide");
+    assert_eq!(file.track_number, 1);
+    assert_eq!(file.genre, "Ambient");
+    assert_eq!(file.channels, 1);
+    assert_eq!(file.saved_path, fs::canonicalize(&path).unwrap());
+    assert!(file.uri.starts_with("file://"));
+}
+
+#[test]
+fn makes_the_sort_keys() {
+    let path = album_folder(&fixtures()).join("02 Salt Flats.wav");
+    let file = scan::reread(&path, 0, TitleFallback::default()).unwrap();
+
+    // "The" is one of the articles left out by default.
+    assert_eq!(file.artist_key, "hollows");
+    assert_eq!(file.album_artist_key, file.artist_key);
+    assert_eq!(file.title_key, "salt flats");
+}
+
+#[test]
+fn names_untitled_tracks_after_the_file() {
+    let path = fixtures().join("untitled_demo.wav");
+    let title = |fallback| scan::reread(&path, 0, fallback).unwrap().track_title;
+
+    assert_eq!(title(TitleFallback::FileName), "untitled_demo.wav");
+    assert_eq!(title(TitleFallback::Cleaned), "untitled demo");
+    assert_eq!(title(TitleFallback::TitleCase), "Untitled Demo");
+
+    let file = scan::reread(&path, 0, TitleFallback::default()).unwrap();
+    assert_eq!(file.artist, "Amber Static");
+    assert_eq!(file.album, "Unknown Album");
+    assert_eq!(file.track_number, 0);
+}
+
+#[test]
+fn skips_files_that_are_not_audio() {
+    assert!(scan::reread(&fixtures().join("notes.txt"), 0, TitleFallback::default()).is_none());
+    assert!(scan::reread(&fixtures().join("missing.wav"), 0, TitleFallback::default()).is_none());
+}
+
+#[test]
+fn scans_every_tagged_file() {
+    let root = library_copy("scan");
+    let (files, held_back) = scan(&root);
+    fs::remove_dir_all(&root).unwrap();
+
+    let titles: Vec<&str> = files.iter().map(|file| file.track_title.as_str()).collect();
+    // "The Hollows" sorts under H, after "Amber Static".
+    assert_eq!(titles, ["untitled demo", "Undertow", "Salt Flats"]);
+    assert!(held_back.is_empty());
+
+    let mut ids: Vec<usize> = files.iter().map(|file| file.id).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, [0, 1, 2]);
+    assert_eq!(scan::next_id(&files), 3);
+}
+
+#[test]
+fn holds_back_files_still_changing() {
+    let root = library_copy("settling");
+    let changing = album_folder(&root).join("02 Salt Flats.wav");
+    set_modified(&changing, SystemTime::now());
+
+    let (files, held_back) = scan(&root);
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(files.len(), 2);
+    assert_eq!(held_back, [changing]);
+}
+
+#[test]
+fn drops_files_already_in_the_library() {
+    let root = library_copy("duplicates");
+    let (library, _) = scan(&root);
+    let (mut found, _) = scan(&root);
+    fs::remove_dir_all(&root).unwrap();
+
+    scan::drop_duplicates(&mut found, &library, ScanOptions::default());
+    assert!(found.is_empty());
+}
+
+#[test]
+fn tells_archived_tracks_apart_by_archive_and_entry() {
+    let root = library_copy("archived");
+    let (files, _) = scan(&root);
+    fs::remove_dir_all(&root).unwrap();
+
+    // Extracted copies of different archives can end up at the same inode.
+    let archived = |archive: &str| MusicFile {
+        inode: Some((1, 7)),
+        archive: Some(ArchiveEntry {
+            archive: PathBuf::from(archive),
+            name: "01 Undertow.flac".to_string(),
+        }),
+        ..files[0].clone()
+    };
+    let library = [archived("/music/Low Tide.zip")];
+    let mut found = vec![
+        archived("/music/Low Tide.zip"),
+        archived("/music/High Tide.zip"),
+    ];
+
+    scan::drop_duplicates(&mut found, &library, ScanOptions::default());
+    assert_eq!(found.len(), 1);
+    assert_eq!(
+        found[0].archive.as_ref().unwrap().archive,
+        Path::new("/music/High Tide.zip")
+    );
+}
+
+#[test]
+fn groups_the_scan_into_albums() {
+    let root = library_copy("albums");
+    let (files, _) = scan(&root);
+    fs::remove_dir_all(&root).unwrap();
+
+    let (albums, _) = scan::build_albums(&files);
+    assert_eq!(albums.len(), 2);
+
+    let low_tide = albums
+        .iter()
+        .find(|album| album.album == "Low Tide")
+        .unwrap();
+    assert_eq!(low_tide.album_artist, "The Hollows");
+    assert_eq!(low_tide.tracks.len(), 2);
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The playback state kept next to the player, as the app moves it along while
+//! [`MockPlayer`] plays.
+
+use std::time::Duration;
+
+use jams::player::{MockPlayer, PlayState, PlaybackState, PlayerBackend, PlayerEvent};
+
+#[test]
+fn starts_idle() {
+    let state = PlaybackState::default();
+
+    assert!(state.is_idle());
+    assert!(!state.is_playing());
+    assert_eq!(state.current, None);
+    assert_eq!(state.track_state(0), PlayState::Idle);
+}
+
+#[test]
+fn pauses_and_resumes_the_loaded_track() {
+    let mut player = MockPlayer::default();
+    let mut state = PlaybackState::default();
+
+    player.set_uri("file:///music/one.flac");
+    player.play();
+    state.start(Some(1));
+    assert!(state.is_playing());
+    assert_eq!(state.track_state(1), PlayState::Playing);
+    assert_eq!(state.track_state(2), PlayState::Idle);
+
+    player.pause();
+    state.pause();
+    assert_eq!(state.track_state(1), PlayState::Paused);
+
+    player.play();
+    state.resume();
+    assert_eq!(state.track_state(1), PlayState::Playing);
+}
+
+#[test]
+fn pausing_while_idle_does_nothing() {
+    let mut state = PlaybackState::default();
+
+    state.pause();
+    assert!(state.is_idle());
+    state.resume();
+    assert!(state.is_idle());
+}
+
+#[test]
+fn stops_at_the_end_of_the_track() {
+    let mut player = MockPlayer::with_duration(Duration::ZERO);
+    let mut state = PlaybackState::default();
+
+    player.set_uri("file:///music/one.flac");
+    player.play();
+    state.start(Some(1));
+
+    // With nothing queued after it, the app stops once the player says the track ended.
+    if player.poll_events().contains(&PlayerEvent::EndOfStream) {
+        player.stop();
+        state.stop();
+    }
+    assert!(state.is_idle());
+    assert_eq!(state.current, None);
+    assert_eq!(state.track_state(1), PlayState::Idle);
+}
+
+#[test]
+fn follows_a_gapless_hand_over() {
+    let mut player = MockPlayer::with_duration(Duration::ZERO);
+    let mut state = PlaybackState::default();
+
+    player.set_uri("file:///music/one.flac");
+    player.set_next_uri(Some("file:///music/two.flac"));
+    player.play();
+    state.start(Some(1));
+
+    assert_eq!(player.poll_events(), [PlayerEvent::NextTrack]);
+    state.start(Some(2));
+    assert_eq!(state.track_state(1), PlayState::Idle);
+    assert_eq!(state.track_state(2), PlayState::Playing);
+}